@@ -0,0 +1,47 @@
+//! Session logger throughput benchmark
+//!
+//! Compares per-line flushing against batched flushing when logging a burst
+//! of output lines. Run with `cargo bench --bench logger_throughput`.
+
+use claude_man::core::logger::{FlushPolicy, SessionLogger};
+use claude_man::types::SessionId;
+use std::time::{Duration, Instant};
+
+/// Number of output lines logged per run
+const LINES: usize = 50_000;
+
+fn run(policy: FlushPolicy) -> Duration {
+    let temp_dir = tempfile::TempDir::new().expect("create temp dir");
+    let session_id = SessionId::from_string("BENCH-001".to_string());
+    let mut logger = SessionLogger::new(session_id, temp_dir.path())
+        .expect("create logger")
+        .with_flush_policy(policy);
+
+    let start = Instant::now();
+    for i in 0..LINES {
+        logger
+            .log_output(format!("line {} of benchmark output", i))
+            .expect("log output");
+    }
+    logger.flush().expect("flush");
+    start.elapsed()
+}
+
+fn main() {
+    let policies = [
+        ("every-line", FlushPolicy::EveryLine),
+        (
+            "batched",
+            FlushPolicy::Batched {
+                max_lines: FlushPolicy::DEFAULT_BATCH_LINES,
+                max_delay: FlushPolicy::DEFAULT_BATCH_DELAY,
+            },
+        ),
+    ];
+
+    for (name, policy) in policies {
+        let elapsed = run(policy);
+        let rate = LINES as f64 / elapsed.as_secs_f64();
+        println!("{:<12} {:>10.2?} {:>12.0} lines/s", name, elapsed, rate);
+    }
+}
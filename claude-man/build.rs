@@ -0,0 +1,56 @@
+//! Build script embedding the git commit for `claude-man version --full`,
+//! and generating the gRPC service with the `grpc` feature
+
+use std::process::Command;
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=CLAUDE_MAN_GIT_COMMIT={}", commit);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
+    #[cfg(feature = "grpc")]
+    grpc::generate();
+}
+
+/// Service stubs for proto/claude_man.proto
+///
+/// Generated from a Rust description of the service so building doesn't
+/// need `protoc`; the messages are defined in src/daemon/grpc.rs.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    pub fn generate() {
+        let method = |name: &str, route: &str, input: &str, output: &str| {
+            Method::builder()
+                .name(name)
+                .route_name(route)
+                .input_type(format!("super::{}", input))
+                .output_type(format!("super::{}", output))
+                .codec_path("tonic::codec::ProstCodec")
+        };
+
+        let service = Service::builder()
+            .name("ClaudeMan")
+            .package("claudeman.v1")
+            .method(method("spawn", "Spawn", "SpawnRequest", "SpawnResponse").build())
+            .method(method("list", "List", "ListRequest", "ListResponse").build())
+            .method(method("info", "Info", "InfoRequest", "Session").build())
+            .method(method("stop", "Stop", "StopRequest", "StopResponse").build())
+            .method(method("attach", "Attach", "AttachRequest", "IoEvent").server_streaming().build())
+            .method(method("events", "Events", "EventsRequest", "LifecycleEvent").server_streaming().build())
+            .build();
+
+        Builder::new().compile(&[service]);
+    }
+}
@@ -2,13 +2,37 @@
 //!
 //! Implements the core CLI commands: spawn, list, stop, etc.
 
-use crate::cli::output;
-use crate::core::session::SessionRegistry;
-use crate::types::error::Result;
+use crate::cli::output::{self, ActionResult, ListFormat, ListPage, ListSort, OutputFormat};
+use crate::cli::pager;
+use crate::daemon::server::stop_message;
+use crate::core::filter::PromptDetector;
+use crate::core::logger::LogSelection;
+use crate::core::session::{ApprovalPrompt, SessionRegistry, SpawnOptions};
+use crate::daemon::client::DaemonClient;
+use crate::daemon::protocol::DaemonResponse;
+use crate::types::error::{ClaudeManError, Result};
 use crate::types::role::Role;
-use crate::types::session::SessionId;
+use crate::types::session::{validate_non_empty, Approval, SessionId, SessionMetadata, SessionStatus};
+use std::path::Path;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Default interval between checks while waiting for a spawned session
+pub const DEFAULT_WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Exit code of `spawn --wait-timeout` when the session is still running at the deadline
+pub const EXIT_STILL_RUNNING: i32 = 124;
+
+/// Exit code of `spawn --wait-for-ready` when the session ends before printing its marker
+pub const EXIT_NOT_READY: i32 = 3;
+
+/// Default interval between checks for new log lines when tailing
+pub const DEFAULT_TAIL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How much of a log `attach --from-end` reads at a time, working back from the end
+const TAIL_CHUNK_SIZE: u64 = 64 * 1024;
 
 /// Spawn a new Claude session
 ///
@@ -17,14 +41,32 @@ use tracing::info;
 /// * `registry` - The session registry
 /// * `role` - The role to assign to the session
 /// * `task` - The task description
+/// * `parent_id` - Spawn as a child of this session
+/// * `options` - Additional spawn options (annotations, etc.)
+/// * `tail_lines` - Log lines to print inline if the session fails (0 for all)
+/// * `poll_interval` - How often to check for completion (default 500ms)
 pub async fn spawn_session(
     registry: Arc<SessionRegistry>,
     role: Role,
     task: String,
+    parent_id: Option<SessionId>,
+    options: SpawnOptions,
+    tail_lines: usize,
+    poll_interval: Option<Duration>,
 ) -> Result<()> {
     info!("Executing spawn command: role={}, task={}", role, task);
 
-    let session_id = registry.spawn_session(role, task).await?;
+    // Subscribe before the process starts so no prompt is missed
+    let prompts = registry.approval_prompts().await;
+
+    let session_id = match parent_id {
+        Some(parent_id) => {
+            registry
+                .spawn_child_session_with_options(parent_id, role, task, options)
+                .await?
+        }
+        None => registry.spawn_session_with_options(role, task, options).await?,
+    };
 
     // Get the PID from the session
     let pid = if let Some(metadata) = registry.get_session(&session_id).await {
@@ -36,27 +78,56 @@ pub async fn spawn_session(
     println!("{}", output::success(&format!("Session {} started{}", session_id, pid)));
     println!();
 
+    wait_for_session(registry, session_id, prompts, tail_lines, poll_interval).await
+}
+
+/// Restart a finished session and wait for the new run to complete
+pub async fn restart_session(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    tail_lines: usize,
+    poll_interval: Option<Duration>,
+) -> Result<()> {
+    info!("Executing restart command: session={}", session_id);
+
+    let prompts = registry.approval_prompts().await;
+    registry.restart_session(&session_id).await?;
+
+    let pid = registry
+        .get_session(&session_id)
+        .await
+        .and_then(|metadata| metadata.pid)
+        .map(|p| format!(" (PID: {})", p))
+        .unwrap_or_default();
+    println!("{}", output::success(&format!("Session {} restarted{}", session_id, pid)));
+    println!();
+
+    wait_for_session(registry, session_id, prompts, tail_lines, poll_interval).await
+}
+
+/// Wait for a session started in this process to finish, then report how it ended
+async fn wait_for_session(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    prompts: Option<broadcast::Receiver<ApprovalPrompt>>,
+    tail_lines: usize,
+    poll_interval: Option<Duration>,
+) -> Result<()> {
+    // Put approval prompts to the user while the session runs
+    let bridge = prompts.map(|prompts| {
+        tokio::spawn(bridge_approval_prompts(registry.clone(), session_id.clone(), prompts))
+    });
+
     // Wait for the session to complete
     info!("Waiting for session {} to complete...", session_id);
 
     loop {
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        tokio::time::sleep(poll_interval.unwrap_or(DEFAULT_WAIT_POLL_INTERVAL)).await;
 
         if let Some(metadata) = registry.get_session(&session_id).await {
-            if !metadata.is_active() {
+            if metadata.is_terminal() {
                 println!();
-                match metadata.status {
-                    crate::types::session::SessionStatus::Completed => {
-                        println!("{}", output::success(&format!("Session {} completed successfully", session_id)));
-                    }
-                    crate::types::session::SessionStatus::Failed => {
-                        println!("{}", output::info(&format!("Session {} failed", session_id)));
-                    }
-                    crate::types::session::SessionStatus::Stopped => {
-                        println!("{}", output::info(&format!("Session {} was stopped", session_id)));
-                    }
-                    _ => {}
-                }
+                print_session_outcome(&metadata, tail_lines);
                 break;
             }
         } else {
@@ -65,24 +136,132 @@ pub async fn spawn_session(
         }
     }
 
+    if let Some(bridge) = bridge {
+        bridge.abort();
+    }
+
     println!();
     println!("View logs:  claude-man logs {}", session_id);
 
     Ok(())
 }
 
+/// Report how a finished session ended, with the end of its log if it failed
+fn print_session_outcome(metadata: &SessionMetadata, tail_lines: usize) {
+    let session_id = &metadata.id;
+    match metadata.status {
+        SessionStatus::Completed => {
+            println!("{}", output::success(&format!("Session {} completed successfully", session_id)));
+        }
+        SessionStatus::Failed => {
+            println!("{}", output::info(&format!("Session {} failed", session_id)));
+            if let Err(e) = print_failure_tail(session_id, &metadata.log_dir, tail_lines) {
+                warn!("Could not read log for failed session {}: {}", session_id, e);
+            }
+        }
+        SessionStatus::Stopped => {
+            println!("{}", output::info(&format!("Session {} was stopped", session_id)));
+        }
+        _ => {}
+    }
+}
+
+/// Wait up to `timeout` for a daemon-managed session to finish
+///
+/// Reports the outcome if it finishes; otherwise reports its status and
+/// leaves it running. Returns whether the session finished.
+///
+/// # Arguments
+///
+/// * `client` - Client for the daemon that owns the session
+/// * `session_id` - The session to wait for
+/// * `timeout` - How long to wait before giving up
+/// * `tail_lines` - Log lines to print if the session fails (0 for all)
+/// * `poll_interval` - How often to check the session (default 500ms)
+pub async fn wait_for_daemon_session(
+    client: &DaemonClient,
+    session_id: &SessionId,
+    timeout: Duration,
+    tail_lines: usize,
+    poll_interval: Option<Duration>,
+) -> Result<bool> {
+    // A timeout too large to add is as good as none
+    let deadline = tokio::time::Instant::now().checked_add(timeout);
+
+    loop {
+        let metadata = match client.info(session_id.to_string()).await? {
+            DaemonResponse::Ok { session: Some(metadata), .. } => *metadata,
+            DaemonResponse::Error { message } => return Err(ClaudeManError::Session(message)),
+            other => {
+                return Err(ClaudeManError::Other(format!("Unexpected daemon response: {:?}", other)));
+            }
+        };
+
+        if metadata.is_terminal() {
+            println!();
+            print_session_outcome(&metadata, tail_lines);
+            return Ok(true);
+        }
+
+        let now = tokio::time::Instant::now();
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            println!();
+            println!(
+                "{}",
+                output::info(&format!(
+                    "Session {} still {} after {}s; it keeps running",
+                    session_id,
+                    metadata.status,
+                    timeout.as_secs()
+                ))
+            );
+            return Ok(false);
+        }
+
+        let interval = poll_interval.unwrap_or(DEFAULT_WAIT_POLL_INTERVAL);
+        let remaining = deadline.map_or(interval, |deadline| deadline - now);
+        tokio::time::sleep(interval.min(remaining)).await;
+    }
+}
+
 /// List all active sessions
 ///
 /// # Arguments
 ///
 /// * `registry` - The session registry
-pub async fn list_sessions(registry: Arc<SessionRegistry>) -> Result<()> {
+/// * `filters` - `key=value` annotation filters; all must match
+/// * `format` - Render as a table, JSON or CSV
+/// * `show_task` - Add a TASK column to tables
+/// * `sort` - Field to order sessions by
+/// * `reverse` - List in descending order
+/// * `page` - Window of the sorted list to print
+pub async fn list_sessions(
+    registry: Arc<SessionRegistry>,
+    filters: &[(String, String)],
+    format: ListFormat,
+    show_task: bool,
+    sort: ListSort,
+    reverse: bool,
+    page: ListPage,
+) -> Result<()> {
     info!("Executing list command");
 
-    let sessions = registry.list_sessions().await;
+    let mut sessions = filter_by_annotations(registry.list_sessions().await, filters);
+    output::sort_sessions(&mut sessions, sort, reverse);
+
+    print_sessions_list(&sessions, format, show_task, page)
+}
 
-    output::print_sessions_table(&sessions);
+/// Show sessions as a parent/child tree
+///
+/// # Arguments
+///
+/// * `registry` - The session registry
+pub async fn show_session_tree(registry: Arc<SessionRegistry>) -> Result<()> {
+    info!("Executing tree command");
 
+    let sessions = registry.list_sessions().await;
+    output::print_session_tree(&sessions);
     Ok(())
 }
 
@@ -92,17 +271,111 @@ pub async fn list_sessions(registry: Arc<SessionRegistry>) -> Result<()> {
 ///
 /// * `registry` - The session registry
 /// * `session_id` - The ID of the session to stop
+/// * `cascade` - Cancel pending dependents instead of failing them
+/// * `format` - Output format for the confirmation
 pub async fn stop_session(
     registry: Arc<SessionRegistry>,
     session_id: SessionId,
+    cascade: bool,
+    format: OutputFormat,
 ) -> Result<()> {
     info!("Executing stop command for session {}", session_id);
 
-    registry.stop_session(&session_id).await?;
+    match registry.stop_session_with_cascade(&session_id, cascade).await {
+        Ok(dependents) => {
+            let message = stop_message(&session_id, &dependents, cascade);
+            output::print_action_result(
+                format,
+                &ActionResult::success("stopped", Some(session_id), message),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            if format == OutputFormat::Json {
+                output::print_action_result(
+                    format,
+                    &ActionResult::failure("stopped", Some(session_id), e.to_string()),
+                );
+            }
+            Err(e)
+        }
+    }
+}
 
-    println!("{}", output::success(&format!("Session {} stopped", session_id)));
+/// Pause a running session, or continue a paused one
+///
+/// # Arguments
+///
+/// * `registry` - The session registry
+/// * `session_id` - The session to pause or continue
+/// * `paused` - Pause (SIGSTOP) when true, continue (SIGCONT) when false
+/// * `format` - Output format for the result
+pub async fn set_paused(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    paused: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let (action, signalled) = if paused {
+        ("paused", registry.pause_session(&session_id).await)
+    } else {
+        ("continued", registry.continue_session(&session_id).await)
+    };
+    match signalled {
+        Ok(pid) => {
+            let message = format!("Session {} {} (PID: {})", session_id, action, pid);
+            output::print_action_result(
+                format,
+                &ActionResult::success(action, Some(session_id), message),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            if format == OutputFormat::Json {
+                output::print_action_result(
+                    format,
+                    &ActionResult::failure(action, Some(session_id), e.to_string()),
+                );
+            }
+            Err(e)
+        }
+    }
+}
 
-    Ok(())
+/// Record an approval decision on a session
+///
+/// # Arguments
+///
+/// * `registry` - The session registry
+/// * `session_id` - The session whose work is judged
+/// * `approval` - The decision to record
+/// * `format` - Output format for the result
+pub async fn record_approval(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    approval: Approval,
+    format: OutputFormat,
+) -> Result<()> {
+    let action = approval.decision.to_string();
+    match registry.record_approval(&session_id, approval).await {
+        Ok(_) => {
+            let message = format!("Session {} {}", session_id, action);
+            output::print_action_result(
+                format,
+                &ActionResult::success(&action, Some(session_id), message),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            if format == OutputFormat::Json {
+                output::print_action_result(
+                    format,
+                    &ActionResult::failure(&action, Some(session_id), e.to_string()),
+                );
+            }
+            Err(e)
+        }
+    }
 }
 
 /// Stop all active sessions
@@ -110,33 +383,164 @@ pub async fn stop_session(
 /// # Arguments
 ///
 /// * `registry` - The session registry
-pub async fn stop_all_sessions(registry: Arc<SessionRegistry>) -> Result<()> {
+/// * `dry_run` - Only list the sessions that would be stopped
+/// * `yes` - Skip the confirmation prompt
+/// * `format` - Output format for the preview and confirmation
+pub async fn stop_all_sessions(
+    registry: Arc<SessionRegistry>,
+    dry_run: bool,
+    yes: bool,
+    format: OutputFormat,
+) -> Result<()> {
     info!("Executing stop-all command");
 
-    let sessions = registry.list_sessions().await;
-    let count = sessions.len();
+    let sessions = active_sessions(registry.list_sessions().await);
 
-    if count == 0 {
-        println!("{}", output::info("No active sessions to stop"));
+    if !confirm_stop_all(&sessions, dry_run, yes, format)? {
         return Ok(());
     }
 
     registry.stop_all_sessions().await?;
 
-    println!("{}", output::success(&format!("Stopped {} session(s)", count)));
+    let message = format!("Stopped {} session(s)", sessions.len());
+    let ids = sessions.into_iter().map(|s| s.id).collect();
+    output::print_action_result(
+        format,
+        &ActionResult::success("stopped", None, message).with_session_ids(ids),
+    );
 
     Ok(())
 }
 
+/// Print a value read from the shared key/value store
+///
+/// Text output is the bare value, so it can be captured with `$(...)`.
+/// Returns whether the key was set.
+///
+/// # Arguments
+///
+/// * `key` - The key that was read
+/// * `value` - Its value, if set
+/// * `format` - Print the bare value or a `{"key", "value"}` object
+pub fn print_kv_value(key: &str, value: Option<String>, format: OutputFormat) -> bool {
+    let found = value.is_some();
+    match (format, value) {
+        (OutputFormat::Json, value) => {
+            println!("{}", serde_json::json!({ "key": key, "value": value }));
+        }
+        (OutputFormat::Text, Some(value)) => println!("{}", value),
+        (OutputFormat::Text, None) => eprintln!("Key not set: {}", key),
+    }
+
+    found
+}
+
+/// Describe the outcome of a health check
+///
+/// # Arguments
+///
+/// * `reconciled` - Sessions the health check marked failed
+pub fn health_check_result(reconciled: Vec<SessionId>) -> ActionResult {
+    let message = if reconciled.is_empty() {
+        "All running sessions have live processes".to_string()
+    } else {
+        let ids: Vec<_> = reconciled.iter().map(|id| id.as_str()).collect();
+        format!(
+            "Marked {} session(s) with dead processes failed: {}",
+            reconciled.len(),
+            ids.join(", ")
+        )
+    };
+    ActionResult::success("health_checked", None, message).with_session_ids(reconciled)
+}
+
+/// Preview the sessions `stop --all` would terminate and ask for confirmation
+///
+/// Returns `true` if the sessions should be stopped. Dry runs, empty session
+/// lists, and declined prompts return `false` after reporting the outcome.
+pub fn confirm_stop_all(
+    sessions: &[crate::types::SessionMetadata],
+    dry_run: bool,
+    yes: bool,
+    format: OutputFormat,
+) -> Result<bool> {
+    let count = sessions.len();
+    let ids: Vec<SessionId> = sessions.iter().map(|s| s.id.clone()).collect();
+
+    if count == 0 {
+        let result = ActionResult::success("stopped", None, "No active sessions to stop".to_string());
+        match format {
+            OutputFormat::Json => output::print_action_result(format, &result),
+            OutputFormat::Text => println!("{}", output::info("No active sessions to stop")),
+        }
+        return Ok(false);
+    }
+
+    if format == OutputFormat::Text {
+        output::print_stop_preview(sessions);
+    }
+
+    if dry_run {
+        let message = format!("Dry run: {} session(s) would be stopped", count);
+        match format {
+            OutputFormat::Json => output::print_action_result(
+                format,
+                &ActionResult::success("would_stop", None, message).with_session_ids(ids),
+            ),
+            OutputFormat::Text => {
+                println!();
+                println!("{}", output::info(&message));
+            }
+        }
+        return Ok(false);
+    }
+
+    if !yes {
+        if format == OutputFormat::Json {
+            return Err(crate::types::error::ClaudeManError::InvalidInput(
+                "stop --all requires --yes with --output json".to_string(),
+            ));
+        }
+        if !confirm(&format!("Stop {} session(s)?", count))? {
+            println!("{}", output::info("Aborted"));
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Keep only the sessions that are currently running
+pub fn active_sessions(
+    sessions: Vec<crate::types::SessionMetadata>,
+) -> Vec<crate::types::SessionMetadata> {
+    sessions.into_iter().filter(|s| s.is_active()).collect()
+}
+
+/// Ask the user a yes/no question on stdin (defaults to no)
+pub fn confirm(prompt: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 /// Get detailed information about a session
 ///
 /// # Arguments
 ///
 /// * `registry` - The session registry
 /// * `session_id` - The ID of the session
+/// * `format` - Print the details as text or as the metadata JSON
 pub async fn get_session_info(
     registry: Arc<SessionRegistry>,
     session_id: SessionId,
+    format: OutputFormat,
 ) -> Result<()> {
     info!("Executing info command for session {}", session_id);
 
@@ -145,9 +549,7 @@ pub async fn get_session_info(
         .await
         .ok_or_else(|| crate::types::error::ClaudeManError::SessionNotFound(session_id.to_string()))?;
 
-    output::print_session_details(&metadata);
-
-    Ok(())
+    print_session_info(&metadata, format)
 }
 
 /// View session logs
@@ -157,22 +559,38 @@ pub async fn get_session_info(
 /// * `registry` - The session registry
 /// * `session_id` - The ID of the session
 /// * `follow` - Whether to follow the log (like tail -f)
-/// * `lines` - Number of lines to show (0 for all)
+/// * `selection` - Number of lines to show (0 for all) and the event types to
+///   show; following prints new events of those types
+/// * `reverse` - Show the selected lines newest first (not with `follow`)
+/// * `page` - Show the selected lines through a pager (not with `follow`)
+/// * `poll_interval` - How often to check for new lines when following (default 200ms)
 pub async fn view_logs(
     registry: Arc<SessionRegistry>,
     session_id: SessionId,
     follow: bool,
-    lines: usize,
+    selection: LogSelection,
+    reverse: bool,
+    page: bool,
+    poll_interval: Option<Duration>,
 ) -> Result<()> {
-    use crate::core::logger::{session_log_dir, IoEvent};
-    use std::fs::File;
-    use std::io::{BufRead, BufReader, Seek, SeekFrom};
-    use tokio::time::{sleep, Duration};
+    use crate::core::logger::{IoEvent, LogTail};
+    use tokio::time::sleep;
 
     info!("Viewing logs for session {}", session_id);
 
+    if follow && reverse {
+        return Err(ClaudeManError::InvalidInput(
+            "--reverse cannot be combined with --follow".to_string(),
+        ));
+    }
+    if follow && page {
+        return Err(ClaudeManError::InvalidInput(
+            "--page cannot be combined with --follow".to_string(),
+        ));
+    }
+
     // Get the log file path
-    let log_dir = session_log_dir(&session_id);
+    let log_dir = registry.session_log_dir(&session_id);
     let log_path = log_dir.join("io.log");
 
     if !log_path.exists() {
@@ -181,30 +599,15 @@ pub async fn view_logs(
         ));
     }
 
-    // Open the log file
-    let mut file = File::open(&log_path)?;
-    let mut reader = BufReader::new(&mut file);
-
-    // Read all lines first
-    let mut all_lines = Vec::new();
-    let mut line = String::new();
-    while reader.read_line(&mut line)? > 0 {
-        all_lines.push(line.clone());
-        line.clear();
-    }
-
-    // Determine which lines to show
-    let start_idx = if lines == 0 || lines >= all_lines.len() {
-        0
-    } else {
-        all_lines.len() - lines
-    };
-
-    // Display the selected lines
-    for log_line in &all_lines[start_idx..] {
-        if let Ok(event) = serde_json::from_str::<IoEvent>(log_line.trim()) {
-            print_log_event(&event, &session_id);
-        }
+    // Display the selected lines, from every log segment
+    let (mut tail, lines) = LogTail::open(&log_dir)?;
+    let events = lines
+        .iter()
+        .filter_map(|log_line| serde_json::from_str::<IoEvent>(log_line.trim()).ok())
+        .collect();
+    show_log_events(selection.apply(events), &session_id, reverse, page)?;
+    if page {
+        return Ok(());
     }
 
     // If follow mode, keep reading new lines
@@ -213,9 +616,6 @@ pub async fn view_logs(
         println!("{}", output::info("Following log output (Ctrl+C to stop)..."));
         println!();
 
-        // Get current position
-        let mut pos = file.seek(SeekFrom::End(0))?;
-
         loop {
             // Check if session is still running
             if let Some(metadata) = registry.get_session(&session_id).await {
@@ -229,53 +629,332 @@ pub async fn view_logs(
             }
 
             // Try to read new lines
-            file.seek(SeekFrom::Start(pos))?;
-            let mut new_reader = BufReader::new(&file);
-            let mut new_line = String::new();
-
-            while new_reader.read_line(&mut new_line)? > 0 {
-                if let Ok(event) = serde_json::from_str::<IoEvent>(new_line.trim()) {
-                    print_log_event(&event, &session_id);
+            for new_line in tail.read_lines()? {
+                match serde_json::from_str::<IoEvent>(new_line.trim()) {
+                    Ok(event) if selection.matches(&event) => print_log_event(&event, &session_id),
+                    _ => {}
                 }
-                pos += new_line.len() as u64;
-                new_line.clear();
             }
 
             // Sleep briefly before checking again
-            sleep(Duration::from_millis(200)).await;
+            sleep(poll_interval.unwrap_or(DEFAULT_TAIL_POLL_INTERVAL)).await;
         }
     }
 
     Ok(())
 }
 
+/// Print log events oldest first (newest first with `reverse`), optionally through the pager
+pub fn show_log_events(
+    mut events: Vec<crate::core::logger::IoEvent>,
+    session_id: &SessionId,
+    reverse: bool,
+    page: bool,
+) -> Result<()> {
+    if reverse {
+        events.reverse();
+    }
+    if page {
+        let text: String = events
+            .iter()
+            .map(|event| format_log_event(event, session_id) + "\n")
+            .collect();
+        return pager::page(&text);
+    }
+    for event in &events {
+        print_log_event(event, session_id);
+    }
+    Ok(())
+}
+
+/// Show CPU, memory and runtime of running sessions, refreshed like `top`
+///
+/// Reads sessions from disk, so it works with or without a daemon. CPU% is
+/// averaged over the refresh interval, so the first view appears after one
+/// interval.
+///
+/// # Arguments
+///
+/// * `registry` - The session registry, whose sessions directory is read
+/// * `interval` - Time between refreshes
+/// * `once` - Print a single view instead of refreshing until interrupted
+pub async fn top(registry: Arc<SessionRegistry>, interval: Duration, once: bool) -> Result<()> {
+    use crate::core::usage::{self, UsageSample};
+    use std::collections::HashMap;
+    use std::io::IsTerminal;
+
+    let clear = !once && std::io::stdout().is_terminal();
+    let mut previous: Option<HashMap<u32, UsageSample>> = None;
+
+    loop {
+        let mut sessions: Vec<SessionMetadata> = registry.list_from_disk()?
+            .into_iter()
+            .filter(|session| session.is_active())
+            .collect();
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let samples: HashMap<u32, UsageSample> = sessions
+            .iter()
+            .filter_map(|session| session.pid)
+            .filter_map(|pid| usage::sample(pid).map(|sample| (pid, sample)))
+            .collect();
+
+        if let Some(previous) = &previous {
+            let rows: Vec<output::UsageRow> = sessions
+                .into_iter()
+                .map(|session| {
+                    let current = session.pid.and_then(|pid| samples.get(&pid));
+                    let earlier = session.pid.and_then(|pid| previous.get(&pid));
+                    output::UsageRow {
+                        cpu_percent: current.zip(earlier).map(|(now, then)| now.cpu_percent_since(then)),
+                        rss_bytes: current.map(|sample| sample.rss_bytes),
+                        session,
+                    }
+                })
+                .collect();
+
+            if clear {
+                print!("\x1b[2J\x1b[H");
+                println!(
+                    "{}",
+                    output::info(&format!("Refreshing every {}s (Ctrl+C to quit)", interval.as_secs_f64()))
+                );
+                println!();
+            }
+            output::print_usage_table(&rows);
+            if once {
+                return Ok(());
+            }
+            if !clear {
+                println!();
+            }
+        }
+
+        previous = Some(samples);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Print a session's conversation as a transcript
+///
+/// The same text `spawn --context-from` prepends to a task, without its size cap.
+pub fn print_transcript(registry: Arc<SessionRegistry>, session_id: SessionId) -> Result<()> {
+    use crate::core::logger::{conversation_transcript, read_events};
+
+    let log_dir = registry.session_log_dir(&session_id);
+    if !log_dir.join("io.log").exists() {
+        return Err(ClaudeManError::SessionNotFound(format!(
+            "Log file not found for session {}",
+            session_id
+        )));
+    }
+
+    match conversation_transcript(&session_id, &read_events(&log_dir)?, usize::MAX) {
+        Some(transcript) => print!("{}", transcript.text),
+        None => println!("{}", output::info(&format!("Session {} has no conversation yet", session_id))),
+    }
+
+    Ok(())
+}
+
+/// Report error rate and timing gaps for a session's log
+///
+/// # Arguments
+///
+/// * `registry` - The session registry
+/// * `session_id` - The ID of the session
+/// * `since` - Only count events this recent (None for the whole log)
+/// * `format` - Output format for the report
+pub fn log_stats(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    since: Option<std::time::Duration>,
+    format: OutputFormat,
+) -> Result<()> {
+    use crate::core::logger::{LogStats, SessionLogReader};
+
+    let log_dir = registry.session_log_dir(&session_id);
+    let log_path = log_dir.join("io.log");
+    if !log_path.exists() {
+        return Err(ClaudeManError::SessionNotFound(format!(
+            "Log file not found for session {}",
+            session_id
+        )));
+    }
+
+    let reader = SessionLogReader::open(&log_dir);
+    let events = match since {
+        Some(since) => {
+            let now = chrono::Utc::now();
+            let since = chrono::Duration::from_std(since)
+                .map_err(|_| ClaudeManError::InvalidInput("--since is too large".to_string()))?;
+            // A window reaching back past the earliest time covers the whole log
+            let start = now.checked_sub_signed(since).unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
+            reader.events_between(start, now)?
+        }
+        None => reader.collect::<Result<Vec<_>>>()?,
+    };
+    let stats = LogStats::from_events(&events);
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string(&stats)?),
+        OutputFormat::Text => output::print_log_stats(&session_id, &stats),
+    }
+
+    Ok(())
+}
+
+/// Check the sessions directory for leftovers and corrupt files
+///
+/// # Arguments
+///
+/// * `fix` - Repair what can be repaired instead of only reporting it
+/// * `format` - Output format for the report
+pub fn gc(fix: bool, format: OutputFormat) -> Result<()> {
+    let report = crate::core::gc::collect_garbage(
+        &crate::core::config::default_home_dir(),
+        &crate::core::logger::default_log_dir(),
+        fix,
+    )?;
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string(&report)?),
+        OutputFormat::Text => {
+            output::print_gc_report(&report);
+            if !fix && report.issues.iter().any(|issue| issue.is_fixable()) {
+                println!("Run with --fix to repair");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the JSON Schema of the daemon protocol
+pub fn print_protocol_schema() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&crate::daemon::protocol::protocol_schema())?);
+    Ok(())
+}
+
+/// Read the last `lines` lines of a log without reading the whole file
+///
+/// Reads backwards from the end in chunks until enough line breaks have
+/// been seen, so the cost depends on `lines` rather than the file size.
+/// Returns the lines and the file length they end at.
+fn read_last_lines(file: &mut std::fs::File, lines: usize) -> Result<(Vec<String>, u64)> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let end = file.seek(SeekFrom::End(0))?;
+    if lines == 0 {
+        return Ok((Vec::new(), end));
+    }
+
+    // One more line break than lines wanted marks where the first one starts
+    let mut start = end;
+    let mut buf: Vec<u8> = Vec::new();
+    while start > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= lines {
+        let chunk = TAIL_CHUNK_SIZE.min(start);
+        start -= chunk;
+        file.seek(SeekFrom::Start(start))?;
+        let mut chunk_buf = vec![0; chunk as usize];
+        file.read_exact(&mut chunk_buf)?;
+        chunk_buf.extend_from_slice(&buf);
+        buf = chunk_buf;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut all_lines: Vec<String> = text.split_inclusive('\n').map(str::to_string).collect();
+    if start > 0 && !all_lines.is_empty() {
+        // Starts mid-line
+        all_lines.remove(0);
+    }
+    let skip = all_lines.len().saturating_sub(lines);
+
+    Ok((all_lines.split_off(skip), end))
+}
+
+/// Select the last `lines` items (all of them when `lines` is 0)
+fn tail<T>(items: &[T], lines: usize) -> &[T] {
+    let start_idx = if lines == 0 || lines >= items.len() {
+        0
+    } else {
+        items.len() - lines
+    };
+    &items[start_idx..]
+}
+
+/// Print the end of a failed session's log inline
+///
+/// # Arguments
+///
+/// * `session_id` - The ID of the failed session
+/// * `log_dir` - The session's log directory
+/// * `lines` - Number of lines to show (0 for all)
+fn print_failure_tail(session_id: &SessionId, log_dir: &Path, lines: usize) -> Result<()> {
+    let events = crate::core::logger::read_events(log_dir)?;
+
+    println!();
+    println!("{}", output::info(&format!("Last lines of {} log:", session_id)));
+    for event in tail(&events, lines) {
+        print_log_event(event, session_id);
+    }
+
+    Ok(())
+}
+
 /// Print a log event to stdout
 fn print_log_event(event: &crate::core::logger::IoEvent, session_id: &SessionId) {
     use crate::core::logger::IoEventType;
 
+    let line = format_log_event(event, session_id);
     match event.event_type {
-        IoEventType::Output => {
-            println!("[{}] {}", session_id, event.content);
-        }
-        IoEventType::Error => {
-            eprintln!("[{} ERROR] {}", session_id, event.content);
-        }
-        IoEventType::Lifecycle => {
-            println!("{}", output::info(&format!("[{}] {}", session_id, event.content)));
-        }
-        IoEventType::Input => {
-            println!("{}", output::info(&format!("[{} INPUT] {}", session_id, event.content)));
-        }
+        IoEventType::Error => eprintln!("{}", line),
+        _ => println!("{}", line),
+    }
+}
+
+/// Format a log event as one display line
+fn format_log_event(event: &crate::core::logger::IoEvent, session_id: &SessionId) -> String {
+    use crate::core::logger::IoEventType;
+
+    match event.event_type {
+        IoEventType::Output => format!("[{}] {}", session_id, event.content),
+        IoEventType::Error => format!("[{} ERROR] {}", session_id, event.content),
+        IoEventType::Lifecycle => output::info(&format!("[{}] {}", session_id, event.content)),
+        IoEventType::Input => output::info(&format!("[{} INPUT] {}", session_id, event.content)),
     }
 }
 
+/// Keep only the sessions whose annotations match every filter
+pub fn filter_by_annotations(
+    sessions: Vec<crate::types::SessionMetadata>,
+    filters: &[(String, String)],
+) -> Vec<crate::types::SessionMetadata> {
+    sessions
+        .into_iter()
+        .filter(|session| session.matches_annotations(filters))
+        .collect()
+}
+
 /// Print a list of sessions (wrapper for daemon mode)
 ///
 /// # Arguments
 ///
 /// * `sessions` - A slice of session metadata
-pub fn print_sessions_list(sessions: &[crate::types::SessionMetadata]) {
-    output::print_sessions_table(sessions);
+/// * `format` - Render as a table, JSON or CSV
+/// * `show_task` - Add a TASK column to tables
+/// * `page` - Window of `sessions` to print; tables note how many were left out
+pub fn print_sessions_list(
+    sessions: &[crate::types::SessionMetadata],
+    format: ListFormat,
+    show_task: bool,
+    page: ListPage,
+) -> Result<()> {
+    output::print_sessions(page.slice(sessions), format, show_task)?;
+    if let Some(summary) = page.summary(sessions.len()).filter(|_| format.is_table()) {
+        println!("{}", output::info(&summary));
+    }
+    Ok(())
 }
 
 /// Print detailed session info (wrapper for daemon mode)
@@ -283,8 +962,45 @@ pub fn print_sessions_list(sessions: &[crate::types::SessionMetadata]) {
 /// # Arguments
 ///
 /// * `metadata` - The session metadata to print
-pub fn print_session_info(metadata: &crate::types::SessionMetadata) {
-    output::print_session_details(metadata);
+/// * `format` - Print the details as text or as the metadata JSON
+pub fn print_session_info(metadata: &crate::types::SessionMetadata, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(metadata)?),
+        OutputFormat::Text => output::print_session_details(metadata),
+    }
+
+    Ok(())
+}
+
+/// Resolve the text for the `input` command
+///
+/// Reads the whole file when `file` is given, preserving its newlines, so
+/// large or multi-line inputs need no shell escaping.
+///
+/// # Arguments
+///
+/// * `text` - Input text given on the command line
+/// * `file` - Path to a file whose contents are the input
+/// * `allow_empty` - Accept empty or blank input (e.g. to just submit a newline)
+pub fn resolve_input(text: Option<String>, file: Option<&Path>, allow_empty: bool) -> Result<String> {
+    let input = match (text, file) {
+        (_, Some(path)) => std::fs::read_to_string(path).map_err(|e| {
+            ClaudeManError::InvalidInput(format!(
+                "Cannot read input file {}: {}",
+                path.display(),
+                e
+            ))
+        }),
+        (Some(text), None) => Ok(text),
+        (None, None) => Err(ClaudeManError::InvalidInput(
+            "Must specify input text or --file".to_string(),
+        )),
+    }?;
+
+    if !allow_empty {
+        validate_non_empty(&input, "input")?;
+    }
+    Ok(input)
 }
 
 /// Attach to a running session (view live output from beginning)
@@ -293,14 +1009,21 @@ pub fn print_session_info(metadata: &crate::types::SessionMetadata) {
 ///
 /// * `registry` - The session registry
 /// * `session_id` - The ID of the session to attach to
+/// * `poll_interval` - How often to check for new lines (default 200ms)
+/// * `keep_open` - After the session ends, wait for Enter before returning
+/// * `from_end` - Only show this many events of history, read from the end of the log
+/// * `approvals` - Ask the user to answer approval prompts this detector recognizes
 pub async fn attach_session(
     registry: Arc<SessionRegistry>,
     session_id: SessionId,
+    poll_interval: Option<Duration>,
+    keep_open: bool,
+    from_end: Option<usize>,
+    approvals: Option<PromptDetector>,
 ) -> Result<()> {
-    use crate::core::logger::{session_log_dir, IoEvent};
+    use crate::core::logger::{rotated_log_paths, IoEvent, LogTail};
     use std::fs::File;
-    use std::io::{BufRead, BufReader, Seek, SeekFrom};
-    use tokio::time::{sleep, Duration};
+    use tokio::time::sleep;
 
     info!("Attaching to session {}", session_id);
 
@@ -315,7 +1038,7 @@ pub async fn attach_session(
     println!();
 
     // Get the log file path
-    let log_dir = session_log_dir(&session_id);
+    let log_dir = registry.session_log_dir(&session_id);
     let log_path = log_dir.join("io.log");
 
     if !log_path.exists() {
@@ -324,53 +1047,298 @@ pub async fn attach_session(
         ));
     }
 
-    // Open the log file
-    let mut file = File::open(&log_path)?;
-    let mut reader = BufReader::new(&mut file);
+    // Show the history: only the tail with --from-end, otherwise all of it,
+    // then follow io.log from where the history ends
+    let (mut tail, history) = match from_end {
+        Some(lines) => {
+            let mut file = File::open(&log_path)?;
+            let (mut history, end) = read_last_lines(&mut file, lines)?;
+            // Top up from rotated segments, newest first
+            for segment in rotated_log_paths(&log_dir).iter().rev() {
+                if history.len() >= lines {
+                    break;
+                }
+                let (mut older, _) = read_last_lines(&mut File::open(segment)?, lines - history.len())?;
+                older.append(&mut history);
+                history = older;
+            }
+            (LogTail::from_position(&log_dir, file, end), history)
+        }
+        None => LogTail::open(&log_dir)?,
+    };
 
-    // Read all existing lines first
-    let mut line = String::new();
-    while reader.read_line(&mut line)? > 0 {
-        if let Ok(event) = serde_json::from_str::<IoEvent>(line.trim()) {
+    // The latest event, if it is a prompt still waiting for an answer
+    let mut pending_prompt = None;
+    for log_line in history {
+        if let Ok(event) = serde_json::from_str::<IoEvent>(log_line.trim()) {
             print_log_event(&event, &session_id);
+            pending_prompt = approval_prompt(approvals.as_ref(), &event);
         }
-        line.clear();
     }
 
-    // Get current position and start following
-    let mut pos = file.seek(SeekFrom::End(0))?;
-
     loop {
         // Check if session is still running
-        if let Some(metadata) = registry.get_session(&session_id).await {
-            if !metadata.is_active() {
-                println!();
-                println!("{}", output::info(&format!("Session ended with status: {}", metadata.status)));
-                break;
+        let ended = match registry.get_session(&session_id).await {
+            Some(metadata) if !metadata.is_active() => {
+                Some(format!("Session ended with status: {}", metadata.status))
             }
-        } else {
+            Some(_) => None,
+            None => Some("Session not found in registry".to_string()),
+        };
+
+        // Try to read new lines (including the final ones once the session ended)
+        for new_line in tail.read_lines()? {
+            if let Ok(event) = serde_json::from_str::<IoEvent>(new_line.trim()) {
+                print_log_event(&event, &session_id);
+                pending_prompt = approval_prompt(approvals.as_ref(), &event);
+            }
+        }
+
+        if let Some(message) = ended {
             println!();
-            println!("{}", output::info("Session not found in registry"));
+            println!("{}", output::info(&message));
             break;
         }
 
-        // Try to read new lines
-        file.seek(SeekFrom::Start(pos))?;
-        let mut new_reader = BufReader::new(&file);
-        let mut new_line = String::new();
+        // The session is blocked on a prompt; let the user answer it
+        if let Some(prompt) = pending_prompt.take() {
+            answer_approval_prompt(&registry, &session_id, &prompt).await;
+        }
 
-        while new_reader.read_line(&mut new_line)? > 0 {
-            if let Ok(event) = serde_json::from_str::<IoEvent>(new_line.trim()) {
-                print_log_event(&event, &session_id);
+        // Sleep briefly before checking again
+        sleep(poll_interval.unwrap_or(DEFAULT_TAIL_POLL_INTERVAL)).await;
+    }
+
+    if keep_open {
+        wait_for_enter().await?;
+    }
+
+    Ok(())
+}
+
+/// The prompt text of an output event that asks for approval
+fn approval_prompt(detector: Option<&PromptDetector>, event: &crate::core::logger::IoEvent) -> Option<String> {
+    use crate::core::logger::IoEventType;
+
+    let detector = detector?;
+    (matches!(event.event_type, IoEventType::Output) && detector.is_prompt(&event.content))
+        .then(|| event.content.clone())
+}
+
+/// Put a session's approval prompts to the user until the task is aborted
+async fn bridge_approval_prompts(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    mut prompts: broadcast::Receiver<ApprovalPrompt>,
+) {
+    loop {
+        match prompts.recv().await {
+            Ok(prompt) if prompt.session_id == session_id => {
+                answer_approval_prompt(&registry, &session_id, &prompt.line).await;
             }
-            pos += new_line.len() as u64;
-            new_line.clear();
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Missed {} approval prompts from {}", missed, session_id);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
         }
+    }
+}
 
-        // Sleep briefly before checking again
-        sleep(Duration::from_millis(200)).await;
+/// Ask the user to approve a prompt and send their y/n answer to the session
+///
+/// Does nothing when stdin isn't a terminal. The answer goes to the session
+/// through this process's registry, or through the daemon if it owns the session.
+async fn answer_approval_prompt(registry: &SessionRegistry, session_id: &SessionId, prompt: &str) {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return;
     }
 
+    println!("{}", output::info(&format!("[{}] Approval requested: {}", session_id, prompt)));
+    print!("Approve? [y/n] ");
+    let _ = std::io::stdout().flush();
+    let answer = tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).map(|_| line)
+    })
+    .await;
+    let answer = match answer {
+        Ok(Ok(line)) if line.trim().to_lowercase().starts_with('y') => "y",
+        Ok(Ok(_)) => "n",
+        _ => {
+            warn!("Could not read an answer for {}", session_id);
+            return;
+        }
+    };
+
+    match send_approval_answer(registry, session_id, answer).await {
+        Ok(()) => println!("{}", output::info(&format!("Sent '{}' to {}", answer, session_id))),
+        Err(e) => eprintln!("{}", output::error(&format!("Could not answer {}: {}", session_id, e))),
+    }
+}
+
+/// Deliver an approval answer as session input
+async fn send_approval_answer(registry: &SessionRegistry, session_id: &SessionId, answer: &str) -> Result<()> {
+    use crate::core::process::InputNewline;
+
+    let local = registry
+        .send_input(session_id, answer.to_string(), InputNewline::Lf, false)
+        .await;
+    if local.is_ok() {
+        return local;
+    }
+
+    // The session belongs to the daemon rather than this process
+    let client = DaemonClient::default();
+    if !client.is_running().await {
+        return local;
+    }
+    match client
+        .input(session_id.to_string(), answer.to_string(), InputNewline::Lf, false)
+        .await?
+    {
+        DaemonResponse::Error { message } => Err(ClaudeManError::Other(message)),
+        _ => Ok(()),
+    }
+}
+
+/// Block until the user presses Enter (no-op when stdin isn't a terminal)
+async fn wait_for_enter() -> Result<()> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+
+    println!("{}", output::info("Press Enter to close"));
+    tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).map(|_| ())
+    })
+    .await
+    .map_err(|e| ClaudeManError::Other(format!("Failed to wait for input: {}", e)))??;
+
+    Ok(())
+}
+
+/// Print version information
+///
+/// # Arguments
+///
+/// * `full` - Include build, protocol, Claude CLI, and platform details
+pub fn print_version(full: bool) {
+    use crate::core::auth;
+    use crate::daemon::protocol::PROTOCOL_VERSION;
+
+    println!("claude-man {}", env!("CARGO_PKG_VERSION"));
+
+    if !full {
+        return;
+    }
+
+    let claude_path = auth::resolve_claude_cli_path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "not found".to_string());
+    let claude_version = auth::claude_cli_version().unwrap_or_else(|| "unavailable".to_string());
+
+    println!("  Commit:         {}", env!("CLAUDE_MAN_GIT_COMMIT"));
+    println!("  Protocol:       v{}", PROTOCOL_VERSION);
+    println!("  Claude CLI:     {}", claude_path);
+    println!("  Claude version: {}", claude_version);
+    println!("  Platform:       {}/{}", std::env::consts::OS, std::env::consts::ARCH);
+}
+
+/// Show the role context and hook files a spawn would generate, without launching
+///
+/// The files are written to a temporary directory, printed, and removed.
+///
+/// # Arguments
+///
+/// * `role` - The role to generate files for
+pub fn validate_session_files(role: Role) -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("claude-man-validate-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir)?;
+    let result = print_session_files(role, &dir);
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        warn!("Failed to remove {}: {}", dir.display(), e);
+    }
+    result
+}
+
+fn print_session_files(role: Role, dir: &Path) -> Result<()> {
+    let files = SessionRegistry::write_session_files(role, dir)?;
+
+    if !files.iter().any(|file| file.ends_with("role-context.md")) {
+        println!(
+            "{}",
+            output::info(&format!(
+                "No role context for {}: add ROLES/{}.md to give its sessions instructions",
+                role,
+                role.to_string().to_uppercase()
+            ))
+        );
+        println!();
+    }
+
+    for file in &files {
+        let name = file.strip_prefix(dir).unwrap_or(file);
+        println!("==> {} <==", name.display());
+        print!("{}", std::fs::read_to_string(file)?);
+        println!();
+    }
+
+    // The auto-approval hook is a bash script
+    #[cfg(windows)]
+    {
+        let has_bash = std::env::var_os("PATH")
+            .map(|path| std::env::split_paths(&path).any(|dir| dir.join("bash.exe").is_file()))
+            .unwrap_or(false);
+        if !has_bash {
+            println!(
+                "{}",
+                output::error("bash.exe is not on PATH: Claude can't run the pre-tool-use hook on this machine")
+            );
+        }
+    }
+
+    println!(
+        "{}",
+        output::success(&format!("Generated {} file(s) for {} without errors", files.len(), role))
+    );
+    Ok(())
+}
+
+/// Back up all sessions to a tarball
+///
+/// # Arguments
+///
+/// * `registry` - The session registry
+/// * `file` - Path of the archive to write
+pub async fn backup_sessions(registry: Arc<SessionRegistry>, file: &Path) -> Result<()> {
+    info!("Executing backup command to {}", file.display());
+
+    let count = registry.export_all(file).await?;
+
+    println!("{}", output::success(&format!("Backed up {} session(s) to {}", count, file.display())));
+
+    Ok(())
+}
+
+/// Restore sessions from a tarball created by `backup`
+///
+/// # Arguments
+///
+/// * `registry` - The session registry
+/// * `file` - Path of the archive to restore
+pub async fn restore_sessions(registry: Arc<SessionRegistry>, file: &Path) -> Result<()> {
+    info!("Executing restore command from {}", file.display());
+
+    let count = registry.import_all(file).await?;
+
+    println!("{}", output::success(&format!("Restored {} session(s) from {}", count, file.display())));
+
     Ok(())
 }
 
@@ -378,10 +1346,60 @@ pub async fn attach_session(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolve_input_from_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("answer.txt");
+        std::fs::write(&path, "line one\nline two\n").unwrap();
+
+        let input = resolve_input(None, Some(&path), false).unwrap();
+        assert_eq!(input, "line one\nline two\n");
+
+        let missing = temp_dir.path().join("missing.txt");
+        let err = resolve_input(None, Some(&missing), false).unwrap_err();
+        assert!(err.to_string().contains("missing.txt"));
+
+        assert_eq!(resolve_input(Some("hi".to_string()), None, false).unwrap(), "hi");
+    }
+
+    #[test]
+    fn test_resolve_input_rejects_empty() {
+        let err = resolve_input(Some("  ".to_string()), None, false).unwrap_err();
+        assert!(matches!(err, ClaudeManError::InvalidInput(_)));
+        assert_eq!(resolve_input(Some(String::new()), None, true).unwrap(), "");
+    }
+
+    #[test]
+    fn test_read_last_lines() {
+        use std::io::Write;
+
+        let mut file = tempfile::tempfile().unwrap();
+        let lines: Vec<String> = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        file.write_all(lines.concat().as_bytes()).unwrap();
+        let len = lines.concat().len() as u64;
+
+        // Spans several chunks
+        let (last, end) = read_last_lines(&mut file, 3000).unwrap();
+        assert_eq!(last, lines[2000..]);
+        assert_eq!(end, len);
+
+        assert_eq!(read_last_lines(&mut file, 2).unwrap().0, ["line 4998\n", "line 4999\n"]);
+        assert_eq!(read_last_lines(&mut file, 10_000).unwrap().0, lines);
+        assert!(read_last_lines(&mut file, 0).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_tail() {
+        let items = [1, 2, 3, 4, 5];
+        assert_eq!(tail(&items, 2), &[4, 5]);
+        assert_eq!(tail(&items, 0), &items);
+        assert_eq!(tail(&items, 10), &items);
+    }
+
     #[tokio::test]
     async fn test_list_sessions_empty() {
         let registry = Arc::new(SessionRegistry::new());
-        let result = list_sessions(registry).await;
+        let result = list_sessions(registry, &[], ListFormat::Table, false, ListSort::Started, false, ListPage::default()).await;
         assert!(result.is_ok());
     }
 
@@ -390,14 +1408,100 @@ mod tests {
         let registry = Arc::new(SessionRegistry::new());
         let session_id = SessionId::from_string("INVALID-999".to_string());
 
-        let result = stop_session(registry, session_id).await;
+        let result = stop_session(registry, session_id, false, OutputFormat::Text).await;
         assert!(result.is_err());
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_approval_answer_reaches_session_stdin() {
+        use crate::core::logger::read_events;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = "read answer; echo got-$answer";
+        let registry = SessionRegistry::new()
+            .with_sessions_dir(dir.path().to_path_buf())
+            .with_wrapper(vec!["sh".to_string(), "-c".to_string(), script.to_string()]);
+        let session_id = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap();
+
+        send_approval_answer(&registry, &session_id, "y").await.unwrap();
+        let log_dir = registry.get_session(&session_id).await.unwrap().log_dir;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !read_events(&log_dir).unwrap_or_default().iter().any(|event| event.content == "got-y") {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("the answer never reached the session");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_input_file_reaches_session_stdin() {
+        use crate::core::logger::read_events;
+        use crate::core::process::InputNewline;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("answer.txt");
+        std::fs::write(&path, "one\ntwo").unwrap();
+        let script = "read a; read b; echo \"$a+$b\"";
+        let registry = SessionRegistry::new()
+            .with_sessions_dir(dir.path().join("sessions"))
+            .with_wrapper(vec!["sh".to_string(), "-c".to_string(), script.to_string()]);
+        let session_id = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap();
+
+        let text = resolve_input(None, Some(&path), false).unwrap();
+        registry.send_input(&session_id, text, InputNewline::Lf, false).await.unwrap();
+        let log_dir = registry.get_session(&session_id).await.unwrap().log_dir;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !read_events(&log_dir).unwrap_or_default().iter().any(|event| event.content == "one+two") {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("the file's input never reached the session");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_for_daemon_session_without_deadline() {
+        use crate::daemon::server::DaemonServer;
+        use crate::daemon::transport::{ClientListener, DaemonTransport};
+        use tokio::sync::RwLock;
+
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Arc::new(
+            SessionRegistry::new()
+                .with_sessions_dir(dir.path().join("sessions"))
+                .with_wrapper(vec!["sh".to_string(), "-c".to_string(), "exit 0".to_string()]),
+        );
+        let session_id = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap();
+
+        let transport = DaemonTransport::Unix(dir.path().join("claude-man.sock"));
+        let listener = transport.bind().await.unwrap();
+        let server = tokio::spawn(async move {
+            let shutdown = Arc::new(RwLock::new(false));
+            loop {
+                let (stream, peer) = listener.accept().await.unwrap();
+                DaemonServer::handle_client(stream, peer, registry.clone(), shutdown.clone(), None, None)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        // A timeout past the end of time waits as long as it takes
+        let client = DaemonClient::new(transport);
+        let waited = wait_for_daemon_session(&client, &session_id, Duration::MAX, 0, Some(Duration::from_millis(20)));
+        assert!(tokio::time::timeout(Duration::from_secs(5), waited).await.unwrap().unwrap());
+
+        server.abort();
+        let _ = server.await;
+    }
+
     #[tokio::test]
     async fn test_stop_all_sessions_empty() {
         let registry = Arc::new(SessionRegistry::new());
-        let result = stop_all_sessions(registry).await;
+        let result = stop_all_sessions(registry, true, false, OutputFormat::Text).await;
         assert!(result.is_ok());
     }
 }
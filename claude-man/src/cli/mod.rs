@@ -4,6 +4,7 @@
 
 pub mod commands;
 pub mod output;
+pub mod pager;
 
 // Re-export commonly used items
 pub use commands::*;
@@ -4,7 +4,312 @@
 //! success/error messages, and status displays.
 
 use chrono::{DateTime, Utc};
-use crate::types::session::SessionMetadata;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::core::gc::GcReport;
+use crate::core::logger::LogStats;
+use crate::types::session::{SessionId, SessionMetadata, SessionStatus};
+
+/// Whether status colors are written to the terminal
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// When to color output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+
+    /// Always color
+    Always,
+
+    /// Never color
+    Never,
+}
+
+/// Enable or disable colored output for the rest of the process
+pub fn init_color(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+        }
+    };
+    COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// ANSI color code used for a session status
+pub fn status_color(status: SessionStatus) -> &'static str {
+    match status {
+        SessionStatus::Created => "0",
+        SessionStatus::Queued => "34",
+        SessionStatus::Running => "33",
+        SessionStatus::Paused => "35",
+        SessionStatus::Completed => "32",
+        SessionStatus::Failed => "31",
+        SessionStatus::Stopped => "90",
+    }
+}
+
+/// Color `text` with the color of `status` (unchanged when color is off)
+pub fn paint_status(status: SessionStatus, text: &str) -> String {
+    if COLOR_ENABLED.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", status_color(status), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Print the status color key
+pub fn print_status_legend() {
+    let key: Vec<String> = LIFECYCLE
+        .iter()
+        .map(|status| paint_status(*status, &status.to_string()))
+        .collect();
+    println!("Legend: {}", key.join("  "));
+}
+
+/// Output format for command results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+
+    /// Machine-readable JSON
+    Json,
+}
+
+/// How `list` renders sessions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// Aligned, colored table with fixed column widths
+    #[default]
+    #[value(alias = "compact")]
+    Table,
+
+    /// Table with columns sized to fit their longest value
+    Wide,
+
+    /// Array of session metadata objects
+    Json,
+
+    /// Spreadsheet-friendly CSV with a header row
+    Csv,
+}
+
+impl ListFormat {
+    /// Whether sessions are shown as a table
+    pub fn is_table(self) -> bool {
+        matches!(self, ListFormat::Table | ListFormat::Wide)
+    }
+}
+
+/// Field `list` orders sessions by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListSort {
+    /// Session ID, numbering in numeric order
+    Id,
+
+    /// Role name
+    Role,
+
+    /// Status, in lifecycle order (created first, stopped last)
+    Status,
+
+    /// Start time; sessions that haven't started come last
+    #[default]
+    Started,
+
+    /// Time run so far (or in total, once ended); unstarted sessions first
+    Duration,
+}
+
+impl ListSort {
+    /// Compare two sessions by this field, breaking ties by ID
+    pub fn compare(self, a: &SessionMetadata, b: &SessionMetadata, now: DateTime<Utc>) -> std::cmp::Ordering {
+        let by_field = match self {
+            ListSort::Id => std::cmp::Ordering::Equal,
+            ListSort::Role => a.role.to_string().cmp(&b.role.to_string()),
+            ListSort::Status => status_rank(a.status).cmp(&status_rank(b.status)),
+            ListSort::Started => (a.started_at.is_none(), a.started_at).cmp(&(b.started_at.is_none(), b.started_at)),
+            ListSort::Duration => run_time(a, now).cmp(&run_time(b, now)),
+        };
+        by_field.then_with(|| id_key(&a.id).cmp(&id_key(&b.id)))
+    }
+}
+
+/// Sort sessions for `list`, last to first with `reverse`
+pub fn sort_sessions(sessions: &mut [SessionMetadata], sort: ListSort, reverse: bool) {
+    let now = Utc::now();
+    sessions.sort_by(|a, b| {
+        let order = sort.compare(a, b, now);
+        if reverse { order.reverse() } else { order }
+    });
+}
+
+/// Window of the sorted session list that `list` shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListPage {
+    /// Sessions to skip from the start
+    pub offset: usize,
+
+    /// Most sessions to show (all when `None`)
+    pub limit: Option<usize>,
+}
+
+impl ListPage {
+    /// The sessions on this page
+    pub fn slice<T>(self, items: &[T]) -> &[T] {
+        let (start, end) = self.bounds(items.len());
+        &items[start..end]
+    }
+
+    /// Line reporting shown vs total sessions, when the page leaves some out
+    pub fn summary(self, total: usize) -> Option<String> {
+        let (start, end) = self.bounds(total);
+        if end - start == total {
+            return None;
+        }
+        Some(if start == end {
+            format!("Showing 0 of {} sessions", total)
+        } else {
+            format!("Showing {}-{} of {} sessions", start + 1, end, total)
+        })
+    }
+
+    /// Index range of the page within `total` sessions
+    fn bounds(self, total: usize) -> (usize, usize) {
+        let start = self.offset.min(total);
+        let end = match self.limit {
+            Some(limit) => start.saturating_add(limit).min(total),
+            None => total,
+        };
+        (start, end)
+    }
+}
+
+/// Position of a status in the session lifecycle
+fn status_rank(status: SessionStatus) -> usize {
+    LIFECYCLE.iter().position(|s| *s == status).unwrap_or(LIFECYCLE.len())
+}
+
+/// Sort key putting DEV-2 before DEV-10
+fn id_key(id: &SessionId) -> (Option<&str>, &str, Option<u32>, &str) {
+    (id.project(), id.role_prefix(), id.sequence(), id.as_str())
+}
+
+/// Session statuses in lifecycle order
+const LIFECYCLE: [SessionStatus; 7] = [
+    SessionStatus::Created,
+    SessionStatus::Queued,
+    SessionStatus::Running,
+    SessionStatus::Paused,
+    SessionStatus::Completed,
+    SessionStatus::Failed,
+    SessionStatus::Stopped,
+];
+
+/// Header of the sessions table; TASK is only shown with `--show-task`
+const TABLE_COLUMNS: [&str; 6] = ["SESSION-ID", "ROLE", "STATUS", "STARTED", "ELAPSED", "TASK"];
+
+/// Column widths of the fixed-width table
+const COMPACT_WIDTHS: [usize; 6] = [15, 12, 12, 23, 10, TASK_COLUMN_CHARS];
+
+/// Tasks are cut to this many characters in the TASK column
+const TASK_COLUMN_CHARS: usize = 40;
+
+/// Header row of `list --format csv`
+const CSV_COLUMNS: [&str; 9] = [
+    "id",
+    "role",
+    "status",
+    "created_at",
+    "started_at",
+    "ended_at",
+    "duration_secs",
+    "pid",
+    "task",
+];
+
+/// Structured outcome of a mutating command (stop, resume, input)
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionResult {
+    /// Whether the action succeeded
+    pub ok: bool,
+
+    /// What was done (e.g. "stopped", "resumed", "input_sent")
+    pub action: String,
+
+    /// Session the action targeted, if a single one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<SessionId>,
+
+    /// Sessions the action targeted, for bulk actions
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub session_ids: Vec<SessionId>,
+
+    /// Human-readable summary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Error description when `ok` is false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ActionResult {
+    /// Create a successful result
+    pub fn success(action: &str, session_id: Option<SessionId>, message: String) -> Self {
+        Self {
+            ok: true,
+            action: action.to_string(),
+            session_id,
+            session_ids: Vec::new(),
+            message: Some(message),
+            error: None,
+        }
+    }
+
+    /// Create a failed result
+    pub fn failure(action: &str, session_id: Option<SessionId>, error: String) -> Self {
+        Self {
+            ok: false,
+            action: action.to_string(),
+            session_id,
+            session_ids: Vec::new(),
+            message: None,
+            error: Some(error),
+        }
+    }
+
+    /// Attach the list of sessions a bulk action covered
+    pub fn with_session_ids(mut self, session_ids: Vec<SessionId>) -> Self {
+        self.session_ids = session_ids;
+        self
+    }
+}
+
+/// Print an action result in the requested format
+///
+/// Text successes go to stdout and failures to stderr; JSON always goes to stdout.
+pub fn print_action_result(format: OutputFormat, result: &ActionResult) {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string(result).unwrap_or_default());
+        }
+        OutputFormat::Text => {
+            if result.ok {
+                println!("{}", success(result.message.as_deref().unwrap_or(&result.action)));
+            } else {
+                eprintln!("Error: {}", result.error.as_deref().unwrap_or("unknown error"));
+            }
+        }
+    }
+}
 
 /// Format a success message with a checkmark
 pub fn success(message: &str) -> String {
@@ -49,39 +354,402 @@ pub fn format_duration(duration: &chrono::Duration) -> String {
 }
 
 /// Print a table of sessions
-pub fn print_sessions_table(sessions: &[SessionMetadata]) {
+///
+/// With `wide`, columns are sized to fit their longest value instead of
+/// using fixed widths. With `show_task`, a TASK column is added.
+pub fn print_sessions_table(sessions: &[SessionMetadata], wide: bool, show_task: bool) {
     if sessions.is_empty() {
         println!("{}", info("No active sessions"));
         return;
     }
 
+    let columns = if show_task { TABLE_COLUMNS.len() } else { TABLE_COLUMNS.len() - 1 };
+    let header = &TABLE_COLUMNS[..columns];
+    let now = Utc::now();
+    let rows: Vec<Vec<String>> = sessions
+        .iter()
+        .map(|session| {
+            let mut row = session_row(session, now);
+            row.truncate(columns);
+            row
+        })
+        .collect();
+    let widths = if wide {
+        fitted_widths(header, &rows)
+    } else {
+        COMPACT_WIDTHS[..columns].to_vec()
+    };
+
     // Header
-    println!("{:<15} {:<12} {:<12} {:<20}", "SESSION-ID", "ROLE", "STATUS", "STARTED");
-    println!("{}", "-".repeat(60));
+    let header: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+    println!("{}", table_line(&header, &widths, None));
+    println!("{}", "-".repeat(widths.iter().sum::<usize>() + widths.len() - 1));
 
     // Rows
+    for (session, row) in sessions.iter().zip(&rows) {
+        println!("{}", table_line(row, &widths, Some(session.status)));
+    }
+}
+
+/// Cells of a session's table row, in [`TABLE_COLUMNS`] order
+fn session_row(session: &SessionMetadata, now: DateTime<Utc>) -> Vec<String> {
+    let started = session
+        .started_at
+        .as_ref()
+        .map(format_timestamp)
+        .unwrap_or_else(|| "Not started".to_string());
+
+    vec![
+        session.id.as_str().to_string(),
+        session.role.to_string(),
+        session.status.to_string(),
+        started,
+        elapsed(session, now),
+        task_cell(&session.task),
+    ]
+}
+
+/// First line of a task, cut to [`TASK_COLUMN_CHARS`] with an ellipsis
+fn task_cell(task: &str) -> String {
+    let line = task.lines().next().unwrap_or_default();
+    if line.chars().count() <= TASK_COLUMN_CHARS && !task.trim_end().contains('\n') {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(TASK_COLUMN_CHARS - 1).collect();
+    format!("{}…", cut)
+}
+
+/// Widths that fit the header and every value of each column
+fn fitted_widths(header: &[&str], rows: &[Vec<String>]) -> Vec<usize> {
+    header
+        .iter()
+        .enumerate()
+        .map(|(column, name)| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain(std::iter::once(name.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Pad cells to their column widths; ELAPSED is right-aligned and the
+/// STATUS cell is colored when the row's status is given
+fn table_line(cells: &[String], widths: &[usize], status: Option<SessionStatus>) -> String {
+    let last = cells.len() - 1;
+    cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(column, (cell, &width))| {
+            let padding = " ".repeat(width.saturating_sub(cell.chars().count()));
+            match (TABLE_COLUMNS[column], status) {
+                ("ELAPSED", _) => format!("{}{}", padding, cell),
+                ("STATUS", Some(status)) => paint_status(status, &format!("{}{}", cell, padding)),
+                _ if column == last => cell.clone(),
+                _ => format!("{}{}", cell, padding),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How long a session has run: up to `now` while running, its duration once
+/// finished, `-` if it never started
+fn elapsed(session: &SessionMetadata, now: DateTime<Utc>) -> String {
+    run_time(session, now).map(|duration| format_duration(&duration)).unwrap_or_else(|| "-".to_string())
+}
+
+/// How long a session has run so far, or ran in total once ended
+fn run_time(session: &SessionMetadata, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    match session.started_at {
+        Some(started) if session.is_active() => Some(now - started),
+        _ => session.duration(),
+    }
+}
+
+/// Print sessions as a tree, children indented under their parents
+pub fn print_session_tree(sessions: &[SessionMetadata]) {
+    if sessions.is_empty() {
+        println!("{}", info("No active sessions"));
+        return;
+    }
+
+    for line in session_tree_lines(sessions) {
+        println!("{}", line);
+    }
+}
+
+/// Lines of the session tree, with box-drawing indentation
+///
+/// Sessions whose parent isn't in `sessions` are shown as roots. Siblings
+/// are ordered by creation time.
+fn session_tree_lines(sessions: &[SessionMetadata]) -> Vec<String> {
+    let ids: HashSet<&SessionId> = sessions.iter().map(|session| &session.id).collect();
+    let mut children: HashMap<Option<&SessionId>, Vec<&SessionMetadata>> = HashMap::new();
     for session in sessions {
-        let started = session
+        let parent = session.parent_id.as_ref().filter(|parent| ids.contains(parent));
+        children.entry(parent).or_default().push(session);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by(|a, b| (a.created_at, a.id.as_str()).cmp(&(b.created_at, b.id.as_str())));
+    }
+
+    let mut lines = Vec::new();
+    for root in children.get(&None).into_iter().flatten() {
+        lines.push(tree_node(root));
+        push_subtree(&children, &root.id, "", &mut lines);
+    }
+    lines
+}
+
+/// Append the lines for the children of `parent`, recursively
+fn push_subtree(
+    children: &HashMap<Option<&SessionId>, Vec<&SessionMetadata>>,
+    parent: &SessionId,
+    prefix: &str,
+    lines: &mut Vec<String>,
+) {
+    let Some(siblings) = children.get(&Some(parent)) else {
+        return;
+    };
+
+    for (index, child) in siblings.iter().enumerate() {
+        let last = index + 1 == siblings.len();
+        let (branch, indent) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        lines.push(format!("{}{}{}", prefix, branch, tree_node(child)));
+        push_subtree(children, &child.id, &format!("{}{}", prefix, indent), lines);
+    }
+}
+
+fn tree_node(session: &SessionMetadata) -> String {
+    format!(
+        "{} [{}] {}",
+        session.id,
+        session.role,
+        paint_status(session.status, &session.status.to_string())
+    )
+}
+
+/// One row of the `top` view
+#[derive(Debug, Clone)]
+pub struct UsageRow {
+    /// The session the process belongs to
+    pub session: SessionMetadata,
+
+    /// CPU use over the last refresh interval (100 = one core), if known
+    pub cpu_percent: Option<f64>,
+
+    /// Resident memory, if known
+    pub rss_bytes: Option<u64>,
+}
+
+/// Print per-session resource usage for `top`
+pub fn print_usage_table(rows: &[UsageRow]) {
+    if rows.is_empty() {
+        println!("{}", info("No running sessions"));
+        return;
+    }
+
+    println!(
+        "{:<15} {:<12} {:<12} {:>8} {:>7} {:>10} {:>10}",
+        "SESSION-ID", "ROLE", "STATUS", "PID", "CPU%", "RSS", "RUNTIME"
+    );
+    println!("{}", "-".repeat(78));
+
+    let now = Utc::now();
+    for row in rows {
+        let session = &row.session;
+        let dash = || "-".to_string();
+        let pid = session.pid.map(|pid| pid.to_string()).unwrap_or_else(dash);
+        let cpu = row.cpu_percent.map(|cpu| format!("{:.1}", cpu)).unwrap_or_else(dash);
+        let rss = row.rss_bytes.map(format_bytes).unwrap_or_else(dash);
+        let runtime = session
             .started_at
-            .as_ref()
-            .map(format_timestamp)
-            .unwrap_or_else(|| "Not started".to_string());
+            .map(|started| format_duration(&(now - started)))
+            .unwrap_or_else(dash);
+
+        println!(
+            "{:<15} {:<12} {} {:>8} {:>7} {:>10} {:>10}",
+            session.id.as_str(),
+            session.role.to_string(),
+            paint_status(session.status, &format!("{:<12}", session.status.to_string())),
+            pid,
+            cpu,
+            rss,
+            runtime
+        );
+    }
+}
+
+/// Format a byte count with a binary unit, e.g. `512.0 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Print sessions in the chosen `list` format
+///
+/// `show_task` adds a TASK column to tables.
+pub fn print_sessions(
+    sessions: &[SessionMetadata],
+    format: ListFormat,
+    show_task: bool,
+) -> crate::types::error::Result<()> {
+    match format {
+        ListFormat::Table => print_sessions_table(sessions, false, show_task),
+        ListFormat::Wide => print_sessions_table(sessions, true, show_task),
+        ListFormat::Json => println!("{}", sessions_json(sessions)?),
+        ListFormat::Csv => print!("{}", sessions_csv(sessions)),
+    }
+
+    Ok(())
+}
+
+/// Render sessions as a pretty-printed JSON array (`[]` when there are none)
+///
+/// Timestamps are RFC 3339.
+pub fn sessions_json(sessions: &[SessionMetadata]) -> crate::types::error::Result<String> {
+    Ok(serde_json::to_string_pretty(sessions)?)
+}
+
+/// Render sessions as CSV, one row per session after a header row
+///
+/// Timestamps are RFC 3339; unset values are empty fields.
+pub fn sessions_csv(sessions: &[SessionMetadata]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push('\n');
+
+    for session in sessions {
+        let timestamp = |dt: Option<&DateTime<Utc>>| dt.map(|dt| dt.to_rfc3339()).unwrap_or_default();
+        let row = [
+            session.id.to_string(),
+            session.role.to_string(),
+            session.status.to_string(),
+            session.created_at.to_rfc3339(),
+            timestamp(session.started_at.as_ref()),
+            timestamp(session.ended_at.as_ref()),
+            session
+                .duration()
+                .map(|d| d.num_seconds().to_string())
+                .unwrap_or_default(),
+            session.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            session.task.clone(),
+        ];
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Quote a CSV field if it contains a comma, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Print the sessions a stop command would terminate
+pub fn print_stop_preview(sessions: &[SessionMetadata]) {
+    println!("{:<15} {:<12} {:<8} {:<12}", "SESSION-ID", "ROLE", "PID", "STATUS");
+    println!("{}", "-".repeat(50));
+
+    for session in sessions {
+        let pid = session
+            .pid
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "-".to_string());
 
         println!(
-            "{:<15} {:<12} {:<12} {:<20}",
+            "{:<15} {:<12} {:<8} {}",
             session.id,
             session.role,
-            session.status,
-            started
+            pid,
+            paint_status(session.status, &session.status.to_string())
         );
     }
 }
 
+/// Print a log health report
+pub fn print_log_stats(session_id: &SessionId, stats: &LogStats) {
+    let millis = |ms: Option<i64>| {
+        ms.map(|ms| {
+            if ms < 1000 {
+                format!("{}ms", ms)
+            } else {
+                format_duration(&chrono::Duration::milliseconds(ms))
+            }
+        })
+        .unwrap_or_else(|| "-".to_string())
+    };
+
+    println!("Log stats: {}", session_id);
+    println!("  Events:     {}", stats.total_events);
+    println!(
+        "  Breakdown:  {} output, {} error, {} input",
+        stats.output_events, stats.error_events, stats.input_events
+    );
+    println!("  Error rate: {:.1}%", stats.error_ratio * 100.0);
+    println!("  Avg gap:    {}", millis(stats.avg_output_gap_ms));
+    println!("  Max gap:    {}", millis(stats.max_output_gap_ms));
+    println!("  Span:       {}", millis(stats.span_ms));
+    match stats.output_per_minute {
+        Some(rate) => println!("  Output:     {:.1} lines/min", rate),
+        None => println!("  Output:     -"),
+    }
+}
+
+/// Print what `gc` found and repaired
+pub fn print_gc_report(report: &GcReport) {
+    for issue in &report.issues {
+        let line = format!("{}: {}", issue.path().display(), issue);
+        if report.fixed.contains(issue) {
+            println!("{} (fixed)", line);
+        } else {
+            println!("{}", warning(&line));
+        }
+    }
+
+    let summary = format!(
+        "Checked {} session(s): {} issue(s), {} fixed",
+        report.sessions_checked,
+        report.issues.len(),
+        report.fixed.len()
+    );
+    if report.remaining() == 0 {
+        println!("{}", success(&summary));
+    } else {
+        println!("{}", info(&summary));
+    }
+}
+
 /// Print detailed session information
 pub fn print_session_details(metadata: &SessionMetadata) {
     println!("Session: {}", metadata.id);
     println!("  Role:       {}", metadata.role);
-    println!("  Status:     {}", metadata.status);
+    println!(
+        "  Status:     {}",
+        paint_status(metadata.status, &metadata.status.to_string())
+    );
+
+    if let Some(reason) = &metadata.status_reason {
+        println!("  Reason:     {}", reason);
+    }
     println!("  Task:       {}", metadata.task);
     println!("  Created:    {}", format_timestamp(&metadata.created_at));
 
@@ -102,11 +770,81 @@ pub fn print_session_details(metadata: &SessionMetadata) {
     }
 
     println!("  Log dir:    {}", metadata.log_dir.display());
+
+    if !metadata.depends_on.is_empty() {
+        let depends_on: Vec<_> = metadata.depends_on.iter().map(|id| id.as_str()).collect();
+        println!("  Depends on: {}", depends_on.join(", "));
+    }
+
+    if let Some(result_path) = &metadata.result_path {
+        println!("  Result:     {}", result_path.display());
+    }
+
+    if let Some(result) = &metadata.result {
+        println!("  JSON result: {}", result);
+    }
+
+    if let Some(encoding) = &metadata.encoding {
+        println!("  Encoding:   {}", encoding);
+    }
+
+    if let Some(on_complete) = &metadata.on_complete {
+        println!("  On complete: {}", on_complete);
+    }
+
+    if let Some(secs) = metadata.timeout_secs {
+        println!("  Timeout:    {}s", secs);
+    }
+
+    if metadata.retry_on_failure > 0 {
+        println!("  Retries:    {} of {}", metadata.retries, metadata.retry_on_failure);
+    }
+
+    if let Some(approval) = &metadata.approval {
+        println!("  Approval:   {}", approval);
+    }
+
+    let settings = &metadata.spawn_settings;
+    if let Some(model) = &settings.model {
+        println!("  Model:      {}", model);
+    }
+
+    if let Some(binary) = &settings.claude_binary {
+        println!("  Binary:     {}", binary);
+    }
+
+    if let Some(dir) = &settings.working_dir {
+        println!("  Cwd:        {}", dir.display());
+    }
+
+    if !settings.env.is_empty() {
+        // Values may hold credentials, so only the names are shown
+        let keys: Vec<_> = settings.env.keys().map(String::as_str).collect();
+        println!("  Env:        {}", keys.join(", "));
+    }
+
+    if !metadata.annotations.is_empty() {
+        let mut annotations: Vec<_> = metadata.annotations.iter().collect();
+        annotations.sort();
+
+        println!("  Annotations:");
+        for (key, value) in annotations {
+            println!("    {}={}", key, value);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn test_status_color() {
+        assert_eq!(status_color(SessionStatus::Running), "33");
+        assert_eq!(status_color(SessionStatus::Completed), "32");
+        assert_eq!(status_color(SessionStatus::Failed), "31");
+        assert_eq!(status_color(SessionStatus::Queued), "34");
+    }
     use crate::types::role::Role;
     use crate::types::session::SessionId;
     use std::path::PathBuf;
@@ -123,6 +861,26 @@ mod tests {
         assert!(error("Test").contains("Test"));
     }
 
+    #[test]
+    fn test_action_result_json() {
+        let result = ActionResult::success(
+            "stopped",
+            Some(SessionId::from_string("DEV-001".to_string())),
+            "Session DEV-001 stopped".to_string(),
+        );
+        let json: serde_json::Value = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["action"], "stopped");
+        assert_eq!(json["session_id"], "DEV-001");
+        assert!(json.get("error").is_none());
+        assert!(json.get("session_ids").is_none());
+
+        let result = ActionResult::failure("resumed", None, "boom".to_string());
+        let json: serde_json::Value = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["ok"], false);
+        assert_eq!(json["error"], "boom");
+    }
+
     #[test]
     fn test_format_duration() {
         let duration = chrono::Duration::seconds(45);
@@ -135,6 +893,14 @@ mod tests {
         assert_eq!(format_duration(&duration), "1h 1m");
     }
 
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(300 * 1024 * 1024), "300.0 MiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
     #[test]
     fn test_format_timestamp() {
         let dt = Utc::now();
@@ -153,7 +919,210 @@ mod tests {
         );
 
         // This just tests that it doesn't panic
-        print_sessions_table(&[metadata]);
-        print_sessions_table(&[]);
+        print_sessions_table(std::slice::from_ref(&metadata), false, false);
+        print_sessions_table(&[metadata], true, true);
+        print_sessions_table(&[], true, false);
+    }
+
+    #[test]
+    fn test_fitted_widths() {
+        let now = Utc::now();
+        let short = SessionMetadata::new(
+            SessionId::from_string("DEV-001".to_string()),
+            Role::Developer,
+            "short".to_string(),
+            PathBuf::from("/tmp"),
+        );
+        let long = SessionMetadata::new(
+            SessionId::from_string("ACME-ARCHITECT-0042".to_string()),
+            Role::Architect,
+            "a task with a long description that goes past the column limit\nand a second line".to_string(),
+            PathBuf::from("/tmp"),
+        );
+        let rows = vec![session_row(&short, now), session_row(&long, now)];
+        assert_eq!(rows[1][5].chars().count(), TASK_COLUMN_CHARS);
+        assert!(rows[1][5].ends_with('…'));
+
+        let widths = fitted_widths(&TABLE_COLUMNS, &rows);
+        for (column, &width) in widths.iter().enumerate() {
+            let longest = rows.iter().map(|row| row[column].chars().count()).max().unwrap();
+            assert_eq!(width, longest.max(TABLE_COLUMNS[column].len()), "{}", TABLE_COLUMNS[column]);
+        }
+        assert_eq!(widths[0], "ACME-ARCHITECT-0042".len());
+        assert_eq!(widths[1], "ARCHITECT".len());
+        // Header wider than every value
+        assert_eq!(widths[4], "ELAPSED".len());
+
+        let line = table_line(&rows[0], &widths, None);
+        assert!(line.starts_with(&format!("DEV-001{} DEVELOPER", " ".repeat(widths[0] - 7))));
+        assert!(line.ends_with(" short"));
+    }
+
+    #[test]
+    fn test_elapsed() {
+        let mut metadata = SessionMetadata::new(
+            SessionId::from_string("DEV-001".to_string()),
+            Role::Developer,
+            "test".to_string(),
+            PathBuf::from("/tmp"),
+        );
+        let started = Utc::now() - chrono::Duration::seconds(600);
+        assert_eq!(elapsed(&metadata, started), "-");
+
+        metadata.mark_started(1234);
+        metadata.started_at = Some(started);
+        assert_eq!(elapsed(&metadata, started + chrono::Duration::seconds(125)), "2m 5s");
+
+        metadata.mark_completed();
+        metadata.ended_at = Some(started + chrono::Duration::seconds(42));
+        assert_eq!(elapsed(&metadata, started + chrono::Duration::seconds(3600)), "42s");
+    }
+
+    #[test]
+    fn test_sort_sessions() {
+        let t0 = Utc::now() - chrono::Duration::seconds(100);
+        let at = |secs: i64| Some(t0 + chrono::Duration::seconds(secs));
+        let session = |id: &str, role: Role| {
+            SessionMetadata::new(SessionId::from_string(id.to_string()), role, "test".to_string(), PathBuf::from("/tmp"))
+        };
+
+        let mut manager = session("MGR-1", Role::Manager);
+        manager.mark_started(1);
+        manager.started_at = at(10);
+        let mut done = session("DEV-10", Role::Developer);
+        done.mark_started(2);
+        done.mark_completed();
+        (done.started_at, done.ended_at) = (at(0), at(30));
+        let mut failed = session("DEV-9", Role::Developer);
+        failed.mark_started(3);
+        failed.mark_failed();
+        (failed.started_at, failed.ended_at) = (at(5), at(6));
+        let unstarted = session("ARCH-1", Role::Architect);
+        let sessions = vec![manager, done, failed, unstarted];
+
+        let order = |sort: ListSort, reverse: bool| {
+            let mut sorted = sessions.clone();
+            sort_sessions(&mut sorted, sort, reverse);
+            sorted.iter().map(|s| s.id.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(order(ListSort::Id, false), ["ARCH-1", "DEV-9", "DEV-10", "MGR-1"]);
+        assert_eq!(order(ListSort::Role, false), ["ARCH-1", "DEV-9", "DEV-10", "MGR-1"]);
+        assert_eq!(order(ListSort::Status, false), ["ARCH-1", "MGR-1", "DEV-10", "DEV-9"]);
+        assert_eq!(order(ListSort::Started, false), ["DEV-10", "DEV-9", "MGR-1", "ARCH-1"]);
+        assert_eq!(order(ListSort::Duration, false), ["ARCH-1", "DEV-9", "DEV-10", "MGR-1"]);
+        assert_eq!(order(ListSort::Started, true), ["ARCH-1", "MGR-1", "DEV-9", "DEV-10"]);
+        assert_eq!(order(ListSort::Id, true), ["MGR-1", "DEV-10", "DEV-9", "ARCH-1"]);
+        assert_eq!(ListSort::default(), ListSort::Started);
+    }
+
+    #[test]
+    fn test_list_page() {
+        let mut sessions: Vec<SessionMetadata> = (1..=12)
+            .rev()
+            .map(|n| SessionMetadata::new(SessionId::new(Role::Developer, n), Role::Developer, "test".to_string(), PathBuf::from("/tmp")))
+            .collect();
+        sort_sessions(&mut sessions, ListSort::Id, false);
+        let ids = |page: ListPage| page.slice(&sessions).iter().map(|s| s.id.sequence().unwrap()).collect::<Vec<_>>();
+
+        let page = ListPage { offset: 5, limit: Some(3) };
+        assert_eq!(ids(page), [6, 7, 8]);
+        assert_eq!(page.summary(sessions.len()).as_deref(), Some("Showing 6-8 of 12 sessions"));
+
+        // The last page may be short, and a page past the end is empty
+        let page = ListPage { offset: 10, limit: Some(5) };
+        assert_eq!(ids(page), [11, 12]);
+        assert_eq!(page.summary(sessions.len()).as_deref(), Some("Showing 11-12 of 12 sessions"));
+        let page = ListPage { offset: 20, limit: None };
+        assert!(ids(page).is_empty());
+        assert_eq!(page.summary(sessions.len()).as_deref(), Some("Showing 0 of 12 sessions"));
+
+        // Everything fits: nothing to report
+        assert_eq!(ids(ListPage::default()), (1..=12).collect::<Vec<_>>());
+        assert_eq!(ListPage { offset: 0, limit: Some(50) }.summary(sessions.len()), None);
+        assert_eq!(ids(ListPage { offset: 0, limit: Some(usize::MAX) }).len(), 12);
+    }
+
+    #[test]
+    fn test_session_tree_lines() {
+        let session = |id: &str, parent: Option<&str>| {
+            let id = SessionId::from_string(id.to_string());
+            match parent {
+                Some(parent) => SessionMetadata::new_child(
+                    id,
+                    Role::Developer,
+                    "test".to_string(),
+                    PathBuf::from("/tmp"),
+                    SessionId::from_string(parent.to_string()),
+                ),
+                None => SessionMetadata::new(id, Role::Manager, "test".to_string(), PathBuf::from("/tmp")),
+            }
+        };
+        let mut sessions = vec![
+            session("MGR-001", None),
+            session("DEV-001", Some("MGR-001")),
+            session("DEV-002", Some("DEV-001")),
+            session("DEV-003", Some("DEV-001")),
+            session("DEV-004", Some("MGR-001")),
+            // Parent not listed: shown as a root
+            session("DEV-005", Some("MGR-009")),
+        ];
+        let created = Utc::now();
+        for (index, session) in sessions.iter_mut().enumerate() {
+            session.created_at = created + chrono::Duration::seconds(index as i64);
+        }
+        sessions[4].mark_started(42);
+        sessions.reverse();
+
+        assert_eq!(
+            session_tree_lines(&sessions),
+            vec![
+                "MGR-001 [MANAGER] created",
+                "├── DEV-001 [DEVELOPER] created",
+                "│   ├── DEV-002 [DEVELOPER] created",
+                "│   └── DEV-003 [DEVELOPER] created",
+                "└── DEV-004 [DEVELOPER] running",
+                "DEV-005 [DEVELOPER] created",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sessions_csv() {
+        let mut metadata = SessionMetadata::new(
+            SessionId::from_string("DEV-001".to_string()),
+            Role::Developer,
+            "fix \"login\", then\nadd tests".to_string(),
+            PathBuf::from("/tmp"),
+        );
+        metadata.mark_started(42);
+
+        let csv = sessions_csv(&[metadata.clone()]);
+        let mut lines = csv.splitn(2, '\n');
+        assert_eq!(
+            lines.next(),
+            Some("id,role,status,created_at,started_at,ended_at,duration_secs,pid,task")
+        );
+        let row = lines.next().unwrap();
+        let started = metadata.started_at.unwrap().to_rfc3339();
+        assert!(row.starts_with(&format!("DEV-001,DEVELOPER,running,{},{},,", metadata.created_at.to_rfc3339(), started)));
+        assert!(row.ends_with(",42,\"fix \"\"login\"\", then\nadd tests\"\n"));
+
+        assert_eq!(sessions_csv(&[]).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_sessions_json() {
+        assert_eq!(sessions_json(&[]).unwrap(), "[]");
+
+        let metadata = SessionMetadata::new(
+            SessionId::from_string("DEV-001".to_string()),
+            Role::Developer,
+            "task".to_string(),
+            PathBuf::from("/tmp"),
+        );
+        let json = sessions_json(std::slice::from_ref(&metadata)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let created_at = parsed[0]["created_at"].as_str().unwrap();
+        assert_eq!(DateTime::parse_from_rfc3339(created_at).unwrap(), metadata.created_at);
     }
 }
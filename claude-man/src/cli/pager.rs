@@ -0,0 +1,141 @@
+//! Paging of long CLI output
+//!
+//! Output goes through `$PAGER` (default `less -R`). When that can't be run,
+//! or `$PAGER` is set but empty, a minimal built-in pager shows one screen at
+//! a time. Output that isn't going to a terminal is printed unpaged.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+use crate::types::error::Result;
+
+/// Pager used when `$PAGER` is not set
+pub const DEFAULT_PAGER: &str = "less -R";
+
+/// Screen height assumed when `$LINES` is not set
+const DEFAULT_SCREEN_LINES: usize = 24;
+
+/// Show `text` one screen at a time
+pub fn page(text: &str) -> Result<()> {
+    let stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        stdout.lock().write_all(text.as_bytes())?;
+        return Ok(());
+    }
+
+    if let Some(command) = pager_command(std::env::var("PAGER").ok()) {
+        match run_external(&command, text) {
+            Ok(()) => return Ok(()),
+            Err(e) => debug!("Pager '{}' failed ({}), using the built-in pager", command.join(" "), e),
+        }
+    }
+
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        stdout.lock().write_all(text.as_bytes())?;
+        return Ok(());
+    }
+    page_builtin(text, screen_lines(), &mut stdin.lock(), &mut stdout.lock())
+}
+
+/// Split the pager command into program and arguments
+///
+/// `None` means the built-in pager: `$PAGER` is set but blank.
+fn pager_command(pager: Option<String>) -> Option<Vec<String>> {
+    let pager = pager.unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let command: Vec<String> = pager.split_whitespace().map(str::to_string).collect();
+    (!command.is_empty()).then_some(command)
+}
+
+/// Pipe `text` into an external pager and wait for it to exit
+fn run_external(command: &[String], text: &str) -> std::io::Result<()> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting early closes the pipe; that's not an error
+        match stdin.write_all(text.as_bytes()) {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Terminal height from `$LINES`, falling back to 24
+fn screen_lines() -> usize {
+    std::env::var("LINES")
+        .ok()
+        .and_then(|lines| lines.parse().ok())
+        .filter(|&lines: &usize| lines > 1)
+        .unwrap_or(DEFAULT_SCREEN_LINES)
+}
+
+/// Built-in pager: print a screen, then wait for Enter (next) or `q` (quit)
+fn page_builtin(
+    text: &str,
+    screen_lines: usize,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> Result<()> {
+    // Leave the last row for the prompt
+    let page_size = screen_lines.saturating_sub(1).max(1);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut pages = lines.chunks(page_size).peekable();
+
+    while let Some(page) = pages.next() {
+        for line in page {
+            writeln!(out, "{}", line)?;
+        }
+        if pages.peek().is_none() {
+            break;
+        }
+
+        write!(out, "-- More -- (Enter: next page, q: quit) ")?;
+        out.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 || answer.trim().eq_ignore_ascii_case("q") {
+            writeln!(out)?;
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None), Some(vec!["less".to_string(), "-R".to_string()]));
+        assert_eq!(
+            pager_command(Some("most -s".to_string())),
+            Some(vec!["most".to_string(), "-s".to_string()])
+        );
+        assert_eq!(pager_command(Some("  ".to_string())), None);
+    }
+
+    #[test]
+    fn test_builtin_pager_pages_and_quits() {
+        let text = "1\n2\n3\n4\n5\n";
+
+        // Enter shows the next page, q stops before the last one
+        let mut out = Vec::new();
+        page_builtin(text, 3, &mut "\nq\n".as_bytes(), &mut out).unwrap();
+        let shown = String::from_utf8(out).unwrap();
+        assert!(shown.starts_with("1\n2\n-- More --"));
+        assert!(shown.contains("3\n4\n"));
+        assert!(!shown.contains('5'));
+
+        // Everything fits on one screen: no prompt
+        let mut out = Vec::new();
+        page_builtin(text, 10, &mut "".as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text);
+    }
+}
@@ -4,6 +4,7 @@
 //! This module does NOT implement its own OAuth - it relies on the
 //! Claude CLI's built-in authentication to respect terms of service.
 
+use std::path::PathBuf;
 use std::process::Command;
 use tracing::debug;
 
@@ -42,6 +43,50 @@ pub fn check_claude_cli_available() -> Result<()> {
     }
 }
 
+/// Name of the Claude CLI executable on this platform
+#[cfg(target_os = "windows")]
+const CLAUDE_CLI_NAME: &str = "claude.cmd";
+
+/// Name of the Claude CLI executable on this platform
+#[cfg(not(target_os = "windows"))]
+const CLAUDE_CLI_NAME: &str = "claude";
+
+/// Resolve the full path of the Claude CLI by searching PATH
+///
+/// # Returns
+///
+/// The first matching executable, or `None` if it isn't on PATH
+pub fn resolve_claude_cli_path() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(CLAUDE_CLI_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Get the Claude CLI's reported version
+///
+/// # Returns
+///
+/// The trimmed output of `claude --version`, or `None` if it can't be run
+pub fn claude_cli_version() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let result = Command::new("cmd")
+        .args(["/C", "claude", "--version"])
+        .output();
+
+    #[cfg(not(target_os = "windows"))]
+    let result = Command::new("claude")
+        .arg("--version")
+        .output();
+
+    match result {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        _ => None,
+    }
+}
+
 /// Validate that the Claude CLI is authenticated
 ///
 /// Checks if the user is logged in to the Claude CLI by running a test command.
@@ -0,0 +1,198 @@
+//! Backup and restore of orchestration state
+//!
+//! Bundles the sessions directory (metadata, logs, and role context for every
+//! session) and the shared key/value store into a single tarball so
+//! orchestration history can be moved between machines.
+
+use std::fs::{self, File};
+use std::path::{Component, Path};
+use tracing::info;
+
+use crate::types::error::{ClaudeManError, Result};
+
+/// Name of the top-level directory inside a backup archive
+const ARCHIVE_ROOT: &str = "sessions";
+
+/// Name of the key/value store file inside a backup archive
+const KV_ENTRY: &str = "kv.json";
+
+/// Write every entry of `sessions_dir`, and the key/value store at
+/// `kv_path` if there is one, into a tarball at `out`
+///
+/// # Returns
+///
+/// The number of session directories included in the archive
+pub fn write_archive(sessions_dir: &Path, kv_path: &Path, out: &Path) -> Result<usize> {
+    let file = File::create(out)?;
+    let mut builder = tar::Builder::new(file);
+
+    let mut count = 0;
+    if sessions_dir.exists() {
+        builder.append_dir_all(ARCHIVE_ROOT, sessions_dir)?;
+        count = fs::read_dir(sessions_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .count();
+    } else {
+        builder.append_dir(ARCHIVE_ROOT, ".")?;
+    }
+    if kv_path.is_file() {
+        builder.append_path_with_name(kv_path, KV_ENTRY)?;
+    }
+
+    builder.into_inner()?.sync_all()?;
+    info!("Wrote {} session(s) to {}", count, out.display());
+
+    Ok(count)
+}
+
+/// Restore a tarball written by [`write_archive`] into `sessions_dir`, and
+/// its key/value store (if it has one) to `kv_path`
+///
+/// Refuses to overwrite a sessions directory that already contains sessions
+/// or an existing key/value store.
+///
+/// # Returns
+///
+/// The number of session directories restored
+pub fn extract_archive(archive: &Path, sessions_dir: &Path, kv_path: &Path) -> Result<usize> {
+    if sessions_dir.exists() && fs::read_dir(sessions_dir)?.next().is_some() {
+        return Err(ClaudeManError::InvalidInput(format!(
+            "Refusing to restore into non-empty directory {}",
+            sessions_dir.display()
+        )));
+    }
+    if kv_path.exists() {
+        return Err(ClaudeManError::InvalidInput(format!(
+            "Refusing to restore over existing key/value store {}",
+            kv_path.display()
+        )));
+    }
+
+    fs::create_dir_all(sessions_dir)?;
+
+    let mut archive = tar::Archive::new(File::open(archive)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+
+        if path == Path::new(KV_ENTRY) && entry.header().entry_type().is_file() {
+            if let Some(parent) = kv_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(kv_path)?;
+            continue;
+        }
+
+        // Only accept plain relative paths beneath the archive root
+        let relative = path
+            .strip_prefix(ARCHIVE_ROOT)
+            .ok()
+            .filter(|rel| rel.components().all(|c| matches!(c, Component::Normal(_))))
+            .ok_or_else(|| {
+                ClaudeManError::InvalidInput(format!(
+                    "Unexpected entry in backup archive: {}",
+                    path.display()
+                ))
+            })?;
+
+        // Backups hold only files and directories (links are followed when
+        // writing); a link could point later entries outside `sessions_dir`
+        let kind = entry.header().entry_type();
+        if !(kind.is_file() || kind.is_dir()) {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Unexpected {:?} entry in backup archive: {}",
+                kind,
+                path.display()
+            )));
+        }
+
+        let target = sessions_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+
+    let count = fs::read_dir(sessions_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .count();
+    info!("Restored {} session(s) into {}", count, sessions_dir.display());
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let sessions_dir = temp_dir.path().join("src").join("sessions");
+        fs::create_dir_all(sessions_dir.join("DEV-001")).unwrap();
+        fs::write(sessions_dir.join("DEV-001").join("io.log"), "{}\n").unwrap();
+        fs::create_dir_all(sessions_dir.join("MGR-001")).unwrap();
+
+        let kv_path = temp_dir.path().join("src").join("kv.json");
+        fs::write(&kv_path, "{\"global\":{\"design\":\"plan-b\"}}").unwrap();
+
+        let archive = temp_dir.path().join("backup.tar");
+        assert_eq!(write_archive(&sessions_dir, &kv_path, &archive).unwrap(), 2);
+
+        let restored_dir = temp_dir.path().join("dst").join("sessions");
+        let restored_kv = temp_dir.path().join("dst").join("kv.json");
+        assert_eq!(extract_archive(&archive, &restored_dir, &restored_kv).unwrap(), 2);
+
+        let log = fs::read_to_string(restored_dir.join("DEV-001").join("io.log")).unwrap();
+        assert_eq!(log, "{}\n");
+        assert_eq!(fs::read_to_string(&restored_kv).unwrap(), fs::read_to_string(&kv_path).unwrap());
+
+        // An existing store isn't overwritten
+        let other_dir = temp_dir.path().join("other").join("sessions");
+        let result = extract_archive(&archive, &other_dir, &restored_kv);
+        assert!(matches!(result, Err(ClaudeManError::InvalidInput(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn test_extract_rejects_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+
+        // sessions/x -> outside, then sessions/x/foo through the link
+        let archive = temp_dir.path().join("evil.tar");
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(tar::EntryType::Symlink);
+        link.set_size(0);
+        builder.append_link(&mut link, "sessions/x", &outside).unwrap();
+        let mut file = tar::Header::new_gnu();
+        file.set_size(4);
+        file.set_mode(0o644);
+        builder.append_data(&mut file, "sessions/x/foo", &b"evil"[..]).unwrap();
+        builder.into_inner().unwrap();
+
+        let sessions_dir = temp_dir.path().join("sessions");
+        let result = extract_archive(&archive, &sessions_dir, &temp_dir.path().join("kv.json"));
+        assert!(matches!(result, Err(ClaudeManError::InvalidInput(_))), "{:?}", result);
+        assert!(!outside.join("foo").exists());
+        assert!(fs::symlink_metadata(sessions_dir.join("x")).is_err());
+    }
+
+    #[test]
+    fn test_extract_refuses_non_empty_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let sessions_dir = temp_dir.path().join("sessions");
+        fs::create_dir_all(sessions_dir.join("DEV-001")).unwrap();
+
+        let archive = temp_dir.path().join("backup.tar");
+        let kv_path = temp_dir.path().join("kv.json");
+        write_archive(&sessions_dir, &kv_path, &archive).unwrap();
+
+        let result = extract_archive(&archive, &sessions_dir, &kv_path);
+        assert!(matches!(result, Err(ClaudeManError::InvalidInput(_))));
+    }
+}
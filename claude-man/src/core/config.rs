@@ -0,0 +1,620 @@
+//! User configuration for claude-man
+//!
+//! Settings are read from `.claude-man/config.toml`. Every field is optional;
+//! a missing file yields the defaults.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::core::filter::{PromptDetector, Redactor};
+use crate::core::logger::{FlushPolicy, SessionDirTemplate, DEFAULT_MAX_LOG_BYTES};
+use crate::core::process::{split_command, DEFAULT_TERMINATION_TIMEOUT};
+use crate::types::error::{ClaudeManError, Result};
+use crate::types::path::expand_path;
+use crate::types::role::{custom_role_names, Role};
+use crate::types::session::validate_project_name;
+
+/// claude-man configuration loaded from `config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Project prefix prepended to generated session IDs (e.g. `A` → `A-DEV-001`)
+    pub project: Option<String>,
+
+    /// Batch log writes, flushing once this many lines are pending
+    pub log_flush_lines: Option<usize>,
+
+    /// Batch log writes, flushing at least this often (milliseconds)
+    pub log_flush_interval_ms: Option<u64>,
+
+    /// Write each session's metadata.json on one line instead of pretty-printed
+    pub compact_metadata: bool,
+
+    /// Truncate session output lines longer than this many bytes
+    pub max_line_bytes: Option<usize>,
+
+    /// Rotate a session's io.log once it reaches this many bytes (default 10 MiB)
+    pub max_log_bytes: Option<u64>,
+
+    /// Limit on running children per parent session; extra children are queued
+    pub max_concurrent_per_parent: Option<usize>,
+
+    /// Limit on sessions spawned in any rolling hour; extra spawns are rejected
+    pub max_sessions_per_hour: Option<usize>,
+
+    /// Limit on sessions running at once; extra spawns are rejected
+    pub max_concurrent_sessions: Option<usize>,
+
+    /// Milliseconds between checks when waiting on or tailing a session
+    pub poll_interval_ms: Option<u64>,
+
+    /// Command prepended to every claude invocation (e.g. `docker run --rm img`)
+    pub wrapper: Option<String>,
+
+    /// Seconds a stopped session gets to exit after SIGTERM before SIGKILL
+    pub termination_timeout_secs: Option<u64>,
+
+    /// Regular expressions whose matches are masked in session output and logs
+    pub redact_patterns: Vec<String>,
+
+    /// Name for new session directories, e.g. `{date}_{id}_{slug}` (default `{id}`)
+    pub session_dir_template: Option<String>,
+
+    /// Regular expression matching the permission prompts `--interactive-approval` answers
+    pub approval_prompt_pattern: Option<String>,
+
+    /// Only accept full role names (`DEVELOPER`, not `DEV`) on the command line
+    pub strict_roles: bool,
+
+    /// User-defined roles beyond the four built-ins (`[[roles]]` tables)
+    pub roles: Vec<RoleConfig>,
+
+    /// Working directory for sessions of a role spawned without `--cwd`,
+    /// e.g. `ARCHITECT = "docs"` (`[role_working_dirs]` table)
+    pub role_working_dirs: BTreeMap<String, String>,
+
+    /// Record every daemon request in `.claude-man/audit.log`
+    pub audit_log: bool,
+
+    /// Don't echo session output on the daemon's console (io.log still gets every line)
+    pub quiet_daemon: bool,
+}
+
+/// A user-defined role, e.g. `{ name = "REVIEWER", prefix = "REV" }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoleConfig {
+    /// Role name used with `--role`
+    pub name: String,
+
+    /// Session ID prefix (default: the first three letters of the name)
+    pub prefix: Option<String>,
+}
+
+impl Config {
+    /// Load configuration from the default location
+    pub fn load() -> Result<Self> {
+        Self::load_from_path(&default_config_path())
+    }
+
+    /// Load configuration from a specific path
+    ///
+    /// Returns the default configuration if the file doesn't exist.
+    pub fn load_from_path(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            debug!("No config file at {:?}, using defaults", path);
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let config: Config = toml::from_str(&contents).map_err(|e| {
+            ClaudeManError::Config(format!("Invalid config file {}: {}", path.display(), e))
+        })?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Get the log flush policy
+    ///
+    /// Logs flush every line unless either batching setting is present.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        if self.log_flush_lines.is_none() && self.log_flush_interval_ms.is_none() {
+            return FlushPolicy::EveryLine;
+        }
+
+        FlushPolicy::Batched {
+            max_lines: self
+                .log_flush_lines
+                .unwrap_or(FlushPolicy::DEFAULT_BATCH_LINES),
+            max_delay: self
+                .log_flush_interval_ms
+                .map(Duration::from_millis)
+                .unwrap_or(FlushPolicy::DEFAULT_BATCH_DELAY),
+        }
+    }
+
+    /// Get the SIGTERM-to-SIGKILL grace period for stopped sessions
+    pub fn termination_timeout(&self) -> Duration {
+        self.termination_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TERMINATION_TIMEOUT)
+    }
+
+    /// Get the size at which session logs are rotated
+    pub fn log_rotation_bytes(&self) -> u64 {
+        self.max_log_bytes.unwrap_or(DEFAULT_MAX_LOG_BYTES)
+    }
+
+    /// Build the redactor for `redact_patterns` (None when there are none)
+    pub fn redactor(&self) -> Result<Option<Redactor>> {
+        if self.redact_patterns.is_empty() {
+            return Ok(None);
+        }
+        Redactor::new(&self.redact_patterns).map(Some)
+    }
+
+    /// Get the naming template for new session directories
+    pub fn session_dir_template(&self) -> Result<SessionDirTemplate> {
+        match &self.session_dir_template {
+            Some(template) => SessionDirTemplate::new(template),
+            None => Ok(SessionDirTemplate::default()),
+        }
+    }
+
+    /// Build the detector for approval prompts (Claude's own format if unset)
+    pub fn prompt_detector(&self) -> Result<PromptDetector> {
+        match &self.approval_prompt_pattern {
+            Some(pattern) => PromptDetector::new(pattern),
+            None => Ok(PromptDetector::default()),
+        }
+    }
+
+    /// Get the wrapper command split into words (empty for none)
+    pub fn wrapper_command(&self) -> Result<Vec<String>> {
+        match &self.wrapper {
+            Some(wrapper) => split_command(wrapper),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get the default working directory of each role in `role_working_dirs`
+    ///
+    /// Custom roles must be registered first.
+    pub fn role_working_dirs(&self) -> Result<HashMap<Role, PathBuf>> {
+        self.role_working_dirs
+            .iter()
+            .map(|(name, dir)| {
+                let invalid = |e: ClaudeManError| ClaudeManError::Config(format!("role_working_dirs.{}: {}", name, e));
+                Ok((Role::parse(name, true).map_err(invalid)?, expand_path(dir).map_err(invalid)?))
+            })
+            .collect()
+    }
+
+    /// Register the configured custom roles so they can be parsed and spawned
+    pub fn register_roles(&self) -> Result<()> {
+        for role in &self.roles {
+            Role::register(&role.name, role.prefix.as_deref())
+                .map_err(|e| ClaudeManError::Config(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Validate configuration values
+    pub fn validate(&self) -> Result<()> {
+        if let Some(project) = &self.project {
+            validate_project_name(project)?;
+        }
+        if self.log_flush_lines == Some(0) {
+            return Err(ClaudeManError::Config(
+                "log_flush_lines must be at least 1".to_string(),
+            ));
+        }
+        if self.max_line_bytes == Some(0) {
+            return Err(ClaudeManError::Config(
+                "max_line_bytes must be at least 1".to_string(),
+            ));
+        }
+        if self.max_log_bytes == Some(0) {
+            return Err(ClaudeManError::Config(
+                "max_log_bytes must be at least 1".to_string(),
+            ));
+        }
+        if let Some(wrapper) = &self.wrapper {
+            split_command(wrapper)?;
+        }
+        self.redactor()?;
+        self.session_dir_template()?;
+        self.prompt_detector()?;
+        for role in &self.roles {
+            custom_role_names(&role.name, role.prefix.as_deref())
+                .map_err(|e| ClaudeManError::Config(e.to_string()))?;
+        }
+        // Custom roles aren't registered yet, so match names against the config
+        for (name, dir) in &self.role_working_dirs {
+            let upper = name.to_uppercase();
+            let known = Role::builtin().iter().any(|role| role.to_string() == upper)
+                || self.roles.iter().any(|role| role.name.trim().to_uppercase() == upper);
+            if !known {
+                return Err(ClaudeManError::Config(format!(
+                    "role_working_dirs: unknown role '{}' (use the full role name)",
+                    name
+                )));
+            }
+            expand_path(dir).map_err(|e| ClaudeManError::Config(format!("role_working_dirs.{}: {}", name, e)))?;
+        }
+        if self.poll_interval_ms == Some(0) {
+            return Err(ClaudeManError::Config(
+                "poll_interval_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.max_concurrent_per_parent == Some(0) {
+            return Err(ClaudeManError::Config(
+                "max_concurrent_per_parent must be at least 1".to_string(),
+            ));
+        }
+        if self.max_sessions_per_hour == Some(0) {
+            return Err(ClaudeManError::Config(
+                "max_sessions_per_hour must be at least 1".to_string(),
+            ));
+        }
+        if self.max_concurrent_sessions == Some(0) {
+            return Err(ClaudeManError::Config(
+                "max_concurrent_sessions must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A single setting that differs between two configurations
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Name of the setting as written in config.toml
+    pub field: &'static str,
+
+    /// Previous value (display form)
+    pub old: String,
+
+    /// New value (display form)
+    pub new: String,
+
+    /// Whether the daemon can apply this change without restarting
+    pub live: bool,
+}
+
+impl Config {
+    /// List the settings that changed between `self` and `other`
+    pub fn diff(&self, other: &Config) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+
+        if self.project != other.project {
+            changes.push(ConfigChange {
+                field: "project",
+                old: display_option(&self.project),
+                new: display_option(&other.project),
+                live: true,
+            });
+        }
+
+        if self.log_flush_lines != other.log_flush_lines {
+            changes.push(ConfigChange {
+                field: "log_flush_lines",
+                old: display_option(&self.log_flush_lines),
+                new: display_option(&other.log_flush_lines),
+                live: true,
+            });
+        }
+
+        if self.log_flush_interval_ms != other.log_flush_interval_ms {
+            changes.push(ConfigChange {
+                field: "log_flush_interval_ms",
+                old: display_option(&self.log_flush_interval_ms),
+                new: display_option(&other.log_flush_interval_ms),
+                live: true,
+            });
+        }
+
+        if self.compact_metadata != other.compact_metadata {
+            changes.push(ConfigChange {
+                field: "compact_metadata",
+                old: self.compact_metadata.to_string(),
+                new: other.compact_metadata.to_string(),
+                live: true,
+            });
+        }
+
+        if self.max_line_bytes != other.max_line_bytes {
+            changes.push(ConfigChange {
+                field: "max_line_bytes",
+                old: display_option(&self.max_line_bytes),
+                new: display_option(&other.max_line_bytes),
+                live: true,
+            });
+        }
+
+        if self.max_log_bytes != other.max_log_bytes {
+            changes.push(ConfigChange {
+                field: "max_log_bytes",
+                old: display_option(&self.max_log_bytes),
+                new: display_option(&other.max_log_bytes),
+                live: true,
+            });
+        }
+
+        if self.max_concurrent_per_parent != other.max_concurrent_per_parent {
+            changes.push(ConfigChange {
+                field: "max_concurrent_per_parent",
+                old: display_option(&self.max_concurrent_per_parent),
+                new: display_option(&other.max_concurrent_per_parent),
+                live: true,
+            });
+        }
+
+        if self.max_sessions_per_hour != other.max_sessions_per_hour {
+            changes.push(ConfigChange {
+                field: "max_sessions_per_hour",
+                old: display_option(&self.max_sessions_per_hour),
+                new: display_option(&other.max_sessions_per_hour),
+                live: true,
+            });
+        }
+
+        if self.max_concurrent_sessions != other.max_concurrent_sessions {
+            changes.push(ConfigChange {
+                field: "max_concurrent_sessions",
+                old: display_option(&self.max_concurrent_sessions),
+                new: display_option(&other.max_concurrent_sessions),
+                live: true,
+            });
+        }
+
+        if self.wrapper != other.wrapper {
+            changes.push(ConfigChange {
+                field: "wrapper",
+                old: display_option(&self.wrapper),
+                new: display_option(&other.wrapper),
+                live: true,
+            });
+        }
+
+        if self.poll_interval_ms != other.poll_interval_ms {
+            changes.push(ConfigChange {
+                field: "poll_interval_ms",
+                old: display_option(&self.poll_interval_ms),
+                new: display_option(&other.poll_interval_ms),
+                live: true,
+            });
+        }
+
+        if self.termination_timeout_secs != other.termination_timeout_secs {
+            changes.push(ConfigChange {
+                field: "termination_timeout_secs",
+                old: display_option(&self.termination_timeout_secs),
+                new: display_option(&other.termination_timeout_secs),
+                live: true,
+            });
+        }
+
+        if self.session_dir_template != other.session_dir_template {
+            changes.push(ConfigChange {
+                field: "session_dir_template",
+                old: display_option(&self.session_dir_template),
+                new: display_option(&other.session_dir_template),
+                live: true,
+            });
+        }
+
+        if self.approval_prompt_pattern != other.approval_prompt_pattern {
+            changes.push(ConfigChange {
+                field: "approval_prompt_pattern",
+                old: display_option(&self.approval_prompt_pattern),
+                new: display_option(&other.approval_prompt_pattern),
+                live: true,
+            });
+        }
+
+        if self.strict_roles != other.strict_roles {
+            changes.push(ConfigChange {
+                field: "strict_roles",
+                old: self.strict_roles.to_string(),
+                new: other.strict_roles.to_string(),
+                live: true,
+            });
+        }
+
+        if self.roles != other.roles {
+            changes.push(ConfigChange {
+                field: "roles",
+                old: display_roles(&self.roles),
+                new: display_roles(&other.roles),
+                live: false,
+            });
+        }
+
+        if self.role_working_dirs != other.role_working_dirs {
+            changes.push(ConfigChange {
+                field: "role_working_dirs",
+                old: format!("{:?}", self.role_working_dirs),
+                new: format!("{:?}", other.role_working_dirs),
+                live: true,
+            });
+        }
+
+        if self.redact_patterns != other.redact_patterns {
+            changes.push(ConfigChange {
+                field: "redact_patterns",
+                old: format!("{:?}", self.redact_patterns),
+                new: format!("{:?}", other.redact_patterns),
+                live: false,
+            });
+        }
+
+        if self.audit_log != other.audit_log {
+            changes.push(ConfigChange {
+                field: "audit_log",
+                old: self.audit_log.to_string(),
+                new: other.audit_log.to_string(),
+                live: false,
+            });
+        }
+
+        if self.quiet_daemon != other.quiet_daemon {
+            changes.push(ConfigChange {
+                field: "quiet_daemon",
+                old: self.quiet_daemon.to_string(),
+                new: other.quiet_daemon.to_string(),
+                live: false,
+            });
+        }
+
+        changes
+    }
+}
+
+/// Format custom roles for log output, e.g. `[REVIEWER, QA]`
+fn display_roles(roles: &[RoleConfig]) -> String {
+    let names: Vec<&str> = roles.iter().map(|role| role.name.as_str()).collect();
+    format!("[{}]", names.join(", "))
+}
+
+/// Format an optional setting for log output
+fn display_option<T: std::fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "(unset)".to_string())
+}
+
+/// Get the claude-man home directory
+pub fn default_home_dir() -> PathBuf {
+    PathBuf::from(".claude-man")
+}
+
+/// Get the default config file path
+pub fn default_config_path() -> PathBuf {
+    default_home_dir().join("config.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_config_uses_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::load_from_path(&temp_dir.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_load_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "project = \"ACME\"\n").unwrap();
+
+        let config = Config::load_from_path(&path).unwrap();
+        assert_eq!(config.project.as_deref(), Some("ACME"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = Config::default();
+        let new = Config {
+            project: Some("ACME".to_string()),
+            ..Config::default()
+        };
+
+        assert!(old.diff(&old).is_empty());
+
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "project");
+        assert_eq!(changes[0].old, "(unset)");
+        assert_eq!(changes[0].new, "ACME");
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        assert_eq!(Config::default().flush_policy(), FlushPolicy::EveryLine);
+
+        let config = Config {
+            log_flush_lines: Some(10),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.flush_policy(),
+            FlushPolicy::Batched {
+                max_lines: 10,
+                max_delay: FlushPolicy::DEFAULT_BATCH_DELAY,
+            }
+        );
+    }
+
+    #[test]
+    fn test_termination_timeout() {
+        assert_eq!(Config::default().termination_timeout(), DEFAULT_TERMINATION_TIMEOUT);
+
+        let config: Config = toml::from_str("termination_timeout_secs = 30").unwrap();
+        assert_eq!(config.termination_timeout(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_session_dir_template() {
+        assert_eq!(Config::default().session_dir_template().unwrap(), SessionDirTemplate::default());
+
+        let config: Config = toml::from_str("session_dir_template = \"{date}_{id}_{slug}\"").unwrap();
+        assert!(config.validate().is_ok());
+
+        let config: Config = toml::from_str("session_dir_template = \"{date}_{slug}\"").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_custom_roles() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[[roles]]\nname = \"QA\"\n\n[[roles]]\nname = \"TESTER\"\nprefix = \"TST\"\n").unwrap();
+
+        let config = Config::load_from_path(&path).unwrap();
+        assert_eq!(config.roles.len(), 2);
+        config.register_roles().unwrap();
+        assert_eq!("QA".parse::<Role>().unwrap().prefix(), "QA");
+        assert_eq!("tester".parse::<Role>().unwrap().prefix(), "TST");
+
+        fs::write(&path, "[[roles]]\nname = \"QA LEAD\"\n").unwrap();
+        assert!(Config::load_from_path(&path).is_err());
+    }
+
+    #[test]
+    fn test_role_working_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[role_working_dirs]\nARCHITECT = \"docs\"\ndeveloper = \"/srv/repo\"\n").unwrap();
+
+        let dirs = Config::load_from_path(&path).unwrap().role_working_dirs().unwrap();
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(dirs[&Role::Architect], PathBuf::from("docs"));
+        assert_eq!(dirs[&Role::Developer], PathBuf::from("/srv/repo"));
+
+        for bad in ["DEV = \"src\"", "NOBODY = \"src\"", "MANAGER = \"~other/x\""] {
+            fs::write(&path, format!("[role_working_dirs]\n{}\n", bad)).unwrap();
+            assert!(Config::load_from_path(&path).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "project = \"bad name\"\n").unwrap();
+
+        let result = Config::load_from_path(&path);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Output filters
+//!
+//! Hooks that see every stdout/stderr line of a session before it is echoed
+//! to the console or written to io.log. A filter may rewrite a line (e.g. to
+//! redact secrets) or drop it entirely. [`PromptDetector`] recognizes the
+//! permission prompts a session blocks on.
+
+use regex::Regex;
+use std::sync::Arc;
+
+use crate::types::error::{ClaudeManError, Result};
+
+/// Text that replaces each redacted match
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Default pattern for Claude's permission prompts
+pub const DEFAULT_APPROVAL_PROMPT_PATTERN: &str = r"(?i)(do you want to [^?]*\?|\[y/n\]|\(y/n\))";
+
+/// A line transform: returns the line to keep, or `None` to drop it
+pub type OutputFilter = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Filters applied to a session's output, in registration order
+#[derive(Clone, Default)]
+pub struct OutputFilters {
+    filters: Vec<OutputFilter>,
+}
+
+impl OutputFilters {
+    /// Add a filter after the existing ones
+    pub fn push(&mut self, filter: OutputFilter) {
+        self.filters.push(filter);
+    }
+
+    /// Check whether no filters are registered
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Run a line through every filter
+    ///
+    /// Returns `None` as soon as one filter drops the line.
+    pub fn apply(&self, line: String) -> Option<String> {
+        self.filters
+            .iter()
+            .try_fold(line, |line, filter| filter(&line))
+    }
+}
+
+impl std::fmt::Debug for OutputFilters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputFilters")
+            .field("filters", &self.filters.len())
+            .finish()
+    }
+}
+
+/// Built-in filter that masks every match of a set of regular expressions
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Compile redaction patterns
+    ///
+    /// Returns a config error naming the first invalid pattern.
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    ClaudeManError::Config(format!("Invalid redact pattern '{}': {}", pattern, e))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    /// Replace every match in `line` with [`REDACTED`]
+    pub fn redact(&self, line: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(line.to_string(), |line, pattern| {
+                pattern.replace_all(&line, REDACTED).into_owned()
+            })
+    }
+}
+
+/// Recognizes permission prompts in session output
+#[derive(Debug, Clone)]
+pub struct PromptDetector {
+    pattern: Regex,
+}
+
+impl Default for PromptDetector {
+    /// Detector for [`DEFAULT_APPROVAL_PROMPT_PATTERN`]
+    fn default() -> Self {
+        Self::new(DEFAULT_APPROVAL_PROMPT_PATTERN).expect("default approval prompt pattern is valid")
+    }
+}
+
+impl PromptDetector {
+    /// Compile a prompt pattern, returning a config error if it is invalid
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern = Regex::new(pattern).map_err(|e| {
+            ClaudeManError::Config(format!("Invalid approval prompt pattern '{}': {}", pattern, e))
+        })?;
+
+        Ok(Self { pattern })
+    }
+
+    /// Check whether a line asks for approval
+    pub fn is_prompt(&self, line: &str) -> bool {
+        self.pattern.is_match(line)
+    }
+}
+
+/// Recognizes the line a session prints once it is ready (`spawn --wait-for-ready`)
+#[derive(Debug, Clone)]
+pub struct ReadyMarker {
+    pattern: Regex,
+}
+
+impl ReadyMarker {
+    /// Compile a readiness pattern, returning an input error if it is invalid
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern = Regex::new(pattern).map_err(|e| {
+            ClaudeManError::InvalidInput(format!("Invalid readiness pattern '{}': {}", pattern, e))
+        })?;
+
+        Ok(Self { pattern })
+    }
+
+    /// Check whether a line shows the session is ready
+    pub fn is_ready(&self, line: &str) -> bool {
+        self.pattern.is_match(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redactor_masks_matches() {
+        let redactor =
+            Redactor::new(&["sk-[A-Za-z0-9]+".to_string(), "token=\\S+".to_string()]).unwrap();
+        assert_eq!(
+            redactor.redact("key sk-abc123 and token=xyz ok"),
+            "key [REDACTED] and [REDACTED] ok"
+        );
+        assert_eq!(redactor.redact("nothing secret"), "nothing secret");
+        assert!(Redactor::new(&["(".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_prompt_detector() {
+        let detector = PromptDetector::default();
+        assert!(detector.is_prompt("Do you want to make this edit to main.rs?"));
+        assert!(detector.is_prompt("Overwrite file? [y/N]"));
+        assert!(!detector.is_prompt("I want to refactor this module."));
+
+        let custom = PromptDetector::new("^Allow .* to run").unwrap();
+        assert!(custom.is_prompt("Allow Bash to run `ls`"));
+        assert!(PromptDetector::new("(").is_err());
+    }
+
+    #[test]
+    fn test_filters_apply_in_order_and_drop() {
+        let mut filters = OutputFilters::default();
+        filters.push(Arc::new(|line: &str| Some(line.to_uppercase())));
+        filters.push(Arc::new(|line: &str| {
+            (!line.starts_with("DEBUG")).then(|| line.to_string())
+        }));
+
+        assert_eq!(filters.apply("hello".to_string()).as_deref(), Some("HELLO"));
+        assert_eq!(filters.apply("debug noise".to_string()), None);
+    }
+}
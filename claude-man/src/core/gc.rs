@@ -0,0 +1,324 @@
+//! Maintenance of the claude-man home directory (`claude-man gc`)
+//!
+//! Finds session directories without valid metadata, metadata.json files
+//! stored in an older format, `.tmp` files left by interrupted writes, and
+//! io.log lines that aren't valid events. Repairs are only made on request;
+//! corrupt log lines are reported but never changed.
+
+use serde::Serialize;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::core::logger::{log_segments, IoEvent};
+use crate::types::error::Result;
+use crate::types::session::SessionMetadata;
+
+/// At most this many corrupt line numbers are listed per log
+const MAX_CORRUPT_LINES_LISTED: usize = 20;
+
+/// A problem found in the home directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GcIssue {
+    /// A session directory with no metadata.json
+    MissingMetadata { path: PathBuf },
+
+    /// A session directory whose metadata.json can't be read
+    InvalidMetadata { path: PathBuf, error: String },
+
+    /// A metadata.json that loads but differs from the current format
+    /// (fields added with defaults, or fields no longer used)
+    OutdatedMetadata { path: PathBuf },
+
+    /// A temporary file left by an interrupted write
+    TempFile { path: PathBuf },
+
+    /// An io.log (or rotated segment) with lines that aren't valid events
+    CorruptLog {
+        path: PathBuf,
+        /// Number of corrupt lines
+        count: usize,
+        /// 1-based numbers of the first corrupt lines
+        lines: Vec<usize>,
+    },
+}
+
+impl GcIssue {
+    /// The file or directory the issue is about
+    pub fn path(&self) -> &Path {
+        match self {
+            GcIssue::MissingMetadata { path }
+            | GcIssue::InvalidMetadata { path, .. }
+            | GcIssue::OutdatedMetadata { path }
+            | GcIssue::TempFile { path }
+            | GcIssue::CorruptLog { path, .. } => path,
+        }
+    }
+
+    /// Whether `--fix` repairs the issue
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, GcIssue::CorruptLog { .. })
+    }
+}
+
+impl std::fmt::Display for GcIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GcIssue::MissingMetadata { .. } => write!(f, "no metadata.json"),
+            GcIssue::InvalidMetadata { error, .. } => write!(f, "unreadable metadata.json: {}", error),
+            GcIssue::OutdatedMetadata { .. } => write!(f, "metadata in an older format"),
+            GcIssue::TempFile { .. } => write!(f, "leftover temporary file"),
+            GcIssue::CorruptLog { count, lines, .. } => {
+                let listed: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+                let more = if *count > lines.len() { ", ..." } else { "" };
+                write!(f, "{} corrupt line(s): {}{}", count, listed.join(", "), more)
+            }
+        }
+    }
+}
+
+/// What a gc run found and repaired
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    /// Session directories checked
+    pub sessions_checked: usize,
+
+    /// Problems found, in the order they were found
+    pub issues: Vec<GcIssue>,
+
+    /// Issues repaired (only with `fix`)
+    pub fixed: Vec<GcIssue>,
+}
+
+impl GcReport {
+    /// Issues found but not repaired
+    pub fn remaining(&self) -> usize {
+        self.issues.len() - self.fixed.len()
+    }
+
+    fn record(&mut self, issue: GcIssue, fixed: bool) {
+        if fixed {
+            self.fixed.push(issue.clone());
+        }
+        self.issues.push(issue);
+    }
+}
+
+/// Check the home directory (e.g. `.claude-man`) and the sessions directory
+/// (`.claude-man/sessions` unless moved with `--data-dir`)
+///
+/// With `fix`, directories without valid metadata and temporary files are
+/// removed, and outdated metadata is rewritten in the current format.
+pub fn collect_garbage(home: &Path, sessions_dir: &Path, fix: bool) -> Result<GcReport> {
+    let mut report = GcReport::default();
+
+    // Files like kv.json.tmp live in the home directory
+    if home.is_dir() {
+        check_temp_files(home, fix, &mut report)?;
+    }
+
+    if sessions_dir.is_dir() {
+        check_temp_files(sessions_dir, fix, &mut report)?;
+
+        let mut dirs: Vec<PathBuf> = fs::read_dir(sessions_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            report.sessions_checked += 1;
+            check_session_dir(&dir, fix, &mut report)?;
+        }
+    }
+
+    info!(
+        "gc checked {} session(s): {} issue(s), {} fixed",
+        report.sessions_checked,
+        report.issues.len(),
+        report.fixed.len()
+    );
+
+    Ok(report)
+}
+
+/// Report (and with `fix` remove) `*.tmp` files directly in `dir`
+fn check_temp_files(dir: &Path, fix: bool, report: &mut GcReport) -> Result<()> {
+    let mut temp_files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "tmp"))
+        .collect();
+    temp_files.sort();
+
+    for path in temp_files {
+        if fix {
+            fs::remove_file(&path)?;
+        }
+        report.record(GcIssue::TempFile { path }, fix);
+    }
+
+    Ok(())
+}
+
+fn check_session_dir(dir: &Path, fix: bool, report: &mut GcReport) -> Result<()> {
+    let metadata_path = dir.join("metadata.json");
+    if !metadata_path.exists() {
+        if fix {
+            fs::remove_dir_all(dir)?;
+        }
+        report.record(GcIssue::MissingMetadata { path: dir.to_path_buf() }, fix);
+        return Ok(());
+    }
+
+    let json = fs::read_to_string(&metadata_path)?;
+    let outdated = match outdated_metadata(&json) {
+        Ok(outdated) => outdated,
+        Err(error) => {
+            if fix {
+                fs::remove_dir_all(dir)?;
+            }
+            report.record(GcIssue::InvalidMetadata { path: dir.to_path_buf(), error }, fix);
+            return Ok(());
+        }
+    };
+
+    if let Some(metadata) = outdated {
+        if fix {
+            // Keep the file's layout: compact files are a single line
+            let rewritten = if json.trim_end().contains('\n') {
+                serde_json::to_string_pretty(&metadata)?
+            } else {
+                serde_json::to_string(&metadata)?
+            };
+            fs::write(&metadata_path, rewritten)?;
+        }
+        report.record(GcIssue::OutdatedMetadata { path: metadata_path }, fix);
+    }
+
+    check_temp_files(dir, fix, report)?;
+
+    for log_path in log_segments(dir) {
+        let (count, lines) = corrupt_log_lines(&log_path)?;
+        if count > 0 {
+            report.record(GcIssue::CorruptLog { path: log_path, count, lines }, false);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse stored metadata, returning it if the current format would store it differently
+fn outdated_metadata(json: &str) -> std::result::Result<Option<SessionMetadata>, String> {
+    let stored: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let metadata: SessionMetadata = serde_json::from_value(stored.clone()).map_err(|e| e.to_string())?;
+    let current = serde_json::to_value(&metadata).map_err(|e| e.to_string())?;
+
+    Ok((current != stored).then_some(metadata))
+}
+
+/// Count io.log lines that aren't events, listing the first few line numbers
+fn corrupt_log_lines(log_path: &Path) -> Result<(usize, Vec<usize>)> {
+    let reader = BufReader::new(fs::File::open(log_path)?);
+    let mut count = 0;
+    let mut lines = Vec::new();
+
+    for (index, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let text = String::from_utf8_lossy(&line);
+        if text.trim().is_empty() {
+            continue;
+        }
+        if serde_json::from_str::<IoEvent>(text.trim()).is_err() {
+            count += 1;
+            if lines.len() < MAX_CORRUPT_LINES_LISTED {
+                lines.push(index + 1);
+            }
+        }
+    }
+
+    Ok((count, lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Role, SessionId};
+
+    fn write_session(sessions_dir: &Path, name: &str) -> PathBuf {
+        let dir = sessions_dir.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let metadata = SessionMetadata::new(
+            SessionId::from_string(name.to_string()),
+            Role::Developer,
+            "test".to_string(),
+            dir.clone(),
+        );
+        fs::write(dir.join("metadata.json"), serde_json::to_string_pretty(&metadata).unwrap()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_gc_reports_then_fixes() {
+        let home = tempfile::tempdir().unwrap();
+        let sessions_dir = home.path().join("sessions");
+
+        let healthy = write_session(&sessions_dir, "DEV-001");
+        fs::write(healthy.join("io.log"), "").unwrap();
+
+        let orphan = sessions_dir.join("DEV-002");
+        fs::create_dir_all(&orphan).unwrap();
+
+        let corrupt = sessions_dir.join("DEV-003");
+        fs::create_dir_all(&corrupt).unwrap();
+        fs::write(corrupt.join("metadata.json"), "{ not json").unwrap();
+
+        // A field this version no longer has marks metadata from an older one
+        let outdated = write_session(&sessions_dir, "DEV-004");
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(outdated.join("metadata.json")).unwrap()).unwrap();
+        value["legacy_field"] = serde_json::json!(true);
+        fs::write(outdated.join("metadata.json"), serde_json::to_string(&value).unwrap()).unwrap();
+        let event = serde_json::to_string(&IoEvent::new(
+            crate::core::logger::IoEventType::Output,
+            "hi".to_string(),
+        ))
+        .unwrap();
+        fs::write(outdated.join("io.log"), format!("{}\n{{truncated\n{}\n", event, event)).unwrap();
+
+        fs::write(home.path().join("kv.json.tmp"), "{}").unwrap();
+
+        // Report only: nothing changes
+        let report = collect_garbage(home.path(), &home.path().join("sessions"), false).unwrap();
+        assert_eq!(report.sessions_checked, 4);
+        assert!(report.fixed.is_empty());
+        assert_eq!(
+            report.issues,
+            vec![
+                GcIssue::TempFile { path: home.path().join("kv.json.tmp") },
+                GcIssue::MissingMetadata { path: orphan.clone() },
+                GcIssue::InvalidMetadata {
+                    path: corrupt.clone(),
+                    error: "key must be a string at line 1 column 3".to_string(),
+                },
+                GcIssue::OutdatedMetadata { path: outdated.join("metadata.json") },
+                GcIssue::CorruptLog { path: outdated.join("io.log"), count: 1, lines: vec![2] },
+            ]
+        );
+        assert!(orphan.exists() && corrupt.exists());
+
+        // Fix: the corrupt log is only reported
+        let report = collect_garbage(home.path(), &home.path().join("sessions"), true).unwrap();
+        assert_eq!(report.fixed.len(), 4);
+        assert_eq!(report.remaining(), 1);
+        assert!(!orphan.exists() && !corrupt.exists());
+        assert!(!home.path().join("kv.json.tmp").exists());
+        let rewritten = fs::read_to_string(outdated.join("metadata.json")).unwrap();
+        assert!(!rewritten.contains("legacy_field") && !rewritten.contains('\n'));
+        assert!(healthy.exists());
+
+        let report = collect_garbage(home.path(), &home.path().join("sessions"), false).unwrap();
+        assert_eq!(report.sessions_checked, 2);
+        assert_eq!(report.issues.len(), 1);
+    }
+}
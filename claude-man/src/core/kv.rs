@@ -0,0 +1,143 @@
+//! Shared key/value store
+//!
+//! A blackboard sessions use to hand values (a chosen design, a file path)
+//! to each other without parsing logs. Keys live either in the global scope
+//! or in the scope of one session tree, named by the tree's root session.
+//! The store is a single JSON file, rewritten on every change.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::default_home_dir;
+use crate::types::error::Result;
+use crate::types::session::SessionId;
+
+/// Get the default store path
+pub fn default_kv_path() -> PathBuf {
+    default_home_dir().join("kv.json")
+}
+
+/// Contents of the store file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KvData {
+    /// Keys visible to every session
+    #[serde(default)]
+    global: BTreeMap<String, String>,
+
+    /// Keys scoped to a session tree, by root session ID
+    #[serde(default)]
+    trees: BTreeMap<SessionId, BTreeMap<String, String>>,
+}
+
+impl KvData {
+    fn scope(&self, tree: Option<&SessionId>) -> Option<&BTreeMap<String, String>> {
+        match tree {
+            Some(root) => self.trees.get(root),
+            None => Some(&self.global),
+        }
+    }
+}
+
+/// File-backed key/value store
+///
+/// Every call reads the file, so concurrent writers must be serialized by
+/// the caller (the daemon does this for all sessions).
+#[derive(Debug, Clone)]
+pub struct KvStore {
+    path: PathBuf,
+}
+
+impl Default for KvStore {
+    /// Store at the default path
+    fn default() -> Self {
+        Self::new(default_kv_path())
+    }
+}
+
+impl KvStore {
+    /// Create a store backed by `path` (created on first write)
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Path of the store file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Look up `key` in the global scope or the scope of the tree rooted at `tree`
+    pub fn get(&self, tree: Option<&SessionId>, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .load()?
+            .scope(tree)
+            .and_then(|scope| scope.get(key))
+            .cloned())
+    }
+
+    /// Set `key` in the global scope or the scope of the tree rooted at `tree`
+    ///
+    /// Returns the previous value, if any.
+    pub fn set(&self, tree: Option<&SessionId>, key: &str, value: &str) -> Result<Option<String>> {
+        let mut data = self.load()?;
+        let scope = match tree {
+            Some(root) => data.trees.entry(root.clone()).or_default(),
+            None => &mut data.global,
+        };
+        let previous = scope.insert(key.to_string(), value.to_string());
+        self.save(&data)?;
+
+        Ok(previous)
+    }
+
+    fn load(&self) -> Result<KvData> {
+        if !self.path.exists() {
+            return Ok(KvData::default());
+        }
+        let json = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Write via a temporary file so readers never see a partial store
+    fn save(&self, data: &KvData) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = tmp_path(&self.path);
+        fs::write(&tmp, serde_json::to_string_pretty(data)?)?;
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kv_scopes_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = KvStore::new(dir.path().join("kv.json"));
+        let tree = SessionId::from_string("MGR-001".to_string());
+
+        assert_eq!(store.get(None, "design").unwrap(), None);
+        assert_eq!(store.set(None, "design", "v1").unwrap(), None);
+        assert_eq!(store.set(Some(&tree), "design", "tree-only").unwrap(), None);
+        assert_eq!(store.set(None, "design", "v2").unwrap(), Some("v1".to_string()));
+
+        // A fresh handle sees the same file
+        let reopened = KvStore::new(dir.path().join("kv.json"));
+        assert_eq!(reopened.get(None, "design").unwrap().as_deref(), Some("v2"));
+        assert_eq!(reopened.get(Some(&tree), "design").unwrap().as_deref(), Some("tree-only"));
+        let other = SessionId::from_string("MGR-002".to_string());
+        assert_eq!(reopened.get(Some(&other), "design").unwrap(), None);
+    }
+}
@@ -2,18 +2,27 @@
 //!
 //! Logs all session I/O to JSONL format for debugging and future session resume.
 //! Log structure: `.claude-man/sessions/{SESSION_ID}/io.log`
+//!
+//! Once io.log reaches its size limit it is renamed to `io.log.1` (older
+//! segments move up to `io.log.2`, ...) and a fresh io.log is started.
 
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::Write;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
-use crate::types::error::Result;
+use crate::core::config::default_home_dir;
+use crate::types::error::{ClaudeManError, Result};
+use crate::types::role::Role;
 use crate::types::session::{SessionId, SessionStatus};
 
 /// Type of I/O event
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, clap::ValueEnum, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum IoEventType {
     /// Input sent to the session (stdin)
@@ -30,7 +39,7 @@ pub enum IoEventType {
 }
 
 /// A single I/O event logged to JSONL
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IoEvent {
     /// Timestamp when the event occurred
     pub timestamp: DateTime<Utc>,
@@ -76,14 +85,54 @@ impl IoEvent {
     }
 }
 
+/// When buffered log writes are flushed to disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// Flush after every event (most durable, one syscall per line)
+    #[default]
+    EveryLine,
+
+    /// Buffer events and flush once `max_lines` are pending or `max_delay`
+    /// has passed since the last flush. Lifecycle events always flush.
+    Batched {
+        max_lines: usize,
+        max_delay: Duration,
+    },
+}
+
+impl FlushPolicy {
+    /// Default pending-line threshold for batched flushing
+    pub const DEFAULT_BATCH_LINES: usize = 64;
+
+    /// Default delay threshold for batched flushing
+    pub const DEFAULT_BATCH_DELAY: Duration = Duration::from_millis(100);
+}
+
+/// Default size at which io.log is rotated (10 MiB)
+pub const DEFAULT_MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// A session logger shared by every writer to the same io.log
+pub type SharedLogger = Arc<Mutex<SessionLogger>>;
+
 /// Session I/O logger
 pub struct SessionLogger {
     session_id: SessionId,
-    log_file: File,
+    log_file: BufWriter<File>,
     log_path: PathBuf,
+    flush_policy: FlushPolicy,
+    max_line_bytes: Option<usize>,
+    max_size: u64,
+    size: u64,
+    pending: usize,
+    last_flush: Instant,
 }
 
 impl SessionLogger {
+    /// Wrap the logger so it can be shared between tasks
+    pub fn shared(self) -> SharedLogger {
+        Arc::new(Mutex::new(self))
+    }
+
     /// Create a new session logger
     ///
     /// Creates the log directory and opens the io.log file for appending
@@ -93,24 +142,101 @@ impl SessionLogger {
 
         let log_path = log_dir.join("io.log");
 
-        // Open log file in append mode
-        let log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)?;
+        let log_file = open_for_append(&log_path)?;
+        let size = log_file.metadata()?.len();
 
         Ok(Self {
             session_id,
-            log_file,
+            log_file: BufWriter::new(log_file),
             log_path,
+            flush_policy: FlushPolicy::default(),
+            max_line_bytes: None,
+            max_size: DEFAULT_MAX_LOG_BYTES,
+            size,
+            pending: 0,
+            last_flush: Instant::now(),
         })
     }
 
+    /// Set when buffered writes are flushed to disk
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Truncate logged output and error lines longer than `limit` bytes
+    pub fn with_max_line_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_line_bytes = limit;
+        self
+    }
+
+    /// Rotate io.log once it reaches `bytes` (default [`DEFAULT_MAX_LOG_BYTES`])
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Get the length past which output lines are truncated
+    pub fn max_line_bytes(&self) -> Option<usize> {
+        self.max_line_bytes
+    }
+
     /// Log an I/O event to the JSONL file
     pub fn log_event(&mut self, event: IoEvent) -> Result<()> {
         let json = serde_json::to_string(&event)?;
         writeln!(self.log_file, "{}", json)?;
+        self.pending += 1;
+        self.size += json.len() as u64 + 1;
+
+        if self.size >= self.max_size {
+            // Rotating flushes everything
+            self.rotate()
+        } else if event.event_type == IoEventType::Lifecycle {
+            self.flush()
+        } else {
+            self.flush_if_due()
+        }
+    }
+
+    /// Move io.log to io.log.1, shifting older segments up, and start a new io.log
+    fn rotate(&mut self) -> Result<()> {
+        self.flush()?;
+
+        let log_dir = self.log_path.parent().unwrap_or(Path::new("."));
+        let rotated = rotated_log_paths(log_dir);
+        for (index, path) in rotated.iter().enumerate() {
+            // Oldest first, so each moves into a free slot
+            std::fs::rename(path, rotated_log_path(log_dir, rotated.len() - index + 1))?;
+        }
+        std::fs::rename(&self.log_path, rotated_log_path(log_dir, 1))?;
+
+        self.log_file = BufWriter::new(open_for_append(&self.log_path)?);
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Flush pending events if the flush policy says they are due
+    pub fn flush_if_due(&mut self) -> Result<()> {
+        let due = match self.flush_policy {
+            FlushPolicy::EveryLine => self.pending > 0,
+            FlushPolicy::Batched { max_lines, max_delay } => {
+                self.pending >= max_lines
+                    || (self.pending > 0 && self.last_flush.elapsed() >= max_delay)
+            }
+        };
+
+        if due {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Flush all pending events to disk
+    pub fn flush(&mut self) -> Result<()> {
         self.log_file.flush()?;
+        self.pending = 0;
+        self.last_flush = Instant::now();
         Ok(())
     }
 
@@ -121,12 +247,28 @@ impl SessionLogger {
 
     /// Log output received from the session
     pub fn log_output(&mut self, content: String) -> Result<()> {
-        self.log_event(IoEvent::new(IoEventType::Output, content))
+        let event = self.limited_event(IoEventType::Output, content);
+        self.log_event(event)
     }
 
     /// Log error output received from the session
     pub fn log_error(&mut self, content: String) -> Result<()> {
-        self.log_event(IoEvent::new(IoEventType::Error, content))
+        let event = self.limited_event(IoEventType::Error, content);
+        self.log_event(event)
+    }
+
+    /// Build an event, truncating content past `max_line_bytes`
+    ///
+    /// A truncated event records the original length as `full_bytes`.
+    fn limited_event(&self, event_type: IoEventType, content: String) -> IoEvent {
+        match truncate_line(&content, self.max_line_bytes) {
+            Some(truncated) => IoEvent::with_metadata(
+                event_type,
+                truncated,
+                serde_json::json!({ "full_bytes": content.len() }),
+            ),
+            None => IoEvent::new(event_type, content),
+        }
     }
 
     /// Log a lifecycle event
@@ -145,14 +287,583 @@ impl SessionLogger {
     }
 }
 
+/// Health summary of a session's logged events
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LogStats {
+    /// Number of events of any type
+    pub total_events: usize,
+
+    /// Number of stdin events
+    pub input_events: usize,
+
+    /// Number of stdout events
+    pub output_events: usize,
+
+    /// Number of stderr events
+    pub error_events: usize,
+
+    /// Share of all events that are stderr events (0.0 to 1.0)
+    pub error_ratio: f64,
+
+    /// Average time between consecutive stdout events, in milliseconds
+    pub avg_output_gap_ms: Option<i64>,
+
+    /// Longest time between consecutive stdout events, in milliseconds
+    pub max_output_gap_ms: Option<i64>,
+
+    /// Time from the first to the last event, in milliseconds
+    pub span_ms: Option<i64>,
+
+    /// Stdout events per minute over the span (None for an empty span)
+    pub output_per_minute: Option<f64>,
+}
+
+impl LogStats {
+    /// Aggregate statistics over events in log order
+    pub fn from_events(events: &[IoEvent]) -> Self {
+        let count = |event_type: IoEventType| {
+            events.iter().filter(|e| e.event_type == event_type).count()
+        };
+
+        let output_times: Vec<DateTime<Utc>> = events
+            .iter()
+            .filter(|e| e.event_type == IoEventType::Output)
+            .map(|e| e.timestamp)
+            .collect();
+        let gaps: Vec<i64> = output_times
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).num_milliseconds())
+            .collect();
+
+        let error_events = count(IoEventType::Error);
+        let span_ms = match (events.first(), events.last()) {
+            (Some(first), Some(last)) => Some((last.timestamp - first.timestamp).num_milliseconds()),
+            _ => None,
+        };
+
+        Self {
+            total_events: events.len(),
+            input_events: count(IoEventType::Input),
+            output_events: output_times.len(),
+            error_events,
+            error_ratio: if events.is_empty() {
+                0.0
+            } else {
+                error_events as f64 / events.len() as f64
+            },
+            avg_output_gap_ms: (!gaps.is_empty())
+                .then(|| gaps.iter().sum::<i64>() / gaps.len() as i64),
+            max_output_gap_ms: gaps.iter().copied().max(),
+            span_ms,
+            output_per_minute: span_ms
+                .filter(|&ms| ms > 0)
+                .map(|ms| output_times.len() as f64 * 60_000.0 / ms as f64),
+        }
+    }
+}
+
+/// Read every event from a session's log segments, skipping malformed lines
+pub fn read_events(log_dir: &Path) -> Result<Vec<IoEvent>> {
+    SessionLogReader::open(log_dir).collect()
+}
+
+/// Streams a session's events from its log segments in the order they were
+/// written, one line at a time, skipping malformed lines
+pub struct SessionLogReader {
+    /// Segments not opened yet, oldest first
+    segments: std::vec::IntoIter<PathBuf>,
+    current: Option<std::io::Split<BufReader<File>>>,
+}
+
+impl SessionLogReader {
+    /// Read the segments present in `log_dir` now
+    pub fn open(log_dir: &Path) -> Self {
+        Self { segments: log_segments(log_dir).into_iter(), current: None }
+    }
+
+    /// Collect the events with `start <= timestamp < end`
+    ///
+    /// Events are logged in time order, so reading stops at the first event
+    /// at or past `end`; later segments aren't opened.
+    pub fn events_between(self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<IoEvent>> {
+        let mut events = Vec::new();
+        for event in self {
+            let event = event?;
+            if event.timestamp >= end {
+                break;
+            }
+            if event.timestamp >= start {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+}
+
+impl Iterator for SessionLogReader {
+    type Item = Result<IoEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let lines = match &mut self.current {
+                Some(lines) => lines,
+                None => {
+                    let file = match File::open(self.segments.next()?) {
+                        Ok(file) => file,
+                        Err(e) => return Some(Err(e.into())),
+                    };
+                    self.current.insert(BufReader::new(file).split(b'\n'))
+                }
+            };
+
+            match lines.next() {
+                Some(Ok(line)) => {
+                    if let Ok(event) = serde_json::from_str::<IoEvent>(String::from_utf8_lossy(&line).trim()) {
+                        return Some(Ok(event));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => self.current = None,
+            }
+        }
+    }
+}
+
+fn open_for_append(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// Path of the `index`th rotated segment (`io.log.1` is the newest)
+fn rotated_log_path(log_dir: &Path, index: usize) -> PathBuf {
+    log_dir.join(format!("io.log.{}", index))
+}
+
+/// Rotated segments of a session's log, oldest first
+pub fn rotated_log_paths(log_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = (1..)
+        .map(|index| rotated_log_path(log_dir, index))
+        .take_while(|path| path.exists())
+        .collect();
+    paths.reverse();
+    paths
+}
+
+/// All of a session's log files in the order they were written: rotated
+/// segments oldest first, then io.log
+pub fn log_segments(log_dir: &Path) -> Vec<PathBuf> {
+    let mut segments = rotated_log_paths(log_dir);
+    let current = log_dir.join("io.log");
+    if current.exists() {
+        segments.push(current);
+    }
+    segments
+}
+
+/// Reads the lines added to a session's io.log, continuing in the new
+/// io.log after a rotation
+pub struct LogTail {
+    path: PathBuf,
+    file: Option<File>,
+    pos: u64,
+}
+
+impl LogTail {
+    /// Read a session's whole log, returning a tail that continues after it
+    ///
+    /// The log doesn't have to exist yet.
+    pub fn open(log_dir: &Path) -> Result<(Self, Vec<String>)> {
+        let path = log_dir.join("io.log");
+        // Opened first, so lines logged while the rotated segments are read
+        // are read by the tail
+        let file = match File::open(&path) {
+            Ok(file) => Some(file),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = Vec::new();
+        for segment in rotated_log_paths(log_dir) {
+            let reader = BufReader::new(File::open(segment)?);
+            for line in reader.split(b'\n') {
+                lines.push(String::from_utf8_lossy(&line?).into_owned());
+            }
+        }
+
+        let mut tail = Self { path, file, pos: 0 };
+        lines.extend(tail.read_lines()?);
+        Ok((tail, lines))
+    }
+
+    /// Continue from byte `pos` of `file`, an open handle to the session's io.log
+    pub fn from_position(log_dir: &Path, file: File, pos: u64) -> Self {
+        Self {
+            path: log_dir.join("io.log"),
+            file: Some(file),
+            pos,
+        }
+    }
+
+    /// Read the complete lines logged since the last read
+    pub fn read_lines(&mut self) -> Result<Vec<String>> {
+        let mut lines = Vec::new();
+
+        loop {
+            if self.file.is_none() {
+                match File::open(&self.path) {
+                    Ok(file) => self.file = Some(file),
+                    // Not created yet, or between a rotation's rename and reopen
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(lines),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            // Checked before reading: a rotated-away file doesn't grow any more
+            let rotated = self.rotated()?;
+
+            let Some(file) = self.file.as_mut() else {
+                return Ok(lines);
+            };
+            if file.metadata()?.len() < self.pos {
+                // Truncated or rewritten; start again from the top
+                self.pos = 0;
+            }
+            file.seek(SeekFrom::Start(self.pos))?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+
+            // Only complete lines; a partial one waits for the rest
+            let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |end| end + 1);
+            self.pos += complete as u64;
+            lines.extend(String::from_utf8_lossy(&buf[..complete]).lines().map(str::to_string));
+
+            if !rotated {
+                return Ok(lines);
+            }
+            self.file = None;
+            self.pos = 0;
+        }
+    }
+
+    /// Whether the open file is no longer the session's io.log
+    #[cfg(unix)]
+    fn rotated(&self) -> Result<bool> {
+        use std::os::unix::fs::MetadataExt;
+
+        let Some(file) = &self.file else {
+            return Ok(false);
+        };
+        match std::fs::metadata(&self.path) {
+            Ok(current) => {
+                let open = file.metadata()?;
+                Ok((open.dev(), open.ino()) != (current.dev(), current.ino()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Whether the open file is no longer the session's io.log
+    ///
+    /// Without inode numbers a rotation shows as io.log being shorter than
+    /// what was read.
+    #[cfg(not(unix))]
+    fn rotated(&self) -> Result<bool> {
+        match std::fs::metadata(&self.path) {
+            Ok(current) => Ok(self.file.is_some() && current.len() < self.pos),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Which events of a log to show: the last `lines` of the selected types
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogSelection {
+    /// Number of events to keep from the end (0 for all)
+    pub lines: usize,
+
+    /// Only keep events of these types (all types if empty)
+    pub event_types: Vec<IoEventType>,
+}
+
+impl LogSelection {
+    /// Select the last `lines` events of any type
+    pub fn new(lines: usize) -> Self {
+        Self { lines, event_types: Vec::new() }
+    }
+
+    /// Only select events of these types
+    pub fn with_event_types(mut self, event_types: Vec<IoEventType>) -> Self {
+        self.event_types = event_types;
+        self
+    }
+
+    /// Check whether an event is of a selected type
+    pub fn matches(&self, event: &IoEvent) -> bool {
+        self.event_types.is_empty() || self.event_types.contains(&event.event_type)
+    }
+
+    /// Keep the selected events, counting `lines` among the matching ones
+    pub fn apply(&self, mut events: Vec<IoEvent>) -> Vec<IoEvent> {
+        events.retain(|event| self.matches(event));
+        if self.lines > 0 {
+            events.drain(..events.len().saturating_sub(self.lines));
+        }
+        events
+    }
+}
+
+/// Recent conversation formatted for replay to a resumed session
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationRecap {
+    /// Number of input/output events included
+    pub events: usize,
+
+    /// The recap text, one `User:`/`Assistant:` line per event
+    pub text: String,
+}
+
+/// Format the last `count` input/output events as a conversation recap
+///
+/// Used to re-prime a resumed session whose own context was lost. Returns
+/// `None` when there is no conversation to replay.
+pub fn conversation_recap(events: &[IoEvent], count: usize) -> Option<ConversationRecap> {
+    let conversation: Vec<&IoEvent> = events
+        .iter()
+        .filter(|e| matches!(e.event_type, IoEventType::Input | IoEventType::Output))
+        .collect();
+    let recent = &conversation[conversation.len().saturating_sub(count)..];
+    if recent.is_empty() {
+        return None;
+    }
+
+    let mut text = format!(
+        "Recap of the previous conversation (last {} events):\n",
+        recent.len()
+    );
+    for event in recent {
+        text.push_str(&conversation_line(event));
+    }
+    Some(ConversationRecap {
+        events: recent.len(),
+        text,
+    })
+}
+
+/// Largest transcript `spawn --context-from` prepends to a task by default
+pub const DEFAULT_CONTEXT_BYTES: usize = 16 * 1024;
+
+/// Room left for the truncation marker when the newest line alone is too long
+const TRUNCATION_MARKER_BYTES: usize = 32;
+
+/// Format `source`'s conversation as a transcript for priming a new session
+///
+/// Keeps the most recent input/output events that fit in `max_bytes`,
+/// header included; if even the newest doesn't fit, it is truncated.
+/// Returns `None` when there is no conversation.
+pub fn conversation_transcript(source: &SessionId, events: &[IoEvent], max_bytes: usize) -> Option<ConversationRecap> {
+    let conversation: Vec<&IoEvent> = events
+        .iter()
+        .filter(|e| matches!(e.event_type, IoEventType::Input | IoEventType::Output))
+        .collect();
+    let header = |count: usize| format!("Transcript of session {} (last {} events):\n", source, count);
+
+    // The header is sized for every event so the final count can't overflow it
+    let mut budget = max_bytes.saturating_sub(header(conversation.len()).len());
+    let mut lines = Vec::new();
+    for event in conversation.iter().rev() {
+        let line = conversation_line(event);
+        if line.len() > budget {
+            if lines.is_empty() && budget > TRUNCATION_MARKER_BYTES {
+                let cut = truncate_line(line.trim_end(), Some(budget - TRUNCATION_MARKER_BYTES));
+                lines.push(cut.map(|cut| cut + "\n").unwrap_or(line));
+            }
+            break;
+        }
+        budget -= line.len();
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut text = header(lines.len());
+    text.extend(lines.iter().rev().map(String::as_str));
+    Some(ConversationRecap { events: lines.len(), text })
+}
+
+/// One `User:`/`Assistant:` line of a recap or transcript
+fn conversation_line(event: &IoEvent) -> String {
+    let speaker = match event.event_type {
+        IoEventType::Input => "User",
+        _ => "Assistant",
+    };
+    format!("{}: {}\n", speaker, event.content)
+}
+
+/// Shorten a line longer than `max_bytes`, marking how much was cut
+///
+/// Returns `None` when the line fits (or there is no limit). The cut falls on
+/// a character boundary at or before `max_bytes`.
+pub fn truncate_line(line: &str, max_bytes: Option<usize>) -> Option<String> {
+    let max_bytes = max_bytes.filter(|&max_bytes| line.len() > max_bytes)?;
+    let mut cut = max_bytes;
+    while !line.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    Some(format!("{}…[truncated {} bytes]", &line[..cut], line.len() - cut))
+}
+
+/// Sessions directory chosen for this process with [`init_data_dir`]
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Store sessions in `dir` instead of `.claude-man/sessions` (`--data-dir`)
+///
+/// Only the first call has an effect.
+pub fn init_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR.set(dir);
+}
+
 /// Get the default log directory for sessions
 pub fn default_log_dir() -> PathBuf {
-    PathBuf::from(".claude-man").join("sessions")
+    DATA_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| default_home_dir().join("sessions"))
 }
 
 /// Get the log directory for a specific session
+///
+/// Directories named by a custom [`SessionDirTemplate`] are found by reading
+/// each `metadata.json`. If the session has no directory yet, this is the
+/// ID-named path.
 pub fn session_log_dir(session_id: &SessionId) -> PathBuf {
-    default_log_dir().join(session_id.as_str())
+    session_log_dir_in(&default_log_dir(), session_id)
+}
+
+/// Get the log directory for a session stored under `sessions_dir`
+///
+/// Like [`session_log_dir`], for a sessions directory other than the default.
+pub fn session_log_dir_in(sessions_dir: &Path, session_id: &SessionId) -> PathBuf {
+    let by_id = sessions_dir.join(session_id.as_str());
+    if by_id.exists() {
+        return by_id;
+    }
+    find_session_dir(sessions_dir, session_id).unwrap_or(by_id)
+}
+
+/// Find the directory under `sessions_dir` whose metadata names `session_id`
+pub fn find_session_dir(sessions_dir: &Path, session_id: &SessionId) -> Option<PathBuf> {
+    /// The one metadata field needed to recognize a session
+    #[derive(Deserialize)]
+    struct MetadataId {
+        id: SessionId,
+    }
+
+    std::fs::read_dir(sessions_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("metadata.json"))
+                .ok()
+                .and_then(|json| serde_json::from_str::<MetadataId>(&json).ok())
+                .is_some_and(|metadata| &metadata.id == session_id)
+        })
+}
+
+/// Template for naming new session directories under [`default_log_dir`]
+///
+/// Placeholders are `{id}` (required, so names stay unique), `{role}`,
+/// `{date}` (`2024-06-01`), `{time}` (`153000`, UTC) and `{slug}` (the task,
+/// lowercased and hyphenated). The default is `{id}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDirTemplate(String);
+
+impl Default for SessionDirTemplate {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_string())
+    }
+}
+
+impl SessionDirTemplate {
+    /// Name directories by session ID alone
+    pub const DEFAULT: &'static str = "{id}";
+
+    const PLACEHOLDERS: [&'static str; 5] = ["{id}", "{role}", "{date}", "{time}", "{slug}"];
+
+    /// Longest `{slug}` in characters
+    const MAX_SLUG_LEN: usize = 40;
+
+    /// Parse a template, rejecting unknown placeholders and path separators
+    pub fn new(template: &str) -> Result<Self> {
+        if !template.contains("{id}") {
+            return Err(ClaudeManError::Config(format!(
+                "session_dir_template must contain {{id}}: {:?}",
+                template
+            )));
+        }
+
+        let literal = Self::PLACEHOLDERS
+            .iter()
+            .fold(template.to_string(), |rest, placeholder| rest.replace(placeholder, ""));
+        if literal.contains(['{', '}']) {
+            return Err(ClaudeManError::Config(format!(
+                "Unknown placeholder in session_dir_template {:?} (use {})",
+                template,
+                Self::PLACEHOLDERS.join(", ")
+            )));
+        }
+        if literal.contains(['/', '\\']) || literal.contains("..") {
+            return Err(ClaudeManError::Config(format!(
+                "session_dir_template must name a single directory: {:?}",
+                template
+            )));
+        }
+
+        Ok(Self(template.to_string()))
+    }
+
+    /// Directory name for a new session
+    pub fn render(
+        &self,
+        session_id: &SessionId,
+        role: Role,
+        task: &str,
+        created_at: DateTime<Utc>,
+    ) -> String {
+        self.0
+            .replace("{id}", session_id.as_str())
+            .replace("{role}", &role.to_string())
+            .replace("{date}", &created_at.format("%Y-%m-%d").to_string())
+            .replace("{time}", &created_at.format("%H%M%S").to_string())
+            .replace("{slug}", &slugify(task, Self::MAX_SLUG_LEN))
+    }
+}
+
+impl std::fmt::Display for SessionDirTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Lowercase `text`, joining runs of letters and digits with single hyphens
+fn slugify(text: &str, max_len: usize) -> String {
+    let mut slug = String::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let word = word.to_lowercase();
+        let needed = word.chars().count() + usize::from(!slug.is_empty());
+        if slug.chars().count() + needed > max_len {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    slug
 }
 
 #[cfg(test)]
@@ -161,6 +872,89 @@ mod tests {
     use tempfile::TempDir;
     use std::fs;
 
+    #[test]
+    fn test_conversation_recap() {
+        let events = vec![
+            IoEvent::new(IoEventType::Output, "hello".to_string()),
+            IoEvent::new(IoEventType::Input, "use JWT".to_string()),
+            IoEvent::new(IoEventType::Error, "warning".to_string()),
+            IoEvent::lifecycle(SessionStatus::Completed, "done".to_string()),
+            IoEvent::new(IoEventType::Output, "ok, using JWT".to_string()),
+        ];
+
+        let recap = conversation_recap(&events, 2).unwrap();
+        assert_eq!(recap.events, 2);
+        assert_eq!(
+            recap.text,
+            "Recap of the previous conversation (last 2 events):\nUser: use JWT\nAssistant: ok, using JWT\n"
+        );
+        assert_eq!(conversation_recap(&events, 10).unwrap().events, 3);
+        assert_eq!(conversation_recap(&events, 0), None);
+    }
+
+    #[test]
+    fn test_conversation_transcript() {
+        let source = SessionId::from_string("DEV-001".to_string());
+        let events = vec![
+            IoEvent::new(IoEventType::Output, "hello".to_string()),
+            IoEvent::new(IoEventType::Error, "warning".to_string()),
+            IoEvent::new(IoEventType::Input, "use JWT".to_string()),
+            IoEvent::new(IoEventType::Output, "ok, using JWT".to_string()),
+        ];
+
+        let full = conversation_transcript(&source, &events, DEFAULT_CONTEXT_BYTES).unwrap();
+        assert_eq!(full.events, 3);
+        assert_eq!(
+            full.text,
+            "Transcript of session DEV-001 (last 3 events):\nAssistant: hello\nUser: use JWT\nAssistant: ok, using JWT\n"
+        );
+
+        // Older lines are dropped to stay within the cap
+        let capped = conversation_transcript(&source, &events, full.text.len() - 1).unwrap();
+        assert_eq!(capped.events, 2);
+        assert!(capped.text.len() < full.text.len());
+        assert!(capped.text.ends_with("User: use JWT\nAssistant: ok, using JWT\n"), "{}", capped.text);
+
+        // A newest line longer than the cap is truncated
+        let long = vec![IoEvent::new(IoEventType::Output, "x".repeat(1000))];
+        let truncated = conversation_transcript(&source, &long, 200).unwrap();
+        assert!(truncated.text.len() <= 200, "{}", truncated.text.len());
+        assert!(truncated.text.contains("[truncated"));
+
+        assert_eq!(conversation_transcript(&source, &events[1..2], DEFAULT_CONTEXT_BYTES), None);
+    }
+
+    #[test]
+    fn test_log_selection_by_type() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_id = SessionId::new(Role::Developer, 1);
+        let mut logger = SessionLogger::new(session_id, temp_dir.path()).unwrap();
+        logger.log_lifecycle(SessionStatus::Running, "started".to_string()).unwrap();
+        logger.log_output("first".to_string()).unwrap();
+        logger.log_error("oops".to_string()).unwrap();
+        logger.log_input("retry".to_string()).unwrap();
+        logger.log_error("oops again".to_string()).unwrap();
+        logger.log_output("second".to_string()).unwrap();
+        logger.flush().unwrap();
+        let events = read_events(temp_dir.path()).unwrap();
+
+        let contents = |selection: LogSelection| -> Vec<String> {
+            selection.apply(events.clone()).into_iter().map(|event| event.content).collect()
+        };
+        assert_eq!(contents(LogSelection::new(0)).len(), 6);
+        assert_eq!(
+            contents(LogSelection::new(0).with_event_types(vec![IoEventType::Error])),
+            ["oops", "oops again"]
+        );
+        assert_eq!(
+            contents(LogSelection::new(2).with_event_types(vec![IoEventType::Error, IoEventType::Input])),
+            ["retry", "oops again"]
+        );
+        assert!(!LogSelection::new(0)
+            .with_event_types(vec![IoEventType::Output])
+            .matches(&events[0]));
+    }
+
     #[test]
     fn test_io_event_creation() {
         let event = IoEvent::new(IoEventType::Input, "test input".to_string());
@@ -190,6 +984,37 @@ mod tests {
         assert_eq!(deserialized.content, "test output");
     }
 
+    #[test]
+    fn test_log_stats() {
+        let start = Utc::now();
+        let at = |ms: i64, event_type: IoEventType| IoEvent {
+            timestamp: start + chrono::Duration::milliseconds(ms),
+            event_type,
+            content: String::new(),
+            metadata: None,
+        };
+        let events = vec![
+            at(0, IoEventType::Lifecycle),
+            at(100, IoEventType::Output),
+            at(300, IoEventType::Output),
+            at(350, IoEventType::Error),
+            at(1300, IoEventType::Output),
+        ];
+
+        let stats = LogStats::from_events(&events);
+        assert_eq!(stats.total_events, 5);
+        assert_eq!(stats.output_events, 3);
+        assert_eq!(stats.error_events, 1);
+        assert!((stats.error_ratio - 0.2).abs() < f64::EPSILON);
+        assert_eq!(stats.avg_output_gap_ms, Some(600));
+        assert_eq!(stats.max_output_gap_ms, Some(1000));
+        assert_eq!(stats.span_ms, Some(1300));
+        let rate = stats.output_per_minute.unwrap();
+        assert!((rate - 3.0 * 60.0 / 1.3).abs() < 1e-9, "{}", rate);
+
+        assert_eq!(LogStats::from_events(&[]), LogStats::default());
+    }
+
     #[test]
     fn test_session_logger_creation() {
         let temp_dir = TempDir::new().unwrap();
@@ -228,6 +1053,136 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_batched_flush_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().join("DEV-004");
+        let session_id = SessionId::from_string("DEV-004".to_string());
+
+        let mut logger = SessionLogger::new(session_id, &log_dir)
+            .unwrap()
+            .with_flush_policy(FlushPolicy::Batched {
+                max_lines: 3,
+                max_delay: Duration::from_secs(3600),
+            });
+
+        let line_count = |logger: &SessionLogger| {
+            fs::read_to_string(logger.log_path()).unwrap().lines().count()
+        };
+
+        logger.log_output("one".to_string()).unwrap();
+        logger.log_output("two".to_string()).unwrap();
+        assert_eq!(line_count(&logger), 0);
+
+        logger.log_output("three".to_string()).unwrap();
+        assert_eq!(line_count(&logger), 3);
+
+        // Lifecycle events always flush
+        logger.log_output("four".to_string()).unwrap();
+        logger.log_lifecycle(SessionStatus::Completed, "done".to_string()).unwrap();
+        assert_eq!(line_count(&logger), 5);
+    }
+
+    #[test]
+    fn test_events_between() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_dir = temp_dir.path();
+        let start = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let line = |secs: i64| {
+            let mut event = IoEvent::new(IoEventType::Output, format!("at {}", secs));
+            event.timestamp = start + chrono::Duration::seconds(secs);
+            serde_json::to_string(&event).unwrap()
+        };
+        let lines = |secs: &[i64]| secs.iter().map(|&s| line(s) + "\n").collect::<String>();
+
+        // The window spans a rotation; a malformed line is skipped
+        fs::write(log_dir.join("io.log.1"), lines(&[0, 10, 20]) + "{truncated\n").unwrap();
+        fs::write(log_dir.join("io.log"), lines(&[30, 40, 50])).unwrap();
+
+        let content = |events: Vec<IoEvent>| events.into_iter().map(|e| e.content).collect::<Vec<_>>();
+        let window = SessionLogReader::open(log_dir)
+            .events_between(start + chrono::Duration::seconds(10), start + chrono::Duration::seconds(40))
+            .unwrap();
+        assert_eq!(content(window), vec!["at 10", "at 20", "at 30"]);
+        assert_eq!(SessionLogReader::open(log_dir).count(), 6);
+
+        // Reading stops at the end of the window: the unreadable io.log is never opened
+        fs::remove_file(log_dir.join("io.log")).unwrap();
+        fs::create_dir(log_dir.join("io.log")).unwrap();
+        let window = SessionLogReader::open(log_dir)
+            .events_between(start, start + chrono::Duration::seconds(15))
+            .unwrap();
+        assert_eq!(content(window), vec!["at 0", "at 10"]);
+        assert!(SessionLogReader::open(log_dir)
+            .events_between(start, start + chrono::Duration::seconds(60))
+            .is_err());
+    }
+
+    #[test]
+    fn test_log_rotation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_dir = temp_dir.path();
+        let session_id = SessionId::new(Role::Developer, 1);
+        let mut logger = SessionLogger::new(session_id, log_dir).unwrap().with_max_size(200);
+        let (mut tail, history) = LogTail::open(log_dir).unwrap();
+        assert!(history.is_empty());
+
+        // Each event is about 80 bytes, so io.log rotates after every third
+        for i in 0..4 {
+            logger.log_output(format!("line {}", i)).unwrap();
+        }
+        assert!(log_dir.join("io.log.1").exists());
+        assert!(!log_dir.join("io.log.2").exists());
+        let followed = tail.read_lines().unwrap();
+
+        for i in 4..7 {
+            logger.log_output(format!("line {}", i)).unwrap();
+        }
+        assert_eq!(
+            log_segments(log_dir),
+            vec![log_dir.join("io.log.2"), log_dir.join("io.log.1"), log_dir.join("io.log")]
+        );
+        assert!(fs::metadata(log_dir.join("io.log.2")).unwrap().len() >= 200);
+
+        // Readers see every event in order, across the segments
+        let contents: Vec<String> = read_events(log_dir).unwrap().into_iter().map(|e| e.content).collect();
+        let expected: Vec<String> = (0..7).map(|i| format!("line {}", i)).collect();
+        assert_eq!(contents, expected);
+
+        let followed: Vec<String> = followed
+            .into_iter()
+            .chain(tail.read_lines().unwrap())
+            .map(|line| serde_json::from_str::<IoEvent>(&line).unwrap().content)
+            .collect();
+        assert_eq!(followed, expected);
+
+        let (_, history) = LogTail::open(log_dir).unwrap();
+        assert_eq!(history.len(), 7);
+    }
+
+    #[test]
+    fn test_long_lines_truncated() {
+        assert_eq!(truncate_line("short", Some(10)), None);
+        assert_eq!(truncate_line("no limit at all", None), None);
+        assert_eq!(truncate_line("abcdefghij", Some(4)).as_deref(), Some("abcd…[truncated 6 bytes]"));
+        // Never splits a multi-byte character
+        assert_eq!(truncate_line("aé€", Some(4)).as_deref(), Some("aé…[truncated 3 bytes]"));
+
+        let temp_dir = TempDir::new().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        let mut logger = SessionLogger::new(session_id, temp_dir.path())
+            .unwrap()
+            .with_max_line_bytes(Some(8));
+        logger.log_output("x".repeat(100)).unwrap();
+        logger.log_error("fits".to_string()).unwrap();
+
+        let events = read_events(temp_dir.path()).unwrap();
+        assert_eq!(events[0].content, "xxxxxxxx…[truncated 92 bytes]");
+        assert_eq!(events[0].metadata.as_ref().unwrap()["full_bytes"], 100);
+        assert_eq!(events[1].content, "fits");
+        assert!(events[1].metadata.is_none());
+    }
+
     #[test]
     fn test_session_log_dir() {
         let session_id = SessionId::from_string("DEV-003".to_string());
@@ -236,4 +1191,39 @@ mod tests {
         assert!(log_dir.to_string_lossy().contains("DEV-003"));
         assert!(log_dir.to_string_lossy().contains(".claude-man"));
     }
+
+    #[test]
+    fn test_find_session_dir_reads_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let named = temp_dir.path().join("2024-06-01_DEV-003_implement-auth");
+        fs::create_dir_all(&named).unwrap();
+        fs::write(named.join("metadata.json"), r#"{"id": "DEV-003", "role": "DEVELOPER"}"#).unwrap();
+        fs::create_dir_all(temp_dir.path().join("stray")).unwrap();
+
+        let id = SessionId::from_string("DEV-003".to_string());
+        assert_eq!(find_session_dir(temp_dir.path(), &id), Some(named));
+        let other = SessionId::from_string("DEV-004".to_string());
+        assert_eq!(find_session_dir(temp_dir.path(), &other), None);
+    }
+
+    #[test]
+    fn test_session_dir_template() {
+        let id = SessionId::from_string("DEV-001".to_string());
+        let created_at = DateTime::parse_from_rfc3339("2024-06-01T15:30:00Z").unwrap().with_timezone(&Utc);
+        let render = |template: &str, task: &str| {
+            SessionDirTemplate::new(template).unwrap().render(&id, Role::Developer, task, created_at)
+        };
+
+        assert_eq!(render(SessionDirTemplate::DEFAULT, "x"), "DEV-001");
+        assert_eq!(
+            render("{date}_{id}_{slug}", "Implement auth (JWT, OAuth)!"),
+            "2024-06-01_DEV-001_implement-auth-jwt-oauth"
+        );
+        assert_eq!(render("{time}-{id}", "x"), "153000-DEV-001");
+        assert_eq!(slugify("one two three", 9), "one-two");
+
+        assert!(SessionDirTemplate::new("{date}_{slug}").is_err());
+        assert!(SessionDirTemplate::new("{id}_{user}").is_err());
+        assert!(SessionDirTemplate::new("{date}/{id}").is_err());
+    }
 }
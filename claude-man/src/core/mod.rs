@@ -2,15 +2,30 @@
 //!
 //! This module contains the core business logic including:
 //! - Authentication (via Claude CLI)
+//! - Backup and restore
+//! - Configuration
+//! - Output filtering
+//! - Home directory maintenance (gc)
 //! - Process management
+//! - Result capture
 //! - Session management
+//! - Shared key/value store
+//! - Process resource usage
 //! - I/O logging
 
 pub mod auth;
+pub mod backup;
+pub mod config;
+pub mod filter;
+pub mod gc;
+pub mod kv;
 pub mod logger;
 pub mod process;
+pub mod result;
 pub mod session;
+pub mod usage;
 
 // Re-export commonly used items
+pub use config::Config;
 pub use logger::SessionLogger;
-pub use session::{SessionHandle, SessionRegistry};
+pub use session::{SessionHandle, SessionRegistry, SpawnOptions};
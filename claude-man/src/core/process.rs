@@ -3,24 +3,210 @@
 //! Handles spawning, monitoring, and terminating Claude Code CLI processes.
 //! Ensures proper cleanup and prevents orphaned processes.
 
+use encoding_rs::Encoding;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
-#[cfg(unix)]
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
-#[cfg(unix)]
 use tokio::time::timeout;
 
-use crate::core::logger::SessionLogger;
+use crate::core::filter::OutputFilters;
+use crate::core::logger::{truncate_line, SharedLogger};
 use crate::types::error::{ClaudeManError, Result};
 use crate::types::session::{SessionId, SessionStatus};
 
-/// Default timeout for graceful process termination (in seconds)
+/// How often the monitor checks whether batched log writes are due
+const LOG_FLUSH_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Default time a process gets to exit after SIGTERM before it is SIGKILLed
+pub const DEFAULT_TERMINATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the monitor keeps reading stderr after stdout has closed
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How long an `--on-complete` hook may run before it is killed
+pub const COMPLETION_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often [`wait_for_exit`] checks whether a process is gone
 #[cfg(unix)]
-const TERMINATION_TIMEOUT_SECS: u64 = 5;
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How input sent to a session is terminated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InputNewline {
+    /// Append `\n`
+    #[default]
+    Lf,
+
+    /// Append `\r\n`, for prompts that expect a carriage return
+    Crlf,
+
+    /// Send the text as-is
+    None,
+
+    /// Append `\n\n`, for prompts that submit on an empty line
+    Double,
+}
+
+impl InputNewline {
+    /// The terminator appended to input
+    pub fn terminator(self) -> &'static str {
+        match self {
+            InputNewline::Lf => "\n",
+            InputNewline::Crlf => "\r\n",
+            InputNewline::None => "",
+            InputNewline::Double => "\n\n",
+        }
+    }
+}
+
+/// How [`monitor_process`] handles a process's output and run time
+#[derive(Clone)]
+pub struct MonitorOptions {
+    /// Charset the output is decoded from
+    pub encoding: &'static Encoding,
+
+    /// Filters every output line passes through before it is echoed and logged
+    pub filters: OutputFilters,
+
+    /// Which streams are echoed to the console
+    pub echo: ConsoleEcho,
+
+    /// Terminate the process once it has run this long
+    pub timeout: Option<Duration>,
+
+    /// Time a timed-out process gets to exit after SIGTERM before SIGKILL
+    pub termination_grace: Duration,
+
+    /// Where echoed lines go
+    pub sink: ConsoleSink,
+}
+
+impl MonitorOptions {
+    /// Monitor without a run time limit
+    pub fn new(encoding: &'static Encoding, filters: OutputFilters, echo: ConsoleEcho) -> Self {
+        Self {
+            encoding,
+            filters,
+            echo,
+            timeout: None,
+            termination_grace: DEFAULT_TERMINATION_TIMEOUT,
+            sink: ConsoleSink::default(),
+        }
+    }
+
+    /// Echo into `sink` instead of the terminal
+    pub fn with_sink(mut self, sink: ConsoleSink) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Terminate the process after `limit`, giving it `grace` to exit after SIGTERM
+    pub fn with_timeout(mut self, limit: Duration, grace: Duration) -> Self {
+        self.timeout = Some(limit);
+        self.termination_grace = grace;
+        self
+    }
+}
+
+/// Which process streams are echoed to the console
+///
+/// Logging to io.log is unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleEcho {
+    /// Echo stdout lines
+    pub stdout: bool,
+
+    /// Echo stderr lines (prefixed `ERROR:`)
+    pub stderr: bool,
+}
+
+impl Default for ConsoleEcho {
+    fn default() -> Self {
+        Self {
+            stdout: true,
+            stderr: true,
+        }
+    }
+}
+
+impl ConsoleEcho {
+    /// Echo neither stream, e.g. under a daemon with `quiet_daemon`
+    pub fn off() -> Self {
+        Self {
+            stdout: false,
+            stderr: false,
+        }
+    }
+}
+
+/// Where [`monitor_process`] echoes the lines [`ConsoleEcho`] lets through
+#[derive(Debug, Clone, Default)]
+pub enum ConsoleSink {
+    /// stdout lines to stdout, stderr lines to stderr
+    #[default]
+    Terminal,
+
+    /// Collect the lines in memory instead
+    Memory(Arc<std::sync::Mutex<Vec<String>>>),
+}
+
+impl ConsoleSink {
+    fn echo(&self, line: String, is_error: bool) {
+        match self {
+            ConsoleSink::Terminal if is_error => eprintln!("{}", line),
+            ConsoleSink::Terminal => println!("{}", line),
+            ConsoleSink::Memory(lines) => lines.lock().unwrap_or_else(|e| e.into_inner()).push(line),
+        }
+    }
+}
+
+/// A message on a session's stdin channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StdinInput {
+    /// Text to send
+    pub text: String,
+
+    /// How the text is terminated
+    pub newline: InputNewline,
+
+    /// Wrap multi-line text in bracketed-paste markers
+    pub paste: bool,
+}
+
+/// Whether spawned processes get a piped stdin that session input is written to
+///
+/// Not on Windows, where piped stdin breaks output through cmd.exe.
+pub const STDIN_PIPED: bool = !cfg!(target_os = "windows");
+
+/// Marks the start of a bracketed paste
+const PASTE_START: &str = "\x1b[200~";
+
+/// Marks the end of a bracketed paste
+const PASTE_END: &str = "\x1b[201~";
+
+impl StdinInput {
+    /// The bytes to write to the process's stdin
+    ///
+    /// With `paste`, multi-line text is sent between `ESC[200~` and
+    /// `ESC[201~` so an interactive prompt takes it as one paste instead of
+    /// submitting each line; the terminator follows the closing marker.
+    pub fn terminated(&self) -> String {
+        if self.paste && self.text.contains('\n') {
+            format!("{}{}{}{}", PASTE_START, self.text, PASTE_END, self.newline.terminator())
+        } else {
+            format!("{}{}", self.text, self.newline.terminator())
+        }
+    }
+}
 
 /// Configuration for spawning a Claude CLI process
 pub struct SpawnConfig {
@@ -32,6 +218,15 @@ pub struct SpawnConfig {
 
     /// Working directory for the process
     pub working_dir: Option<std::path::PathBuf>,
+
+    /// Command prepended to the claude invocation (e.g. `nix-shell --run`)
+    pub wrapper: Vec<String>,
+
+    /// Model passed as `--model`
+    pub model: Option<String>,
+
+    /// Claude executable to run instead of the platform default
+    pub program: Option<String>,
 }
 
 impl SpawnConfig {
@@ -41,6 +236,9 @@ impl SpawnConfig {
             task,
             env_vars: Vec::new(),
             working_dir: None,
+            wrapper: Vec::new(),
+            model: None,
+            program: None,
         }
     }
 
@@ -55,6 +253,97 @@ impl SpawnConfig {
         self.working_dir = Some(dir);
         self
     }
+
+    /// Run claude through a wrapper command, e.g. `["docker", "run", "--rm", "img"]`
+    pub fn with_wrapper(mut self, wrapper: Vec<String>) -> Self {
+        self.wrapper = wrapper;
+        self
+    }
+
+    /// Select the model
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Run a different claude executable
+    pub fn with_program(mut self, program: Option<String>) -> Self {
+        self.program = program;
+        self
+    }
+}
+
+/// Split a wrapper command line into words
+///
+/// Supports single quotes, double quotes and backslash escapes so values like
+/// `docker run -v "$HOME/My Work:/work" img` survive intact. No variable or
+/// glob expansion is performed.
+pub fn split_command(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => {
+                            return Err(ClaudeManError::Config(format!(
+                                "Unclosed single quote in wrapper: {}",
+                                command
+                            )))
+                        }
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => word.push(c),
+                            None => break,
+                        },
+                        Some(c) => word.push(c),
+                        None => {
+                            return Err(ClaudeManError::Config(format!(
+                                "Unclosed double quote in wrapper: {}",
+                                command
+                            )))
+                        }
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
 }
 
 /// Spawns a Claude CLI process with stdin support
@@ -72,10 +361,24 @@ pub async fn spawn_claude_process(config: SpawnConfig) -> Result<Child> {
     // Build the command
     // On Windows, spawn claude.cmd directly (not via cmd /C) to preserve stdin piping
     #[cfg(target_os = "windows")]
-    let mut cmd = Command::new("claude.cmd");
+    let default_program = "claude.cmd";
 
     #[cfg(not(target_os = "windows"))]
-    let mut cmd = Command::new("claude");
+    let default_program = "claude";
+
+    let program = config.program.as_deref().unwrap_or(default_program);
+
+    // With a wrapper the invocation becomes `<wrapper...> claude <args>`; env
+    // vars and the working directory apply to the wrapper process
+    let mut cmd = match config.wrapper.split_first() {
+        Some((wrapper, wrapper_args)) => {
+            debug!("Running claude through wrapper: {:?}", config.wrapper);
+            let mut cmd = Command::new(wrapper);
+            cmd.args(wrapper_args).arg(program);
+            cmd
+        }
+        None => Command::new(program),
+    };
 
     // Add flag to skip permissions (enables autonomous orchestration)
     cmd.arg("--dangerously-skip-permissions");
@@ -90,41 +393,119 @@ pub async fn spawn_claude_process(config: SpawnConfig) -> Result<Child> {
         cmd.current_dir(dir);
     }
 
+    if let Some(model) = &config.model {
+        cmd.arg("--model").arg(model);
+    }
+
     // Add task as argument
     cmd.arg(&config.task);
 
     // Configure stdio
-    // TODO: Enable piped stdin when we solve Windows cmd.exe stdin passthrough issue
+    // TODO: Enable piped stdin on Windows once cmd.exe stdin passthrough works
     cmd.stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .stdin(Stdio::null()); // Temporarily disabled - piped stdin breaks output on Windows
+        .stdin(if STDIN_PIPED { Stdio::piped() } else { Stdio::null() });
 
     // Spawn the process
     let child = cmd
         .spawn()
-        .map_err(|e| ClaudeManError::SpawnFailed(format!("Failed to spawn claude CLI: {}", e)))?;
+        .map_err(|e| match config.wrapper.first() {
+            Some(wrapper) => ClaudeManError::SpawnFailed(format!(
+                "Failed to spawn claude CLI via wrapper {}: {}",
+                wrapper, e
+            )),
+            None => ClaudeManError::SpawnFailed(format!("Failed to spawn claude CLI: {}", e)),
+        })?;
 
     debug!("Claude CLI process spawned with PID: {:?}", child.id());
 
     Ok(child)
 }
 
+/// Look up an output encoding by its WHATWG label (e.g. `windows-1252`, `shift_jis`)
+pub fn resolve_encoding(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| ClaudeManError::InvalidInput(format!("Unknown encoding: {}", label)))
+}
+
+/// Read the next line from `reader` and decode it to UTF-8
+///
+/// Bytes that are invalid in `encoding` are replaced rather than ending the
+/// stream. Returns `None` at end of stream.
+async fn next_decoded_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    encoding: &'static Encoding,
+) -> std::io::Result<Option<String>> {
+    buf.clear();
+    if reader.read_until(b'\n', buf).await? == 0 {
+        return Ok(None);
+    }
+
+    if buf.ends_with(b"\n") {
+        buf.pop();
+        if buf.ends_with(b"\r") {
+            buf.pop();
+        }
+    }
+
+    let (line, _had_errors) = encoding.decode_without_bom_handling(buf);
+    Ok(Some(line.into_owned()))
+}
+
+/// Write each message from `stdin_rx` to the process's stdin
+///
+/// Runs until the channel closes. Input is dropped with a warning when stdin
+/// isn't piped or the process has closed it.
+async fn forward_stdin(
+    mut stdin: Option<ChildStdin>,
+    mut stdin_rx: mpsc::UnboundedReceiver<StdinInput>,
+    session_id: SessionId,
+) {
+    while let Some(input) = stdin_rx.recv().await {
+        let Some(pipe) = stdin.as_mut() else {
+            warn!("Input received for {} but stdin is not piped - ignoring", session_id);
+            continue;
+        };
+        let written = async {
+            pipe.write_all(input.terminated().as_bytes()).await?;
+            pipe.flush().await
+        };
+        if let Err(e) = written.await {
+            warn!("Failed to write input to {}: {}", session_id, e);
+            stdin = None;
+        }
+    }
+}
+
 /// Monitors a child process and logs its output
 ///
 /// Reads stdout and stderr from the child process and logs to the session logger.
-/// Handles stdin input from a channel.
-/// Blocks until the process exits.
+/// The logger is locked per write so other writers to the same session can
+/// share it. Output is decoded from the options' encoding to UTF-8 and
+/// passed through their filters before it is echoed or logged; their echo
+/// setting selects which streams are printed to the console.
+/// Messages from `stdin_rx` are written to the process's stdin.
+/// Blocks until the process exits. A process still running when the
+/// options' timeout elapses is terminated (SIGTERM, then SIGKILL after the
+/// grace period) and `ClaudeManError::Timeout` is returned.
 pub async fn monitor_process(
     mut child: Child,
     session_id: SessionId,
-    mut logger: SessionLogger,
-    mut stdin_rx: mpsc::UnboundedReceiver<String>,
+    logger: SharedLogger,
+    stdin_rx: mpsc::UnboundedReceiver<StdinInput>,
+    options: MonitorOptions,
 ) -> Result<i32> {
+    let MonitorOptions { encoding, filters, echo, timeout: run_timeout, termination_grace, sink } = options;
     let pid = child.id().unwrap_or(0);
     info!("Monitoring process {} for session {}", pid, session_id);
+    let max_line_bytes = logger.lock().await.max_line_bytes();
 
     // Log that the session has started
-    logger.log_lifecycle(SessionStatus::Running, format!("Session started (PID: {})", pid))?;
+    logger
+        .lock()
+        .await
+        .log_lifecycle(SessionStatus::Running, format!("Session started (PID: {})", pid))?;
 
     // Get stdout, stderr, and stdin handles
     let stdout = child.stdout.take().ok_or_else(|| {
@@ -135,35 +516,56 @@ pub async fn monitor_process(
         ClaudeManError::Process("Failed to capture stderr".to_string())
     })?;
 
-    // Note: stdin is currently null (not piped) due to Windows cmd.exe issues
-    // The stdin channel infrastructure exists but input won't reach the process
-    // TODO: Fix Windows stdin piping to enable interactive input
-
-    // Drain stdin_rx to prevent blocking, but input won't actually be sent
-    tokio::spawn(async move {
-        while stdin_rx.recv().await.is_some() {
-            // Input received but can't be sent (stdin is null)
-            warn!("Input received but stdin is not piped - ignoring");
-        }
-    });
+    // Forward session input to the process for as long as it runs
+    tokio::spawn(forward_stdin(child.stdin.take(), stdin_rx, session_id.clone()));
 
     // Create buffered readers
-    let stdout_reader = BufReader::new(stdout);
-    let stderr_reader = BufReader::new(stderr);
+    let mut stdout_reader = BufReader::new(stdout);
+    let mut stderr_reader = BufReader::new(stderr);
+    let mut stdout_buf = Vec::new();
+    let mut stderr_buf = Vec::new();
 
-    let mut stdout_lines = stdout_reader.lines();
-    let mut stderr_lines = stderr_reader.lines();
+    // Periodically flush batched log writes during quiet periods
+    let mut flush_tick = tokio::time::interval(LOG_FLUSH_CHECK_INTERVAL);
+
+    // Fires once the run time limit is up (never without one)
+    let deadline = async {
+        match run_timeout {
+            Some(limit) => tokio::time::sleep(limit).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+    let mut timed_out = false;
 
     // Read output lines concurrently
     loop {
         tokio::select! {
-            result = stdout_lines.next_line() => {
+            _ = flush_tick.tick() => {
+                if let Err(e) = logger.lock().await.flush_if_due() {
+                    warn!("Failed to flush log: {}", e);
+                }
+            }
+            _ = &mut deadline => {
+                let secs = run_timeout.unwrap_or_default().as_secs();
+                warn!("Session {} timed out after {}s, terminating process {}", session_id, secs, pid);
+                if let Err(e) = terminate_pid(pid, &session_id, termination_grace, child.wait()).await {
+                    warn!("Failed to terminate timed-out session {}: {}", session_id, e);
+                }
+                timed_out = true;
+                break;
+            }
+            result = next_decoded_line(&mut stdout_reader, &mut stdout_buf, encoding) => {
                 match result {
                     Ok(Some(line)) => {
+                        let Some(line) = filters.apply(line) else { continue };
                         // Print to console
-                        println!("[{}] {}", session_id, line);
+                        if echo.stdout {
+                            let shown = truncate_line(&line, max_line_bytes);
+                            sink.echo(format!("[{}] {}", session_id, shown.as_deref().unwrap_or(&line)), false);
+                        }
                         // Log to file
-                        if let Err(e) = logger.log_output(line) {
+                        if let Err(e) = logger.lock().await.log_output(line) {
                             warn!("Failed to log output: {}", e);
                         }
                     }
@@ -177,13 +579,17 @@ pub async fn monitor_process(
                     }
                 }
             }
-            result = stderr_lines.next_line() => {
+            result = next_decoded_line(&mut stderr_reader, &mut stderr_buf, encoding) => {
                 match result {
                     Ok(Some(line)) => {
+                        let Some(line) = filters.apply(line) else { continue };
                         // Print to console (stderr)
-                        eprintln!("[{}] ERROR: {}", session_id, line);
+                        if echo.stderr {
+                            let shown = truncate_line(&line, max_line_bytes);
+                            sink.echo(format!("[{}] ERROR: {}", session_id, shown.as_deref().unwrap_or(&line)), true);
+                        }
                         // Log to file
-                        if let Err(e) = logger.log_error(line) {
+                        if let Err(e) = logger.lock().await.log_error(line) {
                             warn!("Failed to log error: {}", e);
                         }
                     }
@@ -198,6 +604,27 @@ pub async fn monitor_process(
         }
     }
 
+    // Stderr may still hold lines written before stdout closed. Only wait
+    // briefly for them, and not at all after a timeout: children the process
+    // left behind can hold the pipe open.
+    if !timed_out {
+        let drain = async {
+            while let Ok(Some(line)) = next_decoded_line(&mut stderr_reader, &mut stderr_buf, encoding).await {
+                let Some(line) = filters.apply(line) else { continue };
+                if echo.stderr {
+                    let shown = truncate_line(&line, max_line_bytes);
+                    sink.echo(format!("[{}] ERROR: {}", session_id, shown.as_deref().unwrap_or(&line)), true);
+                }
+                if let Err(e) = logger.lock().await.log_error(line) {
+                    warn!("Failed to log error: {}", e);
+                }
+            }
+        };
+        if timeout(STDERR_DRAIN_TIMEOUT, drain).await.is_err() {
+            debug!("Stderr still open for session {} after stdout closed, not waiting for it", session_id);
+        }
+    }
+
     // Wait for the process to exit
     let status = child.wait().await.map_err(|e| {
         ClaudeManError::Process(format!("Failed to wait for process: {}", e))
@@ -207,6 +634,15 @@ pub async fn monitor_process(
     info!("Process {} exited with code: {}", pid, exit_code);
 
     // Log completion
+    let mut logger = logger.lock().await;
+    if timed_out {
+        let secs = run_timeout.unwrap_or_default().as_secs();
+        logger.log_lifecycle(
+            SessionStatus::Failed,
+            format!("Session timed out after {}s and was terminated (exit code: {})", secs, exit_code),
+        )?;
+        return Err(ClaudeManError::Timeout(secs));
+    }
     if status.success() {
         logger.log_lifecycle(
             SessionStatus::Completed,
@@ -222,83 +658,293 @@ pub async fn monitor_process(
     Ok(exit_code)
 }
 
+/// Reap a child that exited before it could be tracked and describe why
+///
+/// Waits for the process, then reports its exit status along with anything
+/// it wrote to stderr, so a misconfigured `claude` binary explains itself.
+pub async fn describe_early_exit(child: Child) -> String {
+    match child.wait_with_output().await {
+        Ok(output) => {
+            let status = match output.status.code() {
+                Some(code) => format!("exit code {}", code),
+                None => "terminated by signal".to_string(),
+            };
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr.trim();
+
+            if stderr.is_empty() {
+                format!("Claude CLI exited immediately ({})", status)
+            } else {
+                format!("Claude CLI exited immediately ({}): {}", status, stderr)
+            }
+        }
+        Err(e) => format!("Claude CLI exited immediately and could not be reaped: {}", e),
+    }
+}
+
 /// Gracefully terminate a child process
 ///
 /// Attempts a graceful shutdown (SIGTERM) first, then forcefully kills (SIGKILL)
-/// if the process doesn't exit within the timeout.
-pub async fn terminate_process(mut child: Child, session_id: &SessionId) -> Result<()> {
-    let _pid = child.id();
-    info!("Terminating process for session {}", session_id);
+/// if the process doesn't exit within `grace`.
+pub async fn terminate_process(mut child: Child, session_id: &SessionId, grace: Duration) -> Result<()> {
+    let Some(pid) = child.id() else {
+        // Already reaped
+        return Ok(());
+    };
+
+    let result = terminate_pid(pid, session_id, grace, child.wait()).await;
+
+    // Wait for final cleanup
+    let _ = child.wait().await;
+    result
+}
+
+/// Terminate a process by PID
+///
+/// Sends SIGTERM, then waits up to `grace` for `exited` to resolve before
+/// sending SIGKILL. `exited` should complete once the process is gone, e.g.
+/// its monitoring task finishing or [`wait_for_exit`] for a process we don't own.
+/// On Windows the process is killed immediately.
+pub async fn terminate_pid<F: Future>(
+    pid: u32,
+    session_id: &SessionId,
+    grace: Duration,
+    exited: F,
+) -> Result<()> {
+    info!("Terminating process {} for session {}", pid, session_id);
 
     #[cfg(unix)]
     {
+        use nix::errno::Errno;
         use nix::sys::signal::{kill, Signal};
         use nix::unistd::Pid;
 
-        if let Some(pid) = pid {
-            // Send SIGTERM for graceful shutdown
-            debug!("Sending SIGTERM to PID {}", pid);
-            let nix_pid = Pid::from_raw(pid as i32);
+        let nix_pid = Pid::from_raw(pid as i32);
 
-            if let Err(e) = kill(nix_pid, Signal::SIGTERM) {
-                warn!("Failed to send SIGTERM: {}", e);
-            } else {
-                // Wait for process to exit gracefully
-                let timeout_duration = Duration::from_secs(TERMINATION_TIMEOUT_SECS);
-                match timeout(timeout_duration, child.wait()).await {
-                    Ok(Ok(_status)) => {
-                        info!("Process {} terminated gracefully", pid);
-                        return Ok(());
-                    }
-                    Ok(Err(e)) => {
-                        warn!("Error waiting for process {}: {}", pid, e);
-                    }
-                    Err(_) => {
-                        warn!("Process {} did not exit within timeout, sending SIGKILL", pid);
-                    }
-                }
-
-                // If still running, send SIGKILL
-                debug!("Sending SIGKILL to PID {}", pid);
-                if let Err(e) = kill(nix_pid, Signal::SIGKILL) {
-                    error!("Failed to send SIGKILL: {}", e);
-                    return Err(ClaudeManError::TerminationFailed(format!(
-                        "Failed to kill process {}: {}",
-                        pid, e
-                    )));
-                }
+        // Send SIGTERM for graceful shutdown
+        debug!("Sending SIGTERM to PID {}", pid);
+        match kill(nix_pid, Signal::SIGTERM) {
+            Ok(()) => {}
+            Err(Errno::ESRCH) => {
+                debug!("Process {} already exited", pid);
+                return Ok(());
             }
+            Err(e) => warn!("Failed to send SIGTERM: {}", e),
         }
-    }
+        // A paused process only acts on SIGTERM once continued
+        let _ = kill(nix_pid, Signal::SIGCONT);
 
-    #[cfg(windows)]
-    {
-        // On Windows, kill() is already forceful
-        match child.kill().await {
-            Ok(_) => {
-                info!("Process terminated");
-            }
+        // Wait for process to exit gracefully
+        if timeout(grace, exited).await.is_ok() {
+            info!("Process {} terminated gracefully", pid);
+            return Ok(());
+        }
+        warn!("Process {} did not exit within {:?}, sending SIGKILL", pid, grace);
+
+        debug!("Sending SIGKILL to PID {}", pid);
+        match kill(nix_pid, Signal::SIGKILL) {
+            Ok(()) | Err(Errno::ESRCH) => {}
             Err(e) => {
-                error!("Failed to terminate process: {}", e);
+                error!("Failed to send SIGKILL: {}", e);
                 return Err(ClaudeManError::TerminationFailed(format!(
-                    "Failed to terminate process: {}",
-                    e
+                    "Failed to kill process {}: {}",
+                    pid, e
                 )));
             }
         }
     }
 
-    // Wait for final cleanup
-    let _ = child.wait().await;
-    info!("Process terminated");
+    #[cfg(windows)]
+    {
+        // taskkill /F is already forceful
+        let output = std::process::Command::new("taskkill")
+            .args(["/F", "/PID", &pid.to_string()])
+            .output()
+            .map_err(|e| {
+                ClaudeManError::TerminationFailed(format!("Failed to run taskkill: {}", e))
+            })?;
+        if !output.status.success() {
+            warn!("taskkill failed for process {}", pid);
+        }
+        let _ = timeout(grace, exited).await;
+    }
 
+    info!("Process terminated");
     Ok(())
 }
 
+/// Suspend a process with SIGSTOP
+///
+/// Windows has no job control signals, so pausing is unsupported there.
+pub fn suspend_pid(pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{kill, Signal};
+        use nix::unistd::Pid;
+
+        debug!("Sending SIGSTOP to PID {}", pid);
+        kill(Pid::from_raw(pid as i32), Signal::SIGSTOP)
+            .map_err(|e| ClaudeManError::Process(format!("Failed to pause process {}: {}", pid, e)))
+    }
+
+    #[cfg(windows)]
+    {
+        Err(ClaudeManError::Process(format!(
+            "Can't pause process {}: pausing sessions is not supported on Windows",
+            pid
+        )))
+    }
+}
+
+/// Continue a process suspended with [`suspend_pid`] (SIGCONT)
+pub fn resume_pid(pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{kill, Signal};
+        use nix::unistd::Pid;
+
+        debug!("Sending SIGCONT to PID {}", pid);
+        kill(Pid::from_raw(pid as i32), Signal::SIGCONT)
+            .map_err(|e| ClaudeManError::Process(format!("Failed to continue process {}: {}", pid, e)))
+    }
+
+    #[cfg(windows)]
+    {
+        Err(ClaudeManError::Process(format!(
+            "Can't continue process {}: pausing sessions is not supported on Windows",
+            pid
+        )))
+    }
+}
+
+/// Wait until a process we did not spawn has exited
+///
+/// Polls for the PID; use the child's own wait for processes we spawned, since
+/// an unreaped child never disappears.
+pub async fn wait_for_exit(pid: u32) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::kill;
+        use nix::unistd::Pid;
+
+        while kill(Pid::from_raw(pid as i32), None).is_ok() {
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = pid;
+    }
+}
+
+/// Check if a process is alive
+///
+/// Sends the null signal, which only checks for existence (and doesn't
+/// resume a stopped process the way SIGCONT would).
+pub fn is_process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::kill;
+        use nix::unistd::Pid;
+
+        kill(Pid::from_raw(pid as i32), None).is_ok()
+    }
+
+    #[cfg(windows)]
+    {
+        // On Windows, use tasklist to check if process exists
+        if let Ok(output) = std::process::Command::new("tasklist")
+            .args(&["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+        {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            stdout.contains(&pid.to_string())
+        } else {
+            false
+        }
+    }
+}
+
+/// Run a session's `--on-complete` command through the shell
+///
+/// `env` is added to the hook's environment. Output is captured rather than
+/// inherited; the hook is killed if it outlives `limit`.
+pub async fn run_completion_hook(
+    command: &str,
+    env: &[(&str, String)],
+    limit: Duration,
+) -> Result<std::process::Output> {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+
+    cmd.envs(env.iter().map(|(key, value)| (*key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| ClaudeManError::Process(format!("Failed to run hook: {}", e)))?;
+
+    match timeout(limit, child.wait_with_output()).await {
+        Ok(output) => Ok(output?),
+        Err(_) => Err(ClaudeManError::Process(format!(
+            "Hook timed out after {}s",
+            limit.as_secs()
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_completion_hook_env_and_timeout() {
+        let env = [("CLAUDE_MAN_STATUS", "failed".to_string())];
+        let output = run_completion_hook("echo \"$CLAUDE_MAN_STATUS\"; exit 3", &env, COMPLETION_HOOK_TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "failed");
+        assert_eq!(output.status.code(), Some(3));
+
+        let slow = run_completion_hook("sleep 5", &[], Duration::from_millis(100)).await;
+        assert!(slow.is_err());
+    }
+
+    #[test]
+    fn test_input_newline_policies() {
+        let input = |newline| StdinInput { text: "yes".to_string(), newline, paste: false }.terminated();
+        assert_eq!(input(InputNewline::default()), "yes\n");
+        assert_eq!(input(InputNewline::Crlf), "yes\r\n");
+        assert_eq!(input(InputNewline::None), "yes");
+        assert_eq!(input(InputNewline::Double), "yes\n\n");
+    }
+
+    #[test]
+    fn test_paste_wraps_multi_line_input() {
+        let input = |text: &str, paste| StdinInput { text: text.to_string(), newline: InputNewline::Lf, paste }.terminated();
+        assert_eq!(input("fn main() {\n}", true), "\x1b[200~fn main() {\n}\x1b[201~\n");
+        assert_eq!(input("fn main() {\n}", false), "fn main() {\n}\n");
+
+        // A single line can't be submitted early, so it goes as-is
+        assert_eq!(input("yes", true), "yes\n");
+    }
+
     #[test]
     fn test_spawn_config_creation() {
         let config = SpawnConfig::new("test task".to_string());
@@ -328,4 +974,197 @@ mod tests {
         // Just verify it returns a Result
         assert!(result.is_ok() || result.is_err());
     }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("docker run --rm myimg").unwrap(),
+            vec!["docker", "run", "--rm", "myimg"]
+        );
+        assert_eq!(
+            split_command(r#"nix-shell -p "claude code" --run 'x y' a\ b"#).unwrap(),
+            vec!["nix-shell", "-p", "claude code", "--run", "x y", "a b"]
+        );
+        assert_eq!(split_command("  ").unwrap(), Vec::<String>::new());
+        assert_eq!(split_command("''").unwrap(), vec![""]);
+        assert!(split_command("docker 'unterminated").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_through_wrapper() {
+        let config = SpawnConfig::new("do the task".to_string())
+            .with_wrapper(vec!["echo".to_string(), "wrapped:".to_string()]);
+        let output = spawn_claude_process(config).await.unwrap().wait_with_output().await.unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "wrapped: claude --dangerously-skip-permissions do the task"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_decoded_line() {
+        let latin1 = resolve_encoding("latin1").unwrap();
+        let mut reader = BufReader::new(&b"caf\xe9\r\nna\xefve"[..]);
+        let mut buf = Vec::new();
+
+        let line = next_decoded_line(&mut reader, &mut buf, latin1).await.unwrap();
+        assert_eq!(line.as_deref(), Some("café"));
+        let line = next_decoded_line(&mut reader, &mut buf, latin1).await.unwrap();
+        assert_eq!(line.as_deref(), Some("naïve"));
+        assert!(next_decoded_line(&mut reader, &mut buf, latin1).await.unwrap().is_none());
+
+        assert!(resolve_encoding("not-a-charset").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_describe_early_exit() {
+        let child = Command::new("sh")
+            .args(["-c", "echo 'bad config' >&2; exit 3"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let description = describe_early_exit(child).await;
+        assert!(description.contains("exit code 3"));
+        assert!(description.contains("bad config"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_monitor_returns_while_grandchild_holds_stderr() {
+        use crate::core::logger::SessionLogger;
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        let logger = SessionLogger::new(session_id.clone(), dir.path()).unwrap().shared();
+
+        // The background sleep inherits stderr but not stdout
+        let child = Command::new("sh")
+            .args(["-c", "sleep 5 >/dev/null & echo done"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (_stdin_tx, stdin_rx) = mpsc::unbounded_channel();
+        let options = MonitorOptions::new(encoding_rs::UTF_8, OutputFilters::default(), ConsoleEcho::off());
+
+        let started = std::time::Instant::now();
+        let exit_code = monitor_process(child, session_id, logger, stdin_rx, options).await.unwrap();
+        assert_eq!(exit_code, 0);
+        assert!(started.elapsed() < Duration::from_secs(3), "{:?}", started.elapsed());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_monitor_terminates_after_timeout() {
+        use crate::core::logger::{read_events, SessionLogger};
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        let logger = SessionLogger::new(session_id.clone(), dir.path()).unwrap().shared();
+
+        let child = Command::new("sh")
+            .args(["-c", "echo working; sleep 30"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap();
+        let (_stdin_tx, stdin_rx) = mpsc::unbounded_channel();
+        let options = MonitorOptions::new(encoding_rs::UTF_8, OutputFilters::default(), ConsoleEcho::default())
+            .with_timeout(Duration::from_millis(300), Duration::from_millis(200));
+
+        let started = std::time::Instant::now();
+        let result = monitor_process(child, session_id, logger.clone(), stdin_rx, options).await;
+        assert!(matches!(result, Err(ClaudeManError::Timeout(0))));
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!is_process_alive(pid));
+
+        logger.lock().await.flush().unwrap();
+        let events = read_events(dir.path()).unwrap();
+        assert_eq!(events[1].content, "working");
+        let last = events.last().unwrap();
+        assert!(last.content.starts_with("Session timed out after 0s and was terminated"));
+        assert_eq!(last.metadata.as_ref().unwrap()["status"], "failed");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_monitor_echo_off_still_logs() {
+        use crate::core::logger::SessionLogger;
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        let monitor = |echo: ConsoleEcho| {
+            let logger = SessionLogger::new(session_id.clone(), dir.path()).unwrap().shared();
+            let sink = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let child = Command::new("sh")
+                .args(["-c", "echo out; echo err >&2"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let (_stdin_tx, stdin_rx) = mpsc::unbounded_channel();
+            let options = MonitorOptions::new(encoding_rs::UTF_8, OutputFilters::default(), echo)
+                .with_sink(ConsoleSink::Memory(sink.clone()));
+            let session_id = session_id.clone();
+            async move {
+                monitor_process(child, session_id, logger.clone(), stdin_rx, options).await.unwrap();
+                logger.lock().await.flush().unwrap();
+                sink.lock().unwrap().clone()
+            }
+        };
+
+        let mut echoed = monitor(ConsoleEcho::default()).await;
+        echoed.sort();
+        assert_eq!(echoed, ["[DEV-001] ERROR: err", "[DEV-001] out"]);
+        std::fs::remove_file(dir.path().join("io.log")).unwrap();
+
+        assert!(monitor(ConsoleEcho::off()).await.is_empty());
+        let log = std::fs::read_to_string(dir.path().join("io.log")).unwrap();
+        assert!(log.contains("\"out\"") && log.contains("\"err\""), "{}", log);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_monitor_redacts_output_in_log() {
+        use crate::core::filter::Redactor;
+        use crate::core::logger::SessionLogger;
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        let logger = SessionLogger::new(session_id.clone(), dir.path()).unwrap().shared();
+
+        let redactor = Redactor::new(&["sk-[A-Za-z0-9]+".to_string()]).unwrap();
+        let mut filters = OutputFilters::default();
+        filters.push(Arc::new(move |line: &str| Some(redactor.redact(line))));
+        filters.push(Arc::new(|line: &str| (line != "drop me").then(|| line.to_string())));
+
+        let child = Command::new("sh")
+            .args(["-c", "echo 'key sk-secret123'; echo 'drop me'; echo 'err sk-abc' >&2"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (_stdin_tx, stdin_rx) = mpsc::unbounded_channel();
+        let options = MonitorOptions::new(encoding_rs::UTF_8, filters, ConsoleEcho::default());
+        let exit_code = monitor_process(child, session_id, logger.clone(), stdin_rx, options)
+            .await
+            .unwrap();
+        assert_eq!(exit_code, 0);
+        logger.lock().await.flush().unwrap();
+
+        let log = std::fs::read_to_string(dir.path().join("io.log")).unwrap();
+        assert!(log.contains("key [REDACTED]"));
+        assert!(log.contains("err [REDACTED]"));
+        assert!(!log.contains("sk-secret123"));
+        assert!(!log.contains("sk-abc"));
+        assert!(!log.contains("drop me"));
+    }
 }
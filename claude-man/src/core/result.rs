@@ -0,0 +1,202 @@
+//! Session result capture
+//!
+//! Extracts the "answer" a session produced from its io.log so orchestration
+//! scripts can hand it to the next stage without scraping logs.
+//!
+//! By default the result is the last output block: the final run of
+//! non-blank stdout lines. Alternatively the result is the text between the
+//! last start marker and the end marker that follows it.
+//!
+//! Sessions that emit a structured answer can instead have it parsed as JSON:
+//! see [`extract_json_result`].
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::logger::{read_events, IoEventType};
+use crate::types::error::{ClaudeManError, Result};
+
+/// Rule for picking a session's result out of its output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ResultExtraction {
+    /// The final run of non-blank stdout lines
+    #[default]
+    LastBlock,
+
+    /// The lines between the last `start` marker line and the next `end` marker line
+    Between { start: String, end: String },
+}
+
+/// Where to write a session's result and how to extract it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CaptureResult {
+    /// File the result is written to
+    pub path: PathBuf,
+
+    /// How the result is extracted from the output
+    #[serde(default)]
+    pub rule: ResultExtraction,
+}
+
+impl CaptureResult {
+    /// Capture the last output block to `path`
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            rule: ResultExtraction::default(),
+        }
+    }
+
+    /// Use a different extraction rule
+    pub fn with_rule(mut self, rule: ResultExtraction) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Extract the result from a session's io.log and write it to `path`
+    ///
+    /// Returns an error if the output contains no result under the rule.
+    pub fn write_from_log(&self, log_dir: &Path) -> Result<()> {
+        let outputs = read_outputs(log_dir)?;
+        let result = extract_result(&outputs, &self.rule).ok_or_else(|| {
+            ClaudeManError::Log("Session output contains no result to capture".to_string())
+        })?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, result)?;
+
+        Ok(())
+    }
+}
+
+/// Read the stdout lines of a session's log
+fn read_outputs(log_dir: &Path) -> Result<Vec<String>> {
+    Ok(read_events(log_dir)?
+        .into_iter()
+        .filter(|event| event.event_type == IoEventType::Output)
+        .map(|event| event.content)
+        .collect())
+}
+
+/// Parse the JSON result out of a session's io.log
+///
+/// Returns an error if the output contains no JSON object or array.
+pub fn json_result_from_log(log_dir: &Path) -> Result<Value> {
+    extract_json_result(&read_outputs(log_dir)?).ok_or_else(|| {
+        ClaudeManError::Log("Session output contains no JSON result".to_string())
+    })
+}
+
+/// Extract the last JSON object or array from stdout lines
+///
+/// The lines are joined with newlines, so a value may span several lines
+/// (e.g. pretty-printed). Every `{` or `[` is tried as the start of a value;
+/// of those that parse, the one that ends last wins. A value nested in a
+/// larger one always ends before it, so the outermost value is returned.
+/// Surrounding text such as prose or a ```` ```json ```` fence is ignored,
+/// and bare scalars (`42`, `"done"`) don't count.
+pub fn extract_json_result(lines: &[String]) -> Option<Value> {
+    let text = lines.join("\n");
+    let mut best: Option<(usize, Value)> = None;
+
+    for (start, _) in text.match_indices(['{', '[']) {
+        let mut values = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Value>();
+        if let Some(Ok(value)) = values.next() {
+            let end = start + values.byte_offset();
+            if best.as_ref().is_none_or(|(best_end, _)| end > *best_end) {
+                best = Some((end, value));
+            }
+        }
+    }
+
+    best.map(|(_, value)| value)
+}
+
+/// Extract a result from stdout lines according to `rule`
+pub fn extract_result(lines: &[String], rule: &ResultExtraction) -> Option<String> {
+    let selected: &[String] = match rule {
+        ResultExtraction::LastBlock => {
+            let end = lines.iter().rposition(|line| !line.trim().is_empty())? + 1;
+            let start = lines[..end]
+                .iter()
+                .rposition(|line| line.trim().is_empty())
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            &lines[start..end]
+        }
+        ResultExtraction::Between { start, end } => {
+            let first = lines.iter().rposition(|line| line.trim() == start)? + 1;
+            let last = lines[first..]
+                .iter()
+                .position(|line| line.trim() == end)
+                .map(|i| first + i)?;
+            &lines[first..last]
+        }
+    };
+
+    let mut result = selected.join("\n");
+    result.push('\n');
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_extract_last_block() {
+        let output = lines(&["thinking...", "", "answer line 1", "answer line 2", "", ""]);
+        assert_eq!(
+            extract_result(&output, &ResultExtraction::LastBlock).as_deref(),
+            Some("answer line 1\nanswer line 2\n")
+        );
+        assert_eq!(extract_result(&lines(&["", " "]), &ResultExtraction::LastBlock), None);
+    }
+
+    #[test]
+    fn test_extract_between_markers() {
+        let rule = ResultExtraction::Between {
+            start: "<<<RESULT".to_string(),
+            end: "RESULT>>>".to_string(),
+        };
+        let output = lines(&["<<<RESULT", "draft", "RESULT>>>", "<<<RESULT", "{}", "", "RESULT>>>"]);
+        assert_eq!(extract_result(&output, &rule).as_deref(), Some("{}\n\n"));
+        assert_eq!(extract_result(&lines(&["<<<RESULT", "no end"]), &rule), None);
+    }
+
+    #[test]
+    fn test_extract_json_result() {
+        use serde_json::json;
+
+        // Pretty-printed in a fence, after an earlier draft: the outermost, last value wins
+        let output = lines(&[
+            "draft: {\"status\": \"wip\"}",
+            "```json",
+            "{",
+            "  \"status\": \"done\",",
+            "  \"files\": [\"a.rs\", \"b.rs\"]",
+            "}",
+            "```",
+            "Let me know if [anything] else is needed.",
+        ]);
+        assert_eq!(
+            extract_json_result(&output),
+            Some(json!({"status": "done", "files": ["a.rs", "b.rs"]}))
+        );
+
+        // Arrays count; malformed values and bare scalars don't
+        let output = lines(&["[1, 2]", "{\"broken\": ", "42"]);
+        assert_eq!(extract_json_result(&output), Some(json!([1, 2])));
+        assert_eq!(extract_json_result(&lines(&["\"done\"", "{not json}"])), None);
+    }
+}
@@ -3,19 +3,40 @@
 //! Manages the lifecycle of Claude sessions including creation, tracking,
 //! and cleanup. Maintains an in-memory registry of active sessions.
 
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use encoding_rs::{Encoding, UTF_8};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{Mutex, MutexGuard, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
-use crate::core::logger::{session_log_dir, SessionLogger};
-use crate::core::process::{monitor_process, spawn_claude_process, SpawnConfig};
+use crate::core::backup;
+use crate::core::filter::{OutputFilter, OutputFilters, PromptDetector, ReadyMarker};
+use crate::core::kv::KvStore;
+use crate::core::logger::{
+    conversation_recap, conversation_transcript, default_log_dir, read_events, session_log_dir_in, FlushPolicy, IoEvent,
+    IoEventType, SessionDirTemplate, SessionLogger, SharedLogger, DEFAULT_MAX_LOG_BYTES,
+};
+use crate::core::process::{
+    describe_early_exit, is_process_alive, monitor_process, resolve_encoding, resume_pid, run_completion_hook,
+    spawn_claude_process, suspend_pid, terminate_pid, wait_for_exit, ConsoleEcho, InputNewline, MonitorOptions, SpawnConfig,
+    StdinInput, STDIN_PIPED,
+    COMPLETION_HOOK_TIMEOUT, DEFAULT_TERMINATION_TIMEOUT,
+};
+use crate::core::result::{json_result_from_log, CaptureResult};
 use crate::types::error::{ClaudeManError, Result};
 use crate::types::role::Role;
-use crate::types::session::{SessionId, SessionMetadata};
+use crate::types::session::{
+    validate_non_empty, Approval, SessionId, SessionMetadata, SessionStatus, SpawnSettings,
+};
 
 /// Session handle containing the running process and metadata
 pub struct SessionHandle {
@@ -26,7 +47,10 @@ pub struct SessionHandle {
     pub task_handle: Option<JoinHandle<Result<i32>>>,
 
     /// Channel for sending input to the session's stdin
-    pub stdin_tx: Option<mpsc::UnboundedSender<String>>,
+    pub stdin_tx: Option<mpsc::UnboundedSender<StdinInput>>,
+
+    /// Logger shared by every writer to this session's io.log
+    pub logger: Option<SharedLogger>,
 }
 
 impl SessionHandle {
@@ -39,630 +63,4132 @@ impl SessionHandle {
     }
 }
 
-/// Session registry managing all active sessions
-pub struct SessionRegistry {
-    /// Map of session ID to session handle
-    sessions: Arc<RwLock<HashMap<SessionId, SessionHandle>>>,
+impl Drop for SessionHandle {
+    /// Best-effort terminate a process this handle still owns
+    ///
+    /// Safety net for teardown paths that skip `stop_session`. Only processes
+    /// spawned and monitored by this registry are signalled; sessions
+    /// recovered from disk belong to another claude-man and are left alone.
+    fn drop(&mut self) {
+        if !self.is_running() || !self.metadata.is_active() {
+            return;
+        }
+        let Some(pid) = self.metadata.pid else {
+            return;
+        };
 
-    /// Counter for generating unique session IDs per role
-    role_counters: Arc<RwLock<HashMap<Role, u32>>>,
-}
+        warn!(
+            "Session {} dropped while running, terminating process {}",
+            self.metadata.id, pid
+        );
 
-impl SessionRegistry {
-    /// Create a new empty session registry
-    pub fn new() -> Self {
-        Self {
-            sessions: Arc::new(RwLock::new(HashMap::new())),
-            role_counters: Arc::new(RwLock::new(HashMap::new())),
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::{kill, Signal};
+            use nix::unistd::Pid;
+
+            let _ = kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
+        }
+
+        #[cfg(windows)]
+        {
+            let _ = std::process::Command::new("taskkill")
+                .args(["/F", "/PID", &pid.to_string()])
+                .output();
+        }
+
+        if let Some(task_handle) = self.task_handle.take() {
+            task_handle.abort();
         }
     }
+}
 
-    /// Get role-specific context for a session by reading from ROLES/ directory
-    fn get_role_context(role: Role) -> Option<String> {
-        use std::path::Path;
+/// Optional settings for spawning a session
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    /// Key/value annotations attached to the session metadata
+    pub annotations: HashMap<String, String>,
 
-        // Try to find role file in ROLES/ directory (project root or parent directories)
-        let role_filename = format!("{}.md", role.to_string().to_uppercase());
+    /// Per-spawn override of the registry's limit on running siblings
+    pub max_concurrent_per_parent: Option<usize>,
 
-        // Search in current dir, parent, grandparent for ROLES/ directory
-        let search_paths = vec![
-            Path::new("ROLES").join(&role_filename),
-            Path::new("../ROLES").join(&role_filename),
-            Path::new("../../ROLES").join(&role_filename),
-        ];
+    /// Write the session's result to a file when it completes successfully
+    pub capture_result: Option<CaptureResult>,
 
-        for path in search_paths {
-            if let Ok(content) = fs::read_to_string(&path) {
-                info!("Loaded role context from {:?}", path);
-                return Some(content);
-            }
-        }
+    /// Parse the last JSON object or array in the output into the metadata on success
+    pub expect_json_result: bool,
 
-        warn!("No role context file found for role: {}", role);
-        None
-    }
+    /// Charset of the process output (UTF-8 with lossy replacement if unset)
+    pub encoding: Option<&'static Encoding>,
 
-    /// Write role context to a markdown file in the session directory
-    fn write_role_context(log_dir: &std::path::Path, context: &str) -> Result<()> {
-        let context_path = log_dir.join("role-context.md");
-        fs::write(&context_path, context)?;
-        Ok(())
-    }
+    /// Shell command to run when the session's process exits
+    pub on_complete: Option<String>,
 
-    /// Create .claude directory with hooks for auto-approval
-    fn setup_session_claude_config(log_dir: &std::path::Path) -> Result<()> {
-        let claude_dir = log_dir.join(".claude");
-        let hooks_dir = claude_dir.join("hooks");
-        fs::create_dir_all(&hooks_dir)?;
+    /// Terminate the process and fail the session if it runs longer than this
+    pub timeout: Option<Duration>,
 
-        // Create pre-tool-use hook that auto-approves claude-man commands
-        let hook_script = r#"#!/usr/bin/env bash
-# Auto-approve claude-man commands for orchestration
-if echo "$TOOL_USE_JSON" | grep -q "claude-man"; then
-  exit 0  # Approve
-fi
-exit 1  # Require approval for other commands
-"#;
+    /// Restart the session up to this many times if its process fails
+    pub retry_on_failure: u32,
 
-        let hook_path = hooks_dir.join("pre-tool-use.sh");
-        fs::write(&hook_path, hook_script)?;
+    /// Launch settings (model, env, working dir, claude binary)
+    pub settings: SpawnSettings,
 
-        // Make hook executable (Unix only, no-op on Windows)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&hook_path)?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&hook_path, perms)?;
-        }
+    /// Don't fill unset launch settings from the parent session's
+    pub skip_parent_settings: bool,
 
-        Ok(())
-    }
+    /// Use this ID instead of the next one for the role
+    pub session_id: Option<SessionId>,
 
-    /// Load sessions from disk
-    ///
-    /// Scans the .claude-man/sessions directory and loads all session metadata.
-    /// Only includes sessions that are marked as running and have valid PIDs.
-    pub async fn load_from_disk(&self) -> Result<()> {
-        use crate::core::logger::default_log_dir;
-        use std::fs;
+    /// Replace a finished session that already has `session_id`
+    pub replace: bool,
 
-        let sessions_dir = default_log_dir();
-        if !sessions_dir.exists() {
-            return Ok(());
-        }
+    /// With `replace`, stop the existing session first if it is still active
+    pub force_replace: bool,
 
-        info!("Loading sessions from disk...");
+    /// Prepend a transcript of this session's conversation to the task
+    pub context_from: Option<SessionId>,
 
-        for entry in fs::read_dir(sessions_dir)? {
-            let entry = entry?;
-            if !entry.file_type()?.is_dir() {
-                continue;
-            }
+    /// Size cap on the `context_from` transcript, in bytes
+    pub context_bytes: usize,
 
-            let session_dir = entry.path();
-            let metadata_path = session_dir.join("metadata.json");
+    /// Return the unfinished session spawned with this key instead of spawning again
+    pub idempotency_key: Option<String>,
+}
 
-            if !metadata_path.exists() {
-                continue;
-            }
+impl SpawnOptions {
+    /// Create spawn options with defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-            // Load metadata
-            if let Ok(metadata) = Self::load_metadata_from_path(&metadata_path) {
-                // Only load if marked as running
-                if metadata.status == crate::types::session::SessionStatus::Running {
-                    // Check if process is still alive
-                    if let Some(pid) = metadata.pid {
-                        if Self::is_process_alive(pid) {
-                            info!("Loaded session {} (PID: {})", metadata.id, pid);
-
-                            // Create handle without monitoring task (process already running)
-                            // Note: stdin_tx is None for recovered sessions (can't attach to existing process stdin)
-                            let handle = SessionHandle {
-                                metadata,
-                                task_handle: None,
-                                stdin_tx: None,
-                            };
-
-                            let mut sessions = self.sessions.write().await;
-                            sessions.insert(handle.metadata.id.clone(), handle);
-                        } else {
-                            // Process is dead, update metadata
-                            let mut dead_metadata = metadata;
-                            dead_metadata.mark_failed();
-                            let _ = self.save_metadata(&dead_metadata);
-                            info!("Session {} process is dead, marked as failed", dead_metadata.id);
-                        }
-                    }
-                }
-            }
-        }
+    /// Attach key/value annotations to the session
+    pub fn with_annotations(mut self, annotations: HashMap<String, String>) -> Self {
+        self.annotations = annotations;
+        self
+    }
 
-        Ok(())
+    /// Limit how many children of the same parent may run at once
+    pub fn with_max_concurrent_per_parent(mut self, limit: usize) -> Self {
+        self.max_concurrent_per_parent = Some(limit);
+        self
     }
 
-    /// Load metadata from a specific path
-    fn load_metadata_from_path(path: &std::path::Path) -> Result<crate::types::session::SessionMetadata> {
-        let json = std::fs::read_to_string(path)?;
-        let metadata: crate::types::session::SessionMetadata = serde_json::from_str(&json)?;
-        Ok(metadata)
+    /// Capture the session's result to a file on successful completion
+    pub fn with_capture_result(mut self, capture: CaptureResult) -> Self {
+        self.capture_result = Some(capture);
+        self
     }
 
-    /// Check if a process is alive
-    fn is_process_alive(pid: u32) -> bool {
-        #[cfg(unix)]
-        {
-            use nix::sys::signal::{kill, Signal};
-            use nix::unistd::Pid;
+    /// Parse the session's JSON result into its metadata on successful completion
+    pub fn with_json_result(mut self) -> Self {
+        self.expect_json_result = true;
+        self
+    }
 
-            let nix_pid = Pid::from_raw(pid as i32);
-            kill(nix_pid, Signal::SIGCONT).is_ok()
-        }
+    /// Decode the process output from this charset instead of UTF-8
+    pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
 
-        #[cfg(windows)]
-        {
-            // On Windows, use tasklist to check if process exists
-            if let Ok(output) = std::process::Command::new("tasklist")
-                .args(&["/FI", &format!("PID eq {}", pid), "/NH"])
-                .output()
-            {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                stdout.contains(&pid.to_string())
-            } else {
-                false
-            }
-        }
+    /// Run a shell command when the session's process exits
+    pub fn with_on_complete(mut self, command: String) -> Self {
+        self.on_complete = Some(command);
+        self
     }
 
-    /// Generate the next session ID for a given role
-    async fn next_session_id(&self, role: Role) -> SessionId {
-        let mut counters = self.role_counters.write().await;
-        let counter = counters.entry(role).or_insert(0);
-        *counter += 1;
-        SessionId::new(role, *counter)
+    /// Terminate the session's process if it is still running after `limit`
+    pub fn with_timeout(mut self, limit: Duration) -> Self {
+        self.timeout = Some(limit);
+        self
     }
 
-    /// Spawn a new session
+    /// Restart the session automatically, up to `limit` times, if it fails
     ///
-    /// Creates a new session, spawns the Claude CLI process, and starts monitoring it.
-    pub async fn spawn_session(&self, role: Role, task: String) -> Result<SessionId> {
-        let session_id = self.next_session_id(role).await;
-        let log_dir = session_log_dir(&session_id);
-
-        info!("Spawning session {} with role {:?}", session_id, role);
+    /// Only a process that ran and then failed (or timed out) is retried; a
+    /// process that can't be launched fails the spawn straight away.
+    pub fn with_retry_on_failure(mut self, limit: u32) -> Self {
+        self.retry_on_failure = limit;
+        self
+    }
 
-        // Create session metadata
-        let mut metadata = SessionMetadata::new(
-            session_id.clone(),
-            role,
-            task.clone(),
-            log_dir.clone(),
-        );
+    /// Set the launch settings
+    pub fn with_settings(mut self, settings: SpawnSettings) -> Self {
+        self.settings = settings;
+        self
+    }
 
-        // Set up .claude directory with hooks for auto-approval
-        Self::setup_session_claude_config(&log_dir)?;
+    /// Don't inherit launch settings from the parent session
+    pub fn without_parent_settings(mut self) -> Self {
+        self.skip_parent_settings = true;
+        self
+    }
 
-        // Create logger
-        let logger = SessionLogger::new(session_id.clone(), &log_dir)?;
+    /// Spawn under an explicit ID rather than the next one for the role
+    pub fn with_session_id(mut self, session_id: SessionId) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
 
-        // Save metadata to file
-        self.save_metadata(&metadata)?;
+    /// Replace an existing session with the same ID, archiving its logs
+    ///
+    /// An active session is only replaced (after stopping it) with `force`.
+    pub fn with_replace(mut self, force: bool) -> Self {
+        self.replace = true;
+        self.force_replace = force;
+        self
+    }
 
-        // Write role-specific context file if applicable
-        let task_with_context = if let Some(context) = Self::get_role_context(role) {
-            Self::write_role_context(&log_dir, &context)?;
-            format!("First, read role-context.md in your working directory for your role instructions. Then: {}", task)
-        } else {
-            task.clone()
-        };
+    /// Start the task with a transcript of another session's conversation,
+    /// at most `max_bytes` long (most recent events kept)
+    pub fn with_context_from(mut self, source: SessionId, max_bytes: usize) -> Self {
+        self.context_from = Some(source);
+        self.context_bytes = max_bytes;
+        self
+    }
 
-        // Create spawn configuration with working directory set to log dir
-        let config = SpawnConfig::new(task_with_context).with_working_dir(log_dir.clone());
+    /// Deduplicate spawns: while a session spawned with `key` is unfinished,
+    /// spawning with the same key returns that session's ID
+    pub fn with_idempotency_key(mut self, key: String) -> Self {
+        self.idempotency_key = Some(key);
+        self
+    }
+}
 
-        // Spawn the Claude CLI process with stdin support
-        let child = spawn_claude_process(config).await?;
-        let pid = child.id().ok_or_else(|| {
-            ClaudeManError::Process("Failed to get process ID".to_string())
-        })?;
+/// A permission prompt seen in a session's output
+#[derive(Debug, Clone)]
+pub struct ApprovalPrompt {
+    /// Session waiting for an answer
+    pub session_id: SessionId,
 
-        // Update metadata with PID
-        metadata.mark_started(pid);
-        self.save_metadata(&metadata)?;
+    /// The output line that asked
+    pub line: String,
+}
 
-        // Create stdin channel for sending input to the session
-        let (stdin_tx, stdin_rx) = mpsc::unbounded_channel::<String>();
+/// How many unanswered approval prompts a slow subscriber may fall behind by
+const APPROVAL_PROMPT_BACKLOG: usize = 16;
 
-        // Spawn monitoring task with registry access for metadata updates
-        let session_id_clone = session_id.clone();
-        let sessions_for_task = self.sessions.clone();
+/// A line of a session's output, after output filters
+#[derive(Debug, Clone)]
+pub struct OutputLine {
+    /// Session that printed the line
+    pub session_id: SessionId,
 
-        let task_handle = tokio::spawn(async move {
-            let exit_code = monitor_process(child, session_id_clone.clone(), logger, stdin_rx).await;
+    /// The line, without its newline
+    pub line: String,
+}
 
-            // Update metadata in registry based on exit code
-            let mut sessions = sessions_for_task.write().await;
-            if let Some(handle) = sessions.get_mut(&session_id_clone) {
-                match exit_code {
-                    Ok(0) => handle.metadata.mark_completed(),
-                    Ok(_) => handle.metadata.mark_failed(),
-                    Err(_) => handle.metadata.mark_failed(),
-                }
-            }
+/// What `tail_sessions` reports for the sessions it follows
+#[derive(Debug, Clone)]
+pub enum TailEvent {
+    /// A followed session printed a line
+    Line(OutputLine),
 
-            exit_code
-        });
+    /// A followed session changed to this (non-terminal) status
+    Status(SessionId, SessionStatus),
 
-        // Create session handle with stdin sender
-        let handle = SessionHandle {
-            metadata,
-            task_handle: Some(task_handle),
-            stdin_tx: Some(stdin_tx),
-        };
+    /// A followed session ended with this status
+    Ended(SessionId, SessionStatus),
+}
 
-        // Add to registry
-        let mut sessions = self.sessions.write().await;
-        sessions.insert(session_id.clone(), handle);
+/// Which sessions a tail still follows
+struct TailFilter {
+    /// Follow every session (the tail never finishes on its own)
+    all: bool,
 
-        info!("Session {} started successfully", session_id);
+    /// Followed sessions that haven't ended yet
+    following: HashSet<SessionId>,
+}
 
-        Ok(session_id)
+impl TailFilter {
+    fn line(&self, line: OutputLine) -> Option<TailEvent> {
+        (self.all || self.following.contains(&line.session_id)).then_some(TailEvent::Line(line))
     }
 
-    /// Spawn a child session with a parent
-    ///
-    /// Creates a new session as a child of an existing parent session.
-    pub async fn spawn_child_session(
-        &self,
-        parent_id: SessionId,
-        role: Role,
-        task: String,
-    ) -> Result<SessionId> {
-        // Verify parent session exists
-        if self.get_session(&parent_id).await.is_none() {
-            return Err(ClaudeManError::SessionNotFound(format!(
-                "Parent session not found: {}",
-                parent_id
-            )));
+    fn status(&mut self, event: LifecycleEvent) -> Option<TailEvent> {
+        if !self.all && !self.following.contains(&event.session_id) {
+            return None;
+        }
+        if event.new_status.is_terminal() {
+            self.following.remove(&event.session_id);
+            Some(TailEvent::Ended(event.session_id, event.new_status))
+        } else {
+            Some(TailEvent::Status(event.session_id, event.new_status))
         }
+    }
 
-        let session_id = self.next_session_id(role).await;
-        let log_dir = session_log_dir(&session_id);
+    fn finished(&self) -> bool {
+        !self.all && self.following.is_empty()
+    }
+}
 
-        info!(
-            "Spawning child session {} with role {:?} (parent: {})",
-            session_id, role, parent_id
-        );
+/// How many output lines a slow subscriber may fall behind by before missing some
+const OUTPUT_BACKLOG: usize = 1024;
 
-        // Create session metadata with parent
-        let mut metadata = SessionMetadata::new_child(
-            session_id.clone(),
-            role,
-            task.clone(),
-            log_dir.clone(),
-            parent_id,
-        );
+/// How waiting for a session's readiness marker ended
+#[derive(Debug, Clone)]
+pub enum ReadyOutcome {
+    /// This output line matched
+    Ready(String),
 
-        // Create log directory
-        fs::create_dir_all(&log_dir)?;
+    /// The session finished first; its final metadata
+    Exited(Box<SessionMetadata>),
+}
 
-        // Set up .claude directory with hooks for auto-approval
-        Self::setup_session_claude_config(&log_dir)?;
+/// A session moving from one status to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    /// Session that changed
+    pub session_id: SessionId,
 
-        // Create logger
-        let logger = SessionLogger::new(session_id.clone(), &log_dir)?;
+    /// Role of the session
+    pub role: Role,
 
-        // Save metadata to file
-        self.save_metadata(&metadata)?;
+    /// Status before the change
+    pub old_status: SessionStatus,
 
-        // Write role-specific context file if applicable
-        let task_with_context = if let Some(context) = Self::get_role_context(role) {
-            Self::write_role_context(&log_dir, &context)?;
-            format!("First, read role-context.md in your working directory for your role instructions. Then: {}", task)
-        } else {
-            task.clone()
-        };
+    /// Status after the change
+    pub new_status: SessionStatus,
 
-        // Create spawn configuration with working directory set to log dir
-        let config = SpawnConfig::new(task_with_context).with_working_dir(log_dir.clone());
+    /// When the change happened
+    pub at: DateTime<Utc>,
+}
 
-        // Spawn the Claude CLI process with stdin support
-        let child = spawn_claude_process(config).await?;
-        let pid = child.id().ok_or_else(|| {
-            ClaudeManError::Process("Failed to get process ID".to_string())
-        })?;
+/// How many lifecycle events a slow subscriber may fall behind by before missing some
+const LIFECYCLE_BACKLOG: usize = 256;
 
-        // Update metadata with PID
-        metadata.mark_started(pid);
-        self.save_metadata(&metadata)?;
+/// How often a recovered session's process is checked for exit
+const RECOVERED_EXIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-        // Create stdin channel for sending input to the session
-        let (stdin_tx, stdin_rx) = mpsc::unbounded_channel::<String>();
+/// Rolling window `max_sessions_per_hour` is counted over
+const SPAWN_BUDGET_WINDOW: chrono::Duration = chrono::Duration::hours(1);
 
-        // Spawn monitoring task with registry access for metadata updates
-        let session_id_clone = session_id.clone();
-        let sessions_for_task = self.sessions.clone();
+/// A child session waiting for a free slot under its parent
+struct QueuedChild {
+    session_id: SessionId,
+    parent_id: SessionId,
+    limit: usize,
+    options: SpawnOptions,
+}
 
-        let task_handle = tokio::spawn(async move {
-            let exit_code = monitor_process(child, session_id_clone.clone(), logger, stdin_rx).await;
+/// Lock file marking a session directory as in use by a running session
+const SESSION_LOCK_FILE: &str = "session.lock";
 
-            // Update metadata in registry based on exit code
-            let mut sessions = sessions_for_task.write().await;
-            if let Some(handle) = sessions.get_mut(&session_id_clone) {
-                match exit_code {
-                    Ok(0) => handle.metadata.mark_completed(),
-                    Ok(_) => handle.metadata.mark_failed(),
-                    Err(_) => handle.metadata.mark_failed(),
-                }
-            }
+/// Claim on a session directory, held while the session's process runs
+///
+/// The lock file records the claude-man process and session that own the
+/// directory, so a directory-template collision can't make two sessions
+/// write the same io.log. Dropping the claim removes the file.
+struct SessionLock {
+    /// Lock file to remove on drop (`None` once kept)
+    path: Option<std::path::PathBuf>,
 
-            exit_code
-        });
+    /// Identifies this claim, so dropping it can't remove a later run's lock
+    nonce: String,
+}
 
-        // Create session handle with stdin sender
-        let handle = SessionHandle {
-            metadata,
-            task_handle: Some(task_handle),
-            stdin_tx: Some(stdin_tx),
+impl SessionLock {
+    /// Claim `log_dir` for `session_id`
+    ///
+    /// Fails if the lock is held by a live process for another session (or
+    /// by another claude-man process), or if the directory's metadata.json
+    /// says a session is still running there. A stale lock left by a crashed
+    /// process is taken over.
+    fn acquire(log_dir: &std::path::Path, session_id: &SessionId) -> Result<Self> {
+        Self::check(log_dir, session_id)?;
+
+        fs::create_dir_all(log_dir)?;
+        let path = log_dir.join(SESSION_LOCK_FILE);
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        fs::write(&path, format!("{} {} {}\n", std::process::id(), session_id, nonce))?;
+
+        Ok(Self { path: Some(path), nonce })
+    }
+
+    /// Fail if `log_dir` is in use by anything but this process's `session_id`
+    fn check(log_dir: &std::path::Path, session_id: &SessionId) -> Result<()> {
+        let in_use = |owner: &str, pid: u32| {
+            ClaudeManError::Session(format!(
+                "Session directory {} is in use by {} (PID {}); refusing to start {} there. \
+                 Check the session directory template for collisions",
+                log_dir.display(),
+                owner,
+                pid,
+                session_id
+            ))
         };
 
-        // Add to registry
-        let mut sessions = self.sessions.write().await;
-        sessions.insert(session_id.clone(), handle);
+        if let Some((pid, owner, _)) = read_session_lock(&log_dir.join(SESSION_LOCK_FILE)) {
+            let ours = pid == std::process::id() && owner == session_id.as_str();
+            if !ours && is_process_alive(pid) {
+                return Err(in_use(&owner, pid));
+            }
+        }
 
-        info!("Child session {} started successfully", session_id);
+        // Sessions recovered by a restarted daemon run without a live lock owner
+        if let Ok(contents) = fs::read_to_string(log_dir.join("metadata.json")) {
+            if let Ok(metadata) = serde_json::from_str::<SessionMetadata>(&contents) {
+                if let Some(pid) = metadata.pid.filter(|&pid| metadata.is_active() && is_process_alive(pid)) {
+                    return Err(in_use(metadata.id.as_str(), pid));
+                }
+            }
+        }
 
-        Ok(session_id)
+        Ok(())
     }
 
-    /// Resume an existing session with additional input
+    /// Leave the lock file for whoever claimed the directory next
+    fn keep(mut self) {
+        self.path = None;
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        // Leave the file alone if another claim has taken it over, even one
+        // for the same session (a restart while this run's monitor finishes)
+        let ours = read_session_lock(path)
+            .is_some_and(|(pid, _, nonce)| pid == std::process::id() && nonce == self.nonce);
+        if ours {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Read the owning PID, session ID and claim nonce from a lock file (`None`
+/// if missing or unreadable; the nonce is empty in files without one)
+fn read_session_lock(path: &std::path::Path) -> Option<(u32, String, String)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut fields = contents.split_whitespace();
+    let pid = fields.next()?.parse().ok()?;
+    let owner = fields.next()?.to_string();
+    Some((pid, owner, fields.next().unwrap_or_default().to_string()))
+}
+
+/// Session registry managing all active sessions
+///
+/// Cloning a registry is cheap and shares the same underlying state.
+#[derive(Clone)]
+pub struct SessionRegistry {
+    /// Map of session ID to session handle
+    sessions: Arc<RwLock<HashMap<SessionId, SessionHandle>>>,
+
+    /// Counter for generating unique session IDs per role
+    role_counters: Arc<RwLock<HashMap<Role, u32>>>,
+
+    /// Optional project prefix prepended to generated session IDs
+    project: Arc<RwLock<Option<String>>>,
+
+    /// Flush policy for loggers of newly started processes
+    flush_policy: Arc<RwLock<FlushPolicy>>,
+
+    /// Write metadata.json on one line instead of pretty-printed
     ///
-    /// Uses Claude's --resume flag to continue a session
-    pub async fn resume_session(
+    /// Atomic rather than locked because metadata is saved from sync code.
+    compact_metadata: Arc<AtomicBool>,
+
+    /// Fail loading from disk on an unreadable metadata.json instead of skipping it
+    strict_load: Arc<AtomicBool>,
+
+    /// Directory holding the session directories (`default_log_dir()` unless overridden)
+    sessions_dir: std::path::PathBuf,
+
+    /// Idempotency keys of spawns, with the session each one started
+    idempotency_keys: Arc<Mutex<HashMap<String, SessionId>>>,
+
+    /// One lock per session, held while its metadata.json is written
+    ///
+    /// Blocking rather than async mutexes because metadata is saved from
+    /// sync code; they are only held for the write itself.
+    metadata_locks: Arc<std::sync::Mutex<HashMap<SessionId, Arc<std::sync::Mutex<()>>>>>,
+
+    /// Output lines longer than this are truncated in logs and on the console
+    max_line_bytes: Arc<RwLock<Option<usize>>>,
+
+    /// io.log is rotated once it reaches this many bytes
+    max_log_bytes: Arc<RwLock<u64>>,
+
+    /// Default limit on running children per parent (None for unlimited)
+    max_concurrent_per_parent: Arc<RwLock<Option<usize>>>,
+
+    /// Limit on sessions spawned in any rolling hour (None for unlimited)
+    max_sessions_per_hour: Arc<RwLock<Option<usize>>>,
+
+    /// Limit on running sessions checked by new spawns (None for unlimited)
+    max_concurrent: Arc<RwLock<Option<usize>>>,
+
+    /// Held from the `max_concurrent` check until the new session is running
+    launch_gate: Arc<Mutex<()>>,
+
+    /// When recent sessions were spawned, oldest first, for `max_sessions_per_hour`
+    recent_spawns: Arc<Mutex<VecDeque<DateTime<Utc>>>>,
+
+    /// Child sessions waiting for a sibling to finish, in spawn order
+    queued_children: Arc<RwLock<VecDeque<QueuedChild>>>,
+
+    /// Command prepended to every claude invocation (empty for none)
+    wrapper: Arc<RwLock<Vec<String>>>,
+
+    /// Working directory for sessions of a role spawned without one
+    role_working_dirs: Arc<RwLock<HashMap<Role, std::path::PathBuf>>>,
+
+    /// Time a stopped process gets to exit after SIGTERM before SIGKILL
+    termination_timeout: Arc<RwLock<Duration>>,
+
+    /// Transforms applied to every output line before it is echoed or logged
+    output_filters: Arc<RwLock<OutputFilters>>,
+
+    /// Which process streams are echoed to the console
+    console_echo: Arc<RwLock<ConsoleEcho>>,
+
+    /// Shared key/value store; the lock serializes its read-modify-write cycles
+    kv: Arc<Mutex<KvStore>>,
+
+    /// How new session directories are named
+    session_dir_template: Arc<RwLock<SessionDirTemplate>>,
+
+    /// Recognizes approval prompts in output (None when nobody is answering them)
+    prompt_detector: Arc<RwLock<Option<PromptDetector>>>,
+
+    /// Publishes the approval prompts `prompt_detector` sees
+    approval_prompts: broadcast::Sender<ApprovalPrompt>,
+
+    /// Publishes every session status change
+    lifecycle: broadcast::Sender<LifecycleEvent>,
+
+    /// Publishes output lines while anyone is subscribed
+    output: broadcast::Sender<OutputLine>,
+}
+
+impl SessionRegistry {
+    /// Create a new empty session registry
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            role_counters: Arc::new(RwLock::new(HashMap::new())),
+            project: Arc::new(RwLock::new(None)),
+            flush_policy: Arc::new(RwLock::new(FlushPolicy::default())),
+            compact_metadata: Arc::new(AtomicBool::new(false)),
+            strict_load: Arc::new(AtomicBool::new(false)),
+            sessions_dir: default_log_dir(),
+            idempotency_keys: Arc::new(Mutex::new(HashMap::new())),
+            metadata_locks: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            max_line_bytes: Arc::new(RwLock::new(None)),
+            max_log_bytes: Arc::new(RwLock::new(DEFAULT_MAX_LOG_BYTES)),
+            max_concurrent_per_parent: Arc::new(RwLock::new(None)),
+            max_sessions_per_hour: Arc::new(RwLock::new(None)),
+            max_concurrent: Arc::new(RwLock::new(None)),
+            launch_gate: Arc::new(Mutex::new(())),
+            recent_spawns: Arc::new(Mutex::new(VecDeque::new())),
+            queued_children: Arc::new(RwLock::new(VecDeque::new())),
+            wrapper: Arc::new(RwLock::new(Vec::new())),
+            role_working_dirs: Arc::new(RwLock::new(HashMap::new())),
+            termination_timeout: Arc::new(RwLock::new(DEFAULT_TERMINATION_TIMEOUT)),
+            output_filters: Arc::new(RwLock::new(OutputFilters::default())),
+            console_echo: Arc::new(RwLock::new(ConsoleEcho::default())),
+            kv: Arc::new(Mutex::new(KvStore::default())),
+            session_dir_template: Arc::new(RwLock::new(SessionDirTemplate::default())),
+            prompt_detector: Arc::new(RwLock::new(None)),
+            approval_prompts: broadcast::channel(APPROVAL_PROMPT_BACKLOG).0,
+            lifecycle: broadcast::channel(LIFECYCLE_BACKLOG).0,
+            output: broadcast::channel(OUTPUT_BACKLOG).0,
+        }
+    }
+
+    /// Namespace generated session IDs under a project prefix
+    pub fn with_project(mut self, project: Option<String>) -> Self {
+        self.project = Arc::new(RwLock::new(project));
+        self
+    }
+
+    /// Get the project prefix used for generated session IDs
+    pub async fn project(&self) -> Option<String> {
+        self.project.read().await.clone()
+    }
+
+    /// Change the project prefix for sessions spawned from now on
+    pub async fn set_project(&self, project: Option<String>) {
+        *self.project.write().await = project;
+    }
+
+    /// Name new session directories with a template instead of the bare ID
+    pub fn with_session_dir_template(mut self, template: SessionDirTemplate) -> Self {
+        self.session_dir_template = Arc::new(RwLock::new(template));
+        self
+    }
+
+    /// Change the directory naming template for sessions spawned from now on
+    pub async fn set_session_dir_template(&self, template: SessionDirTemplate) {
+        *self.session_dir_template.write().await = template;
+    }
+
+    /// Watch the output of sessions started from now on for approval prompts
+    pub async fn watch_approval_prompts(&self, detector: PromptDetector) {
+        *self.prompt_detector.write().await = Some(detector);
+    }
+
+    /// Subscribe to approval prompts (None unless they are being watched for)
+    pub async fn approval_prompts(&self) -> Option<broadcast::Receiver<ApprovalPrompt>> {
+        self.prompt_detector
+            .read()
+            .await
+            .is_some()
+            .then(|| self.approval_prompts.subscribe())
+    }
+
+    /// Subscribe to status changes of every session
+    ///
+    /// Events are published after the change is made in the registry. A
+    /// subscriber that falls more than a few hundred events behind misses the
+    /// oldest ones (`RecvError::Lagged`).
+    pub fn subscribe_lifecycle(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle.subscribe()
+    }
+
+    /// Subscribe to the output lines of every session
+    ///
+    /// Lines are published as they pass the output filters, so redacted text
+    /// stays redacted.
+    pub fn subscribe_output(&self) -> broadcast::Receiver<OutputLine> {
+        self.output.subscribe()
+    }
+
+    /// Follow the live output and status changes of several sessions as one stream
+    ///
+    /// Lines arrive in the order the sessions printed them. Each session gets
+    /// an `Ended` event when it finishes, and the stream closes after the
+    /// last one. With no IDs every session is followed until the receiver is
+    /// dropped. Subscribe to output and lifecycle events before starting a
+    /// session so none of its lines are missed.
+    pub async fn tail_sessions(
         &self,
-        session_id: SessionId,
-        message: String,
-    ) -> Result<()> {
-        info!("Resuming session {} with message", session_id);
+        session_ids: Vec<SessionId>,
+        mut output: broadcast::Receiver<OutputLine>,
+        mut lifecycle: broadcast::Receiver<LifecycleEvent>,
+    ) -> Result<mpsc::UnboundedReceiver<TailEvent>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut filter = TailFilter { all: session_ids.is_empty(), following: HashSet::new() };
+        let mut already_ended = Vec::new();
+        for session_id in session_ids {
+            let status = self.get_or_load_session(&session_id).await?.status;
+            if status.is_terminal() {
+                already_ended.push((session_id.clone(), status));
+            }
+            filter.following.insert(session_id);
+        }
+
+        // Pass on what happened since subscribing, so a session that has
+        // already ended still shows its last lines
+        while let Ok(line) = output.try_recv() {
+            if let Some(event) = filter.line(line) {
+                let _ = tx.send(event);
+            }
+        }
+        while let Ok(event) = lifecycle.try_recv() {
+            if let Some(event) = filter.status(event) {
+                let _ = tx.send(event);
+            }
+        }
+        for (session_id, status) in already_ended {
+            if filter.following.remove(&session_id) {
+                let _ = tx.send(TailEvent::Ended(session_id, status));
+            }
+        }
+        if filter.finished() {
+            return Ok(rx);
+        }
+
+        tokio::spawn(async move {
+            loop {
+                // Drain output before exits, so a session's last lines come before its end
+                let event = tokio::select! {
+                    biased;
+                    line = output.recv() => match line {
+                        Ok(line) => filter.line(line),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            warn!("Tail missed {} output lines", missed);
+                            None
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    event = lifecycle.recv() => match event {
+                        Ok(event) => filter.status(event),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            warn!("Tail missed {} status changes", missed);
+                            None
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+                if let Some(event) = event {
+                    if tx.send(event).is_err() || filter.finished() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// Wait until a line of `session_id`'s output matches `marker`, or the session finishes
+    ///
+    /// Subscribe to output and lifecycle events before starting the session
+    /// so no line or exit is missed.
+    pub async fn wait_for_ready(
+        &self,
+        session_id: &SessionId,
+        marker: &ReadyMarker,
+        mut output: broadcast::Receiver<OutputLine>,
+        mut lifecycle: broadcast::Receiver<LifecycleEvent>,
+    ) -> Result<ReadyOutcome> {
+        loop {
+            // Drain output before exits, so a marker printed just before exiting counts
+            tokio::select! {
+                biased;
+                line = output.recv() => match line {
+                    Ok(line) if line.session_id == *session_id && marker.is_ready(&line.line) => {
+                        return Ok(ReadyOutcome::Ready(line.line));
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Missed {} output lines while waiting for {} to be ready", missed, session_id);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        return Err(ClaudeManError::Other("session output is no longer published".to_string()));
+                    }
+                },
+                event = lifecycle.recv() => {
+                    let relevant = match event {
+                        Ok(event) => event.session_id == *session_id,
+                        // Events were missed; look at the session itself
+                        Err(broadcast::error::RecvError::Lagged(_)) => true,
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Err(ClaudeManError::Other("session status is no longer published".to_string()));
+                        }
+                    };
+                    if relevant {
+                        let metadata = self
+                            .get_session(session_id)
+                            .await
+                            .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+                        if metadata.is_terminal() {
+                            return Ok(ReadyOutcome::Exited(Box::new(metadata)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Announce that a session moved from `old_status` to its current status
+    fn publish_transition(&self, old_status: SessionStatus, metadata: &SessionMetadata) {
+        if metadata.status == old_status {
+            return;
+        }
+        // No subscribers is fine
+        let _ = self.lifecycle.send(LifecycleEvent {
+            session_id: metadata.id.clone(),
+            role: metadata.role,
+            old_status,
+            new_status: metadata.status,
+            at: Utc::now(),
+        });
+    }
+
+    /// Output filters for a session's process, plus approval prompt detection if enabled
+    async fn session_filters(&self, session_id: &SessionId) -> OutputFilters {
+        let mut filters = self.output_filters.read().await.clone();
+        if let Some(detector) = self.prompt_detector.read().await.clone() {
+            let session_id = session_id.clone();
+            let prompts = self.approval_prompts.clone();
+            filters.push(Arc::new(move |line: &str| {
+                if detector.is_prompt(line) {
+                    // No subscribers just means nobody is answering
+                    let _ = prompts.send(ApprovalPrompt {
+                        session_id: session_id.clone(),
+                        line: line.to_string(),
+                    });
+                }
+                Some(line.to_string())
+            }));
+        }
+        let session_id = session_id.clone();
+        let output = self.output.clone();
+        filters.push(Arc::new(move |line: &str| {
+            if output.receiver_count() > 0 {
+                let _ = output.send(OutputLine {
+                    session_id: session_id.clone(),
+                    line: line.to_string(),
+                });
+            }
+            Some(line.to_string())
+        }));
+        filters
+    }
+
+    /// Log directory for a new session, named by the directory template
+    async fn new_log_dir(&self, session_id: &SessionId, role: Role, task: &str) -> std::path::PathBuf {
+        let name = self
+            .session_dir_template
+            .read()
+            .await
+            .render(session_id, role, task, Utc::now());
+        self.sessions_dir.join(name)
+    }
+
+    /// Set the log flush policy for sessions
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = Arc::new(RwLock::new(policy));
+        self
+    }
+
+    /// Change the log flush policy for processes started from now on
+    pub async fn set_flush_policy(&self, policy: FlushPolicy) {
+        *self.flush_policy.write().await = policy;
+    }
+
+    /// Store metadata.json as compact single-line JSON instead of pretty-printed
+    pub fn with_compact_metadata(mut self, compact: bool) -> Self {
+        self.compact_metadata = Arc::new(AtomicBool::new(compact));
+        self
+    }
+
+    /// Change how metadata.json is written from the next save on
+    pub fn set_compact_metadata(&self, compact: bool) {
+        self.compact_metadata.store(compact, Ordering::Relaxed);
+    }
+
+    /// Create, load and back up session directories under `dir`
+    pub fn with_sessions_dir(mut self, dir: std::path::PathBuf) -> Self {
+        self.sessions_dir = dir;
+        self
+    }
+
+    /// Directory holding the session directories
+    pub fn sessions_dir(&self) -> &std::path::Path {
+        &self.sessions_dir
+    }
+
+    /// Log directory of a session stored under this registry's sessions directory
+    pub fn session_log_dir(&self, session_id: &SessionId) -> std::path::PathBuf {
+        session_log_dir_in(&self.sessions_dir, session_id)
+    }
+
+    /// Make [`load_from_disk`](Self::load_from_disk) fail on an unreadable
+    /// metadata.json instead of skipping it with a warning
+    pub fn with_strict_load(mut self, strict: bool) -> Self {
+        self.strict_load = Arc::new(AtomicBool::new(strict));
+        self
+    }
+
+    /// Change whether unreadable metadata fails the next load from disk
+    pub fn set_strict_load(&self, strict: bool) {
+        self.strict_load.store(strict, Ordering::Relaxed);
+    }
+
+    /// Truncate output lines longer than `limit` bytes before they are logged
+    pub fn with_max_line_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_line_bytes = Arc::new(RwLock::new(limit));
+        self
+    }
+
+    /// Change the output line limit for processes started from now on
+    pub async fn set_max_line_bytes(&self, limit: Option<usize>) {
+        *self.max_line_bytes.write().await = limit;
+    }
+
+    /// Rotate session logs once they reach `bytes`
+    pub fn with_max_log_bytes(mut self, bytes: u64) -> Self {
+        self.max_log_bytes = Arc::new(RwLock::new(bytes));
+        self
+    }
+
+    /// Change the rotation size for loggers created from now on
+    pub async fn set_max_log_bytes(&self, bytes: u64) {
+        *self.max_log_bytes.write().await = bytes;
+    }
+
+    /// Limit how many children of the same parent may run at once
+    pub fn with_max_concurrent_per_parent(mut self, limit: Option<usize>) -> Self {
+        self.max_concurrent_per_parent = Arc::new(RwLock::new(limit));
+        self
+    }
+
+    /// Change the per-parent child limit for spawns from now on
+    pub async fn set_max_concurrent_per_parent(&self, limit: Option<usize>) {
+        *self.max_concurrent_per_parent.write().await = limit;
+    }
+
+    /// Limit how many sessions may be spawned in any rolling hour
+    pub fn with_max_sessions_per_hour(mut self, limit: Option<usize>) -> Self {
+        self.max_sessions_per_hour = Arc::new(RwLock::new(limit));
+        self
+    }
+
+    /// Change the hourly spawn budget for spawns from now on
+    pub async fn set_max_sessions_per_hour(&self, limit: Option<usize>) {
+        *self.max_sessions_per_hour.write().await = limit;
+    }
+
+    /// Limit how many sessions may run at once; spawns beyond it are rejected
+    pub fn with_max_concurrent(mut self, limit: Option<usize>) -> Self {
+        self.max_concurrent = Arc::new(RwLock::new(limit));
+        self
+    }
+
+    /// Change the running session limit for spawns from now on
+    pub async fn set_max_concurrent(&self, limit: Option<usize>) {
+        *self.max_concurrent.write().await = limit;
+    }
+
+    /// Check there's room for one more running session
+    ///
+    /// The returned guard holds the launch gate; keep it until the new
+    /// session is running so concurrent spawns can't take the same slot.
+    async fn claim_running_slot(&self) -> Result<Option<MutexGuard<'_, ()>>> {
+        let Some(limit) = *self.max_concurrent.read().await else {
+            return Ok(None);
+        };
+
+        let gate = self.launch_gate.lock().await;
+        let running = self.count_active().await;
+        if running >= limit {
+            return Err(ClaudeManError::Session(format!(
+                "concurrency limit reached: {} of {} sessions running",
+                running, limit
+            )));
+        }
+
+        Ok(Some(gate))
+    }
+
+    /// Take a slot from the hourly spawn budget, or say when the next one opens
+    async fn reserve_spawn_slot(&self) -> Result<()> {
+        let Some(limit) = *self.max_sessions_per_hour.read().await else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let mut recent = self.recent_spawns.lock().await;
+        while recent.front().is_some_and(|spawned| now - *spawned >= SPAWN_BUDGET_WINDOW) {
+            recent.pop_front();
+        }
+
+        if recent.len() >= limit {
+            // Enough of the oldest spawns must age out to get back under the limit
+            let opens_at = recent[recent.len() - limit] + SPAWN_BUDGET_WINDOW;
+            let wait = (opens_at - now).num_seconds().max(1);
+            return Err(ClaudeManError::Session(format!(
+                "Spawn budget of {} sessions per hour is used up; next slot opens at {} (in {}m {}s)",
+                limit,
+                opens_at.format("%H:%M:%S UTC"),
+                wait / 60,
+                wait % 60
+            )));
+        }
+
+        recent.push_back(now);
+        Ok(())
+    }
+
+    /// Run claude through a wrapper command (e.g. `docker run --rm img`)
+    pub fn with_wrapper(mut self, wrapper: Vec<String>) -> Self {
+        self.wrapper = Arc::new(RwLock::new(wrapper));
+        self
+    }
+
+    /// Change the wrapper command for processes started from now on
+    pub async fn set_wrapper(&self, wrapper: Vec<String>) {
+        *self.wrapper.write().await = wrapper;
+    }
+
+    /// Start sessions of the mapped roles in these directories unless told otherwise
+    pub fn with_role_working_dirs(mut self, dirs: HashMap<Role, std::path::PathBuf>) -> Self {
+        self.role_working_dirs = Arc::new(RwLock::new(dirs));
+        self
+    }
+
+    /// Change the per-role working directories for sessions spawned from now on
+    pub async fn set_role_working_dirs(&self, dirs: HashMap<Role, std::path::PathBuf>) {
+        *self.role_working_dirs.write().await = dirs;
+    }
+
+    /// Fill in the role's working directory when the settings don't name one
+    async fn apply_role_working_dir(&self, role: Role, settings: &mut SpawnSettings) {
+        if settings.working_dir.is_none() {
+            settings.working_dir = self.role_working_dirs.read().await.get(&role).cloned();
+        }
+    }
+
+    /// Set how long stopped processes get to exit before they are killed
+    pub fn with_termination_timeout(mut self, timeout: Duration) -> Self {
+        self.termination_timeout = Arc::new(RwLock::new(timeout));
+        self
+    }
+
+    /// Change the grace period for sessions stopped from now on
+    pub async fn set_termination_timeout(&self, timeout: Duration) {
+        *self.termination_timeout.write().await = timeout;
+    }
+
+    /// Choose which process streams are echoed to the console
+    pub fn with_console_echo(mut self, echo: ConsoleEcho) -> Self {
+        self.console_echo = Arc::new(RwLock::new(echo));
+        self
+    }
+
+    /// Use a key/value store other than the default `.claude-man/kv.json`
+    pub fn with_kv_store(mut self, store: KvStore) -> Self {
+        self.kv = Arc::new(Mutex::new(store));
+        self
+    }
+
+    /// Change console echoing for processes started from now on
+    pub async fn set_console_echo(&self, echo: ConsoleEcho) {
+        *self.console_echo.write().await = echo;
+    }
+
+    /// Register a filter for the output of processes started from now on
+    ///
+    /// The filter sees each stdout/stderr line before it is echoed or written
+    /// to io.log; it returns the line to keep or `None` to drop it. Filters run
+    /// in registration order.
+    pub async fn on_output<F>(&self, filter: F)
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        let filter: OutputFilter = Arc::new(filter);
+        self.output_filters.write().await.push(filter);
+    }
+
+    /// Build the process configuration for a session's launch settings
+    ///
+    /// `default_dir` is the working directory when the settings don't name one.
+    async fn spawn_config(
+        &self,
+        task: String,
+        settings: &SpawnSettings,
+        default_dir: Option<&std::path::Path>,
+    ) -> SpawnConfig {
+        let mut config = SpawnConfig::new(task);
+        if let Some(dir) = settings.working_dir.as_deref().or(default_dir) {
+            config = config.with_working_dir(dir.to_path_buf());
+        }
+        config = config
+            .with_wrapper(self.wrapper.read().await.clone())
+            .with_model(settings.model.clone())
+            .with_program(settings.claude_binary.clone());
+        for (key, value) in &settings.env {
+            config = config.with_env(key.clone(), value.clone());
+        }
+        config
+    }
+
+    /// Get role-specific context for a session by reading from ROLES/ directory
+    fn get_role_context(role: Role) -> Option<String> {
+        use std::path::Path;
+
+        // Try to find role file in ROLES/ directory (project root or parent directories)
+        let role_filename = format!("{}.md", role.to_string().to_uppercase());
+
+        // Search in current dir, parent, grandparent for ROLES/ directory
+        let search_paths = vec![
+            Path::new("ROLES").join(&role_filename),
+            Path::new("../ROLES").join(&role_filename),
+            Path::new("../../ROLES").join(&role_filename),
+        ];
+
+        for path in search_paths {
+            if let Ok(content) = fs::read_to_string(&path) {
+                info!("Loaded role context from {:?}", path);
+                return Some(content);
+            }
+        }
+
+        warn!("No role context file found for role: {}", role);
+        None
+    }
+
+    /// Write role context to a markdown file in the session directory
+    fn write_role_context(log_dir: &std::path::Path, context: &str) -> Result<std::path::PathBuf> {
+        let context_path = log_dir.join("role-context.md");
+        fs::write(&context_path, context)?;
+        Ok(context_path)
+    }
+
+    /// Create .claude directory with hooks for auto-approval, returning the hook's path
+    fn setup_session_claude_config(log_dir: &std::path::Path) -> Result<std::path::PathBuf> {
+        let claude_dir = log_dir.join(".claude");
+        let hooks_dir = claude_dir.join("hooks");
+        fs::create_dir_all(&hooks_dir)?;
+
+        // Create pre-tool-use hook that auto-approves claude-man commands
+        let hook_script = r#"#!/usr/bin/env bash
+# Auto-approve claude-man commands for orchestration
+if echo "$TOOL_USE_JSON" | grep -q "claude-man"; then
+  exit 0  # Approve
+fi
+exit 1  # Require approval for other commands
+"#;
+
+        let hook_path = hooks_dir.join("pre-tool-use.sh");
+        fs::write(&hook_path, hook_script)?;
+
+        // Make hook executable (Unix only, no-op on Windows)
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&hook_path)?.permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&hook_path, perms)?;
+        }
+
+        Ok(hook_path)
+    }
+
+    /// Write the role context and hook files a session of `role` starts with into `dir`
+    ///
+    /// Returns the paths written. Lets `validate` show what a spawn would
+    /// generate without launching anything.
+    pub fn write_session_files(role: Role, dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>> {
+        let mut files = Vec::new();
+        if let Some(context) = Self::get_role_context(role) {
+            files.push(Self::write_role_context(dir, &context)?);
+        }
+        files.push(Self::setup_session_claude_config(dir)?);
+        Ok(files)
+    }
+
+    /// Load sessions from disk
+    ///
+    /// Scans the .claude-man/sessions directory and loads all session metadata.
+    /// Only includes sessions that are marked as running and have valid PIDs.
+    pub async fn load_from_disk(&self) -> Result<()> {
+        self.load_sessions_from(&self.sessions_dir).await?;
+        Ok(())
+    }
+
+    /// Load sessions from `sessions_dir`, returning the metadata.json files
+    /// that couldn't be read and were skipped
+    ///
+    /// With strict loading the first unreadable file is an error instead.
+    async fn load_sessions_from(&self, sessions_dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>> {
+        let mut skipped = Vec::new();
+        if !sessions_dir.exists() {
+            return Ok(skipped);
+        }
+
+        info!("Loading sessions from disk...");
+
+        // Sessions spawned by earlier runs still count against the hourly budget
+        let budget_start = Utc::now() - SPAWN_BUDGET_WINDOW;
+        let mut recent_spawns = Vec::new();
+
+        for entry in fs::read_dir(sessions_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let session_dir = entry.path();
+            let metadata_path = session_dir.join("metadata.json");
+
+            if !metadata_path.exists() {
+                continue;
+            }
+
+            let metadata = match Self::load_metadata_from_path(&metadata_path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    if self.strict_load.load(Ordering::Relaxed) {
+                        return Err(ClaudeManError::Session(format!(
+                            "Can't load {}: {} (run `claude-man gc` to find unreadable sessions)",
+                            metadata_path.display(),
+                            e
+                        )));
+                    }
+                    match salvage_session_label(&metadata_path) {
+                        Some(label) => warn!("Skipping session {}: can't load {:?}: {}", label, metadata_path, e),
+                        None => warn!("Skipping unreadable {:?}: {}", metadata_path, e),
+                    }
+                    skipped.push(metadata_path);
+                    continue;
+                }
+            };
+
+            if metadata.created_at > budget_start {
+                recent_spawns.push(metadata.created_at);
+            }
+
+            // Only load if marked as running (or paused)
+            if metadata.is_active() {
+                // Check if process is still alive
+                if let Some(pid) = metadata.pid {
+                    if is_process_alive(pid) {
+                        info!("Loaded session {} (PID: {})", metadata.id, pid);
+
+                        // Create handle without monitoring task (process already running)
+                        // Note: stdin_tx is None for recovered sessions (can't attach to existing process stdin)
+                        let handle = SessionHandle {
+                            metadata,
+                            task_handle: None,
+                            stdin_tx: None,
+                            logger: None,
+                        };
+
+                        let session_id = handle.metadata.id.clone();
+                        self.sessions.write().await.insert(session_id.clone(), handle);
+                        self.watch_recovered_session(session_id, pid);
+                    } else {
+                        // Process is dead, update metadata
+                        let old_status = metadata.status;
+                        let mut dead_metadata = metadata;
+                        dead_metadata.mark_failed();
+                        let _ = self.save_metadata(&dead_metadata);
+                        self.publish_transition(old_status, &dead_metadata);
+                        info!("Session {} process is dead, marked as failed", dead_metadata.id);
+                    }
+                }
+            }
+        }
+
+        let mut recent = self.recent_spawns.lock().await;
+        recent.extend(recent_spawns);
+        recent.make_contiguous().sort();
+
+        Ok(skipped)
+    }
+
+    /// Notice when a session recovered from disk exits
+    ///
+    /// The process's output pipes belong to the claude-man that spawned it and
+    /// can't be reopened, so only the exit is observed, by polling the PID.
+    /// No task handle is stored: recovered processes are never ours to kill.
+    fn watch_recovered_session(&self, session_id: SessionId, pid: u32) {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            while is_process_alive(pid) {
+                tokio::time::sleep(RECOVERED_EXIT_POLL_INTERVAL).await;
+            }
+            registry.finish_recovered_session(&session_id, pid).await;
+        });
+    }
+
+    /// Mark a recovered session whose process exited as failed
+    ///
+    /// The exit code of a process we didn't spawn can't be collected, so the
+    /// session can't be known to have succeeded.
+    async fn finish_recovered_session(&self, session_id: &SessionId, pid: u32) {
+        let reason = format!("process {} exited after recovery; exit code unknown", pid);
+        {
+            let mut sessions = self.sessions.write().await;
+            let Some(handle) = sessions
+                .get_mut(session_id)
+                .filter(|handle| handle.metadata.is_active())
+            else {
+                return;
+            };
+            info!("Recovered session {} process {} exited", session_id, pid);
+            let old_status = handle.metadata.status;
+            handle.metadata.mark_failed_with_reason(reason.clone());
+            if let Err(e) = self.save_metadata(&handle.metadata) {
+                warn!("Failed to save metadata for {}: {}", session_id, e);
+            }
+            self.publish_transition(old_status, &handle.metadata);
+        }
+
+        // Followers of io.log see the session end
+        match self.get_logger(session_id).await {
+            Ok(logger) => {
+                if let Err(e) = logger.lock().await.log_lifecycle(SessionStatus::Failed, reason) {
+                    warn!("Failed to log exit of {}: {}", session_id, e);
+                }
+            }
+            Err(e) => warn!("Failed to open log for {}: {}", session_id, e),
+        }
+
+        // A slot is free; start any queued children that fit
+        self.launch_all_queued_children().await;
+    }
+
+    /// Load metadata from a specific path
+    fn load_metadata_from_path(path: &std::path::Path) -> Result<crate::types::session::SessionMetadata> {
+        let json = std::fs::read_to_string(path)?;
+        let metadata: crate::types::session::SessionMetadata = serde_json::from_str(&json)?;
+        Ok(metadata)
+    }
+
+    /// Generate the next session ID for a given role
+    async fn next_session_id(&self, role: Role) -> SessionId {
+        let mut counters = self.role_counters.write().await;
+        let counter = counters.entry(role).or_insert(0);
+        *counter += 1;
+        let project = self.project.read().await;
+        SessionId::new_in_project(project.as_deref(), role, *counter)
+    }
+
+    /// Pick the ID for a new session: the explicit one in `options`, or the
+    /// next one for the role
+    ///
+    /// An explicit ID already in use is an error unless `options` allow
+    /// replacing it, in which case the old session's logs are archived and
+    /// its slot freed.
+    async fn assign_session_id(&self, role: Role, options: &SpawnOptions) -> Result<SessionId> {
+        let Some(session_id) = options.session_id.as_ref() else {
+            return Ok(self.next_session_id(role).await);
+        };
+        // The ID becomes a directory name, so it must not be able to leave the data dir
+        let session_id = SessionId::parse(session_id.as_str())?;
+
+        if session_id.role_prefix() != role.prefix() || session_id.sequence().is_none() {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Session ID {} doesn't match role {} (expected {}-<number>)",
+                session_id,
+                role,
+                role.prefix()
+            )));
+        }
+
+        if let Ok(existing) = self.get_or_load_session(&session_id).await {
+            if !options.replace {
+                return Err(ClaudeManError::InvalidInput(format!(
+                    "Session {} already exists (use --replace to replace it)",
+                    session_id
+                )));
+            }
+            if !existing.is_terminal() {
+                if !options.force_replace {
+                    return Err(ClaudeManError::InvalidInput(format!(
+                        "Session {} is still {} (use --force to stop and replace it)",
+                        session_id, existing.status
+                    )));
+                }
+                self.stop_session(&session_id).await?;
+            }
+
+            self.sessions.write().await.remove(&session_id);
+            let archived = archive_log_dir(&existing.log_dir, &session_id)?;
+            info!("Replacing session {} (old logs archived to {})", session_id, archived.display());
+        }
+
+        // Keep generated IDs from colliding with the explicit one later
+        if session_id.project() == self.project.read().await.as_deref() {
+            if let Some(sequence) = session_id.sequence() {
+                let mut counters = self.role_counters.write().await;
+                let counter = counters.entry(role).or_insert(0);
+                *counter = (*counter).max(sequence);
+            }
+        }
+
+        Ok(session_id)
+    }
+
+    /// Settle the explicit ID in `options`, if any, with `assign_session_id`
+    ///
+    /// Spawns do this before claiming a running slot: replacing a running
+    /// session stops it, which frees a slot and takes the launch gate itself.
+    async fn settle_explicit_id(&self, role: Role, options: &SpawnOptions) -> Result<Option<SessionId>> {
+        match options.session_id {
+            Some(_) => self.assign_session_id(role, options).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Spawn a new session
+    ///
+    /// Creates a new session, spawns the Claude CLI process, and starts monitoring it.
+    pub async fn spawn_session(&self, role: Role, task: String) -> Result<SessionId> {
+        self.spawn_session_with_options(role, task, SpawnOptions::new()).await
+    }
+
+    /// Spawn a new session with additional options
+    pub async fn spawn_session_with_options(
+        &self,
+        role: Role,
+        task: String,
+        options: SpawnOptions,
+    ) -> Result<SessionId> {
+        let key = options.idempotency_key.clone();
+        self.spawn_once(key, self.spawn_root_session(role, task, options)).await
+    }
+
+    /// Spawn a session without a parent (see `spawn_session_with_options`)
+    async fn spawn_root_session(&self, role: Role, task: String, mut options: SpawnOptions) -> Result<SessionId> {
+        validate_non_empty(&task, "task")?;
+        let task = self.prepend_context(task, &options).await?;
+        self.apply_role_working_dir(role, &mut options.settings).await;
+        options.settings.validate_working_dir()?;
+        let explicit_id = self.settle_explicit_id(role, &options).await?;
+        let _slot = self.claim_running_slot().await?;
+        self.reserve_spawn_slot().await?;
+
+        let session_id = match explicit_id {
+            Some(session_id) => session_id,
+            None => self.next_session_id(role).await,
+        };
+        let log_dir = self.new_log_dir(&session_id, role, &task).await;
+
+        info!("Spawning session {} with role {:?}", session_id, role);
+
+        // Create session metadata
+        let metadata = SessionMetadata::new(session_id, role, task, log_dir);
+
+        self.launch_session(metadata, options).await
+    }
+
+    /// Run `spawn` unless a session spawned with the same idempotency key is unfinished
+    ///
+    /// Keyed spawns are serialized so two identical requests racing each other
+    /// still start one session. A key is forgotten once its session finishes.
+    async fn spawn_once(
+        &self,
+        key: Option<String>,
+        spawn: impl Future<Output = Result<SessionId>>,
+    ) -> Result<SessionId> {
+        let Some(key) = key else {
+            return spawn.await;
+        };
+
+        let mut keys = self.idempotency_keys.lock().await;
+        {
+            let sessions = self.sessions.read().await;
+            keys.retain(|_, id| sessions.get(id).is_some_and(|handle| !handle.metadata.is_terminal()));
+        }
+        if let Some(existing) = keys.get(&key) {
+            info!("Idempotency key {} matches unfinished session {}, not spawning", key, existing);
+            return Ok(existing.clone());
+        }
+
+        let session_id = spawn.await?;
+        keys.insert(key, session_id.clone());
+        Ok(session_id)
+    }
+
+    /// Put a transcript of the `context_from` session, if any, before `task`
+    async fn prepend_context(&self, task: String, options: &SpawnOptions) -> Result<String> {
+        let Some(source) = &options.context_from else {
+            return Ok(task);
+        };
+
+        let metadata = self.get_or_load_session(source).await?;
+        // Include output the source's logger hasn't written out yet
+        let logger = self.sessions.read().await.get(source).and_then(|handle| handle.logger.clone());
+        if let Some(logger) = logger {
+            logger.lock().await.flush()?;
+        }
+
+        let events = read_events(&metadata.log_dir)?;
+        let transcript = conversation_transcript(source, &events, options.context_bytes).ok_or_else(|| {
+            let conversation = events
+                .iter()
+                .any(|e| matches!(e.event_type, IoEventType::Input | IoEventType::Output));
+            ClaudeManError::InvalidInput(if conversation {
+                format!("A {}-byte context can't hold any of {}'s conversation", options.context_bytes, source)
+            } else {
+                format!("Session {} has no conversation to use as context", source)
+            })
+        })?;
+        info!("Priming the new session with {} event(s) from {}", transcript.events, source);
+
+        Ok(format!("{}\n{}", transcript.text, task))
+    }
+
+    /// Spawn a child session with a parent
+    ///
+    /// Creates a new session as a child of an existing parent session.
+    pub async fn spawn_child_session(
+        &self,
+        parent_id: SessionId,
+        role: Role,
+        task: String,
+    ) -> Result<SessionId> {
+        self.spawn_child_session_with_options(parent_id, role, task, SpawnOptions::new())
+            .await
+    }
+
+    /// Spawn a child session with a parent and additional options
+    pub async fn spawn_child_session_with_options(
+        &self,
+        parent_id: SessionId,
+        role: Role,
+        task: String,
+        options: SpawnOptions,
+    ) -> Result<SessionId> {
+        let key = options.idempotency_key.clone();
+        self.spawn_once(key, self.spawn_child(parent_id, role, task, options)).await
+    }
+
+    /// Spawn a child session (see `spawn_child_session_with_options`)
+    async fn spawn_child(
+        &self,
+        parent_id: SessionId,
+        role: Role,
+        task: String,
+        mut options: SpawnOptions,
+    ) -> Result<SessionId> {
+        validate_non_empty(&task, "task")?;
+        let task = self.prepend_context(task, &options).await?;
+
+        // Verify parent session exists
+        let parent = self.get_session(&parent_id).await.ok_or_else(|| {
+            ClaudeManError::SessionNotFound(format!("Parent session not found: {}", parent_id))
+        })?;
+
+        // Children launch like their parent unless told otherwise, but the
+        // child role's own directory beats the parent's
+        self.apply_role_working_dir(role, &mut options.settings).await;
+        if !options.skip_parent_settings {
+            options.settings = options.settings.inherit_from(&parent.spawn_settings);
+        }
+        options.settings.validate_working_dir()?;
+
+        let explicit_id = self.settle_explicit_id(role, &options).await?;
+        let _slot = self.claim_running_slot().await?;
+        self.reserve_spawn_slot().await?;
+
+        let session_id = match explicit_id {
+            Some(session_id) => session_id,
+            None => self.next_session_id(role).await,
+        };
+        let log_dir = self.new_log_dir(&session_id, role, &task).await;
+
+        info!(
+            "Spawning child session {} with role {:?} (parent: {})",
+            session_id, role, parent_id
+        );
+
+        // Create session metadata with parent
+        let mut metadata =
+            SessionMetadata::new_child(session_id.clone(), role, task, log_dir, parent_id.clone());
+
+        // Queue the child if its parent already has too many running
+        let limit = match options.max_concurrent_per_parent {
+            Some(limit) => Some(limit),
+            None => *self.max_concurrent_per_parent.read().await,
+        };
+        if let Some(limit) = limit {
+            let running = self.running_children(&parent_id).await;
+            if running >= limit {
+                info!(
+                    "Parent {} has {} running children (limit {}), queueing {}",
+                    parent_id, running, limit, session_id
+                );
+                SessionLock::check(&metadata.log_dir, &session_id)?;
+                let old_status = metadata.status;
+                metadata.mark_queued(format!(
+                    "waiting for a free slot under {} (limit {})",
+                    parent_id, limit
+                ));
+                self.save_metadata(&metadata)?;
+                self.publish_transition(old_status, &metadata);
+
+                let handle = SessionHandle {
+                    metadata,
+                    task_handle: None,
+                    stdin_tx: None,
+                    logger: None,
+                };
+                self.sessions.write().await.insert(session_id.clone(), handle);
+                self.queued_children.write().await.push_back(QueuedChild {
+                    session_id: session_id.clone(),
+                    parent_id,
+                    limit,
+                    options,
+                });
+
+                return Ok(session_id);
+            }
+        }
+
+        self.launch_session(metadata, options).await
+    }
+
+    /// Count the running children of a parent session
+    async fn running_children(&self, parent_id: &SessionId) -> usize {
+        self.count_active_where(|metadata| metadata.parent_id.as_ref() == Some(parent_id))
+            .await
+    }
+
+    /// Launch queued children of every parent that has any
+    ///
+    /// Called when any session finishes: that frees a slot under its own
+    /// parent and one under the overall limit, which children queued under
+    /// other parents may be waiting for. Boxed like `launch_queued_children`.
+    fn launch_all_queued_children(&self) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let registry = self.clone();
+
+        Box::pin(async move {
+            let mut parents: Vec<SessionId> = Vec::new();
+            for queued in registry.queued_children.read().await.iter() {
+                if !parents.contains(&queued.parent_id) {
+                    parents.push(queued.parent_id.clone());
+                }
+            }
+            for parent_id in parents {
+                registry.launch_queued_children(parent_id).await;
+            }
+        })
+    }
+
+    /// Launch queued children of a parent while it has free slots
+    ///
+    /// Queued children that were stopped or failed in the meantime are
+    /// dropped; while the overall limit is reached the rest stay queued.
+    /// Returns a boxed future because it is awaited from the monitoring task
+    /// that `launch_session` itself spawns.
+    fn launch_queued_children(
+        &self,
+        parent_id: SessionId,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let registry = self.clone();
+
+        Box::pin(async move {
+            loop {
+                let running = registry.running_children(&parent_id).await;
+
+                let queued = {
+                    let mut queue = registry.queued_children.write().await;
+                    let Some(index) = queue.iter().position(|q| q.parent_id == parent_id) else {
+                        return;
+                    };
+                    if running >= queue[index].limit {
+                        return;
+                    }
+                    match queue.remove(index) {
+                        Some(queued) => queued,
+                        None => return,
+                    }
+                };
+
+                let metadata = match registry.get_session(&queued.session_id).await {
+                    Some(metadata) if metadata.status == SessionStatus::Queued => metadata,
+                    _ => {
+                        debug!("Dropping queued session {} (no longer queued)", queued.session_id);
+                        continue;
+                    }
+                };
+
+                // The parent's limit leaves room, but the overall one may
+                // not; then the child waits for the next session to finish
+                let slot = match registry.claim_running_slot().await {
+                    Ok(slot) => slot,
+                    Err(e) => {
+                        debug!("Keeping {} queued: {}", queued.session_id, e);
+                        registry.queued_children.write().await.push_front(queued);
+                        return;
+                    }
+                };
+                info!("Launching queued session {} (parent: {})", queued.session_id, parent_id);
+                let launched = registry.launch_session(metadata, queued.options).await;
+                drop(slot);
+                if let Err(e) = launched {
+                    warn!("Failed to launch queued session {}: {}", queued.session_id, e);
+                    let mut sessions = registry.sessions.write().await;
+                    if let Some(handle) = sessions.get_mut(&queued.session_id) {
+                        let old_status = handle.metadata.status;
+                        handle.metadata.mark_failed_with_reason(format!("launch failed: {}", e));
+                        let _ = registry.save_metadata(&handle.metadata);
+                        registry.publish_transition(old_status, &handle.metadata);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Set up the session directory, spawn the Claude CLI process, and start monitoring it
+    async fn launch_session(
+        &self,
+        mut metadata: SessionMetadata,
+        options: SpawnOptions,
+    ) -> Result<SessionId> {
+        let session_id = metadata.id.clone();
+        let role = metadata.role;
+        let log_dir = metadata.log_dir.clone();
+
+        metadata.annotations = options.annotations;
+        metadata.result_path = options.capture_result.as_ref().map(|c| c.path.clone());
+        let capture_result = options.capture_result;
+        let expect_json_result = options.expect_json_result;
+        metadata.encoding = options.encoding.map(|encoding| encoding.name().to_string());
+        let encoding = options.encoding.unwrap_or(UTF_8);
+        metadata.spawn_settings = options.settings;
+        metadata.on_complete = options.on_complete;
+        metadata.timeout_secs = options.timeout.map(|limit| limit.as_secs());
+        let run_timeout = options.timeout;
+        metadata.retry_on_failure = options.retry_on_failure;
+
+        // Refuse a directory another running session is writing to
+        let lock = SessionLock::acquire(&log_dir, &session_id)?;
+
+        // Set up .claude directory with hooks for auto-approval
+        Self::setup_session_claude_config(&log_dir)?;
+
+        // Create logger, shared with any later writers such as resume
+        let logger = SessionLogger::new(session_id.clone(), &log_dir)?
+            .with_flush_policy(*self.flush_policy.read().await)
+            .with_max_line_bytes(*self.max_line_bytes.read().await)
+            .with_max_size(*self.max_log_bytes.read().await)
+            .shared();
+        let task_logger = logger.clone();
+        let hook_logger = logger.clone();
+
+        // Save metadata to file
+        self.save_metadata(&metadata)?;
+
+        // Write role-specific context file if applicable
+        let task_with_context = if let Some(context) = Self::get_role_context(role) {
+            Self::write_role_context(&log_dir, &context)?;
+            match &metadata.spawn_settings.working_dir {
+                Some(_) => format!(
+                    "First, read {} for your role instructions. Then: {}",
+                    log_dir.join("role-context.md").display(),
+                    metadata.task
+                ),
+                None => format!("First, read role-context.md in your working directory for your role instructions. Then: {}", metadata.task),
+            }
+        } else {
+            metadata.task.clone()
+        };
+
+        // Create spawn configuration, working in the log dir unless overridden
+        let config = self
+            .spawn_config(task_with_context, &metadata.spawn_settings, Some(&log_dir))
+            .await;
+
+        // Spawn the Claude CLI process with stdin support
+        let child = spawn_claude_process(config).await?;
+        let pid = match child.id() {
+            Some(pid) => pid,
+            None => {
+                // The process exited before we could track it; reap it and
+                // record why instead of leaving a half-created session
+                let reason = describe_early_exit(child).await;
+                warn!("Session {} failed to start: {}", session_id, reason);
+                let old_status = metadata.status;
+                metadata.mark_failed_with_reason(reason.clone());
+                self.save_metadata(&metadata)?;
+                self.publish_transition(old_status, &metadata);
+
+                let handle = SessionHandle {
+                    metadata,
+                    task_handle: None,
+                    stdin_tx: None,
+                    logger: None,
+                };
+                self.sessions.write().await.insert(session_id, handle);
+
+                return Err(ClaudeManError::SpawnFailed(reason));
+            }
+        };
+
+        // Update metadata with PID
+        let old_status = metadata.status;
+        metadata.mark_started(pid);
+        self.save_metadata(&metadata)?;
+        self.publish_transition(old_status, &metadata);
+
+        // Create stdin channel for sending input to the session
+        let (stdin_tx, stdin_rx) = mpsc::unbounded_channel::<StdinInput>();
+
+        // Spawn monitoring task with registry access for metadata updates
+        let session_id_clone = session_id.clone();
+        let registry = self.clone();
+        let task_log_dir = log_dir.clone();
+        let on_complete = metadata.on_complete.clone();
+
+        let mut monitor_options = MonitorOptions::new(
+            encoding,
+            self.session_filters(&session_id).await,
+            *self.console_echo.read().await,
+        );
+        if let Some(limit) = run_timeout {
+            monitor_options = monitor_options.with_timeout(limit, *self.termination_timeout.read().await);
+        }
+
+        // Hold the map until the handle is in, so a process that exits at
+        // once can't finish monitoring before there's a handle to update
+        let mut sessions = self.sessions.write().await;
+        let task_handle = tokio::spawn(async move {
+            let exit_code =
+                monitor_process(child, session_id_clone.clone(), task_logger, stdin_rx, monitor_options)
+                    .await;
+
+            // Write the result artifact before reporting completion
+            let capture_error = match (&exit_code, &capture_result) {
+                (Ok(0), Some(capture)) => capture.write_from_log(&task_log_dir).err(),
+                _ => None,
+            };
+            let json_result = match &exit_code {
+                Ok(0) if expect_json_result => Some(json_result_from_log(&task_log_dir)),
+                _ => None,
+            };
+
+            // Update metadata in registry based on exit code
+            let mut retrying = false;
+            let mut final_metadata = {
+                let mut sessions = registry.sessions.write().await;
+                if let Some(handle) = sessions
+                    .get_mut(&session_id_clone)
+                    .filter(|handle| !handle.metadata.is_terminal())
+                {
+                    let old_status = handle.metadata.status;
+                    match &exit_code {
+                        Ok(0) => handle.metadata.mark_completed(),
+                        Ok(_) => handle.metadata.mark_failed(),
+                        Err(e) => handle.metadata.mark_failed_with_reason(e.to_string()),
+                    }
+                    if handle.metadata.status == SessionStatus::Failed
+                        && handle.metadata.retries < handle.metadata.retry_on_failure
+                    {
+                        // The next attempt decides the outcome, so waiters keep waiting
+                        let reason = match (&exit_code, handle.metadata.status_reason.take()) {
+                            (_, Some(reason)) => reason,
+                            (Ok(code), None) => format!("exit code {}", code),
+                            (Err(e), None) => e.to_string(),
+                        };
+                        handle.metadata.mark_queued(format!("retrying after failure: {}", reason));
+                        handle.metadata.ended_at = None;
+                        retrying = true;
+                    }
+                    registry.publish_transition(old_status, &handle.metadata);
+                    if let Some(e) = capture_error {
+                        warn!("Failed to capture result for {}: {}", session_id_clone, e);
+                        handle.metadata.status_reason = Some(format!("result capture failed: {}", e));
+                    }
+                    match json_result {
+                        Some(Ok(result)) => handle.metadata.result = Some(result),
+                        Some(Err(e)) => {
+                            warn!("No JSON result for {}: {}", session_id_clone, e);
+                            handle.metadata.status_reason = Some(format!("JSON result missing: {}", e));
+                        }
+                        None => {}
+                    }
+                    // Persist the result so readers of metadata.json see it too
+                    if expect_json_result {
+                        let _ = registry.save_metadata(&handle.metadata);
+                    }
+                }
+                sessions.get(&session_id_clone).map(|handle| handle.metadata.clone())
+            };
+
+            // A retry keeps the session's slot, and only the last attempt is reported
+            if retrying {
+                if registry.retry_session(session_id_clone.clone()).await {
+                    // The next attempt has claimed the directory again
+                    lock.keep();
+                    return exit_code;
+                }
+                final_metadata = registry.get_session(&session_id_clone).await;
+            }
+
+            // The hook reports on the session but never changes its status
+            if let (Some(command), Some(metadata)) = (on_complete, final_metadata) {
+                run_on_complete(&command, &metadata, exit_code.as_ref().ok().copied(), &hook_logger).await;
+            }
+            drop(lock);
+
+            // A slot is free; start any queued children that fit
+            registry.launch_all_queued_children().await;
+
+            exit_code
+        });
+
+        // Create session handle with stdin sender
+        let handle = SessionHandle {
+            metadata,
+            task_handle: Some(task_handle),
+            stdin_tx: Some(stdin_tx),
+            logger: Some(logger),
+        };
+
+        // Add to registry
+        sessions.insert(session_id.clone(), handle);
+        drop(sessions);
+
+        info!("Session {} started successfully", session_id);
+
+        Ok(session_id)
+    }
+
+    /// Resume an existing session with additional input
+    ///
+    /// Uses Claude's --resume flag to continue a session
+    pub async fn resume_session(
+        &self,
+        session_id: SessionId,
+        message: String,
+    ) -> Result<()> {
+        self.resume_session_with_recap(session_id, message, 0).await
+    }
+
+    /// Resume a session, re-priming it with a recap of its recent conversation
+    ///
+    /// The last `replay_events` input/output events from io.log are prepended
+    /// to the message, for when Claude's own context can't be restored.
+    /// With 0 this is a plain resume.
+    pub async fn resume_session_with_recap(
+        &self,
+        session_id: SessionId,
+        message: String,
+        replay_events: usize,
+    ) -> Result<()> {
+        let (_, monitor) = self.start_resume(session_id, message, replay_events).await?;
+        let exit_code = monitor.await?;
+
+        info!("Resume process completed with exit code: {}", exit_code);
+
+        Ok(())
+    }
+
+    /// Resume a session without waiting for the resume process to finish
+    ///
+    /// Like [`resume_session_with_recap`](Self::resume_session_with_recap),
+    /// but the process is monitored by a background task, so this returns
+    /// its PID as soon as it has started. Its output still goes to io.log.
+    pub async fn resume_session_in_background(
+        &self,
+        session_id: SessionId,
+        message: String,
+        replay_events: usize,
+    ) -> Result<u32> {
+        let (pid, monitor) = self.start_resume(session_id.clone(), message, replay_events).await?;
+        tokio::spawn(async move {
+            match monitor.await {
+                Ok(exit_code) => info!("Resume of {} completed with exit code: {}", session_id, exit_code),
+                Err(e) => warn!("Monitoring the resume of {} failed: {}", session_id, e),
+            }
+        });
+
+        Ok(pid)
+    }
+
+    /// Start a resume process, returning its PID and the future that
+    /// monitors it to completion
+    async fn start_resume(
+        &self,
+        session_id: SessionId,
+        message: String,
+        replay_events: usize,
+    ) -> Result<(u32, Pin<Box<dyn Future<Output = Result<i32>> + Send>>)> {
+        validate_non_empty(&message, "message")?;
+        info!("Resuming session {} with message", session_id);
+
+        let metadata = self.get_or_load_session(&session_id).await?;
+        metadata.spawn_settings.validate_working_dir()?;
+
+        // Reuse the session's logger so writes don't race the original process
+        let logger = self.get_logger(&metadata.id).await?;
+
+        // Build the recap before the resume itself is logged
+        let recap = if replay_events > 0 {
+            logger.lock().await.flush()?;
+            conversation_recap(&read_events(&metadata.log_dir)?, replay_events)
+        } else {
+            None
+        };
+
+        // Log that we're resuming, noting whether a recap was included
+        let mut event = IoEvent::lifecycle(
+            crate::types::SessionStatus::Running,
+            format!("Resuming session with message: {}", message),
+        );
+        if let (Some(recap_meta), Some(recap)) = (event.metadata.as_mut(), &recap) {
+            recap_meta["recap_events"] = serde_json::json!(recap.events);
+        }
+        logger.lock().await.log_event(event)?;
+
+        let message = match recap {
+            Some(recap) => format!("{}\n{}", recap.text, message),
+            None => message,
+        };
+
+        // Create spawn config for resume, launching the way the session was
+        let config = self
+            .spawn_config(
+                format!("--resume {} {}", session_id, message),
+                &metadata.spawn_settings,
+                None,
+            )
+            .await;
+
+        // Spawn the resume process
+        let child = spawn_claude_process(config).await?;
+        let pid = match child.id() {
+            Some(pid) => pid,
+            None => {
+                let reason = describe_early_exit(child).await;
+                logger.lock().await.log_lifecycle(
+                    crate::types::SessionStatus::Failed,
+                    format!("Resume failed: {}", reason),
+                )?;
+                return Err(ClaudeManError::SpawnFailed(reason));
+            }
+        };
+
+        info!("Resume process started with PID {}", pid);
+
+        // Create stdin channel (unused but required for monitor_process signature)
+        let (stdin_tx, stdin_rx) = mpsc::unbounded_channel::<StdinInput>();
+
+        let encoding = metadata
+            .encoding
+            .as_deref()
+            .and_then(|label| resolve_encoding(label).ok())
+            .unwrap_or(UTF_8);
+        let filters = self.session_filters(&session_id).await;
+        let echo = *self.console_echo.read().await;
+        let options = MonitorOptions::new(encoding, filters, echo);
+        let monitor = async move {
+            // Keep stdin open until the process is done
+            let _stdin_tx = stdin_tx;
+            monitor_process(child, session_id, logger, stdin_rx, options).await
+        };
+
+        Ok((pid, Box::pin(monitor)))
+    }
+
+    /// Restart a finished session with its original role and task
+    ///
+    /// The session keeps its ID and log directory, so the new run's output
+    /// is appended to the same io.log. Annotations, launch settings,
+    /// encoding, `--on-complete`, the timeout, the retry limit and the result
+    /// file carry over; results from the previous run are cleared and the
+    /// retry count starts again. Fails if the session hasn't ended.
+    pub async fn restart_session(&self, session_id: &SessionId) -> Result<SessionId> {
+        let mut metadata = self.get_or_load_session(session_id).await?;
+        if !metadata.is_terminal() {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Session {} is {}; only finished sessions can be restarted",
+                session_id, metadata.status
+            )));
+        }
+
+        info!("Restarting session {} with role {:?}", session_id, metadata.role);
+
+        let note = format!("Restarting session (previously {})", metadata.status);
+        metadata.retries = 0;
+        self.relaunch_session(metadata, note).await
+    }
+
+    /// Run a session that failed with retries left again
+    ///
+    /// Returns whether the new attempt started. If it couldn't, the session
+    /// is failed with the reason. Returns a boxed future because it is
+    /// awaited from the monitoring task that `launch_session` itself spawns.
+    fn retry_session(&self, session_id: SessionId) -> Pin<Box<dyn Future<Output = bool> + Send>> {
+        let registry = self.clone();
+
+        Box::pin(async move {
+            // Stopped while waiting for the retry
+            let mut metadata = match registry.get_session(&session_id).await {
+                Some(metadata) if metadata.status == SessionStatus::Queued => metadata,
+                _ => return false,
+            };
+
+            metadata.retries += 1;
+            info!(
+                "Retrying session {} (retry {} of {})",
+                session_id, metadata.retries, metadata.retry_on_failure
+            );
+            let note = format!(
+                "Retrying session after failure (retry {} of {})",
+                metadata.retries, metadata.retry_on_failure
+            );
+
+            match registry.relaunch_session(metadata, note).await {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("Failed to retry session {}: {}", session_id, e);
+                    let mut sessions = registry.sessions.write().await;
+                    if let Some(handle) = sessions
+                        .get_mut(&session_id)
+                        .filter(|handle| !handle.metadata.is_terminal())
+                    {
+                        let old_status = handle.metadata.status;
+                        handle.metadata.mark_failed_with_reason(format!("retry failed: {}", e));
+                        let _ = registry.save_metadata(&handle.metadata);
+                        registry.publish_transition(old_status, &handle.metadata);
+                    }
+                    false
+                }
+            }
+        })
+    }
+
+    /// Launch an existing session's process again, noting why in its io.log
+    async fn relaunch_session(&self, mut metadata: SessionMetadata, note: String) -> Result<SessionId> {
+        metadata.spawn_settings.validate_working_dir()?;
+        let _slot = self.claim_running_slot().await?;
+        self.reserve_spawn_slot().await?;
+
+        // Flush the existing log before the new logger opens
+        {
+            let logger = self.get_logger(&metadata.id).await?;
+            let mut logger = logger.lock().await;
+            logger.log_lifecycle(SessionStatus::Running, note)?;
+            logger.flush()?;
+        }
+
+        let mut options = SpawnOptions::new()
+            .with_annotations(std::mem::take(&mut metadata.annotations))
+            .with_settings(std::mem::take(&mut metadata.spawn_settings))
+            .with_retry_on_failure(metadata.retry_on_failure);
+        if let Some(path) = metadata.result_path.take() {
+            options = options.with_capture_result(CaptureResult::new(path));
+        }
+        if let Some(encoding) = metadata.encoding.as_deref().and_then(|label| resolve_encoding(label).ok()) {
+            options = options.with_encoding(encoding);
+        }
+        if let Some(command) = metadata.on_complete.take() {
+            options = options.with_on_complete(command);
+        }
+        if let Some(secs) = metadata.timeout_secs {
+            options = options.with_timeout(Duration::from_secs(secs));
+        }
+
+        // Starting reports the move from the old status straight to running
+        metadata.status_reason = None;
+        metadata.ended_at = None;
+        metadata.result = None;
+        metadata.approval = None;
+
+        self.launch_session(metadata, options).await
+    }
+
+    /// Get the shared logger for a session
+    ///
+    /// Returns the logger already held by the session's handle, or opens
+    /// io.log once and stores the logger in the handle for later writers.
+    pub async fn get_logger(&self, session_id: &SessionId) -> Result<SharedLogger> {
+        let mut sessions = self.sessions.write().await;
+        let handle = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+
+        if let Some(logger) = &handle.logger {
+            return Ok(logger.clone());
+        }
+
+        let logger = SessionLogger::new(session_id.clone(), &handle.metadata.log_dir)?
+            .with_flush_policy(*self.flush_policy.read().await)
+            .with_max_line_bytes(*self.max_line_bytes.read().await)
+            .with_max_size(*self.max_log_bytes.read().await)
+            .shared();
+        handle.logger = Some(logger.clone());
+
+        Ok(logger)
+    }
+
+    /// Get a list of all active sessions
+    pub async fn list_sessions(&self) -> Vec<SessionMetadata> {
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .map(|handle| handle.metadata.clone())
+            .collect()
+    }
+
+    /// Count running sessions without cloning their metadata
+    ///
+    /// Sessions recovered from disk count too, since their processes are alive.
+    pub async fn count_active(&self) -> usize {
+        self.count_active_where(|_| true).await
+    }
+
+    /// Count running sessions of a role without cloning their metadata
+    pub async fn count_active_of_role(&self, role: Role) -> usize {
+        self.count_active_where(|metadata| metadata.role == role).await
+    }
+
+    /// Count running sessions whose metadata matches `filter`
+    async fn count_active_where(&self, filter: impl Fn(&SessionMetadata) -> bool) -> usize {
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .filter(|handle| handle.metadata.is_active() && filter(&handle.metadata))
+            .count()
+    }
+
+    /// Get child sessions of a parent
+    pub async fn get_children(&self, parent_id: &SessionId) -> Vec<SessionMetadata> {
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .filter(|handle| {
+                handle.metadata.parent_id.as_ref() == Some(parent_id)
+            })
+            .map(|handle| handle.metadata.clone())
+            .collect()
+    }
+
+    /// Find sessions whose ID starts with the given prefix
+    ///
+    /// The prefix is matched against the full ID and, for sessions in this
+    /// registry's project, against the ID without its project prefix, so
+    /// `DEV` finds `A-DEV-001` when the registry's project is `A`.
+    pub async fn find_by_prefix(&self, prefix: &str) -> Vec<SessionMetadata> {
+        let project = self.project().await;
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .filter(|handle| {
+                let id = &handle.metadata.id;
+                id.as_str().starts_with(prefix)
+                    || (id.project() == project.as_deref()
+                        && id.without_project().starts_with(prefix))
+            })
+            .map(|handle| handle.metadata.clone())
+            .collect()
+    }
+
+    /// Get metadata for a specific session
+    pub async fn get_session(&self, session_id: &SessionId) -> Option<SessionMetadata> {
+        let sessions = self.sessions.read().await;
+        sessions.get(session_id).map(|handle| handle.metadata.clone())
+    }
+
+    /// Send input to a running session
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The ID of the session
+    /// * `input` - The input text to send
+    /// * `newline` - How the input is terminated
+    /// * `paste` - Send multi-line input as a bracketed paste
+    pub async fn send_input(
+        &self,
+        session_id: &SessionId,
+        input: String,
+        newline: InputNewline,
+        paste: bool,
+    ) -> Result<()> {
+        if !STDIN_PIPED {
+            return Err(ClaudeManError::InvalidInput(
+                "Session input isn't supported on this platform (the process's stdin isn't piped)".to_string(),
+            ));
+        }
+
+        info!("Sending input to session {}: {}", session_id, input);
+
+        let sessions = self.sessions.read().await;
+
+        let handle = sessions
+            .get(session_id)
+            .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+
+        // Check if session is still active
+        if !handle.metadata.is_active() {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Session {} is not active (status: {})",
+                session_id, handle.metadata.status
+            )));
+        }
+
+        // Send input through the channel
+        if let Some(stdin_tx) = &handle.stdin_tx {
+            stdin_tx
+                .send(StdinInput { text: input, newline, paste })
+                .map_err(|_| ClaudeManError::Process("Failed to send input: channel closed".to_string()))?;
+        } else {
+            return Err(ClaudeManError::Process(
+                "Session stdin channel not available".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get a session's metadata, loading it from disk if this registry
+    /// didn't load it (e.g. completed ones in direct mode)
+    async fn get_or_load_session(&self, session_id: &SessionId) -> Result<SessionMetadata> {
+        if let Some(metadata) = self.get_session(session_id).await {
+            return Ok(metadata);
+        }
+
+        let metadata = self.load_metadata(session_id)?;
+        let handle = SessionHandle {
+            metadata: metadata.clone(),
+            task_handle: None,
+            stdin_tx: None,
+            logger: None,
+        };
+        self.sessions.write().await.insert(session_id.clone(), handle);
+        Ok(metadata)
+    }
+
+    /// Find the root of the session tree a session belongs to
+    pub async fn tree_root(&self, session_id: &SessionId) -> Result<SessionId> {
+        let mut current = self.get_or_load_session(session_id).await?;
+        while let Some(parent_id) = current.parent_id.clone() {
+            current = self.get_or_load_session(&parent_id).await?;
+        }
+        Ok(current.id)
+    }
+
+    /// Read a shared value
+    ///
+    /// With `tree`, the key is looked up in the scope of the session tree
+    /// that session belongs to instead of the global scope.
+    pub async fn kv_get(&self, tree: Option<&SessionId>, key: &str) -> Result<Option<String>> {
+        validate_non_empty(key, "key")?;
+        let root = match tree {
+            Some(session_id) => Some(self.tree_root(session_id).await?),
+            None => None,
+        };
+        self.kv.lock().await.get(root.as_ref(), key)
+    }
+
+    /// Write a shared value, returning the one it replaced
+    ///
+    /// With `tree`, the key is set in the scope of the session tree that
+    /// session belongs to instead of the global scope.
+    pub async fn kv_set(
+        &self,
+        tree: Option<&SessionId>,
+        key: &str,
+        value: &str,
+    ) -> Result<Option<String>> {
+        validate_non_empty(key, "key")?;
+        let root = match tree {
+            Some(session_id) => Some(self.tree_root(session_id).await?),
+            None => None,
+        };
+        self.kv.lock().await.set(root.as_ref(), key, value)
+    }
+
+    /// Record an approval decision on a session
+    ///
+    /// The decision is stored in the session's metadata (replacing any earlier
+    /// one) and logged to its io.log. Returns the updated metadata.
+    pub async fn record_approval(
+        &self,
+        session_id: &SessionId,
+        approval: Approval,
+    ) -> Result<SessionMetadata> {
+        info!("Recording {} for session {}", approval.decision, session_id);
+
+        self.get_or_load_session(session_id).await?;
+        let metadata = {
+            let mut sessions = self.sessions.write().await;
+            let handle = sessions
+                .get_mut(session_id)
+                .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+            handle.metadata.approval = Some(approval.clone());
+            self.save_metadata(&handle.metadata)?;
+            handle.metadata.clone()
+        };
+
+        let event = IoEvent::with_metadata(
+            IoEventType::Lifecycle,
+            format!("Session {}", approval),
+            serde_json::json!({
+                "status": metadata.status.to_string(),
+                "approval": approval,
+            }),
+        );
+        self.get_logger(session_id).await?.lock().await.log_event(event)?;
+
+        Ok(metadata)
+    }
+
+    /// Suspend a running session's process (SIGSTOP) without ending it
+    ///
+    /// The session keeps its slot and stays active; `continue_session`
+    /// picks up where it left off. Returns the session's PID.
+    pub async fn pause_session(&self, session_id: &SessionId) -> Result<u32> {
+        self.signal_session(session_id, SessionStatus::Running, SessionStatus::Paused)
+            .await
+    }
+
+    /// Continue a session paused with `pause_session` (SIGCONT)
+    pub async fn continue_session(&self, session_id: &SessionId) -> Result<u32> {
+        self.signal_session(session_id, SessionStatus::Paused, SessionStatus::Running)
+            .await
+    }
+
+    /// Move a session between running and paused, signalling its process
+    async fn signal_session(
+        &self,
+        session_id: &SessionId,
+        from: SessionStatus,
+        to: SessionStatus,
+    ) -> Result<u32> {
+        let pausing = to == SessionStatus::Paused;
+        let (action, signal): (&str, fn(u32) -> Result<()>) = if pausing {
+            ("pause", suspend_pid)
+        } else {
+            ("continue", resume_pid)
+        };
+        info!("Sending {} to session {}", action, session_id);
+
+        let pid = {
+            let mut sessions = self.sessions.write().await;
+            let handle = sessions
+                .get_mut(session_id)
+                .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+            let pid = match handle.metadata.pid {
+                Some(pid) if handle.metadata.status == from => pid,
+                _ => {
+                    return Err(ClaudeManError::Session(format!(
+                        "Can't {} session {}: it is {}, not {}",
+                        action, session_id, handle.metadata.status, from
+                    )))
+                }
+            };
+
+            signal(pid)?;
+            if pausing {
+                handle.metadata.mark_paused();
+            } else {
+                handle.metadata.mark_continued();
+            }
+            self.save_metadata(&handle.metadata)?;
+            self.publish_transition(from, &handle.metadata);
+            pid
+        };
+
+        let message = format!("Session {} (PID: {})", if pausing { "paused" } else { "continued" }, pid);
+        self.get_logger(session_id).await?.lock().await.log_lifecycle(to, message)?;
+
+        Ok(pid)
+    }
+
+    /// Stop a specific session
+    ///
+    /// Sessions that have not started yet and depend on it (transitively) are
+    /// marked failed so they don't wait forever.
+    pub async fn stop_session(&self, session_id: &SessionId) -> Result<()> {
+        self.stop_session_with_cascade(session_id, false).await?;
+        Ok(())
+    }
+
+    /// Stop a specific session and resolve its pending dependents
+    ///
+    /// With `cascade`, pending dependents are cancelled (marked stopped);
+    /// otherwise they are marked failed. Returns the affected dependents.
+    pub async fn stop_session_with_cascade(
+        &self,
+        session_id: &SessionId,
+        cascade: bool,
+    ) -> Result<Vec<SessionId>> {
+        info!("Stopping session {}", session_id);
+
+        let (pid, task_handle, dependents) = {
+            let mut sessions = self.sessions.write().await;
+
+            let handle = sessions
+                .get_mut(session_id)
+                .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+
+            // Mark stopped before signalling so the monitor doesn't report a failure
+            let pid = handle.metadata.pid;
+            let task_handle = handle.task_handle.take();
+            let old_status = handle.metadata.status;
+            handle.metadata.mark_stopped();
+            self.save_metadata(&handle.metadata)?;
+            self.publish_transition(old_status, &handle.metadata);
+
+            let dependents = Self::pending_dependents(&sessions, session_id);
+            for dependent_id in &dependents {
+                if let Some(dependent) = sessions.get_mut(dependent_id) {
+                    let old_status = dependent.metadata.status;
+                    if cascade {
+                        dependent.metadata.mark_stopped_with_reason("dependency stopped".to_string());
+                    } else {
+                        dependent
+                            .metadata
+                            .mark_failed_with_reason(format!("dependency {} stopped", session_id));
+                    }
+                    self.save_metadata(&dependent.metadata)?;
+                    self.publish_transition(old_status, &dependent.metadata);
+                    info!("Dependent session {} marked {}", dependent_id, dependent.metadata.status);
+                }
+            }
+
+            (pid, task_handle, dependents)
+        };
+
+        // Kill the process if we have a PID, waiting for it to actually exit.
+        // The registry lock is released so the monitor task can finish.
+        if let Some(pid) = pid {
+            let grace = *self.termination_timeout.read().await;
+            let mut task_handle = task_handle;
+            let exited = async {
+                match task_handle.as_mut() {
+                    Some(task) => {
+                        let _ = task.await;
+                    }
+                    None => wait_for_exit(pid).await,
+                }
+            };
+            if let Err(e) = terminate_pid(pid, session_id, grace, exited).await {
+                warn!("Failed to terminate session {}: {}", session_id, e);
+            }
+
+            // Abort the monitoring task if still running
+            if let Some(task_handle) = task_handle {
+                task_handle.abort();
+            }
+        } else if let Some(task_handle) = task_handle {
+            task_handle.abort();
+        }
+
+        info!("Session {} stopped", session_id);
+
+        Ok(dependents)
+    }
+
+    /// Find pending sessions that depend, directly or transitively, on `root`
+    fn pending_dependents(
+        sessions: &HashMap<SessionId, SessionHandle>,
+        root: &SessionId,
+    ) -> Vec<SessionId> {
+        let mut found: Vec<SessionId> = Vec::new();
+        let mut frontier = vec![root.clone()];
+
+        while let Some(current) = frontier.pop() {
+            for handle in sessions.values() {
+                let id = &handle.metadata.id;
+                if handle.metadata.is_pending()
+                    && handle.metadata.depends_on.contains(&current)
+                    && !found.contains(id)
+                {
+                    found.push(id.clone());
+                    frontier.push(id.clone());
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Stop all active sessions
+    ///
+    /// Children are stopped before their parents, so a MANAGER never sees
+    /// its children outlive it.
+    pub async fn stop_all_sessions(&self) -> Result<()> {
+        info!("Stopping all sessions");
+
+        let session_ids = {
+            let sessions = self.sessions.read().await;
+            let parents: HashMap<SessionId, Option<SessionId>> = sessions
+                .iter()
+                .map(|(id, handle)| (id.clone(), handle.metadata.parent_id.clone()))
+                .collect();
+            children_first(&parents)
+        };
+
+        for session_id in session_ids {
+            if let Err(e) = self.stop_session(&session_id).await {
+                warn!("Failed to stop session {}: {}", session_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile running sessions with the processes actually alive
+    ///
+    /// A `Running` session whose process is gone and that has no live
+    /// monitoring task (it panicked, or the session was recovered from disk)
+    /// would otherwise stay running forever. Such sessions are marked failed
+    /// and get the bookkeeping their monitor would have done: the metadata
+    /// is saved and queued siblings are started. Sessions with a live monitor
+    /// are left to it. Returns the sessions that were marked failed.
+    pub async fn health_check(&self) -> Vec<SessionId> {
+        let mut reconciled = Vec::new();
+
+        {
+            let mut sessions = self.sessions.write().await;
+            for handle in sessions.values_mut() {
+                let monitored = handle.task_handle.as_ref().is_some_and(|task| !task.is_finished());
+                let Some(pid) = handle.metadata.pid else {
+                    continue;
+                };
+                if !handle.metadata.is_active() || monitored || is_process_alive(pid) {
+                    continue;
+                }
+
+                warn!("Session {} process {} is gone, marking failed", handle.metadata.id, pid);
+                let old_status = handle.metadata.status;
+                handle
+                    .metadata
+                    .mark_failed_with_reason(format!("process {} exited unmonitored", pid));
+                self.publish_transition(old_status, &handle.metadata);
+                if let Err(e) = self.save_metadata(&handle.metadata) {
+                    warn!("Failed to save metadata for {}: {}", handle.metadata.id, e);
+                }
+                handle.stdin_tx = None;
+
+                reconciled.push(handle.metadata.id.clone());
+            }
+        }
+
+        // Slots are free; start any queued children that fit
+        if !reconciled.is_empty() {
+            self.launch_all_queued_children().await;
+        }
+
+        reconciled
+    }
+
+    /// Clean up completed sessions from the registry
+    pub async fn cleanup_completed(&self) {
+        let mut sessions = self.sessions.write().await;
+        sessions.retain(|_id, handle| handle.is_running());
+    }
+
+    /// Save session metadata to disk
+    ///
+    /// Saves of the same session are serialized and each replaces
+    /// metadata.json whole, so readers never see a partial file and the
+    /// last save wins.
+    fn save_metadata(&self, metadata: &SessionMetadata) -> Result<()> {
+        let metadata_path = metadata.log_dir.join("metadata.json");
+
+        // Ensure directory exists
+        fs::create_dir_all(&metadata.log_dir)?;
+
+        // Write metadata as JSON
+        let json = if self.compact_metadata.load(Ordering::Relaxed) {
+            serde_json::to_string(metadata)?
+        } else {
+            serde_json::to_string_pretty(metadata)?
+        };
+
+        let lock = {
+            let mut locks = self.metadata_locks.lock().unwrap_or_else(|e| e.into_inner());
+            locks.entry(metadata.id.clone()).or_default().clone()
+        };
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        // Write via a temporary file; gc removes one left by a crash
+        let tmp_path = metadata_path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &metadata_path)?;
+
+        debug!("Saved metadata for session {}", metadata.id);
+
+        Ok(())
+    }
+
+    /// Export the full orchestration state to a tarball
+    ///
+    /// Flushes in-memory metadata to disk first so the snapshot reflects the
+    /// current state, then bundles every session directory under
+    /// the sessions directory and the key/value store into `out`. Returns
+    /// the number of sessions exported.
+    pub async fn export_all(&self, out: &std::path::Path) -> Result<usize> {
+        {
+            let sessions = self.sessions.read().await;
+            for handle in sessions.values() {
+                self.save_metadata(&handle.metadata)?;
+            }
+        }
+
+        let kv = self.kv.lock().await;
+        backup::write_archive(&self.sessions_dir, kv.path(), out)
+    }
+
+    /// Restore orchestration state from a tarball written by `export_all`
+    ///
+    /// Only restores into an empty sessions directory and where there's no
+    /// key/value store yet, then reloads any sessions whose processes are
+    /// still alive. Returns the number of sessions restored.
+    pub async fn import_all(&self, archive: &std::path::Path) -> Result<usize> {
+        let count = backup::extract_archive(archive, &self.sessions_dir, self.kv.lock().await.path())?;
+        self.load_from_disk().await?;
+
+        Ok(count)
+    }
+
+    /// Load metadata for every session stored on disk, whatever its status
+    pub fn list_from_disk(&self) -> Result<Vec<SessionMetadata>> {
+        if !self.sessions_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(&self.sessions_dir)? {
+            let metadata_path = entry?.path().join("metadata.json");
+            if metadata_path.exists() {
+                match Self::load_metadata_from_path(&metadata_path) {
+                    Ok(metadata) => sessions.push(metadata),
+                    Err(e) => warn!("Skipping unreadable {:?}: {}", metadata_path, e),
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    /// Pick the most recently created session, optionally of a given role
+    pub fn most_recent(
+        sessions: impl IntoIterator<Item = SessionMetadata>,
+        role: Option<Role>,
+    ) -> Option<SessionMetadata> {
+        sessions
+            .into_iter()
+            .filter(|metadata| role.is_none_or(|role| metadata.role == role))
+            .max_by_key(|metadata| metadata.created_at)
+    }
+
+    /// Find the most recently created session in memory or on disk
+    pub async fn find_most_recent(&self, role: Option<Role>) -> Result<Option<SessionMetadata>> {
+        let mut sessions = self.list_sessions().await;
+        for metadata in self.list_from_disk()? {
+            if !sessions.iter().any(|known| known.id == metadata.id) {
+                sessions.push(metadata);
+            }
+        }
+
+        Ok(Self::most_recent(sessions, role))
+    }
+
+    /// Load session metadata from disk
+    pub fn load_metadata(&self, session_id: &SessionId) -> Result<SessionMetadata> {
+        let log_dir = self.session_log_dir(session_id);
+        let metadata_path = log_dir.join("metadata.json");
+
+        if !metadata_path.exists() {
+            return Err(ClaudeManError::SessionNotFound(session_id.to_string()));
+        }
+
+        let json = fs::read_to_string(metadata_path)?;
+        let metadata: SessionMetadata = serde_json::from_str(&json)?;
+
+        Ok(metadata)
+    }
+}
+
+impl Default for SessionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Order sessions so every child comes before its parent
+///
+/// `parents` maps each session to its parent. Sessions are sorted deepest
+/// first; a `parent_id` chain that loops back on itself (hand-edited
+/// metadata) is cut where it repeats.
+fn children_first(parents: &HashMap<SessionId, Option<SessionId>>) -> Vec<SessionId> {
+    let depth = |id: &SessionId| {
+        let mut seen = vec![id];
+        let mut current = id;
+        while let Some(Some(parent)) = parents.get(current) {
+            if seen.contains(&parent) {
+                warn!("Session {} is in a parent_id cycle", id);
+                break;
+            }
+            seen.push(parent);
+            current = parent;
+        }
+        seen.len()
+    };
+
+    let mut ids: Vec<SessionId> = parents.keys().cloned().collect();
+    ids.sort_by_cached_key(|id| (std::cmp::Reverse(depth(id)), id.to_string()));
+    ids
+}
+
+/// Best-effort name for a session whose metadata.json doesn't load
+///
+/// Reads only the `id` and `role` fields, so a file with other fields missing
+/// or malformed still names its session in the warning.
+fn salvage_session_label(metadata_path: &std::path::Path) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(metadata_path).ok()?).ok()?;
+    let id = value.get("id")?.as_str()?;
+    Some(match value.get("role").and_then(|role| role.as_str()) {
+        Some(role) => format!("{} ({})", id, role),
+        None => id.to_string(),
+    })
+}
+
+/// Move a replaced session's log directory into the archive
+///
+/// The archive sits next to the sessions directory (`.claude-man/archive`),
+/// with a timestamp so repeated replacements don't collide. Returns where
+/// the logs went.
+fn archive_log_dir(log_dir: &std::path::Path, session_id: &SessionId) -> Result<std::path::PathBuf> {
+    let sessions_dir = log_dir.parent().unwrap_or_else(|| std::path::Path::new("."));
+    let archive_dir = sessions_dir.with_file_name("archive");
+    let archived = archive_dir.join(format!(
+        "{}-{}",
+        session_id,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+    ));
+
+    if log_dir.exists() {
+        fs::create_dir_all(&archive_dir)?;
+        fs::rename(log_dir, &archived)?;
+    }
+
+    Ok(archived)
+}
+
+/// Run a session's `--on-complete` hook and log how it went
+///
+/// The session's ID, status, exit code, role and log directory are passed
+/// as `CLAUDE_MAN_*` environment variables. Failures are only logged.
+async fn run_on_complete(
+    command: &str,
+    metadata: &SessionMetadata,
+    exit_code: Option<i32>,
+    logger: &SharedLogger,
+) {
+    let env = [
+        ("CLAUDE_MAN_SESSION_ID", metadata.id.to_string()),
+        ("CLAUDE_MAN_STATUS", metadata.status.to_string()),
+        ("CLAUDE_MAN_EXIT_CODE", exit_code.map(|code| code.to_string()).unwrap_or_default()),
+        ("CLAUDE_MAN_ROLE", metadata.role.to_string()),
+        ("CLAUDE_MAN_LOG_DIR", metadata.log_dir.display().to_string()),
+    ];
+
+    let mut event = match run_completion_hook(command, &env, COMPLETION_HOOK_TIMEOUT).await {
+        Ok(output) => {
+            let outcome = match output.status.code() {
+                Some(code) => format!("exited with code {}", code),
+                None => "was terminated by a signal".to_string(),
+            };
+            let mut event = IoEvent::lifecycle(metadata.status, format!("On-complete hook {}", outcome));
+            if let Some(hook_meta) = event.metadata.as_mut() {
+                hook_meta["hook_exit_code"] = serde_json::json!(output.status.code());
+                hook_meta["hook_stdout"] = serde_json::json!(String::from_utf8_lossy(&output.stdout));
+                hook_meta["hook_stderr"] = serde_json::json!(String::from_utf8_lossy(&output.stderr));
+            }
+            event
+        }
+        Err(e) => {
+            warn!("On-complete hook for {} failed: {}", metadata.id, e);
+            IoEvent::lifecycle(metadata.status, format!("On-complete hook failed: {}", e))
+        }
+    };
+    if let Some(hook_meta) = event.metadata.as_mut() {
+        hook_meta["hook"] = serde_json::json!(command);
+    }
+
+    if let Err(e) = logger.lock().await.log_event(event) {
+        warn!("Failed to log on-complete hook for {}: {}", metadata.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_registry_creation() {
+        let registry = SessionRegistry::new();
+        let sessions = registry.list_sessions().await;
+        assert!(sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_next_session_id() {
+        let registry = SessionRegistry::new();
+
+        let id1 = registry.next_session_id(Role::Developer).await;
+        assert_eq!(id1.as_str(), "DEV-001");
+
+        let id2 = registry.next_session_id(Role::Developer).await;
+        assert_eq!(id2.as_str(), "DEV-002");
+
+        let id3 = registry.next_session_id(Role::Architect).await;
+        assert_eq!(id3.as_str(), "ARCH-001");
+    }
+
+    #[tokio::test]
+    async fn test_next_session_id_with_project() {
+        let registry = SessionRegistry::new().with_project(Some("A".to_string()));
+
+        let id = registry.next_session_id(Role::Developer).await;
+        assert_eq!(id.as_str(), "A-DEV-001");
+        assert_eq!(registry.session_log_dir(&id).file_name().unwrap(), "A-DEV-001");
+    }
+
+    #[tokio::test]
+    async fn test_find_by_prefix_with_project() {
+        let registry = SessionRegistry::new().with_project(Some("A".to_string()));
+
+        for id in ["A-DEV-001", "A-MGR-001", "B-DEV-001"] {
+            let session_id = SessionId::from_string(id.to_string());
+            let metadata = SessionMetadata::new(
+                session_id.clone(),
+                Role::Developer,
+                "test".to_string(),
+                registry.session_log_dir(&session_id),
+            );
+            let handle = SessionHandle {
+                metadata,
+                task_handle: None,
+                stdin_tx: None,
+                logger: None,
+            };
+            registry.sessions.write().await.insert(session_id, handle);
+        }
+
+        let found = registry.find_by_prefix("DEV").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id.as_str(), "A-DEV-001");
+
+        assert_eq!(registry.find_by_prefix("B-DEV").await.len(), 1);
+        assert_eq!(registry.find_by_prefix("A-").await.len(), 2);
+    }
+
+    /// Wrapper that runs `script` with `sh -c` in place of claude
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    /// Registry keeping its sessions under `dir` whose processes run `script`
+    fn sh_registry(dir: &std::path::Path, script: &str) -> SessionRegistry {
+        SessionRegistry::new().with_sessions_dir(dir.to_path_buf()).with_wrapper(sh(script))
+    }
+
+    /// Insert a not-yet-started session with the given dependencies
+    async fn insert_pending(
+        registry: &SessionRegistry,
+        dir: &std::path::Path,
+        id: &str,
+        depends_on: &[&str],
+    ) {
+        let session_id = SessionId::from_string(id.to_string());
+        let mut metadata = SessionMetadata::new(
+            session_id.clone(),
+            Role::Developer,
+            "test".to_string(),
+            dir.join(id),
+        );
+        metadata.depends_on = depends_on
+            .iter()
+            .map(|d| SessionId::from_string(d.to_string()))
+            .collect();
+        let handle = SessionHandle {
+            metadata,
+            task_handle: None,
+            stdin_tx: None,
+            logger: None,
+        };
+        registry.sessions.write().await.insert(session_id, handle);
+    }
+
+    #[tokio::test]
+    async fn test_custom_role_session_ids() {
+        let reviewer = Role::register("REVIEWER", None).unwrap();
+        let registry = SessionRegistry::new();
+        assert_eq!(registry.next_session_id(reviewer).await.as_str(), "REV-001");
+        assert_eq!(registry.next_session_id(reviewer).await.as_str(), "REV-002");
+
+        let explicit = SpawnOptions::new().with_session_id(SessionId::from_string("REV-007".to_string()));
+        assert_eq!(registry.assign_session_id(reviewer, &explicit).await.unwrap().as_str(), "REV-007");
+        assert!(registry.assign_session_id(Role::Developer, &explicit).await.is_err());
+
+        // IDs that would leave the sessions directory are refused outright
+        let escape = SpawnOptions::new().with_session_id(SessionId::from_string("../../escape-REV-001".to_string()));
+        assert!(matches!(
+            registry.assign_session_id(reviewer, &escape).await,
+            Err(ClaudeManError::InvalidInput(_))
+        ));
+
+        let metadata = SessionMetadata::new(
+            SessionId::from_string("REV-001".to_string()),
+            reviewer,
+            "review".to_string(),
+            std::path::PathBuf::from("/tmp"),
+        );
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(serde_json::from_str::<SessionMetadata>(&json).unwrap().role, reviewer);
+    }
+
+    #[tokio::test]
+    async fn test_explicit_id_replaces_finished_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let sessions_dir = dir.path().join("sessions");
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, &sessions_dir, "DEV-007", &[]).await;
+        let id = SessionId::from_string("DEV-007".to_string());
+        registry.save_metadata(&registry.get_session(&id).await.unwrap()).unwrap();
+
+        // Taken IDs, active sessions and foreign role prefixes are refused
+        let explicit = SpawnOptions::new().with_session_id(id.clone());
+        assert!(registry.assign_session_id(Role::Developer, &explicit).await.is_err());
+        let replace = explicit.clone().with_replace(false);
+        assert!(registry.assign_session_id(Role::Developer, &replace).await.is_err());
+        assert!(registry.assign_session_id(Role::Manager, &replace).await.is_err());
+
+        registry.sessions.write().await.get_mut(&id).unwrap().metadata.mark_failed();
+        assert_eq!(registry.assign_session_id(Role::Developer, &replace).await.unwrap(), id);
+        assert!(registry.get_session(&id).await.is_none());
+        assert!(!sessions_dir.join("DEV-007").exists());
+        let archived: Vec<_> = fs::read_dir(dir.path().join("archive")).unwrap().collect();
+        assert_eq!(archived.len(), 1);
+
+        // Generated IDs continue past the explicit one
+        assert_eq!(registry.next_session_id(Role::Developer).await.as_str(), "DEV-008");
+    }
+
+    #[tokio::test]
+    async fn test_approval_prompts_published_when_watched() {
+        let registry = SessionRegistry::new();
+        let id = SessionId::from_string("DEV-001".to_string());
+        assert!(registry.approval_prompts().await.is_none());
+
+        registry.watch_approval_prompts(PromptDetector::default()).await;
+        let mut prompts = registry.approval_prompts().await.unwrap();
+        let filters = registry.session_filters(&id).await;
+
+        // Lines pass through unchanged; only prompts are published
+        assert_eq!(filters.apply("working...".to_string()).as_deref(), Some("working..."));
+        let line = "Do you want to proceed?".to_string();
+        assert_eq!(filters.apply(line.clone()).as_deref(), Some(line.as_str()));
+
+        let prompt = prompts.try_recv().unwrap();
+        assert_eq!(prompt.session_id, id);
+        assert_eq!(prompt.line, line);
+        assert!(prompts.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hourly_spawn_budget() {
+        let registry = SessionRegistry::new().with_max_sessions_per_hour(Some(2));
+        registry.reserve_spawn_slot().await.unwrap();
+        registry.reserve_spawn_slot().await.unwrap();
+
+        let err = registry.reserve_spawn_slot().await.unwrap_err();
+        assert!(err.to_string().contains("next slot opens at"));
+
+        // Spawns older than an hour no longer count
+        registry.recent_spawns.lock().await[0] -= SPAWN_BUDGET_WINDOW;
+        registry.reserve_spawn_slot().await.unwrap();
+
+        registry.set_max_sessions_per_hour(None).await;
+        registry.reserve_spawn_slot().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kv_tree_scope_shared_by_descendants() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new().with_kv_store(KvStore::new(dir.path().join("kv.json")));
+        insert_pending(&registry, dir.path(), "MGR-001", &[]).await;
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, dir.path(), "DEV-002", &[]).await;
+        let manager = SessionId::from_string("MGR-001".to_string());
+        let child = SessionId::from_string("DEV-001".to_string());
+        let unrelated = SessionId::from_string("DEV-002".to_string());
+        {
+            let mut sessions = registry.sessions.write().await;
+            sessions.get_mut(&child).unwrap().metadata.parent_id = Some(manager.clone());
+        }
+
+        registry.kv_set(Some(&child), "design", "plan-b").await.unwrap();
+        assert_eq!(registry.kv_get(Some(&manager), "design").await.unwrap().as_deref(), Some("plan-b"));
+        assert_eq!(registry.kv_get(Some(&unrelated), "design").await.unwrap(), None);
+        assert_eq!(registry.kv_get(None, "design").await.unwrap(), None);
+        assert!(registry.kv_set(None, "", "x").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_fails_dead_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, dir.path(), "DEV-002", &[]).await;
+
+        // A process that has exited and been reaped
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+
+        let dead = SessionId::from_string("DEV-001".to_string());
+        let alive = SessionId::from_string("DEV-002".to_string());
+        {
+            let mut sessions = registry.sessions.write().await;
+            sessions.get_mut(&dead).unwrap().metadata.mark_started(dead_pid);
+            sessions.get_mut(&alive).unwrap().metadata.mark_started(std::process::id());
+        }
+
+        assert_eq!(registry.health_check().await, vec![dead.clone()]);
+
+        let metadata = registry.get_session(&dead).await.unwrap();
+        assert!(metadata.status_reason.unwrap().contains("unmonitored"));
+        assert!(dir.path().join("DEV-001").join("metadata.json").exists());
+        assert_eq!(registry.get_session(&alive).await.unwrap().status, SessionStatus::Running);
+
+        // Nothing left to reconcile
+        assert!(registry.health_check().await.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_recovered_session_marked_when_process_exits() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+
+        let mut child = tokio::process::Command::new("sleep").arg("0.2").spawn().unwrap();
+        let pid = child.id().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        registry.sessions.write().await.get_mut(&session_id).unwrap().metadata.mark_started(pid);
+
+        let mut events = registry.subscribe_lifecycle();
+        registry.watch_recovered_session(session_id.clone(), pid);
+        // Reap it, as its real parent would
+        child.wait().await.unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.session_id, session_id);
+        assert_eq!(event.new_status, SessionStatus::Failed);
+
+        let metadata = registry.get_session(&session_id).await.unwrap();
+        assert!(metadata.status_reason.unwrap().contains("exit code unknown"));
+        let events = read_events(&dir.path().join("DEV-001")).unwrap();
+        assert!(events.iter().any(|e| e.content.contains("exit code unknown")));
+    }
+
+    #[tokio::test]
+    async fn test_queued_child_waits_for_overall_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exec sleep 30")
+            .with_max_concurrent(Some(3))
+            .with_max_concurrent_per_parent(Some(1));
+        let parent = registry.spawn_session(Role::Manager, "plan".to_string()).await.unwrap();
+        let first = registry.spawn_child_session(parent.clone(), Role::Developer, "a".to_string()).await.unwrap();
+        let second = registry.spawn_child_session(parent.clone(), Role::Developer, "b".to_string()).await.unwrap();
+        let other = registry.spawn_session(Role::Architect, "design".to_string()).await.unwrap();
+        assert_eq!(registry.get_session(&second).await.unwrap().status, SessionStatus::Queued);
+
+        // Room under the parent, but none overall: the child stays queued
+        registry.set_max_concurrent(Some(2)).await;
+        registry.stop_session(&first).await.unwrap();
+        registry.launch_all_queued_children().await;
+        assert_eq!(registry.get_session(&second).await.unwrap().status, SessionStatus::Queued);
+        assert_eq!(registry.queued_children.read().await.len(), 1);
+
+        // Any session finishing frees the overall slot it's waiting for
+        registry.stop_session(&other).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while registry.get_session(&second).await.unwrap().status != SessionStatus::Running {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(registry.queued_children.read().await.is_empty());
+
+        registry.stop_all_sessions().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_force_replace_running_session_at_concurrency_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exec sleep 30").with_max_concurrent(Some(1));
+        let id = registry.spawn_session(Role::Developer, "first".to_string()).await.unwrap();
+
+        // Stopping the old run frees the only slot for its replacement
+        let options = SpawnOptions::new().with_session_id(id.clone()).with_replace(true);
+        let spawned = registry.spawn_session_with_options(Role::Developer, "second".to_string(), options);
+        let replaced = tokio::time::timeout(Duration::from_secs(10), spawned).await.unwrap().unwrap();
+        assert_eq!(replaced, id);
+        let metadata = registry.get_session(&id).await.unwrap();
+        assert_eq!((metadata.status, metadata.task.as_str()), (SessionStatus::Running, "second"));
+
+        registry.stop_all_sessions().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queued_children_wait_for_free_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        let parent_id = SessionId::from_string("MGR-001".to_string());
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, dir.path(), "DEV-002", &[]).await;
+
+        {
+            let mut sessions = registry.sessions.write().await;
+            for id in ["DEV-001", "DEV-002"] {
+                let handle = sessions.get_mut(&SessionId::from_string(id.to_string())).unwrap();
+                handle.metadata.parent_id = Some(parent_id.clone());
+            }
+            let running = sessions.get_mut(&SessionId::from_string("DEV-001".to_string())).unwrap();
+            running.metadata.mark_started(1);
+            let queued = sessions.get_mut(&SessionId::from_string("DEV-002".to_string())).unwrap();
+            queued.metadata.mark_queued("waiting".to_string());
+        }
+        assert_eq!(registry.running_children(&parent_id).await, 1);
+
+        let queued_id = SessionId::from_string("DEV-002".to_string());
+        registry.queued_children.write().await.push_back(QueuedChild {
+            session_id: queued_id.clone(),
+            parent_id: parent_id.clone(),
+            limit: 1,
+            options: SpawnOptions::new(),
+        });
+
+        // Parent is at its limit, so the child stays queued
+        registry.launch_queued_children(parent_id.clone()).await;
+        assert_eq!(registry.queued_children.read().await.len(), 1);
+
+        // A queued child that was stopped meanwhile is dropped, not launched
+        {
+            let mut sessions = registry.sessions.write().await;
+            let finished = SessionId::from_string("DEV-001".to_string());
+            sessions.get_mut(&finished).unwrap().metadata.mark_completed();
+            sessions.get_mut(&queued_id).unwrap().metadata.mark_stopped();
+        }
+        registry.launch_queued_children(parent_id).await;
+        assert!(registry.queued_children.read().await.is_empty());
+        assert!(!registry.get_session(&queued_id).await.unwrap().is_active());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_drop_terminates_owned_process() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        let mut metadata = SessionMetadata::new(
+            session_id,
+            Role::Developer,
+            "test".to_string(),
+            std::path::PathBuf::from("/tmp"),
+        );
+        metadata.mark_started(child.id());
+
+        let handle = SessionHandle {
+            metadata,
+            task_handle: Some(tokio::spawn(std::future::pending())),
+            stdin_tx: None,
+            logger: None,
+        };
+        drop(handle);
+
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(15));
+    }
+
+    #[test]
+    fn test_most_recent() {
+        let mut sessions = Vec::new();
+        let ids = [
+            ("DEV-001", Role::Developer),
+            ("ARCH-001", Role::Architect),
+            ("DEV-002", Role::Developer),
+        ];
+        for (id, role) in ids {
+            let mut metadata = SessionMetadata::new(
+                SessionId::from_string(id.to_string()),
+                role,
+                "test".to_string(),
+                std::path::PathBuf::from("/tmp"),
+            );
+            metadata.created_at += chrono::Duration::seconds(sessions.len() as i64);
+            sessions.push(metadata);
+        }
+
+        let latest = SessionRegistry::most_recent(sessions.clone(), None).unwrap();
+        assert_eq!(latest.id.as_str(), "DEV-002");
+
+        let latest = SessionRegistry::most_recent(sessions.clone(), Some(Role::Architect)).unwrap();
+        assert_eq!(latest.id.as_str(), "ARCH-001");
+
+        assert!(SessionRegistry::most_recent(sessions, Some(Role::Manager)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_count_active() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, dir.path(), "DEV-002", &[]).await;
+        insert_pending(&registry, dir.path(), "ARCH-001", &[]).await;
+        assert_eq!(registry.count_active().await, 0);
+
+        {
+            let mut sessions = registry.sessions.write().await;
+            for id in ["DEV-001", "ARCH-001"] {
+                let handle = sessions.get_mut(&SessionId::from_string(id.to_string())).unwrap();
+                handle.metadata.mark_started(1);
+            }
+            let architect = sessions.get_mut(&SessionId::from_string("ARCH-001".to_string())).unwrap();
+            architect.metadata.role = Role::Architect;
+        }
+
+        assert_eq!(registry.count_active().await, 2);
+        assert_eq!(registry.count_active_of_role(Role::Developer).await, 1);
+        assert_eq!(registry.count_active_of_role(Role::Manager).await, 0);
+    }
 
-        // Get existing session metadata
-        let metadata = self
-            .get_session(&session_id)
+    #[tokio::test]
+    async fn test_spawn_rejected_at_concurrency_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exec sleep 30").with_max_concurrent(Some(2));
+        for id in ["DEV-001", "DEV-002"] {
+            insert_pending(&registry, dir.path(), id, &[]).await;
+            let id = SessionId::from_string(id.to_string());
+            registry.sessions.write().await.get_mut(&id).unwrap().metadata.mark_failed();
+            registry.restart_session(&id).await.unwrap();
+        }
+        assert_eq!(registry.count_active().await, 2);
+
+        let err = registry
+            .spawn_session(Role::Developer, "one too many".to_string())
             .await
-            .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+            .unwrap_err();
+        assert!(matches!(err, ClaudeManError::Session(_)));
+        assert!(err.to_string().contains("concurrency limit reached"), "{}", err);
+
+        let err = registry
+            .spawn_child_session(
+                SessionId::from_string("DEV-001".to_string()),
+                Role::Developer,
+                "child".to_string(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("concurrency limit reached"), "{}", err);
+        assert_eq!(registry.list_sessions().await.len(), 2);
+
+        // Restarting a finished session needs a slot too
+        insert_pending(&registry, dir.path(), "DEV-003", &[]).await;
+        let finished = SessionId::from_string("DEV-003".to_string());
+        registry.sessions.write().await.get_mut(&finished).unwrap().metadata.mark_failed();
+        let err = registry.restart_session(&finished).await.unwrap_err();
+        assert!(err.to_string().contains("concurrency limit reached"), "{}", err);
+        assert_eq!(registry.get_session(&finished).await.unwrap().status, SessionStatus::Failed);
+        assert_eq!(registry.count_active().await, 2);
+
+        // A finished session frees its slot
+        registry
+            .stop_session(&SessionId::from_string("DEV-001".to_string()))
+            .await
+            .unwrap();
+        assert!(registry.claim_running_slot().await.unwrap().is_some());
 
-        let log_dir = &metadata.log_dir;
+        registry.stop_all_sessions().await.unwrap();
+    }
 
-        // Create logger (will append to existing log)
-        let mut logger = SessionLogger::new(session_id.clone(), log_dir)?;
+    /// Start a pending session through the registry's wrapper and wait for `marker`
+    async fn restart_and_wait_for_ready(
+        registry: &SessionRegistry,
+        dir: &std::path::Path,
+        id: &str,
+        marker: &ReadyMarker,
+    ) -> ReadyOutcome {
+        let session_id = SessionId::from_string(id.to_string());
+        insert_pending(registry, dir, id, &[]).await;
+        registry.sessions.write().await.get_mut(&session_id).unwrap().metadata.mark_failed();
+
+        let output = registry.subscribe_output();
+        let lifecycle = registry.subscribe_lifecycle();
+        registry.restart_session(&session_id).await.unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            registry.wait_for_ready(&session_id, marker, output, lifecycle),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+    }
 
-        // Log that we're resuming
-        logger.log_lifecycle(
-            crate::types::SessionStatus::Running,
-            format!("Resuming session with message: {}", message),
-        )?;
+    #[tokio::test]
+    async fn test_wait_for_ready() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "echo starting; echo 'READY on port 8080'; echo serving; exec sleep 30");
+        let marker = ReadyMarker::new(r"READY on port \d+").unwrap();
+        // The marker matched and the session keeps running
+        match restart_and_wait_for_ready(&registry, dir.path(), "DEV-001", &marker).await {
+            ReadyOutcome::Ready(line) => assert_eq!(line, "READY on port 8080"),
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        let session_id = SessionId::from_string("DEV-001".to_string());
+        assert_eq!(registry.get_session(&session_id).await.unwrap().status, SessionStatus::Running);
+        registry.stop_session(&session_id).await.unwrap();
+
+        // The session exits without printing the marker
+        registry.set_wrapper(sh("echo starting; exit 1")).await;
+        match restart_and_wait_for_ready(&registry, dir.path(), "DEV-002", &marker).await {
+            ReadyOutcome::Exited(metadata) => assert_eq!(metadata.status, SessionStatus::Failed),
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
 
-        // Create spawn config for resume
-        let config = SpawnConfig::new(format!("--resume {} {}", session_id, message));
+    #[tokio::test]
+    async fn test_spawn_rejects_missing_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry =
+            sh_registry(&dir.path().join("sessions"), "exec sleep 30").with_project(Some("NOCWD".to_string()));
+        fs::write(dir.path().join("file"), "").unwrap();
+
+        for working_dir in [dir.path().join("missing"), dir.path().join("file")] {
+            let options = SpawnOptions::new().with_settings(SpawnSettings {
+                working_dir: Some(working_dir.clone()),
+                ..SpawnSettings::default()
+            });
+            let err = registry
+                .spawn_session_with_options(Role::Developer, "task".to_string(), options)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ClaudeManError::InvalidInput(_)), "{:?}", err);
+            assert!(err.to_string().contains(&working_dir.display().to_string()), "{}", err);
+        }
 
-        // Spawn the resume process
-        let child = spawn_claude_process(config).await?;
-        let pid = child.id().ok_or_else(|| {
-            ClaudeManError::Process("Failed to get process ID".to_string())
-        })?;
+        // Nothing was created or started
+        assert!(registry.list_sessions().await.is_empty());
+        assert_eq!(registry.count_active().await, 0);
+        assert!(!dir.path().join("sessions").exists());
+    }
 
-        info!("Resume process started with PID {}", pid);
+    #[tokio::test]
+    async fn test_spawn_uses_role_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        let pwd_file = dir.path().join("pwd");
+        let script = format!("pwd > '{}'; exec sleep 30", pwd_file.display());
+        let registry = sh_registry(&dir.path().join("sessions"), &script)
+            .with_project(Some("ROLEDIR".to_string()))
+            .with_role_working_dirs(HashMap::from([(Role::Architect, docs.clone())]));
+
+        let architect = registry.spawn_session(Role::Architect, "design".to_string()).await.unwrap();
+        let metadata = registry.get_session(&architect).await.unwrap();
+        assert_eq!(metadata.spawn_settings.working_dir.as_deref(), Some(docs.as_path()));
+        for _ in 0..100 {
+            if fs::read_to_string(&pwd_file).is_ok_and(|pwd| !pwd.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let pwd = std::path::PathBuf::from(fs::read_to_string(&pwd_file).unwrap().trim());
+        assert_eq!(pwd.canonicalize().unwrap(), docs.canonicalize().unwrap());
 
-        // Create stdin channel (unused but required for monitor_process signature)
-        let (_stdin_tx, stdin_rx) = mpsc::unbounded_channel::<String>();
+        // An explicit directory wins, and unmapped roles keep the default
+        let options = SpawnOptions::new().with_settings(SpawnSettings {
+            working_dir: Some(dir.path().to_path_buf()),
+            ..SpawnSettings::default()
+        });
+        let explicit = registry
+            .spawn_session_with_options(Role::Architect, "review".to_string(), options)
+            .await
+            .unwrap();
+        let developer = registry.spawn_session(Role::Developer, "build".to_string()).await.unwrap();
+        let explicit_dir = registry.get_session(&explicit).await.unwrap().spawn_settings.working_dir;
+        assert_eq!(explicit_dir.as_deref(), Some(dir.path()));
+        assert_eq!(registry.get_session(&developer).await.unwrap().spawn_settings.working_dir, None);
 
-        // Monitor the resume process (this blocks until complete)
-        let exit_code = monitor_process(child, session_id.clone(), logger, stdin_rx).await?;
+        registry.stop_all_sessions().await.unwrap();
+    }
 
-        info!("Resume process completed with exit code: {}", exit_code);
+    #[tokio::test]
+    async fn test_spawn_with_context_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry =
+            sh_registry(&dir.path().join("sessions"), "exec sleep 30").with_project(Some("CTXFROM".to_string()));
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        let source = SessionId::from_string("DEV-001".to_string());
+        {
+            let logger = registry.get_logger(&source).await.unwrap();
+            let mut logger = logger.lock().await;
+            logger.log_input("design the schema".to_string()).unwrap();
+            logger.log_output("users table has id and email".to_string()).unwrap();
+        }
 
-        Ok(())
+        let options = SpawnOptions::new().with_context_from(source.clone(), crate::core::logger::DEFAULT_CONTEXT_BYTES);
+        let id = registry
+            .spawn_session_with_options(Role::Developer, "write the migration".to_string(), options)
+            .await
+            .unwrap();
+        let task = registry.get_session(&id).await.unwrap().task;
+        assert!(task.starts_with("Transcript of session DEV-001"), "{}", task);
+        assert!(task.contains("Assistant: users table has id and email\n"), "{}", task);
+        assert!(task.ends_with("\nwrite the migration"), "{}", task);
+
+        let options = SpawnOptions::new().with_context_from(SessionId::from_string("NOPE-001".to_string()), 100);
+        assert!(registry
+            .spawn_session_with_options(Role::Developer, "task".to_string(), options)
+            .await
+            .is_err());
+
+        registry.stop_all_sessions().await.unwrap();
     }
 
-    /// Get a list of all active sessions
-    pub async fn list_sessions(&self) -> Vec<SessionMetadata> {
-        let sessions = self.sessions.read().await;
-        sessions
-            .values()
-            .map(|handle| handle.metadata.clone())
-            .collect()
+    #[test]
+    fn test_children_first() {
+        let id = |s: &str| SessionId::from_string(s.to_string());
+        let parents = HashMap::from([
+            (id("MGR-001"), None),
+            (id("DEV-001"), Some(id("MGR-001"))),
+            (id("DEV-002"), Some(id("DEV-001"))),
+            (id("ARC-001"), Some(id("MGR-001"))),
+            (id("DEV-009"), Some(id("GONE-001"))),
+            // A cycle still yields every session once
+            (id("CYC-001"), Some(id("CYC-002"))),
+            (id("CYC-002"), Some(id("CYC-001"))),
+        ]);
+
+        let order = children_first(&parents);
+        assert_eq!(order.len(), parents.len());
+        let position = |s: &str| order.iter().position(|o| o.as_str() == s).unwrap();
+        assert!(position("DEV-002") < position("DEV-001"));
+        assert!(position("DEV-001") < position("MGR-001"));
+        assert!(position("ARC-001") < position("MGR-001"));
     }
 
-    /// Get child sessions of a parent
-    pub async fn get_children(&self, parent_id: &SessionId) -> Vec<SessionMetadata> {
-        let sessions = self.sessions.read().await;
-        sessions
-            .values()
-            .filter(|handle| {
-                handle.metadata.parent_id.as_ref() == Some(parent_id)
-            })
-            .map(|handle| handle.metadata.clone())
-            .collect()
+    #[tokio::test]
+    async fn test_stop_all_stops_children_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, dir.path(), "MGR-001", &[]).await;
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        let child = SessionId::from_string("DEV-001".to_string());
+        registry.sessions.write().await.get_mut(&child).unwrap().metadata.parent_id =
+            Some(SessionId::from_string("MGR-001".to_string()));
+
+        let mut lifecycle = registry.subscribe_lifecycle();
+        registry.stop_all_sessions().await.unwrap();
+
+        let first = lifecycle.try_recv().unwrap();
+        let second = lifecycle.try_recv().unwrap();
+        assert_eq!((first.session_id.as_str(), first.new_status), ("DEV-001", SessionStatus::Stopped));
+        assert_eq!((second.session_id.as_str(), second.new_status), ("MGR-001", SessionStatus::Stopped));
     }
 
-    /// Get metadata for a specific session
-    pub async fn get_session(&self, session_id: &SessionId) -> Option<SessionMetadata> {
-        let sessions = self.sessions.read().await;
-        sessions.get(session_id).map(|handle| handle.metadata.clone())
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_pause_and_continue_session() {
+        // Wait for /proc/<pid>/stat to show the process stopped (`T`) or not
+        let reaches_stopped = |pid: u32, stopped: bool| async move {
+            for _ in 0..100 {
+                let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap();
+                if stat[stat.rfind(')').unwrap() + 2..].starts_with('T') == stopped {
+                    return true;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            false
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exec sleep 30").with_project(Some("PAUSE".to_string()));
+        let session_id = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap();
+        let pid = registry.get_session(&session_id).await.unwrap().pid.unwrap();
+
+        // Only running sessions can be paused, and only paused ones continued
+        assert!(registry.continue_session(&session_id).await.is_err());
+
+        let mut lifecycle = registry.subscribe_lifecycle();
+        assert_eq!(registry.pause_session(&session_id).await.unwrap(), pid);
+        let metadata = registry.get_session(&session_id).await.unwrap();
+        assert_eq!(metadata.status, SessionStatus::Paused);
+        assert!(metadata.is_active());
+        assert!(reaches_stopped(pid, true).await);
+        let event = lifecycle.try_recv().unwrap();
+        assert_eq!((event.old_status, event.new_status), (SessionStatus::Running, SessionStatus::Paused));
+        assert!(registry.pause_session(&session_id).await.is_err());
+
+        assert_eq!(registry.continue_session(&session_id).await.unwrap(), pid);
+        assert_eq!(registry.get_session(&session_id).await.unwrap().status, SessionStatus::Running);
+        assert!(reaches_stopped(pid, false).await);
+
+        // A paused session still stops promptly
+        registry.pause_session(&session_id).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(3), registry.stop_session(&session_id))
+            .await
+            .expect("stopping a paused session hung")
+            .unwrap();
+        assert_eq!(registry.get_session(&session_id).await.unwrap().status, SessionStatus::Stopped);
     }
 
-    /// Send input to a running session
-    ///
-    /// # Arguments
-    ///
-    /// * `session_id` - The ID of the session
-    /// * `input` - The input text to send
-    pub async fn send_input(&self, session_id: &SessionId, input: String) -> Result<()> {
-        info!("Sending input to session {}: {}", session_id, input);
+    #[tokio::test]
+    async fn test_sessions_land_in_sessions_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exec sleep 30");
+        let session_id = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap();
 
-        let sessions = self.sessions.read().await;
+        let log_dir = registry.get_session(&session_id).await.unwrap().log_dir;
+        assert_eq!(log_dir, dir.path().join(session_id.as_str()));
+        assert!(log_dir.join("metadata.json").exists());
+        assert!(!default_log_dir().join(session_id.as_str()).join("metadata.json").exists());
 
-        let handle = sessions
-            .get(session_id)
-            .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+        // Another registry on the same directory picks the session up
+        let reloaded = SessionRegistry::new().with_sessions_dir(dir.path().to_path_buf());
+        reloaded.load_from_disk().await.unwrap();
+        assert!(reloaded.get_session(&session_id).await.is_some());
 
-        // Check if session is still active
-        if !handle.metadata.is_active() {
-            return Err(ClaudeManError::InvalidInput(format!(
-                "Session {} is not active (status: {})",
-                session_id, handle.metadata.status
-            )));
-        }
+        registry.stop_session(&session_id).await.unwrap();
 
-        // Send input through the channel
-        if let Some(stdin_tx) = &handle.stdin_tx {
-            stdin_tx
-                .send(input)
-                .map_err(|_| ClaudeManError::Process("Failed to send input: channel closed".to_string()))?;
-        } else {
-            return Err(ClaudeManError::Process(
-                "Session stdin channel not available".to_string(),
-            ));
+        // Finished sessions are looked up on disk in the same directory
+        let fresh = SessionRegistry::new().with_sessions_dir(dir.path().to_path_buf());
+        assert_eq!(fresh.list_from_disk().unwrap().len(), 1);
+        assert_eq!(fresh.find_most_recent(None).await.unwrap().unwrap().id, session_id);
+        assert_eq!(fresh.tree_root(&session_id).await.unwrap(), session_id);
+        assert_eq!(fresh.session_log_dir(&session_id), log_dir);
+    }
+
+    #[tokio::test]
+    async fn test_tail_interleaves_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "sleep 0.5; echo alpha; echo done");
+        let first = registry.spawn_session(Role::Developer, "a".to_string()).await.unwrap();
+        registry.set_wrapper(sh("sleep 0.5; echo beta; exit 1")).await;
+        let second = registry.spawn_session(Role::Developer, "b".to_string()).await.unwrap();
+
+        let mut events = registry
+            .tail_sessions(vec![first.clone(), second.clone()], registry.subscribe_output(), registry.subscribe_lifecycle())
+            .await
+            .unwrap();
+        let mut lines = Vec::new();
+        let mut ended = HashMap::new();
+        // The stream closes once both sessions have ended
+        while let Some(event) = tokio::time::timeout(Duration::from_secs(10), events.recv()).await.unwrap() {
+            match event {
+                TailEvent::Line(line) => {
+                    assert!(!ended.contains_key(&line.session_id), "line after end: {:?}", line);
+                    lines.push((line.session_id, line.line));
+                }
+                TailEvent::Status(..) => {}
+                TailEvent::Ended(session_id, status) => {
+                    ended.insert(session_id, status);
+                }
+            }
         }
 
-        Ok(())
+        assert!(lines.contains(&(first.clone(), "alpha".to_string())), "{:?}", lines);
+        assert!(lines.contains(&(first.clone(), "done".to_string())), "{:?}", lines);
+        assert!(lines.contains(&(second.clone(), "beta".to_string())), "{:?}", lines);
+        assert!(!lines.contains(&(second.clone(), "alpha".to_string())), "{:?}", lines);
+        assert_eq!(ended[&first], SessionStatus::Completed);
+        assert_eq!(ended[&second], SessionStatus::Failed);
+
+        // Sessions that already ended are reported at once
+        let mut events = registry
+            .tail_sessions(vec![first.clone()], registry.subscribe_output(), registry.subscribe_lifecycle())
+            .await
+            .unwrap();
+        assert!(matches!(events.recv().await, Some(TailEvent::Ended(id, SessionStatus::Completed)) if id == first));
+        assert!(events.recv().await.is_none());
     }
 
-    /// Stop a specific session
-    pub async fn stop_session(&self, session_id: &SessionId) -> Result<()> {
-        info!("Stopping session {}", session_id);
+    #[tokio::test]
+    async fn test_spawns_with_same_idempotency_key_share_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exec sleep 30");
+        let keyed = || SpawnOptions::new().with_idempotency_key("nightly-build".to_string());
+
+        let (first, second) = tokio::join!(
+            registry.spawn_session_with_options(Role::Developer, "build".to_string(), keyed()),
+            registry.spawn_session_with_options(Role::Developer, "build".to_string(), keyed()),
+        );
+        let first = first.unwrap();
+        assert_eq!(second.unwrap(), first);
+        assert_eq!(registry.list_sessions().await.len(), 1);
+
+        // Other keys and unkeyed spawns are unaffected
+        let other = registry
+            .spawn_session_with_options(
+                Role::Developer,
+                "build".to_string(),
+                SpawnOptions::new().with_idempotency_key("other".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_ne!(other, first);
 
-        let mut sessions = self.sessions.write().await;
+        // Once the session finishes its key is free again
+        registry.stop_session(&first).await.unwrap();
+        let again = registry
+            .spawn_session_with_options(Role::Developer, "build".to_string(), keyed())
+            .await
+            .unwrap();
+        assert_ne!(again, first);
 
-        let handle = sessions
-            .get_mut(session_id)
-            .ok_or_else(|| ClaudeManError::SessionNotFound(session_id.to_string()))?;
+        registry.stop_all_sessions().await.unwrap();
+    }
 
-        // Kill the process if we have a PID
-        if let Some(pid) = handle.metadata.pid {
-            info!("Terminating process {} for session {}", pid, session_id);
+    #[tokio::test]
+    async fn test_load_skips_unreadable_metadata() {
+        let sessions_dir = tempfile::tempdir().unwrap();
 
-            #[cfg(unix)]
-            {
-                use nix::sys::signal::{kill, Signal};
-                use nix::unistd::Pid;
+        // Running under this test's own PID, so it counts as still alive
+        let valid_dir = sessions_dir.path().join("LOAD-DEV-001");
+        fs::create_dir_all(&valid_dir).unwrap();
+        let mut metadata = SessionMetadata::new(
+            SessionId::from_string("LOAD-DEV-001".to_string()),
+            Role::Developer,
+            "task".to_string(),
+            valid_dir.clone(),
+        );
+        metadata.mark_started(std::process::id());
+        fs::write(valid_dir.join("metadata.json"), serde_json::to_string(&metadata).unwrap()).unwrap();
 
-                let nix_pid = Pid::from_raw(pid as i32);
-                // Try SIGTERM first for graceful shutdown
-                let _ = kill(nix_pid, Signal::SIGTERM);
+        let corrupt_dir = sessions_dir.path().join("LOAD-DEV-002");
+        fs::create_dir_all(&corrupt_dir).unwrap();
+        let corrupt_path = corrupt_dir.join("metadata.json");
+        fs::write(&corrupt_path, r#"{"id": "LOAD-DEV-002", "role": "DEVELOPER"}"#).unwrap();
+        assert_eq!(salvage_session_label(&corrupt_path).as_deref(), Some("LOAD-DEV-002 (DEVELOPER)"));
 
-                // Give it a moment, then SIGKILL if needed
-                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                let _ = kill(nix_pid, Signal::SIGKILL);
-            }
+        let registry = SessionRegistry::new();
+        let skipped = registry.load_sessions_from(sessions_dir.path()).await.unwrap();
+        assert_eq!(skipped, vec![corrupt_path.clone()]);
+        let loaded = registry.list_sessions().await;
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, metadata.id);
+
+        let err = SessionRegistry::new()
+            .with_strict_load(true)
+            .load_sessions_from(sessions_dir.path())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(&corrupt_path.display().to_string()), "{}", err);
+    }
 
-            #[cfg(windows)]
-            {
-                // On Windows, use taskkill
-                let _ = std::process::Command::new("taskkill")
-                    .args(&["/F", "/PID", &pid.to_string()])
-                    .output();
-            }
-        }
+    #[tokio::test]
+    async fn test_rejects_blank_task_and_message() {
+        let registry = SessionRegistry::new();
 
-        // Abort the monitoring task if still running
-        if let Some(task_handle) = handle.task_handle.take() {
-            task_handle.abort();
-        }
+        let err = registry
+            .spawn_session(Role::Developer, " \n ".to_string())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClaudeManError::InvalidInput(ref msg) if msg == "task must not be empty"));
+        assert!(registry.list_sessions().await.is_empty());
 
-        // Update metadata
-        handle.metadata.mark_stopped();
-        self.save_metadata(&handle.metadata)?;
+        let err = registry
+            .resume_session(SessionId::from_string("DEV-001".to_string()), String::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClaudeManError::InvalidInput(_)));
+    }
 
-        info!("Session {} stopped", session_id);
+    #[tokio::test]
+    async fn test_record_approval() {
+        use crate::types::session::ApprovalDecision;
 
-        Ok(())
-    }
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        let session_id = SessionId::from_string("DEV-001".to_string());
 
-    /// Stop all active sessions
-    pub async fn stop_all_sessions(&self) -> Result<()> {
-        info!("Stopping all sessions");
+        let approval = Approval::new(ApprovalDecision::Rejected)
+            .with_note(Some("missing tests".to_string()))
+            .with_decided_by(Some(SessionId::from_string("STAKE-001".to_string())));
+        let metadata = registry.record_approval(&session_id, approval.clone()).await.unwrap();
+        assert_eq!(metadata.approval.as_ref(), Some(&approval));
 
-        let session_ids: Vec<SessionId> = {
-            let sessions = self.sessions.read().await;
-            sessions.keys().cloned().collect()
-        };
+        let log_dir = metadata.log_dir.clone();
+        let saved: SessionMetadata =
+            serde_json::from_str(&fs::read_to_string(log_dir.join("metadata.json")).unwrap()).unwrap();
+        assert_eq!(saved.approval, Some(approval));
 
-        for session_id in session_ids {
-            if let Err(e) = self.stop_session(&session_id).await {
-                warn!("Failed to stop session {}: {}", session_id, e);
+        let log = fs::read_to_string(log_dir.join("io.log")).unwrap();
+        assert!(log.contains("rejected by STAKE-001: missing tests"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restart_failed_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "sleep 30").with_termination_timeout(Duration::from_millis(100));
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        let id = SessionId::from_string("DEV-001".to_string());
+
+        // Only finished sessions can be restarted
+        let err = registry.restart_session(&id).await.unwrap_err();
+        assert!(matches!(err, ClaudeManError::InvalidInput(_)));
+
+        registry
+            .sessions
+            .write()
+            .await
+            .get_mut(&id)
+            .unwrap()
+            .metadata
+            .mark_failed_with_reason("exit code 1".to_string());
+        let mut lifecycle = registry.subscribe_lifecycle();
+
+        assert_eq!(registry.restart_session(&id).await.unwrap(), id);
+        let metadata = registry.get_session(&id).await.unwrap();
+        assert_eq!(metadata.status, SessionStatus::Running);
+        assert_eq!(metadata.task, "test");
+        assert_eq!(metadata.log_dir, dir.path().join("DEV-001"));
+        assert!(metadata.status_reason.is_none() && metadata.ended_at.is_none());
+        let event = lifecycle.try_recv().unwrap();
+        assert_eq!((event.old_status, event.new_status), (SessionStatus::Failed, SessionStatus::Running));
+
+        let err = registry.restart_session(&id).await.unwrap_err();
+        assert!(matches!(err, ClaudeManError::InvalidInput(_)));
+
+        registry.stop_session(&id).await.unwrap();
+        let events = read_events(&metadata.log_dir).unwrap();
+        assert!(events[0].content.starts_with("Restarting session (previously failed)"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_session_retried_until_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = sh_registry(dir.path(), "exit 3");
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        let id = SessionId::from_string("DEV-001".to_string());
+        {
+            let mut sessions = registry.sessions.write().await;
+            let metadata = &mut sessions.get_mut(&id).unwrap().metadata;
+            metadata.retry_on_failure = 2;
+            metadata.mark_failed();
+        }
+        let mut lifecycle = registry.subscribe_lifecycle();
+
+        registry.restart_session(&id).await.unwrap();
+        let metadata = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let metadata = registry.get_session(&id).await.unwrap();
+                if metadata.is_terminal() {
+                    return metadata;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
             }
+        })
+        .await
+        .unwrap();
+
+        // The last attempt decides the outcome
+        assert_eq!(metadata.status, SessionStatus::Failed);
+        assert_eq!((metadata.retries, metadata.retry_on_failure), (2, 2));
+
+        // Between attempts the session waits as queued, never failed
+        let mut statuses = Vec::new();
+        while let Ok(event) = lifecycle.try_recv() {
+            statuses.push(event.new_status);
         }
+        assert_eq!(
+            statuses,
+            [
+                SessionStatus::Running,
+                SessionStatus::Queued,
+                SessionStatus::Running,
+                SessionStatus::Queued,
+                SessionStatus::Running,
+                SessionStatus::Failed,
+            ]
+        );
 
-        Ok(())
+        let events = read_events(&metadata.log_dir).unwrap();
+        let retries: Vec<_> = events
+            .iter()
+            .filter(|event| event.content.starts_with("Retrying session after failure"))
+            .map(|event| event.content.as_str())
+            .collect();
+        assert_eq!(
+            retries,
+            [
+                "Retrying session after failure (retry 1 of 2)",
+                "Retrying session after failure (retry 2 of 2)",
+            ]
+        );
     }
 
-    /// Clean up completed sessions from the registry
-    pub async fn cleanup_completed(&self) {
-        let mut sessions = self.sessions.write().await;
-        sessions.retain(|_id, handle| handle.is_running());
-    }
+    #[test]
+    fn test_write_session_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = SessionRegistry::write_session_files(Role::Developer, dir.path()).unwrap();
 
-    /// Save session metadata to disk
-    fn save_metadata(&self, metadata: &SessionMetadata) -> Result<()> {
-        let metadata_path = metadata.log_dir.join("metadata.json");
+        assert_eq!(files.last().unwrap(), &dir.path().join(".claude/hooks/pre-tool-use.sh"));
+        assert!(files.iter().all(|file| file.starts_with(dir.path()) && file.is_file()));
+    }
 
-        // Ensure directory exists
-        fs::create_dir_all(&metadata.log_dir)?;
+    #[tokio::test]
+    async fn test_get_logger_is_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, dir.path(), "DEV-001", &[]).await;
+        let session_id = SessionId::from_string("DEV-001".to_string());
 
-        // Write metadata as JSON
-        let json = serde_json::to_string_pretty(metadata)?;
-        fs::write(metadata_path, json)?;
+        let first = registry.get_logger(&session_id).await.unwrap();
+        let second = registry.get_logger(&session_id).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
 
-        debug!("Saved metadata for session {}", metadata.id);
+        let missing = SessionId::from_string("DEV-999".to_string());
+        assert!(registry.get_logger(&missing).await.is_err());
+    }
 
-        Ok(())
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_on_complete_hook_is_logged() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = SessionId::from_string("DEV-001".to_string());
+        let mut metadata = SessionMetadata::new(id.clone(), Role::Developer, "task".to_string(), dir.path().to_path_buf());
+        metadata.mark_failed();
+        let logger = SessionLogger::new(id, dir.path()).unwrap().shared();
+
+        run_on_complete("echo \"$CLAUDE_MAN_SESSION_ID $CLAUDE_MAN_STATUS $CLAUDE_MAN_EXIT_CODE\"; exit 2", &metadata, Some(1), &logger).await;
+        logger.lock().await.flush().unwrap();
+
+        let events = read_events(dir.path()).unwrap();
+        let hook = &events.last().unwrap().metadata.as_ref().unwrap();
+        assert_eq!(hook["hook_exit_code"], 2);
+        assert_eq!(hook["hook_stdout"], "DEV-001 failed 1\n");
+        assert_eq!(hook["status"], "failed");
     }
 
-    /// Load session metadata from disk
-    pub fn load_metadata(session_id: &SessionId) -> Result<SessionMetadata> {
-        let log_dir = session_log_dir(session_id);
-        let metadata_path = log_dir.join("metadata.json");
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_on_complete_hook_runs_after_session_finishes() {
+        let dir = tempfile::tempdir().unwrap();
+        let sentinel = dir.path().join("sentinel");
+        let registry = sh_registry(&dir.path().join("sessions"), "echo working");
+        let hook = format!("echo \"$CLAUDE_MAN_SESSION_ID $CLAUDE_MAN_STATUS\" > '{}'; echo hook ran", sentinel.display());
+
+        let id = registry
+            .spawn_session_with_options(Role::Developer, "task".to_string(), SpawnOptions::new().with_on_complete(hook))
+            .await
+            .unwrap();
+        let log_dir = registry.get_session(&id).await.unwrap().log_dir;
+
+        // The hook sees the final status, and its output lands in io.log
+        let hook_event = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let events = read_events(&log_dir).unwrap_or_default();
+                if let Some(event) = events.into_iter().find(|e| e.metadata.as_ref().is_some_and(|m| m["hook"].is_string())) {
+                    return event;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(fs::read_to_string(&sentinel).unwrap(), format!("{} completed\n", id));
+        let hook_meta = hook_event.metadata.unwrap();
+        assert_eq!(hook_meta["hook_exit_code"], 0);
+        assert_eq!(hook_meta["hook_stdout"], "hook ran\n");
+    }
 
-        if !metadata_path.exists() {
-            return Err(ClaudeManError::SessionNotFound(session_id.to_string()));
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_metadata_saves_stay_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = SessionRegistry::new();
+        let id = SessionId::from_string("DEV-001".to_string());
+        let path = dir.path().join("metadata.json");
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let registry = registry.clone();
+                let mut metadata = SessionMetadata::new(id.clone(), Role::Developer, "task".to_string(), dir.path().to_path_buf());
+                tokio::spawn(async move {
+                    for round in 0..50 {
+                        // Sizes differ so an interleaved or partial write would show
+                        metadata.task = format!("writer {} round {} {}", writer, round, "x".repeat(writer * 100));
+                        registry.save_metadata(&metadata).unwrap();
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        let reader = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || {
+                for _ in 0..200 {
+                    if let Ok(json) = fs::read_to_string(&path) {
+                        serde_json::from_str::<SessionMetadata>(&json).unwrap();
+                    }
+                }
+            }
+        });
+        for writer in writers {
+            writer.await.unwrap();
         }
+        reader.await.unwrap();
 
-        let json = fs::read_to_string(metadata_path)?;
-        let metadata: SessionMetadata = serde_json::from_str(&json)?;
+        let saved: SessionMetadata = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        // Whichever writer saved last, it was on its last round
+        assert!(saved.task.contains("round 49 "), "{}", saved.task);
+        assert!(!dir.path().join("metadata.json.tmp").exists());
+    }
 
-        Ok(metadata)
+    #[tokio::test]
+    async fn test_spawn_refuses_locked_session_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let sessions_dir = dir.path().join("sessions");
+        let session_dir = sessions_dir.join("DEV-001");
+        fs::create_dir_all(&session_dir).unwrap();
+
+        // Another live claude-man process holds the directory
+        let mut owner = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let lock_path = session_dir.join(SESSION_LOCK_FILE);
+        fs::write(&lock_path, format!("{} OTHER-DEV-001\n", owner.id())).unwrap();
+
+        let registry = sh_registry(&sessions_dir, "exec sleep 30");
+        let err = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap_err();
+        assert!(matches!(err, ClaudeManError::Session(_)), "{:?}", err);
+        assert!(err.to_string().contains("OTHER-DEV-001"), "{}", err);
+
+        // Nothing was started or written into the directory
+        assert!(registry.list_sessions().await.is_empty());
+        assert!(!session_dir.join("io.log").exists());
+        assert!(fs::read_to_string(&lock_path).unwrap().contains("OTHER-DEV-001"));
+
+        // Once the owner is gone its lock is stale and is taken over
+        owner.kill().unwrap();
+        owner.wait().unwrap();
+        let options = SpawnOptions::new().with_session_id(SessionId::from_string("DEV-001".to_string()));
+        let id = registry
+            .spawn_session_with_options(Role::Developer, "task".to_string(), options)
+            .await
+            .unwrap();
+        assert_eq!(registry.get_session(&id).await.unwrap().log_dir, session_dir);
+        let (pid, owner, _) = read_session_lock(&lock_path).unwrap();
+        assert_eq!((pid, owner), (std::process::id(), id.to_string()));
+
+        // A second registry numbering from scratch collides with the running session
+        let other = sh_registry(&sessions_dir, "exec sleep 30");
+        let err = other.spawn_session(Role::Developer, "task".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("in use by DEV-001"), "{}", err);
+        assert!(other.list_sessions().await.is_empty());
+
+        // Stopping the session releases the directory
+        registry.stop_session(&id).await.unwrap();
+        assert!(!lock_path.exists());
     }
-}
 
-impl Default for SessionRegistry {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_stale_session_lock_leaves_newer_claim() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = SessionId::from_string("DEV-001".to_string());
+        let lock_path = dir.path().join(SESSION_LOCK_FILE);
+
+        // A restart claims the directory before the old run's lock is dropped
+        let old = SessionLock::acquire(dir.path(), &id).unwrap();
+        let new = SessionLock::acquire(dir.path(), &id).unwrap();
+        drop(old);
+        assert_eq!(read_session_lock(&lock_path).unwrap().2, new.nonce);
+
+        drop(new);
+        assert!(!lock_path.exists());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_stop_cascades_to_dependents() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let registry = SessionRegistry::new();
+        insert_pending(&registry, temp_dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, temp_dir.path(), "DEV-002", &["DEV-001"]).await;
+        insert_pending(&registry, temp_dir.path(), "DEV-003", &["DEV-002"]).await;
+        insert_pending(&registry, temp_dir.path(), "DEV-004", &[]).await;
+
+        let root = SessionId::from_string("DEV-001".to_string());
+        let affected = registry.stop_session_with_cascade(&root, true).await.unwrap();
+        assert_eq!(affected.len(), 2);
+
+        for id in ["DEV-002", "DEV-003"] {
+            let metadata = registry
+                .get_session(&SessionId::from_string(id.to_string()))
+                .await
+                .unwrap();
+            assert_eq!(metadata.status, SessionStatus::Stopped);
+            assert_eq!(metadata.status_reason.as_deref(), Some("dependency stopped"));
+        }
+
+        let unrelated = registry
+            .get_session(&SessionId::from_string("DEV-004".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(unrelated.status, SessionStatus::Created);
+    }
 
     #[tokio::test]
-    async fn test_session_registry_creation() {
+    async fn test_subscribe_lifecycle_sees_stop() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
         let registry = SessionRegistry::new();
-        let sessions = registry.list_sessions().await;
-        assert!(sessions.is_empty());
+        insert_pending(&registry, temp_dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, temp_dir.path(), "DEV-002", &["DEV-001"]).await;
+        let mut events = registry.subscribe_lifecycle();
+
+        let root = SessionId::from_string("DEV-001".to_string());
+        registry.stop_session_with_cascade(&root, true).await.unwrap();
+
+        let first = events.try_recv().unwrap();
+        assert_eq!(first.session_id, root);
+        assert_eq!(first.role, Role::Developer);
+        assert_eq!(first.old_status, SessionStatus::Created);
+        assert_eq!(first.new_status, SessionStatus::Stopped);
+
+        let second = events.try_recv().unwrap();
+        assert_eq!(second.session_id.as_str(), "DEV-002");
+        assert_eq!(second.new_status, SessionStatus::Stopped);
+        assert!(events.try_recv().is_err());
     }
 
     #[tokio::test]
-    async fn test_next_session_id() {
+    async fn test_stop_fails_dependents_without_cascade() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
         let registry = SessionRegistry::new();
+        insert_pending(&registry, temp_dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, temp_dir.path(), "DEV-002", &["DEV-001"]).await;
 
-        let id1 = registry.next_session_id(Role::Developer).await;
-        assert_eq!(id1.as_str(), "DEV-001");
+        let root = SessionId::from_string("DEV-001".to_string());
+        registry.stop_session(&root).await.unwrap();
 
-        let id2 = registry.next_session_id(Role::Developer).await;
-        assert_eq!(id2.as_str(), "DEV-002");
+        let dependent = registry
+            .get_session(&SessionId::from_string("DEV-002".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(dependent.status, SessionStatus::Failed);
+        assert!(dependent.status_reason.unwrap().contains("DEV-001"));
+    }
 
-        let id3 = registry.next_session_id(Role::Architect).await;
-        assert_eq!(id3.as_str(), "ARCH-001");
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_waits_for_exit_then_kills() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let registry = SessionRegistry::new().with_termination_timeout(Duration::from_secs(10));
+        insert_pending(&registry, temp_dir.path(), "DEV-001", &[]).await;
+        insert_pending(&registry, temp_dir.path(), "DEV-002", &[]).await;
+
+        // Exits on SIGTERM: stop returns as soon as the monitor sees the exit
+        let mut graceful = tokio::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let graceful_id = SessionId::from_string("DEV-001".to_string());
+        {
+            let mut sessions = registry.sessions.write().await;
+            let handle = sessions.get_mut(&graceful_id).unwrap();
+            handle.metadata.mark_started(graceful.id().unwrap());
+            handle.task_handle = Some(tokio::spawn(async move {
+                let status = graceful.wait().await?;
+                Ok(status.code().unwrap_or(-1))
+            }));
+        }
+        let started = std::time::Instant::now();
+        registry.stop_session(&graceful_id).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let metadata = registry.get_session(&graceful_id).await.unwrap();
+        assert_eq!(metadata.status, SessionStatus::Stopped);
+
+        // Ignores SIGTERM: killed once the grace period runs out
+        registry.set_termination_timeout(Duration::from_millis(200)).await;
+        let mut stubborn = std::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; echo ready; exec sleep 30"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut ready = String::new();
+        std::io::BufRead::read_line(
+            &mut std::io::BufReader::new(stubborn.stdout.as_mut().unwrap()),
+            &mut ready,
+        )
+        .unwrap();
+        let stubborn_id = SessionId::from_string("DEV-002".to_string());
+        {
+            let mut sessions = registry.sessions.write().await;
+            let handle = sessions.get_mut(&stubborn_id).unwrap();
+            handle.metadata.mark_started(stubborn.id());
+        }
+        registry.stop_session(&stubborn_id).await.unwrap();
+        assert_eq!(stubborn.wait().unwrap().signal(), Some(9));
     }
 
     #[test]
@@ -694,4 +4220,29 @@ mod tests {
         assert_eq!(loaded.id.as_str(), metadata.id.as_str());
         assert_eq!(loaded.task, metadata.task);
     }
+
+    #[test]
+    fn test_compact_metadata_is_one_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_dir = temp_dir.path().join("DEV-001");
+        let metadata = SessionMetadata::new(
+            SessionId::from_string("DEV-001".to_string()),
+            Role::Developer,
+            "test task".to_string(),
+            log_dir.clone(),
+        );
+        let metadata_path = log_dir.join("metadata.json");
+
+        // Pretty-printed by default
+        let registry = SessionRegistry::new();
+        registry.save_metadata(&metadata).unwrap();
+        assert!(fs::read_to_string(&metadata_path).unwrap().lines().count() > 1);
+
+        // Compact metadata is one line and loads the same way
+        registry.set_compact_metadata(true);
+        registry.save_metadata(&metadata).unwrap();
+        assert_eq!(fs::read_to_string(&metadata_path).unwrap().lines().count(), 1);
+        let loaded = SessionRegistry::load_metadata_from_path(&metadata_path).unwrap();
+        assert_eq!(loaded.task, metadata.task);
+    }
 }
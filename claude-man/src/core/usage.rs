@@ -0,0 +1,106 @@
+//! Resource usage of session processes
+//!
+//! Reads CPU time and resident memory from `/proc` on Linux. Other platforms
+//! report no usage, and `top` shows `-` for those columns.
+
+use std::time::Instant;
+
+/// Kernel clock ticks per second in `/proc/<pid>/stat` (USER_HZ, fixed at 100 on Linux)
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const USER_HZ: f64 = 100.0;
+
+/// CPU time and memory of a process at one moment
+#[derive(Debug, Clone, Copy)]
+pub struct UsageSample {
+    /// User plus system CPU time consumed so far
+    pub cpu_seconds: f64,
+
+    /// Resident set size
+    pub rss_bytes: u64,
+
+    /// When the sample was taken
+    pub taken_at: Instant,
+}
+
+impl UsageSample {
+    /// Average CPU use between an earlier sample and this one (100 = one core)
+    pub fn cpu_percent_since(&self, earlier: &UsageSample) -> f64 {
+        let elapsed = self.taken_at.duration_since(earlier.taken_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        ((self.cpu_seconds - earlier.cpu_seconds) / elapsed * 100.0).max(0.0)
+    }
+}
+
+/// Sample a process's usage, or `None` if it's gone or usage isn't available here
+pub fn sample(pid: u32) -> Option<UsageSample> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        Some(UsageSample {
+            cpu_seconds: parse_stat_cpu_seconds(&stat)?,
+            rss_bytes: parse_status_rss_bytes(&status)?,
+            taken_at: Instant::now(),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// CPU seconds (utime + stime) from the contents of `/proc/<pid>/stat`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_stat_cpu_seconds(stat: &str) -> Option<f64> {
+    // The command name is parenthesized and may contain spaces; fields resume after it
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 1..)?.split_whitespace().collect();
+    // utime and stime are fields 14 and 15 of the line, 12 and 13 after the name
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) as f64 / USER_HZ)
+}
+
+/// Resident memory in bytes from the `VmRSS` line of `/proc/<pid>/status`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_status_rss_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_files() {
+        let stat = "4242 (claude (v2) x) S 1 4242 4242 0 -1 4194560 1500 0 0 0 250 50 0 0 20 0 8 0 12345";
+        assert_eq!(parse_stat_cpu_seconds(stat), Some(3.0));
+        assert_eq!(parse_stat_cpu_seconds("garbage"), None);
+
+        let status = "Name:\tclaude\nVmPeak:\t  900 kB\nVmRSS:\t  2048 kB\nThreads:\t8\n";
+        assert_eq!(parse_status_rss_bytes(status), Some(2048 * 1024));
+        assert_eq!(parse_status_rss_bytes("Name:\tkthreadd\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sample_own_process() {
+        let usage = sample(std::process::id()).unwrap();
+        assert!(usage.rss_bytes > 0);
+
+        // Fixed CPU times keep the arithmetic exact
+        let earlier = UsageSample { cpu_seconds: 2.0, ..usage };
+        let later = UsageSample {
+            cpu_seconds: 2.5,
+            taken_at: usage.taken_at + std::time::Duration::from_secs(1),
+            ..usage
+        };
+        assert_eq!(later.cpu_percent_since(&earlier), 50.0);
+        assert_eq!(earlier.cpu_percent_since(&later), 0.0);
+    }
+}
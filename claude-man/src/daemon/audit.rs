@@ -0,0 +1,191 @@
+//! Audit trail of daemon client requests
+//!
+//! With `audit_log = true` in config, the daemon appends one JSON line per
+//! client request to `.claude-man/audit.log`: when it arrived, where from,
+//! the command, its outcome and the request itself with secrets redacted.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::core::config::default_home_dir;
+use crate::daemon::protocol::{DaemonRequest, DaemonResponse};
+use crate::types::error::Result;
+
+/// Replaces secret values in audited requests
+const REDACTED: &str = "[REDACTED]";
+
+/// Get the default audit log path
+pub fn default_audit_path() -> PathBuf {
+    default_home_dir().join("audit.log")
+}
+
+/// How a request was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// Handled successfully
+    Ok,
+
+    /// Handled, but the daemon answered with an error
+    Error,
+
+    /// Refused for a missing or wrong token
+    Unauthorized,
+
+    /// Not a valid request
+    Invalid,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the request was received
+    pub timestamp: DateTime<Utc>,
+
+    /// Client address (`unix` for Unix socket clients)
+    pub source: String,
+
+    /// The request's `command` tag (None if it couldn't be parsed)
+    pub command: Option<String>,
+
+    /// How the request was answered
+    pub outcome: AuditOutcome,
+
+    /// The daemon's error message, for `error` and `invalid` outcomes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// The request with environment values and tokens redacted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    /// Start an entry for a request as it arrives
+    pub fn received(source: &str, request: &DaemonRequest) -> Self {
+        let mut value = serde_json::to_value(request).unwrap_or(serde_json::Value::Null);
+        redact(&mut value);
+        let command = value.get("command").and_then(|c| c.as_str()).map(str::to_string);
+
+        Self {
+            timestamp: Utc::now(),
+            source: source.to_string(),
+            command,
+            outcome: AuditOutcome::Ok,
+            error: None,
+            request: Some(value),
+        }
+    }
+
+    /// Set the outcome from the daemon's response
+    pub fn with_response(mut self, response: &DaemonResponse, authorized: bool) -> Self {
+        (self.outcome, self.error) = match response {
+            _ if !authorized => (AuditOutcome::Unauthorized, None),
+            DaemonResponse::Error { message } => (AuditOutcome::Error, Some(message.clone())),
+            _ => (AuditOutcome::Ok, None),
+        };
+        self
+    }
+
+    /// Record a request line that couldn't be parsed
+    pub fn invalid(source: &str, error: String) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            source: source.to_string(),
+            command: None,
+            outcome: AuditOutcome::Invalid,
+            error: Some(error),
+            request: None,
+        }
+    }
+}
+
+/// Append-only JSONL audit log shared by the daemon's client tasks
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open (or create) the audit log at `path` for appending
+    pub fn open(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file: Mutex::new(file) })
+    }
+
+    /// Where entries are written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry as one line
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Blank out environment values and anything token-like, at any depth
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if key == "env" {
+                    if let serde_json::Value::Object(env) = field {
+                        env.values_mut().for_each(|v| *v = REDACTED.into());
+                    }
+                } else if key.to_lowercase().contains("token") && !field.is_null() {
+                    *field = REDACTED.into();
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::protocol::SpawnLaunch;
+    use crate::types::session::SpawnSettings;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_redacts_env_values() {
+        let request = DaemonRequest::Spawn {
+            role: "DEVELOPER".to_string(),
+            task: "deploy".to_string(),
+            annotations: HashMap::new(),
+            capture_result: None,
+            encoding: None,
+            launch: Box::new(SpawnLaunch {
+                settings: SpawnSettings {
+                    env: BTreeMap::from([("API_KEY".to_string(), "sk-secret".to_string())]),
+                    ..SpawnSettings::default()
+                },
+                ..SpawnLaunch::default()
+            }),
+        };
+        let entry = AuditEntry::received("127.0.0.1:5000", &request).with_response(&DaemonResponse::ok(), true);
+
+        assert_eq!(entry.command.as_deref(), Some("spawn"));
+        assert_eq!(entry.outcome, AuditOutcome::Ok);
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(!line.contains("sk-secret"), "{}", line);
+        assert_eq!(entry.request.unwrap()["settings"]["env"]["API_KEY"], REDACTED);
+    }
+}
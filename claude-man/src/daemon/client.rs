@@ -1,25 +1,46 @@
 //! IPC client for communicating with the daemon
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
 
-use crate::daemon::protocol::{DaemonRequest, DaemonResponse};
+use crate::core::logger::LogSelection;
+use crate::core::process::InputNewline;
+use crate::core::result::CaptureResult;
+use crate::daemon::token::{default_token_path, read_token};
+use crate::daemon::protocol::{AuthenticatedRequest, DaemonRequest, DaemonResponse, SpawnLaunch};
+use crate::daemon::transport::{Connection, DaemonTransport};
+use crate::types::session::Approval;
 use crate::types::error::{ClaudeManError, Result};
 
 /// Client for communicating with the daemon
 pub struct DaemonClient {
-    address: String,
+    transport: DaemonTransport,
+
+    /// File holding the daemon's authentication token
+    token_path: PathBuf,
+}
+
+impl Default for DaemonClient {
+    /// Create a client for the default transport
+    fn default() -> Self {
+        Self::new(DaemonTransport::default())
+    }
 }
 
 impl DaemonClient {
     /// Create a new daemon client
-    pub fn new(address: String) -> Self {
-        Self { address }
+    pub fn new(transport: DaemonTransport) -> Self {
+        Self {
+            transport,
+            token_path: default_token_path(),
+        }
     }
 
-    /// Create a client with the default address
-    pub fn default() -> Self {
-        Self::new(format!("127.0.0.1:{}", crate::daemon::server::DEFAULT_DAEMON_PORT))
+    /// Read the daemon's token from this file instead of `.claude-man/daemon.token`
+    pub fn with_token_file(mut self, path: PathBuf) -> Self {
+        self.token_path = path;
+        self
     }
 
     /// Check if daemon is running
@@ -28,39 +49,98 @@ impl DaemonClient {
     }
 
     /// Send a request to the daemon and receive a response
+    ///
+    /// The token is re-read for every request, so a restarted daemon's new
+    /// token is picked up.
     pub async fn send_request(&self, request: DaemonRequest) -> Result<DaemonResponse> {
+        let mut reader = self.open(request).await?;
+
+        // Read response
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+
+        parse_response(&line)
+    }
+
+    /// Connect to the daemon and send an authenticated request
+    async fn open(&self, request: DaemonRequest) -> Result<BufReader<Box<dyn Connection>>> {
+        let token = read_token(&self.token_path).map_err(|e| {
+            ClaudeManError::Other(format!(
+                "Failed to read daemon token {}: {}",
+                self.token_path.display(),
+                e
+            ))
+        })?;
+
         // Connect to daemon
-        let stream = TcpStream::connect(&self.address)
+        let mut stream = self.transport
+            .connect()
             .await
-            .map_err(|e| ClaudeManError::Other(format!("Failed to connect to daemon at {}. Is it running? Error: {}", self.address, e)))?;
-
-        let (reader, mut writer) = stream.into_split();
-        let mut reader = BufReader::new(reader);
+            .map_err(|e| ClaudeManError::Other(format!("Failed to connect to daemon at {}. Is it running? Error: {}", self.transport, e)))?;
 
         // Send request
-        let request_json = serde_json::to_string(&request)?;
-        writer.write_all(request_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        let request_json = serde_json::to_string(&AuthenticatedRequest { token, request })?;
+        stream.write_all(request_json.as_bytes()).await?;
+        stream.write_all(b"\n").await?;
+        stream.flush().await?;
 
-        // Read response
-        let mut line = String::new();
-        reader.read_line(&mut line).await?;
+        Ok(BufReader::new(stream))
+    }
 
-        let response: DaemonResponse = serde_json::from_str(&line.trim())
-            .map_err(|e| ClaudeManError::Other(format!("Invalid response: {}", e)))?;
+    /// Follow the live output of sessions (all of them when empty)
+    ///
+    /// Calls `on_response` with each `output` and `session_ended` event until
+    /// the daemon closes the stream, or with the error if it refuses.
+    pub async fn tail(&self, session_ids: Vec<String>, on_response: impl FnMut(DaemonResponse)) -> Result<()> {
+        self.stream(DaemonRequest::Tail { session_ids }, on_response).await
+    }
 
-        Ok(response)
+    /// Send a request answered with several responses, such as `tail` or an
+    /// attached spawn, calling `on_response` with each until the daemon is done
+    pub async fn stream(&self, request: DaemonRequest, mut on_response: impl FnMut(DaemonResponse)) -> Result<()> {
+        let mut reader = self.open(request).await?;
+        let mut line = String::new();
+        while reader.read_line(&mut line).await? > 0 {
+            on_response(parse_response(&line)?);
+            line.clear();
+        }
+        Ok(())
     }
 
     /// Spawn a session
-    pub async fn spawn(&self, role: String, task: String) -> Result<DaemonResponse> {
-        self.send_request(DaemonRequest::Spawn { role, task }).await
+    pub async fn spawn(
+        &self,
+        role: String,
+        task: String,
+        annotations: HashMap<String, String>,
+        capture_result: Option<CaptureResult>,
+        encoding: Option<String>,
+        launch: SpawnLaunch,
+    ) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Spawn {
+            role,
+            task,
+            annotations,
+            capture_result,
+            encoding,
+            launch: Box::new(launch),
+        })
+        .await
     }
 
     /// Resume a session
-    pub async fn resume(&self, session_id: String, message: String) -> Result<DaemonResponse> {
-        self.send_request(DaemonRequest::Resume { session_id, message }).await
+    pub async fn resume(
+        &self,
+        session_id: String,
+        message: String,
+        replay_context: usize,
+    ) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Resume { session_id, message, replay_context }).await
+    }
+
+    /// Restart a finished session
+    pub async fn restart(&self, session_id: String) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Restart { session_id }).await
     }
 
     /// List sessions
@@ -73,9 +153,38 @@ impl DaemonClient {
         self.send_request(DaemonRequest::Info { session_id }).await
     }
 
+    /// Fetch the selected events of a session's log
+    pub async fn get_log(&self, session_id: String, selection: LogSelection) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::GetLog {
+            session_id,
+            lines: selection.lines,
+            event_types: selection.event_types,
+        })
+        .await
+    }
+
+    /// Record an approval decision on a session
+    pub async fn record_approval(
+        &self,
+        session_id: String,
+        approval: Approval,
+    ) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::RecordApproval { session_id, approval }).await
+    }
+
     /// Stop a session
-    pub async fn stop(&self, session_id: String) -> Result<DaemonResponse> {
-        self.send_request(DaemonRequest::Stop { session_id }).await
+    pub async fn stop(&self, session_id: String, cascade: bool) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Stop { session_id, cascade }).await
+    }
+
+    /// Suspend a running session
+    pub async fn pause(&self, session_id: String) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Pause { session_id }).await
+    }
+
+    /// Continue a paused session
+    pub async fn continue_session(&self, session_id: String) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Continue { session_id }).await
     }
 
     /// Stop all sessions
@@ -83,9 +192,30 @@ impl DaemonClient {
         self.send_request(DaemonRequest::StopAll).await
     }
 
+    /// Write a value to the shared key/value store
+    pub async fn kv_set(&self, key: String, value: String, tree: Option<String>) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::KvSet { key, value, tree }).await
+    }
+
+    /// Read a value from the shared key/value store
+    pub async fn kv_get(&self, key: String, tree: Option<String>) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::KvGet { key, tree }).await
+    }
+
+    /// Reconcile running sessions with their processes
+    pub async fn health_check(&self) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::HealthCheck).await
+    }
+
     /// Send input to a running session
-    pub async fn input(&self, session_id: String, text: String) -> Result<DaemonResponse> {
-        self.send_request(DaemonRequest::Input { session_id, text }).await
+    pub async fn input(
+        &self,
+        session_id: String,
+        text: String,
+        newline: InputNewline,
+        paste: bool,
+    ) -> Result<DaemonResponse> {
+        self.send_request(DaemonRequest::Input { session_id, text, newline, paste }).await
     }
 
     /// Shutdown the daemon
@@ -93,3 +223,8 @@ impl DaemonClient {
         self.send_request(DaemonRequest::Shutdown).await
     }
 }
+
+/// Parse one line the daemon sent
+fn parse_response(line: &str) -> Result<DaemonResponse> {
+    serde_json::from_str(line.trim()).map_err(|e| ClaudeManError::Other(format!("Invalid response: {}", e)))
+}
@@ -0,0 +1,536 @@
+//! gRPC interface for the daemon (`grpc` feature)
+//!
+//! Serves the `claudeman.v1.ClaudeMan` service from proto/claude_man.proto
+//! for clients in other languages. Spawn, List, Info and Stop are translated
+//! to a `DaemonRequest` and handled by the same code path as IPC clients;
+//! Attach follows the session's io.log and Events relays the registry's
+//! lifecycle broadcast.
+
+// `tonic::Status` is large, but it's the error type the service must return
+#![allow(clippy::result_large_err)]
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, RwLock};
+use tokio_stream::wrappers::{BroadcastStream, ReceiverStream, TcpListenerStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+use tracing::{debug, info};
+
+use crate::core::logger;
+use crate::core::session::{self, SessionRegistry};
+use crate::daemon::protocol::{DaemonRequest, DaemonResponse, SpawnLaunch};
+use crate::daemon::server::DaemonServer;
+use crate::daemon::token::bearer_authorized;
+use crate::types::error::{ClaudeManError, Result};
+use crate::types::session::{SessionId, SessionMetadata, SpawnSettings};
+
+/// How often Attach checks io.log for new events
+const ATTACH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Messages and generated service stubs of the `claudeman.v1` package
+///
+/// Field numbers match proto/claude_man.proto.
+pub mod proto {
+    use std::collections::HashMap;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SpawnRequest {
+        #[prost(string, tag = "1")]
+        pub role: String,
+        #[prost(string, tag = "2")]
+        pub task: String,
+        #[prost(map = "string, string", tag = "3")]
+        pub annotations: HashMap<String, String>,
+        #[prost(string, optional, tag = "4")]
+        pub parent_id: Option<String>,
+        #[prost(string, optional, tag = "5")]
+        pub model: Option<String>,
+        #[prost(map = "string, string", tag = "6")]
+        pub env: HashMap<String, String>,
+        #[prost(string, optional, tag = "7")]
+        pub working_dir: Option<String>,
+        #[prost(string, optional, tag = "8")]
+        pub claude_binary: Option<String>,
+        #[prost(string, optional, tag = "9")]
+        pub encoding: Option<String>,
+        #[prost(string, optional, tag = "10")]
+        pub on_complete: Option<String>,
+        #[prost(bool, tag = "11")]
+        pub expect_json_result: bool,
+        #[prost(string, optional, tag = "12")]
+        pub session_id: Option<String>,
+        #[prost(uint64, optional, tag = "13")]
+        pub timeout_secs: Option<u64>,
+        #[prost(uint32, tag = "14")]
+        pub retry_on_failure: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SpawnResponse {
+        #[prost(string, tag = "1")]
+        pub session_id: String,
+        #[prost(uint32, tag = "2")]
+        pub pid: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub sessions: Vec<Session>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InfoRequest {
+        #[prost(string, tag = "1")]
+        pub session_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopRequest {
+        #[prost(string, tag = "1")]
+        pub session_id: String,
+        #[prost(bool, tag = "2")]
+        pub cascade: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopResponse {
+        #[prost(string, tag = "1")]
+        pub message: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AttachRequest {
+        #[prost(string, tag = "1")]
+        pub session_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct EventsRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Session {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub role: String,
+        #[prost(string, tag = "3")]
+        pub status: String,
+        #[prost(string, tag = "4")]
+        pub task: String,
+        #[prost(string, optional, tag = "5")]
+        pub parent_id: Option<String>,
+        #[prost(string, tag = "6")]
+        pub created_at: String,
+        #[prost(string, optional, tag = "7")]
+        pub started_at: Option<String>,
+        #[prost(string, optional, tag = "8")]
+        pub ended_at: Option<String>,
+        #[prost(uint32, optional, tag = "9")]
+        pub pid: Option<u32>,
+        #[prost(string, tag = "10")]
+        pub log_dir: String,
+        #[prost(map = "string, string", tag = "11")]
+        pub annotations: HashMap<String, String>,
+        #[prost(string, repeated, tag = "12")]
+        pub depends_on: Vec<String>,
+        #[prost(string, optional, tag = "13")]
+        pub status_reason: Option<String>,
+        #[prost(uint32, tag = "14")]
+        pub retries: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct IoEvent {
+        #[prost(string, tag = "1")]
+        pub timestamp: String,
+        #[prost(string, tag = "2")]
+        pub event_type: String,
+        #[prost(string, tag = "3")]
+        pub content: String,
+        #[prost(string, optional, tag = "4")]
+        pub metadata_json: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct LifecycleEvent {
+        #[prost(string, tag = "1")]
+        pub session_id: String,
+        #[prost(string, tag = "2")]
+        pub role: String,
+        #[prost(string, tag = "3")]
+        pub old_status: String,
+        #[prost(string, tag = "4")]
+        pub new_status: String,
+        #[prost(string, tag = "5")]
+        pub at: String,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/claudeman.v1.ClaudeMan.rs"));
+}
+
+use proto::claude_man_server::{ClaudeMan, ClaudeManServer};
+
+impl From<SessionMetadata> for proto::Session {
+    fn from(metadata: SessionMetadata) -> Self {
+        Self {
+            id: metadata.id.to_string(),
+            role: metadata.role.to_string(),
+            status: metadata.status.to_string(),
+            task: metadata.task,
+            parent_id: metadata.parent_id.map(|id| id.to_string()),
+            created_at: metadata.created_at.to_rfc3339(),
+            started_at: metadata.started_at.map(|at| at.to_rfc3339()),
+            ended_at: metadata.ended_at.map(|at| at.to_rfc3339()),
+            pid: metadata.pid,
+            log_dir: metadata.log_dir.display().to_string(),
+            annotations: metadata.annotations,
+            depends_on: metadata.depends_on.iter().map(|id| id.to_string()).collect(),
+            status_reason: metadata.status_reason,
+            retries: metadata.retries,
+        }
+    }
+}
+
+impl From<logger::IoEvent> for proto::IoEvent {
+    fn from(event: logger::IoEvent) -> Self {
+        // The event type serializes as a bare lowercase string
+        let event_type = serde_json::to_value(event.event_type)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+        Self {
+            timestamp: event.timestamp.to_rfc3339(),
+            event_type,
+            content: event.content,
+            metadata_json: event.metadata.map(|metadata| metadata.to_string()),
+        }
+    }
+}
+
+impl From<session::LifecycleEvent> for proto::LifecycleEvent {
+    fn from(event: session::LifecycleEvent) -> Self {
+        Self {
+            session_id: event.session_id.to_string(),
+            role: event.role.to_string(),
+            old_status: event.old_status.to_string(),
+            new_status: event.new_status.to_string(),
+            at: event.at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<proto::SpawnRequest> for DaemonRequest {
+    fn from(spawn: proto::SpawnRequest) -> Self {
+        let settings = SpawnSettings {
+            model: spawn.model,
+            env: spawn.env.into_iter().collect(),
+            working_dir: spawn.working_dir.map(Into::into),
+            claude_binary: spawn.claude_binary,
+        };
+        DaemonRequest::Spawn {
+            role: spawn.role,
+            task: spawn.task,
+            annotations: spawn.annotations,
+            capture_result: None,
+            encoding: spawn.encoding,
+            launch: Box::new(SpawnLaunch {
+                parent_id: spawn.parent_id,
+                settings,
+                expect_json_result: spawn.expect_json_result,
+                on_complete: spawn.on_complete,
+                timeout_secs: spawn.timeout_secs,
+                retry_on_failure: spawn.retry_on_failure,
+                session_id: spawn.session_id,
+                ..SpawnLaunch::default()
+            }),
+        }
+    }
+}
+
+/// Map a daemon error message to a gRPC status
+fn error_status(message: String) -> Status {
+    if message.contains("not found") {
+        Status::not_found(message)
+    } else {
+        Status::invalid_argument(message)
+    }
+}
+
+/// The `ClaudeMan` service, backed by the daemon's registry
+struct GrpcService {
+    registry: Arc<SessionRegistry>,
+    shutdown: Arc<RwLock<bool>>,
+}
+
+impl GrpcService {
+    /// Handle a request the way IPC clients' requests are handled
+    async fn handle(&self, request: DaemonRequest) -> std::result::Result<DaemonResponse, Status> {
+        match DaemonServer::handle_request(request, self.registry.clone(), self.shutdown.clone()).await {
+            DaemonResponse::Error { message } => Err(error_status(message)),
+            response => Ok(response),
+        }
+    }
+}
+
+type EventStream<T> = Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl ClaudeMan for GrpcService {
+    async fn spawn(
+        &self,
+        request: Request<proto::SpawnRequest>,
+    ) -> std::result::Result<Response<proto::SpawnResponse>, Status> {
+        match self.handle(request.into_inner().into()).await? {
+            DaemonResponse::Ok {
+                session_id: Some(session_id),
+                pid,
+                ..
+            } => Ok(Response::new(proto::SpawnResponse {
+                session_id: session_id.to_string(),
+                pid: pid.unwrap_or(0),
+            })),
+            other => Err(Status::internal(format!("Unexpected response: {:?}", other))),
+        }
+    }
+
+    async fn list(
+        &self,
+        _request: Request<proto::ListRequest>,
+    ) -> std::result::Result<Response<proto::ListResponse>, Status> {
+        match self.handle(DaemonRequest::List).await? {
+            DaemonResponse::Ok {
+                sessions: Some(sessions),
+                ..
+            } => Ok(Response::new(proto::ListResponse {
+                sessions: sessions.into_iter().map(Into::into).collect(),
+            })),
+            other => Err(Status::internal(format!("Unexpected response: {:?}", other))),
+        }
+    }
+
+    async fn info(
+        &self,
+        request: Request<proto::InfoRequest>,
+    ) -> std::result::Result<Response<proto::Session>, Status> {
+        let session_id = request.into_inner().session_id;
+        match self.handle(DaemonRequest::Info { session_id }).await? {
+            DaemonResponse::Ok {
+                session: Some(session),
+                ..
+            } => Ok(Response::new((*session).into())),
+            other => Err(Status::internal(format!("Unexpected response: {:?}", other))),
+        }
+    }
+
+    async fn stop(
+        &self,
+        request: Request<proto::StopRequest>,
+    ) -> std::result::Result<Response<proto::StopResponse>, Status> {
+        let proto::StopRequest { session_id, cascade } = request.into_inner();
+        match self.handle(DaemonRequest::Stop { session_id, cascade }).await? {
+            DaemonResponse::Ok { message, .. } => Ok(Response::new(proto::StopResponse {
+                message: message.unwrap_or_default(),
+            })),
+            other => Err(Status::internal(format!("Unexpected response: {:?}", other))),
+        }
+    }
+
+    type AttachStream = EventStream<proto::IoEvent>;
+
+    async fn attach(
+        &self,
+        request: Request<proto::AttachRequest>,
+    ) -> std::result::Result<Response<Self::AttachStream>, Status> {
+        let session_id = SessionId::parse(&request.into_inner().session_id)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let log_dir = self
+            .registry
+            .get_session(&session_id)
+            .await
+            .map(|metadata| metadata.log_dir)
+            .ok_or_else(|| Status::not_found(format!("Session not found: {}", session_id)))?;
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(follow_log(self.registry.clone(), session_id, log_dir, tx));
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    type EventsStream = EventStream<proto::LifecycleEvent>;
+
+    async fn events(
+        &self,
+        _request: Request<proto::EventsRequest>,
+    ) -> std::result::Result<Response<Self::EventsStream>, Status> {
+        // A subscriber that falls too far behind skips the events it missed
+        let events = BroadcastStream::new(self.registry.subscribe_lifecycle())
+            .filter_map(|event| event.ok().map(|event| Ok(event.into())));
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// Send a session's logged events to `tx`, following the log until the
+/// session has ended or the client goes away
+async fn follow_log(
+    registry: Arc<SessionRegistry>,
+    session_id: SessionId,
+    log_dir: std::path::PathBuf,
+    tx: mpsc::Sender<std::result::Result<proto::IoEvent, Status>>,
+) {
+    let mut tail: Option<logger::LogTail> = None;
+
+    loop {
+        // Check before reading so the final events are sent after the session ends
+        let finished = registry
+            .get_session(&session_id)
+            .await
+            .is_none_or(|metadata| metadata.is_terminal());
+
+        // The first read covers the rotated segments too
+        let lines = match &mut tail {
+            Some(tail) => tail.read_lines(),
+            None => logger::LogTail::open(&log_dir).map(|(opened, history)| {
+                tail = Some(opened);
+                history
+            }),
+        };
+        let lines = match lines {
+            Ok(lines) => lines,
+            Err(e) => {
+                let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                return;
+            }
+        };
+
+        for line in lines {
+            let Ok(event) = serde_json::from_str::<logger::IoEvent>(line.trim()) else {
+                continue;
+            };
+            if tx.send(Ok(event.into())).await.is_err() {
+                debug!("gRPC attach client for {} went away", session_id);
+                return;
+            }
+        }
+
+        if finished || tx.is_closed() {
+            return;
+        }
+        tokio::time::sleep(ATTACH_POLL_INTERVAL).await;
+    }
+}
+
+/// Serve the gRPC interface on `listener` until the task is aborted
+///
+/// With a `token`, calls must send `authorization: Bearer <token>` metadata
+/// or fail with `unauthenticated`.
+pub async fn serve(
+    listener: TcpListener,
+    registry: Arc<SessionRegistry>,
+    shutdown: Arc<RwLock<bool>>,
+    token: Option<Arc<str>>,
+) -> Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        info!("gRPC interface listening on {}", addr);
+    }
+
+    let service = GrpcService { registry, shutdown };
+    let check_token = move |request: Request<()>| {
+        let authorization = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+        if bearer_authorized(token.as_deref(), authorization) {
+            Ok(request)
+        } else {
+            Err(Status::unauthenticated("unauthorized"))
+        }
+    };
+    tonic::transport::Server::builder()
+        .add_service(ClaudeManServer::with_interceptor(service, check_token))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+        .map_err(|e| ClaudeManError::Other(format!("gRPC server failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::logger::IoEventType;
+    use crate::types::session::SessionStatus;
+    use proto::claude_man_client::ClaudeManClient;
+
+    #[test]
+    fn test_io_event_conversion() {
+        let event: proto::IoEvent = logger::IoEvent::lifecycle(SessionStatus::Failed, "done".to_string()).into();
+        assert_eq!(event.event_type, "lifecycle");
+        assert_eq!(event.content, "done");
+        assert_eq!(event.metadata_json.as_deref(), Some(r#"{"status":"failed"}"#));
+
+        let event: proto::IoEvent = logger::IoEvent::new(IoEventType::Output, "hi".to_string()).into();
+        assert_eq!(event.event_type, "output");
+        assert!(event.metadata_json.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_serve_list_and_missing_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let registry = Arc::new(SessionRegistry::new());
+        let shutdown = Arc::new(RwLock::new(false));
+        let server = tokio::spawn(serve(listener, registry, shutdown, None));
+
+        let mut client = ClaudeManClient::connect(format!("http://{}", addr)).await.unwrap();
+
+        let list = client.list(proto::ListRequest {}).await.unwrap().into_inner();
+        assert!(list.sessions.is_empty());
+
+        let missing = proto::InfoRequest {
+            session_id: "DEV-999".to_string(),
+        };
+        let status = client.info(missing).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let attach = proto::AttachRequest {
+            session_id: "DEV-999".to_string(),
+        };
+        let status = client.attach(attach).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_serve_requires_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let registry = Arc::new(SessionRegistry::new());
+        let shutdown = Arc::new(RwLock::new(false));
+        let server = tokio::spawn(serve(listener, registry.clone(), shutdown, Some(Arc::from("secret"))));
+
+        let mut client = ClaudeManClient::connect(format!("http://{}", addr)).await.unwrap();
+        let spawn = proto::SpawnRequest {
+            role: "DEVELOPER".to_string(),
+            task: "task".to_string(),
+            ..Default::default()
+        };
+        let status = client.spawn(spawn).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert!(registry.list_sessions().await.is_empty());
+
+        let channel = tonic::transport::Endpoint::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = ClaudeManClient::with_interceptor(channel, |mut request: Request<()>| {
+            request.metadata_mut().insert("authorization", "Bearer secret".parse().unwrap());
+            Ok(request)
+        });
+        assert!(client.list(proto::ListRequest {}).await.is_ok());
+
+        server.abort();
+    }
+}
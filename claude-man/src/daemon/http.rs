@@ -0,0 +1,395 @@
+//! Minimal HTTP REST facade for the daemon
+//!
+//! Exposes the daemon's session operations over plain HTTP/1.1 for tooling
+//! that can't speak the line-based IPC protocol. Each route is translated to
+//! a `DaemonRequest` and handled by the same code path as IPC clients.
+//!
+//! Routes:
+//! - `GET /sessions` - list sessions
+//! - `GET /sessions/{id}` - session metadata
+//! - `POST /sessions` - spawn (`{"role": ..., "task": ...}`, optionally `capture_result`)
+//! - `DELETE /sessions/{id}` - stop
+//! - `GET /sessions/{id}/logs` - all logged I/O events
+//!
+//! When the daemon has a token, every request must carry it as
+//! `Authorization: Bearer <token>` or is answered with 401.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+
+use crate::core::logger::{read_events, IoEvent};
+use crate::core::result::CaptureResult;
+use crate::core::SessionRegistry;
+use crate::daemon::protocol::{DaemonRequest, DaemonResponse, SpawnLaunch};
+use crate::daemon::server::DaemonServer;
+use crate::daemon::token::bearer_authorized;
+use crate::types::error::{ClaudeManError, Result};
+use crate::types::SessionId;
+
+/// Largest request body accepted (spawn requests are small)
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// A parsed HTTP request route
+#[derive(Debug, PartialEq)]
+enum Route {
+    ListSessions,
+    GetSession(String),
+    SpawnSession,
+    StopSession(String),
+    SessionLogs(String),
+}
+
+/// Body of a `POST /sessions` request
+#[derive(Debug, Deserialize)]
+struct SpawnBody {
+    role: String,
+    task: String,
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+    #[serde(default)]
+    capture_result: Option<CaptureResult>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default, flatten)]
+    launch: SpawnLaunch,
+}
+
+/// An HTTP response ready to be written
+struct HttpResponse {
+    status: u16,
+    body: serde_json::Value,
+}
+
+impl HttpResponse {
+    fn ok(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message.into() }),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Match a method and path to a route
+fn route(method: &str, path: &str) -> Option<Route> {
+    let path = path.split('?').next().unwrap_or(path);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["sessions"]) => Some(Route::ListSessions),
+        ("POST", ["sessions"]) => Some(Route::SpawnSession),
+        ("GET", ["sessions", id]) => Some(Route::GetSession(id.to_string())),
+        ("DELETE", ["sessions", id]) => Some(Route::StopSession(id.to_string())),
+        ("GET", ["sessions", id, "logs"]) => Some(Route::SessionLogs(id.to_string())),
+        _ => None,
+    }
+}
+
+/// Serve HTTP requests on `listener` until the daemon shuts down
+///
+/// With a `token`, requests without the matching bearer token are refused.
+pub async fn serve(
+    listener: TcpListener,
+    registry: Arc<SessionRegistry>,
+    shutdown: Arc<RwLock<bool>>,
+    token: Option<Arc<str>>,
+) -> Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        info!("HTTP facade listening on {}", addr);
+    }
+
+    loop {
+        if *shutdown.read().await {
+            break;
+        }
+
+        match listener.accept().await {
+            Ok((stream, _addr)) => {
+                let registry = registry.clone();
+                let shutdown = shutdown.clone();
+                let token = token.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, registry, shutdown, token).await {
+                        error!("Error handling HTTP client: {}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                error!("Error accepting HTTP connection: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read one request from the connection, handle it, and write the response
+async fn handle_connection(
+    stream: TcpStream,
+    registry: Arc<SessionRegistry>,
+    shutdown: Arc<RwLock<bool>>,
+    token: Option<Arc<str>>,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    // Request line: METHOD PATH VERSION
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    // Headers (only Content-Length and Authorization matter here)
+    let mut content_length = 0;
+    let mut authorization = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.trim().eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    debug!("HTTP {} {}", method, path);
+
+    let response = if !bearer_authorized(token.as_deref(), authorization.as_deref()) {
+        warn!("Refusing HTTP {} {} without a valid token", method, path);
+        HttpResponse::error(401, "unauthorized")
+    } else if content_length > MAX_BODY_BYTES {
+        HttpResponse::error(413, "Request body too large")
+    } else {
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).await?;
+
+        match route(&method, &path) {
+            Some(route) => handle_route(route, &body, registry, shutdown).await,
+            None if path.trim_matches('/').starts_with("sessions") => {
+                HttpResponse::error(405, format!("{} not supported on {}", method, path))
+            }
+            None => HttpResponse::error(404, format!("No route for {}", path)),
+        }
+    };
+
+    let body = serde_json::to_string(&response.body)?;
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(body.as_bytes()).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Translate a route to a daemon request and shape the response body
+async fn handle_route(
+    route: Route,
+    body: &[u8],
+    registry: Arc<SessionRegistry>,
+    shutdown: Arc<RwLock<bool>>,
+) -> HttpResponse {
+    let request = match route {
+        Route::ListSessions => DaemonRequest::List,
+        Route::GetSession(session_id) => DaemonRequest::Info { session_id },
+        Route::StopSession(session_id) => DaemonRequest::Stop {
+            session_id,
+            cascade: false,
+        },
+        Route::SpawnSession => match serde_json::from_slice::<SpawnBody>(body) {
+            Ok(spawn) => DaemonRequest::Spawn {
+                role: spawn.role,
+                task: spawn.task,
+                annotations: spawn.annotations,
+                capture_result: spawn.capture_result,
+                encoding: spawn.encoding,
+                launch: Box::new(spawn.launch),
+            },
+            Err(e) => return HttpResponse::error(400, format!("Invalid spawn body: {}", e)),
+        },
+        Route::SessionLogs(session_id) => {
+            let session_id = match SessionId::parse(&session_id) {
+                Ok(id) => id,
+                Err(e) => return HttpResponse::error(400, e.to_string()),
+            };
+            return match read_session_logs(&registry, &session_id).await {
+                Ok(events) => HttpResponse::ok(serde_json::json!(events)),
+                Err(e) => HttpResponse::error(404, e.to_string()),
+            };
+        }
+    };
+
+    let not_found_status = match request {
+        DaemonRequest::Info { .. } | DaemonRequest::Stop { .. } => 404,
+        _ => 400,
+    };
+
+    match DaemonServer::handle_request(request, registry, shutdown).await {
+        DaemonResponse::Ok {
+            sessions: Some(sessions),
+            ..
+        } => HttpResponse::ok(serde_json::json!(sessions)),
+        DaemonResponse::Ok {
+            session: Some(session),
+            ..
+        } => HttpResponse::ok(serde_json::json!(session)),
+        DaemonResponse::Ok {
+            session_id: Some(session_id),
+            pid,
+            ..
+        } => HttpResponse {
+            status: 201,
+            body: serde_json::json!({ "session_id": session_id, "pid": pid }),
+        },
+        DaemonResponse::Ok { message, .. } => {
+            HttpResponse::ok(serde_json::json!({ "message": message }))
+        }
+        DaemonResponse::Error { message } if message.contains("not found") => {
+            HttpResponse::error(not_found_status, message)
+        }
+        DaemonResponse::Error { message } => HttpResponse::error(400, message),
+        other => HttpResponse::error(500, format!("Unexpected response: {:?}", other)),
+    }
+}
+
+/// Read every event from a session's log segments
+async fn read_session_logs(
+    registry: &SessionRegistry,
+    session_id: &SessionId,
+) -> Result<Vec<IoEvent>> {
+    let log_dir = registry
+        .get_session(session_id)
+        .await
+        .map(|metadata| metadata.log_dir)
+        .unwrap_or_else(|| registry.session_log_dir(session_id));
+    let log_path = log_dir.join("io.log");
+
+    if !log_path.exists() {
+        return Err(ClaudeManError::SessionNotFound(session_id.to_string()));
+    }
+
+    read_events(&log_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("GET", "/sessions"), Some(Route::ListSessions));
+        assert_eq!(route("GET", "/sessions/"), Some(Route::ListSessions));
+        assert_eq!(route("POST", "/sessions"), Some(Route::SpawnSession));
+        assert_eq!(
+            route("GET", "/sessions/DEV-001"),
+            Some(Route::GetSession("DEV-001".to_string()))
+        );
+        assert_eq!(
+            route("DELETE", "/sessions/DEV-001"),
+            Some(Route::StopSession("DEV-001".to_string()))
+        );
+        assert_eq!(
+            route("GET", "/sessions/DEV-001/logs?x=1"),
+            Some(Route::SessionLogs("DEV-001".to_string()))
+        );
+        assert_eq!(route("PUT", "/sessions"), None);
+        assert_eq!(route("GET", "/other"), None);
+    }
+
+    #[tokio::test]
+    async fn test_serve_list_and_missing_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let registry = Arc::new(SessionRegistry::new());
+        let shutdown = Arc::new(RwLock::new(false));
+        tokio::spawn(serve(listener, registry, shutdown, None));
+
+        let request = |raw: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = request("GET /sessions HTTP/1.1\r\nHost: x\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("[]"));
+
+        let response = request("GET /sessions/NOPE-001 HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 404"));
+
+        // IDs that could name a path outside the data dir are refused
+        let response = request("GET /sessions/../logs HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+
+        let response = request("POST /sessions HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}").await;
+        assert!(response.starts_with("HTTP/1.1 400"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_requires_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let registry = Arc::new(
+            SessionRegistry::new().with_wrapper(vec!["sh".to_string(), "-c".to_string(), "exec sleep 30".to_string()]),
+        );
+        let shutdown = Arc::new(RwLock::new(false));
+        tokio::spawn(serve(listener, registry.clone(), shutdown, Some(Arc::from("secret"))));
+
+        let request = |raw: String| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let spawn = |authorization: &str| {
+            let body = r#"{"role":"DEVELOPER","task":"task"}"#;
+            format!("POST /sessions HTTP/1.1\r\n{}Content-Length: {}\r\n\r\n{}", authorization, body.len(), body)
+        };
+
+        // Missing or wrong tokens are refused before anything is spawned
+        for authorization in ["", "Authorization: Bearer wrong\r\n", "Authorization: secret\r\n"] {
+            let response = request(spawn(authorization)).await;
+            assert!(response.starts_with("HTTP/1.1 401 Unauthorized"), "{}", response);
+        }
+        let response = request("DELETE /sessions/DEV-001 HTTP/1.1\r\n\r\n".to_string()).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+        assert!(registry.list_sessions().await.is_empty());
+
+        let response = request("GET /sessions HTTP/1.1\r\nauthorization: Bearer secret\r\n\r\n".to_string()).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    }
+}
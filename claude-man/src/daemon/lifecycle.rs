@@ -0,0 +1,346 @@
+//! Daemon process lifecycle
+//!
+//! While it runs, the daemon records its PID in `.claude-man/daemon.pid` so
+//! `daemon --status` and `shutdown` can find it even when it stops answering
+//! on its port. `daemon --background` starts it as a detached process.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::core::config::default_home_dir;
+use crate::core::process::is_process_alive;
+use crate::daemon::client::DaemonClient;
+use crate::daemon::protocol::DaemonResponse;
+use crate::types::error::{ClaudeManError, Result};
+
+/// How long `daemon --background` waits for the daemon to answer
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the daemon gets to answer a status ping or shutdown request
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `daemon --background` checks whether the daemon is up
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Get the default PID file path
+pub fn default_pid_path() -> PathBuf {
+    default_home_dir().join("daemon.pid")
+}
+
+/// Get the log file a background daemon writes its console output to
+pub fn default_daemon_log_path() -> PathBuf {
+    default_home_dir().join("daemon.log")
+}
+
+/// PID file owned by the running daemon, removed when dropped
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Record the current process in `path`
+    ///
+    /// Fails if the file names another process that is still alive; a
+    /// stale file left by a crashed daemon is overwritten.
+    pub fn create(path: PathBuf) -> Result<Self> {
+        if let Some(pid) = read_pid(&path)? {
+            if pid != std::process::id() && is_process_alive(pid) {
+                return Err(ClaudeManError::Other(format!(
+                    "Daemon already running (PID {}, see {})",
+                    pid,
+                    path.display()
+                )));
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, format!("{}\n", std::process::id()))?;
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another daemon has taken it over
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Read the PID recorded in `path` (`None` if there is no PID file)
+pub fn read_pid(path: &Path) -> Result<Option<u32>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => contents.trim().parse().map(Some).map_err(|_| {
+            ClaudeManError::Other(format!(
+                "Invalid PID file {}: {:?}",
+                path.display(),
+                contents.trim()
+            ))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Ask a process to shut down (SIGTERM on Unix, taskkill on Windows)
+pub fn terminate(pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{kill, Signal};
+        use nix::unistd::Pid;
+
+        kill(Pid::from_raw(pid as i32), Signal::SIGTERM).map_err(|e| {
+            ClaudeManError::Process(format!("Failed to send SIGTERM to {}: {}", pid, e))
+        })
+    }
+
+    #[cfg(windows)]
+    {
+        let status = Command::new("taskkill")
+            .args(["/PID", &pid.to_string()])
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(ClaudeManError::Process(format!("taskkill failed for {}", pid)))
+        }
+    }
+}
+
+/// What the PID file and the daemon's port say about the daemon
+///
+/// Serialized for `--output json` as e.g. `{"state": "running", "pid": 42}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DaemonStatus {
+    /// Answering pings (the PID is unknown without a PID file)
+    Running { pid: Option<u32> },
+
+    /// Process alive but not answering on its port
+    Unresponsive { pid: u32 },
+
+    /// PID file left behind by a daemon that is gone
+    Stale { pid: u32 },
+
+    /// No daemon
+    Stopped,
+}
+
+impl DaemonStatus {
+    /// Combine the recorded PID with whether the daemon answered a ping
+    pub fn probe(pid: Option<u32>, responding: bool) -> Self {
+        match (pid, responding) {
+            (pid, true) => DaemonStatus::Running { pid },
+            (Some(pid), false) if is_process_alive(pid) => DaemonStatus::Unresponsive { pid },
+            (Some(pid), false) => DaemonStatus::Stale { pid },
+            (None, false) => DaemonStatus::Stopped,
+        }
+    }
+
+    /// Check whether the daemon is up and serving requests
+    pub fn is_running(&self) -> bool {
+        matches!(self, DaemonStatus::Running { .. })
+    }
+}
+
+impl std::fmt::Display for DaemonStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DaemonStatus::Running { pid: Some(pid) } => write!(f, "Daemon running (PID {})", pid),
+            DaemonStatus::Running { pid: None } => write!(f, "Daemon running (no PID file)"),
+            DaemonStatus::Unresponsive { pid } => {
+                write!(f, "Daemon process {} is alive but not responding", pid)
+            }
+            DaemonStatus::Stale { pid } => {
+                write!(f, "Daemon not running (stale PID file for {})", pid)
+            }
+            DaemonStatus::Stopped => write!(f, "Daemon not running"),
+        }
+    }
+}
+
+/// Check the PID file and ping the daemon
+pub async fn status(client: &DaemonClient, pid_path: &Path) -> Result<DaemonStatus> {
+    let pid = read_pid(pid_path)?;
+    Ok(DaemonStatus::probe(pid, responding(client).await))
+}
+
+/// Ping the daemon, treating no answer in time as not running
+async fn responding(client: &DaemonClient) -> bool {
+    tokio::time::timeout(RESPONSE_TIMEOUT, client.is_running())
+        .await
+        .unwrap_or(false)
+}
+
+/// Shut the daemon down, falling back to SIGTERM via the PID file
+///
+/// If the daemon doesn't accept the shutdown request in time, the process
+/// named in the PID file is terminated instead. Returns what was done.
+pub async fn shutdown(client: &DaemonClient, pid_path: &Path) -> Result<String> {
+    let error = match tokio::time::timeout(RESPONSE_TIMEOUT, client.shutdown()).await {
+        Ok(Ok(DaemonResponse::Error { message })) => message,
+        Ok(Ok(_)) => return Ok("Daemon shut down successfully".to_string()),
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => format!("no response within {}s", RESPONSE_TIMEOUT.as_secs()),
+    };
+
+    match read_pid(pid_path)? {
+        Some(pid) if is_process_alive(pid) => {
+            terminate(pid)?;
+            Ok(format!("Daemon not responding ({}); sent SIGTERM to PID {}", error, pid))
+        }
+        _ => Err(ClaudeManError::Other(format!("Error shutting down daemon: {}", error))),
+    }
+}
+
+/// Re-run the current command as a detached daemon and wait until it answers
+///
+/// `args` are the daemon's arguments with `--background` removed. A new
+/// process is started rather than forking, since the async runtime already
+/// runs threads. Its console output goes to `log_path`. Returns the daemon's PID.
+pub async fn spawn_background(
+    args: Vec<std::ffi::OsString>,
+    client: &DaemonClient,
+    log_path: &Path,
+) -> Result<u32> {
+    if responding(client).await {
+        return Err(ClaudeManError::Other("Daemon already running".to_string()));
+    }
+
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let log = fs::OpenOptions::new().create(true).append(true).open(log_path)?;
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    detach(&mut command);
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    debug!("Started background daemon with PID {}", pid);
+
+    let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if responding(client).await {
+            return Ok(pid);
+        }
+        if let Some(status) = child.try_wait()? {
+            return Err(ClaudeManError::Other(format!(
+                "Daemon exited during startup ({}); see {}",
+                status,
+                log_path.display()
+            )));
+        }
+        tokio::time::sleep(STARTUP_POLL_INTERVAL).await;
+    }
+
+    Err(ClaudeManError::Other(format!(
+        "Daemon (PID {}) did not answer within {}s; see {}",
+        pid,
+        STARTUP_TIMEOUT.as_secs(),
+        log_path.display()
+    )))
+}
+
+/// Detach a command from the terminal that started it
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: setsid is async-signal-safe and touches no memory of ours
+    unsafe {
+        command.pre_exec(|| {
+            nix::unistd::setsid()
+                .map(|_| ())
+                .map_err(std::io::Error::from)
+        });
+    }
+}
+
+/// Detach a command from the console that started it
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+
+        // A stale file from a dead daemon is taken over
+        let mut child = Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        fs::write(&path, format!("{}\n", dead_pid)).unwrap();
+
+        let pid_file = PidFile::create(path.clone()).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id()));
+        drop(pid_file);
+        assert_eq!(read_pid(&path).unwrap(), None);
+
+        // A live daemon's file is not
+        let mut other = Command::new("sleep").arg("5").spawn().unwrap();
+        fs::write(&path, other.id().to_string()).unwrap();
+        assert!(PidFile::create(path.clone()).is_err());
+        other.kill().unwrap();
+        other.wait().unwrap();
+
+        fs::write(&path, "garbage").unwrap();
+        assert!(read_pid(&path).is_err());
+    }
+
+    #[test]
+    fn test_daemon_status_probe() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        let live_pid = std::process::id();
+
+        assert_eq!(DaemonStatus::probe(Some(live_pid), true), DaemonStatus::Running { pid: Some(live_pid) });
+        assert_eq!(DaemonStatus::probe(Some(live_pid), false), DaemonStatus::Unresponsive { pid: live_pid });
+        assert_eq!(DaemonStatus::probe(Some(dead_pid), false), DaemonStatus::Stale { pid: dead_pid });
+        assert_eq!(DaemonStatus::probe(None, false), DaemonStatus::Stopped);
+        assert!(!DaemonStatus::Stopped.is_running());
+    }
+
+    #[test]
+    fn test_daemon_status_json() {
+        let json = serde_json::to_value(DaemonStatus::Stale { pid: 42 }).unwrap();
+        assert_eq!(json, serde_json::json!({ "state": "stale", "pid": 42 }));
+        assert_eq!(serde_json::to_value(DaemonStatus::Stopped).unwrap(), serde_json::json!({ "state": "stopped" }));
+
+        for status in [
+            DaemonStatus::Running { pid: Some(7) },
+            DaemonStatus::Running { pid: None },
+            DaemonStatus::Unresponsive { pid: 7 },
+            DaemonStatus::Stale { pid: 7 },
+            DaemonStatus::Stopped,
+        ] {
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(serde_json::from_str::<DaemonStatus>(&json).unwrap(), status);
+        }
+    }
+}
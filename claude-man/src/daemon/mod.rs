@@ -3,10 +3,19 @@
 //! The daemon runs as a long-lived background process that manages
 //! all Claude sessions. CLI commands communicate with the daemon via IPC.
 
+pub mod audit;
 pub mod client;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod http;
+pub mod lifecycle;
 pub mod protocol;
 pub mod server;
+pub mod token;
+pub mod transport;
 
 pub use client::DaemonClient;
-pub use protocol::{DaemonRequest, DaemonResponse};
+pub use lifecycle::DaemonStatus;
+pub use protocol::{DaemonRequest, DaemonResponse, SpawnLaunch};
 pub use server::DaemonServer;
+pub use transport::DaemonTransport;
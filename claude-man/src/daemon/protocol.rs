@@ -1,22 +1,74 @@
 //! IPC protocol definitions for daemon communication
 
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::types::session::{SessionId, SessionMetadata};
+use std::collections::HashMap;
+use crate::core::logger::{IoEvent, IoEventType};
+use crate::core::process::InputNewline;
+use crate::core::result::CaptureResult;
+use crate::types::session::{Approval, SessionId, SessionMetadata, SessionStatus, SpawnSettings};
+
+/// Version of the daemon IPC protocol
+///
+/// Bump this when requests or responses change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// JSON Schema (draft 7) of the wire format
+///
+/// Each line on the socket is an [`AuthenticatedRequest`] from the client or
+/// a [`DaemonResponse`] from the daemon; both are listed under `anyOf`.
+pub fn protocol_schema() -> serde_json::Value {
+    let mut gen = SchemaSettings::draft07().into_generator();
+    let request = gen.subschema_for::<AuthenticatedRequest>();
+    let response = gen.subschema_for::<DaemonResponse>();
+
+    serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "claude-man daemon protocol",
+        "description": format!(
+            "Protocol version {}. Each line is a JSON request (tagged by `command`) or response (tagged by `status`).",
+            PROTOCOL_VERSION
+        ),
+        "anyOf": [request, response],
+        "definitions": gen.definitions(),
+    })
+}
 
 /// Request from CLI client to daemon
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "command", rename_all = "lowercase")]
 pub enum DaemonRequest {
     /// Spawn a new session
     Spawn {
         role: String,
         task: String,
+
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        annotations: HashMap<String, String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        capture_result: Option<CaptureResult>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<String>,
+
+        #[serde(default, flatten)]
+        launch: Box<SpawnLaunch>,
     },
 
     /// Resume an existing session with additional input
     Resume {
         session_id: String,
         message: String,
+        /// Prepend a recap of this many recent input/output events (0 for none)
+        #[serde(default)]
+        replay_context: usize,
+    },
+
+    /// Re-run a finished session with its original role and task
+    Restart {
+        session_id: String,
     },
 
     /// List all active sessions
@@ -30,11 +82,67 @@ pub enum DaemonRequest {
     /// Stop a session
     Stop {
         session_id: String,
+
+        /// Cancel pending dependents instead of failing them
+        #[serde(default)]
+        cascade: bool,
     },
 
     /// Stop all sessions
     StopAll,
 
+    /// Suspend a running session's process
+    Pause { session_id: String },
+
+    /// Continue a paused session's process
+    Continue { session_id: String },
+
+    /// Stream the live output of sessions (all of them when empty)
+    ///
+    /// Answered with `output` events and a `session_ended` event per session
+    /// instead of a single response; the daemon closes the connection after
+    /// the last session ends.
+    Tail {
+        #[serde(default)]
+        session_ids: Vec<String>,
+    },
+
+    /// Write a value to the shared key/value store
+    KvSet {
+        key: String,
+        value: String,
+        /// Scope the key to this session's tree instead of the global scope
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tree: Option<String>,
+    },
+
+    /// Read a value from the shared key/value store
+    KvGet {
+        key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tree: Option<String>,
+    },
+
+    /// Mark running sessions whose process is gone as failed
+    HealthCheck,
+
+    /// Record an approval decision on a session
+    RecordApproval {
+        session_id: String,
+        approval: Approval,
+    },
+
+    /// Fetch the end of a session's io.log
+    GetLog {
+        session_id: String,
+        /// Number of events to return (0 for all)
+        #[serde(default)]
+        lines: usize,
+        /// Only return events of these types (all types if empty)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        event_types: Vec<IoEventType>,
+    },
+
     /// Attach to session output stream
     Attach {
         session_id: String,
@@ -44,6 +152,10 @@ pub enum DaemonRequest {
     Input {
         session_id: String,
         text: String,
+        #[serde(default)]
+        newline: InputNewline,
+        #[serde(default)]
+        paste: bool,
     },
 
     /// Shutdown the daemon
@@ -53,8 +165,86 @@ pub enum DaemonRequest {
     Ping,
 }
 
+/// A request as sent over the daemon socket, with the client's token
+///
+/// On the wire this is the request object with an extra `token` field.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AuthenticatedRequest {
+    /// Token read from the daemon's token file (`None` if there was none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    #[serde(flatten)]
+    pub request: DaemonRequest,
+}
+
+/// Identity, parentage, launch settings and result handling of a spawn request
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SpawnLaunch {
+    /// Spawn as a child of this session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+
+    /// Model, env, working dir and claude binary for the session
+    #[serde(default, skip_serializing_if = "SpawnSettings::is_empty")]
+    pub settings: SpawnSettings,
+
+    /// Don't fill unset settings from the parent's
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_parent_settings: bool,
+
+    /// Parse the last JSON object or array in the output into the session's result
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expect_json_result: bool,
+
+    /// Shell command to run when the session's process exits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<String>,
+
+    /// Terminate the process and fail the session after this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    /// Restart the session up to this many times if it fails
+    #[serde(default, skip_serializing_if = "crate::types::session::is_zero")]
+    pub retry_on_failure: u32,
+
+    /// Spawn under this session ID instead of the next free one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Replace a finished session that already has `session_id`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub replace: bool,
+
+    /// With `replace`, stop the existing session first if it is still running
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub force_replace: bool,
+
+    /// Respond only once an output line matches this regex, or the session ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_for_ready: Option<String>,
+
+    /// Start the task with a transcript of this session's conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_from: Option<String>,
+
+    /// Cap on the `context_from` transcript in bytes (default 16 KiB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_bytes: Option<usize>,
+
+    /// Return the unfinished session spawned with this key instead of spawning again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+
+    /// After the spawn response, stream the session's output like `tail`
+    /// until it ends
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub attach: bool,
+}
+
 /// Response from daemon to CLI client
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "status", rename_all = "lowercase")]
 pub enum DaemonResponse {
     /// Success response
@@ -72,7 +262,12 @@ pub enum DaemonResponse {
         sessions: Option<Vec<SessionMetadata>>,
 
         #[serde(skip_serializing_if = "Option::is_none")]
-        session: Option<SessionMetadata>,
+        session: Option<Box<SessionMetadata>>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        events: Option<Vec<IoEvent>>,
     },
 
     /// Error response
@@ -80,17 +275,17 @@ pub enum DaemonResponse {
         message: String,
     },
 
-    /// Output event (for attach command)
+    /// Output event (for tail command)
     Output {
         session_id: SessionId,
         content: String,
         event_type: String,
     },
 
-    /// Session ended (for attach command)
+    /// Session ended (for tail command)
     SessionEnded {
         session_id: SessionId,
-        exit_code: i32,
+        session_status: SessionStatus,
     },
 }
 
@@ -103,6 +298,8 @@ impl DaemonResponse {
             pid: None,
             sessions: None,
             session: None,
+            value: None,
+            events: None,
         }
     }
 
@@ -114,6 +311,8 @@ impl DaemonResponse {
             pid: None,
             sessions: None,
             session: None,
+            value: None,
+            events: None,
         }
     }
 
@@ -125,6 +324,37 @@ impl DaemonResponse {
             pid: Some(pid),
             sessions: None,
             session: None,
+            value: None,
+            events: None,
+        }
+    }
+
+    /// Create a success response for a spawn whose readiness marker matched `line`
+    pub fn ready(session_id: SessionId, pid: u32, line: String) -> Self {
+        Self::Ok {
+            message: Some(line),
+            session_id: Some(session_id),
+            pid: Some(pid),
+            sessions: None,
+            session: None,
+            value: None,
+            events: None,
+        }
+    }
+
+    /// Create a success response for a spawn whose session ended before it was ready
+    ///
+    /// `session` holds the ended session's metadata, which tells it apart
+    /// from [`DaemonResponse::ready`].
+    pub fn exited_before_ready(session: SessionMetadata) -> Self {
+        Self::Ok {
+            message: None,
+            session_id: Some(session.id.clone()),
+            pid: session.pid,
+            sessions: None,
+            session: Some(Box::new(session)),
+            value: None,
+            events: None,
         }
     }
 
@@ -136,6 +366,8 @@ impl DaemonResponse {
             pid: None,
             sessions: Some(sessions),
             session: None,
+            value: None,
+            events: None,
         }
     }
 
@@ -146,7 +378,35 @@ impl DaemonResponse {
             session_id: None,
             pid: None,
             sessions: None,
-            session: Some(session),
+            session: Some(Box::new(session)),
+            value: None,
+            events: None,
+        }
+    }
+
+    /// Create a success response for a key/value lookup
+    pub fn kv_value(value: Option<String>) -> Self {
+        Self::Ok {
+            message: None,
+            session_id: None,
+            pid: None,
+            sessions: None,
+            session: None,
+            value,
+            events: None,
+        }
+    }
+
+    /// Create a success response carrying log events
+    pub fn log_events(events: Vec<IoEvent>) -> Self {
+        Self::Ok {
+            message: None,
+            session_id: None,
+            pid: None,
+            sessions: None,
+            session: None,
+            value: None,
+            events: Some(events),
         }
     }
 
@@ -165,10 +425,39 @@ impl DaemonResponse {
     }
 
     /// Create a session ended event
-    pub fn session_ended(session_id: SessionId, exit_code: i32) -> Self {
-        Self::SessionEnded {
-            session_id,
-            exit_code,
+    pub fn session_ended(session_id: SessionId, session_status: SessionStatus) -> Self {
+        Self::SessionEnded { session_id, session_status }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_schema() {
+        let schema = protocol_schema();
+        let definitions = &schema["definitions"];
+
+        // Every request variant is tagged by `command`, every response by `status`
+        for (name, tag) in [("AuthenticatedRequest", "command"), ("DaemonResponse", "status")] {
+            let variants = definitions[name]["oneOf"].as_array().unwrap_or_else(|| panic!("{} has no variants", name));
+            assert!(!variants.is_empty());
+            for variant in variants {
+                assert!(variant["properties"][tag]["enum"].is_array(), "{} variant without {}: {}", name, tag, variant);
+                assert!(variant["required"].as_array().unwrap().contains(&serde_json::json!(tag)));
+            }
         }
+        let commands: Vec<&str> = definitions["AuthenticatedRequest"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| variant["properties"]["command"]["enum"][0].as_str().unwrap())
+            .collect();
+        assert!(commands.contains(&"spawn") && commands.contains(&"ping"));
+
+        // The token and the flattened launch settings are part of the request
+        assert!(definitions["AuthenticatedRequest"]["properties"]["token"].is_object());
+        assert!(schema.to_string().contains("\"wait_for_ready\""));
     }
 }
@@ -2,50 +2,340 @@
 //!
 //! Runs as a long-lived background process managing all Claude sessions.
 
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use std::time::SystemTime;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{debug, error, info, warn};
 
-use crate::core::SessionRegistry;
-use crate::daemon::protocol::{DaemonRequest, DaemonResponse};
+use crate::core::filter::ReadyMarker;
+use crate::core::logger::{read_events, LogSelection, DEFAULT_CONTEXT_BYTES};
+use crate::core::process::{resolve_encoding, ConsoleEcho};
+use crate::core::session::{ReadyOutcome, TailEvent};
+use crate::core::{Config, SessionRegistry, SpawnOptions};
+use crate::daemon::token::TokenFile;
+use crate::daemon::http;
+use crate::daemon::lifecycle::PidFile;
+use crate::daemon::protocol::{AuthenticatedRequest, DaemonRequest, DaemonResponse};
+use crate::daemon::audit::{default_audit_path, AuditEntry, AuditLog};
+use crate::daemon::transport::{ClientListener, Connection, DaemonTransport};
 use crate::types::error::{ClaudeManError, Result};
 use crate::types::{Role, SessionId};
 
 /// Default daemon port
 pub const DEFAULT_DAEMON_PORT: u16 = 47520;
 
+/// How often the config file is checked for changes when watching
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the accept loop re-checks the shutdown flag while idle
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Consecutive accept errors after which the listener is bound again
+const MAX_ACCEPT_FAILURES: u32 = 5;
+
+/// Rebinds without an accepted connection in between before the daemon gives up
+const MAX_REBINDS: u32 = 3;
+
+/// Wait after the first accept error, doubling with each further one
+const ACCEPT_BACKOFF_START: Duration = Duration::from_millis(10);
+
+/// Longest wait between accept attempts
+const ACCEPT_BACKOFF_MAX: Duration = Duration::from_millis(500);
+
+/// What the accept loop does after an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AcceptRecovery {
+    /// Wait this long, then accept again
+    Retry(Duration),
+
+    /// Bind the listener again
+    Rebind,
+
+    /// Stop the daemon: rebinding didn't help
+    GiveUp,
+}
+
+/// Tracks consecutive accept errors so a broken listener doesn't spin
+#[derive(Debug, Default)]
+struct AcceptBackoff {
+    failures: u32,
+    rebinds: u32,
+}
+
+impl AcceptBackoff {
+    /// A connection was accepted
+    fn succeeded(&mut self) {
+        self.failures = 0;
+        self.rebinds = 0;
+    }
+
+    /// An accept failed; decide how to recover
+    fn failed(&mut self) -> AcceptRecovery {
+        self.failures += 1;
+        if self.failures < MAX_ACCEPT_FAILURES {
+            let delay = ACCEPT_BACKOFF_START.saturating_mul(1 << (self.failures - 1).min(16));
+            return AcceptRecovery::Retry(delay.min(ACCEPT_BACKOFF_MAX));
+        }
+
+        self.failures = 0;
+        if self.rebinds >= MAX_REBINDS {
+            return AcceptRecovery::GiveUp;
+        }
+        self.rebinds += 1;
+        AcceptRecovery::Rebind
+    }
+}
+
+/// How often the daemon reconciles running sessions with their processes
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Resolve when the daemon is asked to terminate (SIGTERM, or Ctrl+C on Windows)
+async fn termination_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!("Cannot listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Describe the outcome of stopping a session and its pending dependents
+pub fn stop_message(session_id: &SessionId, dependents: &[SessionId], cascade: bool) -> String {
+    if dependents.is_empty() {
+        return format!("Session {} stopped", session_id);
+    }
+
+    let ids: Vec<_> = dependents.iter().map(|id| id.as_str()).collect();
+    let outcome = if cascade { "cancelled" } else { "failed" };
+    format!(
+        "Session {} stopped; {} dependent(s) {}: {}",
+        session_id,
+        dependents.len(),
+        outcome,
+        ids.join(", ")
+    )
+}
+
+/// Which settings were given on the command line, so config reloads leave them alone
+#[derive(Debug, Clone, Copy, Default)]
+struct PinnedSettings {
+    project: bool,
+    wrapper: bool,
+    termination_timeout: bool,
+    max_line_bytes: bool,
+    max_sessions_per_hour: bool,
+    max_concurrent: bool,
+    compact_metadata: bool,
+}
+
 /// Daemon server managing all sessions
 pub struct DaemonServer {
     /// Session registry
     registry: Arc<SessionRegistry>,
 
-    /// TCP port to listen on
-    port: u16,
+    /// Where to listen for clients
+    transport: DaemonTransport,
 
     /// Shutdown flag
     shutdown: Arc<RwLock<bool>>,
+
+    /// Current configuration, shared with the config watcher
+    config: Arc<RwLock<Config>>,
+
+    /// Project prefix given on the command line (takes precedence over config)
+    project_override: Option<String>,
+
+    /// Wrapper command given on the command line (takes precedence over config)
+    wrapper_override: Option<Vec<String>>,
+
+    /// Termination grace period pinned on the command line
+    termination_timeout_override: Option<Duration>,
+
+    /// Output line limit pinned on the command line
+    max_line_bytes_override: Option<usize>,
+
+    /// Hourly spawn budget pinned on the command line
+    max_sessions_per_hour_override: Option<usize>,
+
+    /// Running session limit pinned on the command line
+    max_concurrent_override: Option<usize>,
+
+    /// Compact metadata.json forced on the command line
+    compact_metadata_override: bool,
+
+    /// Fail startup on unreadable session metadata instead of skipping it
+    strict_load: bool,
+
+    /// Which session streams the daemon echoes to its console
+    console_echo: ConsoleEcho,
+
+    /// Config file to watch for hot-reload, if enabled
+    watch_path: Option<PathBuf>,
+
+    /// Port for the optional HTTP REST facade (disabled when `None`)
+    http_port: Option<u16>,
+
+    /// Port for the gRPC interface (disabled when `None`; needs the `grpc` feature)
+    grpc_port: Option<u16>,
+
+    /// File recording the daemon's PID while it runs, if any
+    pid_path: Option<PathBuf>,
+
+    /// File holding the token clients must send (no authentication when `None`)
+    token_path: Option<PathBuf>,
+}
+
+impl Default for DaemonServer {
+    /// Create a daemon server on the default transport
+    fn default() -> Self {
+        Self::new(DEFAULT_DAEMON_PORT).with_transport(DaemonTransport::default())
+    }
 }
 
 impl DaemonServer {
-    /// Create a new daemon server
+    /// Create a new daemon server listening on a TCP port
     pub fn new(port: u16) -> Self {
         Self {
             registry: Arc::new(SessionRegistry::new()),
-            port,
+            transport: DaemonTransport::Tcp(port),
             shutdown: Arc::new(RwLock::new(false)),
+            config: Arc::new(RwLock::new(Config::default())),
+            project_override: None,
+            wrapper_override: None,
+            termination_timeout_override: None,
+            max_line_bytes_override: None,
+            max_sessions_per_hour_override: None,
+            max_concurrent_override: None,
+            compact_metadata_override: false,
+            strict_load: false,
+            console_echo: ConsoleEcho::default(),
+            watch_path: None,
+            http_port: None,
+            grpc_port: None,
+            pid_path: None,
+            token_path: None,
         }
     }
 
-    /// Create a daemon server with the default port
-    pub fn default() -> Self {
-        Self::new(DEFAULT_DAEMON_PORT)
+    /// Set the initial configuration
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Arc::new(RwLock::new(config));
+        self
+    }
+
+    /// Pin the project prefix, overriding the config file
+    pub fn with_project(mut self, project: Option<String>) -> Self {
+        self.project_override = project;
+        self
+    }
+
+    /// Pin the wrapper command, overriding the config file
+    pub fn with_wrapper(mut self, wrapper: Option<Vec<String>>) -> Self {
+        self.wrapper_override = wrapper;
+        self
+    }
+
+    /// Pin the termination grace period, overriding the config file
+    pub fn with_termination_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.termination_timeout_override = timeout;
+        self
+    }
+
+    /// Pin the output line limit, overriding the config file
+    pub fn with_max_line_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_line_bytes_override = limit;
+        self
+    }
+
+    /// Pin the hourly spawn budget, overriding the config file
+    pub fn with_max_sessions_per_hour(mut self, limit: Option<usize>) -> Self {
+        self.max_sessions_per_hour_override = limit;
+        self
+    }
+
+    /// Pin the running session limit, overriding the config file
+    pub fn with_max_concurrent(mut self, limit: Option<usize>) -> Self {
+        self.max_concurrent_override = limit;
+        self
+    }
+
+    /// Always write compact metadata.json, overriding the config file
+    pub fn with_compact_metadata(mut self, compact: bool) -> Self {
+        self.compact_metadata_override = compact;
+        self
+    }
+
+    /// Refuse to start if a stored session's metadata.json can't be read
+    pub fn with_strict_load(mut self, strict: bool) -> Self {
+        self.strict_load = strict;
+        self
+    }
+
+    /// Choose which session streams are echoed to the daemon's console
+    pub fn with_console_echo(mut self, echo: ConsoleEcho) -> Self {
+        self.console_echo = echo;
+        self
+    }
+
+    /// Watch a config file and hot-reload settings when it changes
+    pub fn with_config_watch(mut self, path: PathBuf) -> Self {
+        self.watch_path = Some(path);
+        self
+    }
+
+    /// Listen for clients on a Unix socket or TCP port
+    pub fn with_transport(mut self, transport: DaemonTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Serve the HTTP REST facade on the given port
+    pub fn with_http_port(mut self, port: u16) -> Self {
+        self.http_port = Some(port);
+        self
+    }
+
+    /// Serve the gRPC interface on the given port
+    ///
+    /// Starting fails unless claude-man was built with the `grpc` feature.
+    pub fn with_grpc_port(mut self, port: u16) -> Self {
+        self.grpc_port = Some(port);
+        self
+    }
+
+    /// Record the daemon's PID in this file while it runs
+    pub fn with_pid_file(mut self, path: PathBuf) -> Self {
+        self.pid_path = Some(path);
+        self
+    }
+
+    /// Require clients to send the token written to this file on startup
+    pub fn with_token_file(mut self, path: PathBuf) -> Self {
+        self.token_path = Some(path);
+        self
     }
 
     /// Get the daemon address
     pub fn address(&self) -> String {
-        format!("127.0.0.1:{}", self.port)
+        self.transport.to_string()
     }
 
     /// Check if daemon should shutdown
@@ -58,79 +348,497 @@ impl DaemonServer {
         let addr = self.address();
         info!("Starting daemon server at {}", addr);
 
+        let project = match &self.project_override {
+            Some(project) => Some(project.clone()),
+            None => self.config.read().await.project.clone(),
+        };
+        self.registry.set_project(project).await;
+        self.config.read().await.register_roles()?;
+        self.registry
+            .set_flush_policy(self.config.read().await.flush_policy())
+            .await;
+        self.registry
+            .set_max_concurrent_per_parent(self.config.read().await.max_concurrent_per_parent)
+            .await;
+        let wrapper = match &self.wrapper_override {
+            Some(wrapper) => wrapper.clone(),
+            None => self.config.read().await.wrapper_command()?,
+        };
+        self.registry.set_wrapper(wrapper).await;
+        let termination_timeout = match self.termination_timeout_override {
+            Some(timeout) => timeout,
+            None => self.config.read().await.termination_timeout(),
+        };
+        self.registry.set_termination_timeout(termination_timeout).await;
+        let max_line_bytes = match self.max_line_bytes_override {
+            Some(limit) => Some(limit),
+            None => self.config.read().await.max_line_bytes,
+        };
+        self.registry.set_max_line_bytes(max_line_bytes).await;
+        let max_log_bytes = self.config.read().await.log_rotation_bytes();
+        self.registry.set_max_log_bytes(max_log_bytes).await;
+        let max_sessions_per_hour = match self.max_sessions_per_hour_override {
+            Some(limit) => Some(limit),
+            None => self.config.read().await.max_sessions_per_hour,
+        };
+        self.registry.set_max_sessions_per_hour(max_sessions_per_hour).await;
+        let max_concurrent = match self.max_concurrent_override {
+            Some(limit) => Some(limit),
+            None => self.config.read().await.max_concurrent_sessions,
+        };
+        self.registry.set_max_concurrent(max_concurrent).await;
+        self.registry
+            .set_compact_metadata(self.compact_metadata_override || self.config.read().await.compact_metadata);
+        self.registry
+            .set_session_dir_template(self.config.read().await.session_dir_template()?)
+            .await;
+        self.registry
+            .set_role_working_dirs(self.config.read().await.role_working_dirs()?)
+            .await;
+        let echo = if self.config.read().await.quiet_daemon { ConsoleEcho::off() } else { self.console_echo };
+        self.registry.set_console_echo(echo).await;
+        if let Some(redactor) = self.config.read().await.redactor()? {
+            self.registry
+                .on_output(move |line| Some(redactor.redact(line)))
+                .await;
+        }
+
+        let watcher = self.watch_path.clone().map(|path| {
+            info!("Watching {:?} for config changes", path);
+            Self::spawn_config_watcher(
+                path,
+                self.config.clone(),
+                self.registry.clone(),
+                PinnedSettings {
+                    project: self.project_override.is_some(),
+                    wrapper: self.wrapper_override.is_some(),
+                    termination_timeout: self.termination_timeout_override.is_some(),
+                    max_line_bytes: self.max_line_bytes_override.is_some(),
+                    max_sessions_per_hour: self.max_sessions_per_hour_override.is_some(),
+                    max_concurrent: self.max_concurrent_override.is_some(),
+                    compact_metadata: self.compact_metadata_override,
+                },
+            )
+        });
+
         // Load existing sessions from disk
+        self.registry.set_strict_load(self.strict_load);
         self.registry.load_from_disk().await?;
 
-        // Bind to TCP port
-        let listener = TcpListener::bind(&addr)
-            .await
-            .map_err(|e| ClaudeManError::Other(format!("Failed to bind to {}: {}", addr, e)))?;
+        let health_checker = Self::spawn_health_checker(self.registry.clone());
+
+        // Bind to the TCP port or Unix socket, removed again when start returns
+        let listener = self.transport.bind().await?;
 
         info!("Daemon listening on {}", addr);
 
-        // Accept connections
+        // Held until start returns, which removes the file
+        let _pid_file = self.pid_path.clone().map(PidFile::create).transpose()?;
+        let token_file = self.token_path.clone().map(TokenFile::create).transpose()?;
+        let token: Option<Arc<str>> = token_file.as_ref().map(|file| file.token().into());
+        let audit = if self.config.read().await.audit_log {
+            let audit = AuditLog::open(default_audit_path())?;
+            info!("Recording client requests in {:?}", audit.path());
+            Some(Arc::new(audit))
+        } else {
+            None
+        };
+
+        let http_server = match self.http_port {
+            Some(port) => {
+                let http_addr = format!("127.0.0.1:{}", port);
+                let http_listener = TcpListener::bind(&http_addr).await.map_err(|e| {
+                    ClaudeManError::Other(format!("Failed to bind HTTP facade to {}: {}", http_addr, e))
+                })?;
+                Some(tokio::spawn(http::serve(
+                    http_listener,
+                    self.registry.clone(),
+                    self.shutdown.clone(),
+                    token.clone(),
+                )))
+            }
+            None => None,
+        };
+
+        let grpc_server = match self.grpc_port {
+            Some(port) => Some(self.spawn_grpc_server(port, token.clone()).await?),
+            None => None,
+        };
+
+        // Accept connections until asked to shut down or terminated
+        let served = self.accept_clients(listener, token, audit).await;
+
+        // Cleanup
+        if let Some(watcher) = watcher {
+            watcher.abort();
+        }
+        if let Some(http_server) = http_server {
+            http_server.abort();
+        }
+        if let Some(grpc_server) = grpc_server {
+            grpc_server.abort();
+        }
+        health_checker.abort();
+
+        info!("Stopping all sessions...");
+        self.registry.stop_all_sessions().await?;
+
+        info!("Daemon stopped");
+        served
+    }
+
+    /// Hand connections to client handlers until shutdown or termination
+    ///
+    /// Repeated accept errors are retried with a growing delay, then the
+    /// listener is bound again. If rebinding doesn't help, or fails, the
+    /// error is returned so the daemon stops instead of spinning.
+    async fn accept_clients<L: ClientListener>(
+        &self,
+        mut listener: L,
+        token: Option<Arc<str>>,
+        audit: Option<Arc<AuditLog>>,
+    ) -> Result<()> {
+        let terminated = termination_signal();
+        tokio::pin!(terminated);
+        let mut backoff = AcceptBackoff::default();
         loop {
             if self.should_shutdown().await {
                 info!("Shutdown signal received, stopping daemon");
-                break;
+                return Ok(());
             }
 
-            match listener.accept().await {
-                Ok((stream, _addr)) => {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = &mut terminated => {
+                    info!("Termination signal received, stopping daemon");
+                    return Ok(());
+                }
+                // Re-check the shutdown flag even when no client connects
+                _ = sleep(SHUTDOWN_POLL_INTERVAL) => continue,
+            };
+            match accepted {
+                Ok((stream, peer)) => {
+                    backoff.succeeded();
                     let registry = self.registry.clone();
                     let shutdown = self.shutdown.clone();
+                    let token = token.clone();
+                    let audit = audit.clone();
 
                     tokio::spawn(async move {
-                        if let Err(e) = Self::handle_client(stream, registry, shutdown).await {
+                        if let Err(e) = Self::handle_client(stream, peer, registry, shutdown, token, audit).await {
                             error!("Error handling client: {}", e);
                         }
                     });
                 }
                 Err(e) => {
                     error!("Error accepting connection: {}", e);
+                    match backoff.failed() {
+                        AcceptRecovery::Retry(delay) => sleep(delay).await,
+                        AcceptRecovery::Rebind => {
+                            warn!("{} accept errors in a row, binding {} again", MAX_ACCEPT_FAILURES, self.address());
+                            listener = listener.rebind().await.map_err(|e| {
+                                ClaudeManError::Other(format!("Daemon stopped: could not bind {} again: {}", self.address(), e))
+                            })?;
+                        }
+                        AcceptRecovery::GiveUp => {
+                            return Err(ClaudeManError::Other(format!(
+                                "Daemon stopped: accepting connections on {} kept failing after {} rebinds (last error: {})",
+                                self.address(),
+                                MAX_REBINDS,
+                                e
+                            )));
+                        }
+                    }
                 }
             }
         }
+    }
 
-        // Cleanup
-        info!("Stopping all sessions...");
-        self.registry.stop_all_sessions().await?;
+    /// Bind the gRPC interface and serve it in the background
+    #[cfg(feature = "grpc")]
+    async fn spawn_grpc_server(&self, port: u16, token: Option<Arc<str>>) -> Result<JoinHandle<Result<()>>> {
+        let grpc_addr = format!("127.0.0.1:{}", port);
+        let grpc_listener = TcpListener::bind(&grpc_addr).await.map_err(|e| {
+            ClaudeManError::Other(format!("Failed to bind gRPC interface to {}: {}", grpc_addr, e))
+        })?;
+        Ok(tokio::spawn(crate::daemon::grpc::serve(
+            grpc_listener,
+            self.registry.clone(),
+            self.shutdown.clone(),
+            token,
+        )))
+    }
 
-        info!("Daemon stopped");
-        Ok(())
+    /// The gRPC interface isn't compiled in without the `grpc` feature
+    #[cfg(not(feature = "grpc"))]
+    async fn spawn_grpc_server(&self, port: u16, _token: Option<Arc<str>>) -> Result<JoinHandle<Result<()>>> {
+        Err(ClaudeManError::InvalidInput(format!(
+            "Cannot serve gRPC on port {}: claude-man was built without the `grpc` feature",
+            port
+        )))
+    }
+
+    /// Spawn a task that periodically reconciles sessions with their processes
+    fn spawn_health_checker(registry: Arc<SessionRegistry>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                sleep(HEALTH_CHECK_INTERVAL).await;
+
+                let reconciled = registry.health_check().await;
+                if !reconciled.is_empty() {
+                    info!("Health check marked {} session(s) failed", reconciled.len());
+                }
+            }
+        })
+    }
+
+    /// Spawn a task that polls the config file and applies changes
+    ///
+    /// Live settings are applied immediately; settings that need a restart
+    /// are logged and keep their current value. Invalid files are ignored.
+    fn spawn_config_watcher(
+        path: PathBuf,
+        config: Arc<RwLock<Config>>,
+        registry: Arc<SessionRegistry>,
+        pinned: PinnedSettings,
+    ) -> JoinHandle<()> {
+        let modified_at = |path: &PathBuf| -> Option<SystemTime> {
+            std::fs::metadata(path).and_then(|m| m.modified()).ok()
+        };
+
+        tokio::spawn(async move {
+            let mut last_modified = modified_at(&path);
+
+            loop {
+                sleep(CONFIG_POLL_INTERVAL).await;
+
+                let modified = modified_at(&path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                let new_config = match Config::load_from_path(&path) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        warn!("Ignoring config change: {}", e);
+                        continue;
+                    }
+                };
+
+                let mut current = config.write().await;
+                let changes = current.diff(&new_config);
+                if changes.is_empty() {
+                    continue;
+                }
+
+                for change in &changes {
+                    if !change.live {
+                        warn!(
+                            "Config {} changed ({} -> {}) but requires a daemon restart",
+                            change.field, change.old, change.new
+                        );
+                    } else if change.field == "project" && pinned.project {
+                        warn!("Config project changed but is pinned by --project; ignoring");
+                    } else if change.field == "wrapper" && pinned.wrapper {
+                        warn!("Config wrapper changed but is pinned by --wrapper; ignoring");
+                    } else if change.field == "termination_timeout_secs" && pinned.termination_timeout {
+                        warn!(
+                            "Config termination_timeout_secs changed but is pinned by --termination-timeout; ignoring"
+                        );
+                    } else if change.field == "max_line_bytes" && pinned.max_line_bytes {
+                        warn!("Config max_line_bytes changed but is pinned by --max-line-bytes; ignoring");
+                    } else if change.field == "max_sessions_per_hour" && pinned.max_sessions_per_hour {
+                        warn!(
+                            "Config max_sessions_per_hour changed but is pinned by --max-sessions-per-hour; ignoring"
+                        );
+                    } else if change.field == "max_concurrent_sessions" && pinned.max_concurrent {
+                        warn!(
+                            "Config max_concurrent_sessions changed but is pinned by --max-sessions; ignoring"
+                        );
+                    } else if change.field == "compact_metadata" && pinned.compact_metadata {
+                        warn!("Config compact_metadata changed but is pinned by --compact-json; ignoring");
+                    } else {
+                        info!("Config {} changed: {} -> {}", change.field, change.old, change.new);
+                    }
+                }
+
+                if !pinned.project {
+                    registry.set_project(new_config.project.clone()).await;
+                }
+                registry.set_flush_policy(new_config.flush_policy()).await;
+                registry
+                    .set_max_concurrent_per_parent(new_config.max_concurrent_per_parent)
+                    .await;
+                if !pinned.wrapper {
+                    registry
+                        .set_wrapper(new_config.wrapper_command().unwrap_or_default())
+                        .await;
+                }
+                if !pinned.termination_timeout {
+                    registry
+                        .set_termination_timeout(new_config.termination_timeout())
+                        .await;
+                }
+                if !pinned.max_line_bytes {
+                    registry.set_max_line_bytes(new_config.max_line_bytes).await;
+                }
+                registry.set_max_log_bytes(new_config.log_rotation_bytes()).await;
+                if !pinned.compact_metadata {
+                    registry.set_compact_metadata(new_config.compact_metadata);
+                }
+                if !pinned.max_sessions_per_hour {
+                    registry
+                        .set_max_sessions_per_hour(new_config.max_sessions_per_hour)
+                        .await;
+                }
+                if !pinned.max_concurrent {
+                    registry.set_max_concurrent(new_config.max_concurrent_sessions).await;
+                }
+                registry
+                    .set_session_dir_template(new_config.session_dir_template().unwrap_or_default())
+                    .await;
+                // A custom role added to the file isn't registered until restart
+                match new_config.role_working_dirs() {
+                    Ok(dirs) => registry.set_role_working_dirs(dirs).await,
+                    Err(e) => warn!("Keeping the previous role_working_dirs: {}", e),
+                }
+
+                *current = new_config;
+            }
+        })
     }
 
-    /// Handle a client connection
-    async fn handle_client(
-        stream: TcpStream,
+    /// Handle a client connection from `peer`
+    ///
+    /// With a `token`, requests that don't carry it are refused. With an
+    /// `audit` log, the request and its outcome are recorded there.
+    pub(crate) async fn handle_client(
+        stream: Box<dyn Connection>,
+        peer: String,
         registry: Arc<SessionRegistry>,
         shutdown: Arc<RwLock<bool>>,
+        token: Option<Arc<str>>,
+        audit: Option<Arc<AuditLog>>,
     ) -> Result<()> {
-        let (reader, mut writer) = stream.into_split();
+        let (reader, mut writer) = tokio::io::split(stream);
         let mut reader = BufReader::new(reader);
         let mut line = String::new();
 
         // Read request
         reader.read_line(&mut line).await?;
-        let request: DaemonRequest = serde_json::from_str(&line.trim())
-            .map_err(|e| ClaudeManError::Other(format!("Invalid request: {}", e)))?;
+        let parsed: std::result::Result<AuthenticatedRequest, _> = serde_json::from_str(line.trim());
+        let AuthenticatedRequest { token: client_token, request } = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let message = format!("Invalid request: {}", e);
+                Self::audit(&audit, AuditEntry::invalid(&peer, message.clone()));
+                return Err(ClaudeManError::Other(message));
+            }
+        };
 
-        debug!("Received request: {:?}", request);
+        debug!("Received request from {}: {:?}", peer, request);
+        let entry = audit.as_ref().map(|_| AuditEntry::received(&peer, &request));
 
         // Handle request
-        let response = Self::handle_request(request, registry, shutdown).await;
+        let authorized = match &token {
+            Some(token) => client_token.as_deref() == Some(&**token),
+            None => true,
+        };
+        let response = match request {
+            _ if !authorized => {
+                warn!("Refusing request without a valid token from {}", peer);
+                DaemonResponse::error("unauthorized".to_string())
+            }
+            // Tail answers with a stream of events rather than one response
+            DaemonRequest::Tail { session_ids } => {
+                let session_ids = match session_ids.iter().map(|id| SessionId::parse(id)).collect() {
+                    Ok(ids) => ids,
+                    Err(e) => {
+                        let response = DaemonResponse::error(e.to_string());
+                        if let Some(entry) = entry {
+                            Self::audit(&audit, entry.with_response(&response, authorized));
+                        }
+                        return Self::write_response(&mut writer, &response).await;
+                    }
+                };
+                let (output, lifecycle) = (registry.subscribe_output(), registry.subscribe_lifecycle());
+                match registry.tail_sessions(session_ids, output, lifecycle).await {
+                    Ok(events) => {
+                        if let Some(entry) = entry {
+                            Self::audit(&audit, entry.with_response(&DaemonResponse::ok(), authorized));
+                        }
+                        return Self::stream_tail(events, &mut writer).await;
+                    }
+                    Err(e) => DaemonResponse::error(format!("Failed to tail sessions: {}", e)),
+                }
+            }
+            // An attached spawn answers as usual, then streams like tail
+            DaemonRequest::Spawn { ref launch, .. } if launch.attach => {
+                // Subscribe before the process starts so its first lines can't be missed
+                let (output, lifecycle) = (registry.subscribe_output(), registry.subscribe_lifecycle());
+                let response = Self::handle_request(request, registry.clone(), shutdown).await;
+                if let Some(entry) = entry {
+                    Self::audit(&audit, entry.with_response(&response, authorized));
+                }
+                Self::write_response(&mut writer, &response).await?;
+                let DaemonResponse::Ok { session_id: Some(session_id), .. } = response else {
+                    return Ok(());
+                };
+                let events = registry.tail_sessions(vec![session_id], output, lifecycle).await?;
+                return Self::stream_tail(events, &mut writer).await;
+            }
+            request => Self::handle_request(request, registry, shutdown).await,
+        };
+        if let Some(entry) = entry {
+            Self::audit(&audit, entry.with_response(&response, authorized));
+        }
 
         // Send response
-        let response_json = serde_json::to_string(&response)?;
+        Self::write_response(&mut writer, &response).await
+    }
+
+    /// Write one response line
+    async fn write_response<W: AsyncWrite + Unpin>(writer: &mut W, response: &DaemonResponse) -> Result<()> {
+        let response_json = serde_json::to_string(response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
+        Ok(())
+    }
 
+    /// Forward tail events to the client until the followed sessions end
+    async fn stream_tail<W: AsyncWrite + Unpin>(
+        mut events: mpsc::UnboundedReceiver<TailEvent>,
+        writer: &mut W,
+    ) -> Result<()> {
+        while let Some(event) = events.recv().await {
+            let response = match event {
+                TailEvent::Line(line) => DaemonResponse::output(line.session_id, line.line, "output".to_string()),
+                TailEvent::Status(session_id, status) => {
+                    DaemonResponse::output(session_id, format!("Session {}", status), "lifecycle".to_string())
+                }
+                TailEvent::Ended(session_id, status) => DaemonResponse::session_ended(session_id, status),
+            };
+            // A failed write means the client went away
+            Self::write_response(writer, &response).await?;
+        }
         Ok(())
     }
 
+    /// Append `entry` to the audit log, if there is one
+    ///
+    /// A failed write is logged rather than failing the request.
+    fn audit(audit: &Option<Arc<AuditLog>>, entry: AuditEntry) {
+        if let Some(audit) = audit {
+            if let Err(e) = audit.record(&entry) {
+                error!("Failed to write audit log {:?}: {}", audit.path(), e);
+            }
+        }
+    }
+
     /// Handle a daemon request
-    async fn handle_request(
+    pub(crate) async fn handle_request(
         request: DaemonRequest,
         registry: Arc<SessionRegistry>,
         shutdown: Arc<RwLock<bool>>,
@@ -140,7 +848,7 @@ impl DaemonServer {
                 DaemonResponse::ok_with_message("pong".to_string())
             }
 
-            DaemonRequest::Spawn { role, task } => {
+            DaemonRequest::Spawn { role, task, annotations, capture_result, encoding, launch } => {
                 // Parse role
                 let role = match role.parse::<Role>() {
                     Ok(r) => r,
@@ -148,7 +856,72 @@ impl DaemonServer {
                 };
 
                 // Spawn session
-                match registry.spawn_session(role, task).await {
+                let mut options = SpawnOptions::new().with_annotations(annotations);
+                if let Some(capture) = capture_result {
+                    options = options.with_capture_result(capture);
+                }
+                if let Some(label) = encoding {
+                    match resolve_encoding(&label) {
+                        Ok(encoding) => options = options.with_encoding(encoding),
+                        Err(e) => return DaemonResponse::error(e.to_string()),
+                    }
+                }
+                options = options.with_settings(launch.settings);
+                if launch.skip_parent_settings {
+                    options = options.without_parent_settings();
+                }
+                if launch.expect_json_result {
+                    options = options.with_json_result();
+                }
+                if let Some(command) = launch.on_complete {
+                    options = options.with_on_complete(command);
+                }
+                if let Some(secs) = launch.timeout_secs {
+                    options = options.with_timeout(Duration::from_secs(secs));
+                }
+                if launch.retry_on_failure > 0 {
+                    options = options.with_retry_on_failure(launch.retry_on_failure);
+                }
+                if let Some(id) = launch.session_id {
+                    match SessionId::parse(&id) {
+                        Ok(id) => options = options.with_session_id(id),
+                        Err(e) => return DaemonResponse::error(e.to_string()),
+                    }
+                }
+                if launch.replace {
+                    options = options.with_replace(launch.force_replace);
+                }
+                if let Some(source) = launch.context_from {
+                    let max_bytes = launch.context_bytes.unwrap_or(DEFAULT_CONTEXT_BYTES);
+                    match SessionId::parse(&source) {
+                        Ok(source) => options = options.with_context_from(source, max_bytes),
+                        Err(e) => return DaemonResponse::error(e.to_string()),
+                    }
+                }
+                if let Some(key) = launch.idempotency_key {
+                    options = options.with_idempotency_key(key);
+                }
+                let ready_marker = match launch.wait_for_ready.as_deref().map(ReadyMarker::new).transpose() {
+                    Ok(marker) => marker,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                // Subscribe before the process starts so the marker can't be missed
+                let subscriptions = ready_marker
+                    .as_ref()
+                    .map(|_| (registry.subscribe_output(), registry.subscribe_lifecycle()));
+                let spawned = match launch.parent_id {
+                    Some(parent_id) => {
+                        let parent_id = match SessionId::parse(&parent_id) {
+                            Ok(id) => id,
+                            Err(e) => return DaemonResponse::error(e.to_string()),
+                        };
+                        registry
+                            .spawn_child_session_with_options(parent_id, role, task, options)
+                            .await
+                    }
+                    None => registry.spawn_session_with_options(role, task, options).await,
+                };
+                match spawned {
                     Ok(session_id) => {
                         // Get PID
                         let pid = registry
@@ -157,38 +930,113 @@ impl DaemonServer {
                             .and_then(|m| m.pid)
                             .unwrap_or(0);
 
-                        DaemonResponse::spawned(session_id, pid)
+                        let (Some(marker), Some((output, lifecycle))) = (ready_marker, subscriptions) else {
+                            return DaemonResponse::spawned(session_id, pid);
+                        };
+                        match registry.wait_for_ready(&session_id, &marker, output, lifecycle).await {
+                            Ok(ReadyOutcome::Ready(line)) => DaemonResponse::ready(session_id, pid, line),
+                            Ok(ReadyOutcome::Exited(metadata)) => DaemonResponse::exited_before_ready(*metadata),
+                            Err(e) => DaemonResponse::error(format!("Failed to wait for {} to be ready: {}", session_id, e)),
+                        }
                     }
                     Err(e) => DaemonResponse::error(format!("Failed to spawn session: {}", e)),
                 }
             }
 
-            DaemonRequest::Resume { session_id, message } => {
-                let session_id = SessionId::from_string(session_id);
+            DaemonRequest::Resume { session_id, message, replay_context } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
 
-                match registry.resume_session(session_id, message).await {
-                    Ok(_) => DaemonResponse::ok_with_message("Session resumed".to_string()),
+                // Acknowledge once the process is up, like spawn, rather than
+                // holding the connection until the resumed run finishes
+                match registry
+                    .resume_session_in_background(session_id, message, replay_context)
+                    .await
+                {
+                    Ok(pid) => DaemonResponse::ok_with_message(format!("Session resumed (PID: {})", pid)),
                     Err(e) => DaemonResponse::error(format!("Failed to resume session: {}", e)),
                 }
             }
 
+            DaemonRequest::Restart { session_id } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+
+                match registry.restart_session(&session_id).await {
+                    Ok(session_id) => {
+                        let pid = registry
+                            .get_session(&session_id)
+                            .await
+                            .and_then(|m| m.pid)
+                            .unwrap_or(0);
+                        DaemonResponse::ok_with_message(format!("Session restarted (PID: {})", pid))
+                    }
+                    Err(e) => DaemonResponse::error(format!("Failed to restart session: {}", e)),
+                }
+            }
+
             DaemonRequest::List => {
                 let sessions = registry.list_sessions().await;
                 DaemonResponse::sessions(sessions)
             }
 
             DaemonRequest::Info { session_id } => {
-                let session_id = SessionId::from_string(session_id);
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
                 match registry.get_session(&session_id).await {
                     Some(metadata) => DaemonResponse::session_info(metadata),
                     None => DaemonResponse::error(format!("Session not found: {}", session_id)),
                 }
             }
 
-            DaemonRequest::Stop { session_id } => {
-                let session_id = SessionId::from_string(session_id);
-                match registry.stop_session(&session_id).await {
-                    Ok(_) => DaemonResponse::ok_with_message(format!("Session {} stopped", session_id)),
+            DaemonRequest::GetLog { session_id, lines, event_types } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                let log_dir = registry
+                    .get_session(&session_id)
+                    .await
+                    .map(|metadata| metadata.log_dir)
+                    .unwrap_or_else(|| registry.session_log_dir(&session_id));
+                if !log_dir.join("io.log").exists() {
+                    return DaemonResponse::error(format!("Log file not found for session {}", session_id));
+                }
+                match read_events(&log_dir) {
+                    Ok(events) => {
+                        let selection = LogSelection::new(lines).with_event_types(event_types);
+                        DaemonResponse::log_events(selection.apply(events))
+                    }
+                    Err(e) => DaemonResponse::error(format!("Failed to read log: {}", e)),
+                }
+            }
+
+            DaemonRequest::RecordApproval { session_id, approval } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                match registry.record_approval(&session_id, approval).await {
+                    Ok(metadata) => DaemonResponse::session_info(metadata),
+                    Err(e) => DaemonResponse::error(format!("Failed to record approval: {}", e)),
+                }
+            }
+
+            DaemonRequest::Stop { session_id, cascade } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                match registry.stop_session_with_cascade(&session_id, cascade).await {
+                    Ok(dependents) => DaemonResponse::ok_with_message(
+                        stop_message(&session_id, &dependents, cascade),
+                    ),
                     Err(e) => DaemonResponse::error(format!("Failed to stop session: {}", e)),
                 }
             }
@@ -200,8 +1048,69 @@ impl DaemonServer {
                 }
             }
 
+            DaemonRequest::Pause { session_id } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                match registry.pause_session(&session_id).await {
+                    Ok(pid) => DaemonResponse::ok_with_message(format!("Session {} paused (PID: {})", session_id, pid)),
+                    Err(e) => DaemonResponse::error(format!("Failed to pause session: {}", e)),
+                }
+            }
+
+            DaemonRequest::Tail { .. } => {
+                DaemonResponse::error("tail is only available over the daemon socket".to_string())
+            }
+
+            DaemonRequest::Continue { session_id } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                match registry.continue_session(&session_id).await {
+                    Ok(pid) => DaemonResponse::ok_with_message(format!("Session {} continued (PID: {})", session_id, pid)),
+                    Err(e) => DaemonResponse::error(format!("Failed to continue session: {}", e)),
+                }
+            }
+
+            DaemonRequest::KvSet { key, value, tree } => {
+                let tree = match tree.as_deref().map(SessionId::parse).transpose() {
+                    Ok(tree) => tree,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                match registry.kv_set(tree.as_ref(), &key, &value).await {
+                    Ok(_) => DaemonResponse::ok_with_message(format!("Set {}", key)),
+                    Err(e) => DaemonResponse::error(format!("Failed to set {}: {}", key, e)),
+                }
+            }
+
+            DaemonRequest::KvGet { key, tree } => {
+                let tree = match tree.as_deref().map(SessionId::parse).transpose() {
+                    Ok(tree) => tree,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
+                match registry.kv_get(tree.as_ref(), &key).await {
+                    Ok(value) => DaemonResponse::kv_value(value),
+                    Err(e) => DaemonResponse::error(format!("Failed to get {}: {}", key, e)),
+                }
+            }
+
+            DaemonRequest::HealthCheck => {
+                let mut reconciled = Vec::new();
+                for session_id in registry.health_check().await {
+                    if let Some(metadata) = registry.get_session(&session_id).await {
+                        reconciled.push(metadata);
+                    }
+                }
+                DaemonResponse::sessions(reconciled)
+            }
+
             DaemonRequest::Attach { session_id } => {
-                let session_id = SessionId::from_string(session_id);
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
 
                 // Check if session exists
                 if registry.get_session(&session_id).await.is_none() {
@@ -212,10 +1121,13 @@ impl DaemonServer {
                 DaemonResponse::ok_with_message(format!("Attaching to session {}", session_id))
             }
 
-            DaemonRequest::Input { session_id, text } => {
-                let session_id = SessionId::from_string(session_id);
+            DaemonRequest::Input { session_id, text, newline, paste } => {
+                let session_id = match SessionId::parse(&session_id) {
+                    Ok(id) => id,
+                    Err(e) => return DaemonResponse::error(e.to_string()),
+                };
 
-                match registry.send_input(&session_id, text).await {
+                match registry.send_input(&session_id, text, newline, paste).await {
                     Ok(_) => DaemonResponse::ok_with_message(format!("Input sent to session {}", session_id)),
                     Err(e) => DaemonResponse::error(format!("Failed to send input: {}", e)),
                 }
@@ -230,3 +1142,233 @@ impl DaemonServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SessionStatus;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::io::AsyncReadExt;
+
+    /// Fails the next `errors` accepts, then waits for a client that never comes
+    struct FlakyListener {
+        errors: Arc<AtomicU32>,
+        rebinds: Arc<AtomicU32>,
+        rebind_fails: bool,
+    }
+
+    impl ClientListener for FlakyListener {
+        async fn accept(&self) -> std::io::Result<(Box<dyn Connection>, String)> {
+            if self.errors.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Err(std::io::Error::other("injected accept error"));
+            }
+            std::future::pending().await
+        }
+
+        async fn rebind(self) -> Result<Self> {
+            if self.rebind_fails {
+                return Err(ClaudeManError::Other("injected bind error".to_string()));
+            }
+            self.rebinds.fetch_add(1, Ordering::SeqCst);
+            Ok(self)
+        }
+    }
+
+    fn flaky(errors: u32, rebind_fails: bool) -> (FlakyListener, Arc<AtomicU32>, Arc<AtomicU32>) {
+        let errors = Arc::new(AtomicU32::new(errors));
+        let rebinds = Arc::new(AtomicU32::new(0));
+        let listener = FlakyListener { errors: errors.clone(), rebinds: rebinds.clone(), rebind_fails };
+        (listener, errors, rebinds)
+    }
+
+    #[tokio::test]
+    async fn test_accept_errors_back_off_then_rebind() {
+        let mut backoff = AcceptBackoff::default();
+        let delays: Vec<AcceptRecovery> = (0..MAX_ACCEPT_FAILURES).map(|_| backoff.failed()).collect();
+        assert_eq!(delays[0], AcceptRecovery::Retry(ACCEPT_BACKOFF_START));
+        assert_eq!(delays[1], AcceptRecovery::Retry(ACCEPT_BACKOFF_START * 2));
+        assert_eq!(delays.last(), Some(&AcceptRecovery::Rebind));
+        backoff.succeeded();
+        assert_eq!(backoff.failed(), AcceptRecovery::Retry(ACCEPT_BACKOFF_START));
+
+        // Errors that stop after a rebind: the daemon keeps serving
+        let server = Arc::new(DaemonServer::new(0));
+        let (listener, errors, rebinds) = flaky(MAX_ACCEPT_FAILURES * 2, false);
+        let serving = tokio::spawn({
+            let server = server.clone();
+            async move { server.accept_clients(listener, None, None).await }
+        });
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while errors.load(Ordering::SeqCst) > 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(rebinds.load(Ordering::SeqCst), 2);
+        assert!(!serving.is_finished());
+        *server.shutdown.write().await = true;
+        serving.await.unwrap().unwrap();
+
+        // Errors that persist: the daemon gives up after MAX_REBINDS
+        let server = DaemonServer::new(0);
+        let (listener, _, rebinds) = flaky(u32::MAX, false);
+        let err = server.accept_clients(listener, None, None).await.unwrap_err();
+        assert!(err.to_string().contains("kept failing"), "{}", err);
+        assert_eq!(rebinds.load(Ordering::SeqCst), MAX_REBINDS);
+
+        // A failed rebind stops the daemon with the reason
+        let (listener, _, _) = flaky(u32::MAX, true);
+        let err = server.accept_clients(listener, None, None).await.unwrap_err();
+        assert!(err.to_string().contains("injected bind error"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_resume_acks_while_process_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let sh = |script: &str| vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        let registry = Arc::new(
+            SessionRegistry::new()
+                .with_project(Some("RESUMEBG".to_string()))
+                .with_sessions_dir(dir.path().to_path_buf())
+                .with_wrapper(sh("exec sleep 30")),
+        );
+        let session_id = registry.spawn_session(Role::Developer, "task".to_string()).await.unwrap();
+        registry.stop_session(&session_id).await.unwrap();
+
+        registry.set_wrapper(sh("echo resumed-output; exec sleep 30")).await;
+        let request = DaemonRequest::Resume {
+            session_id: session_id.to_string(),
+            message: "carry on".to_string(),
+            replay_context: 0,
+        };
+        let shutdown = Arc::new(RwLock::new(false));
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            DaemonServer::handle_request(request, registry.clone(), shutdown),
+        )
+        .await
+        .expect("resume waited for the process to finish");
+        let pid: u32 = match response {
+            DaemonResponse::Ok { message: Some(message), .. } => message
+                .trim_start_matches("Session resumed (PID: ")
+                .trim_end_matches(')')
+                .parse()
+                .unwrap(),
+            other => panic!("unexpected response: {:?}", other),
+        };
+        assert!(crate::core::process::is_process_alive(pid));
+
+        // The resumed run's output still reaches io.log
+        let log_dir = registry.get_session(&session_id).await.unwrap().log_dir;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !read_events(&log_dir)
+                .unwrap_or_default()
+                .iter()
+                .any(|event| event.content.contains("resumed-output"))
+            {
+                sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        std::process::Command::new("kill").arg(pid.to_string()).status().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_malformed_session_ids_are_refused() {
+        let registry = Arc::new(SessionRegistry::new());
+        let shutdown = Arc::new(RwLock::new(false));
+        let escape = "../../escape-DEV-001".to_string();
+
+        let requests = [
+            DaemonRequest::GetLog { session_id: escape.clone(), lines: 0, event_types: Vec::new() },
+            DaemonRequest::Stop { session_id: escape.clone(), cascade: false },
+            DaemonRequest::KvGet { key: "k".to_string(), tree: Some(escape.clone()) },
+        ];
+        for request in requests {
+            match DaemonServer::handle_request(request, registry.clone(), shutdown.clone()).await {
+                DaemonResponse::Error { message } => assert!(message.contains("Invalid session ID"), "{}", message),
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handled_request_is_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = Arc::new(AuditLog::open(dir.path().join("audit.log")).unwrap());
+        let registry = Arc::new(SessionRegistry::new());
+        let shutdown = Arc::new(RwLock::new(false));
+
+        for (line, token) in [("{\"command\":\"ping\"}\n", None), ("{\"command\":\"list\"}\n", Some("secret"))] {
+            let (mut client, server) = tokio::io::duplex(64 * 1024);
+            client.write_all(line.as_bytes()).await.unwrap();
+            DaemonServer::handle_client(
+                Box::new(server),
+                "127.0.0.1:50000".to_string(),
+                registry.clone(),
+                shutdown.clone(),
+                token.map(Arc::from),
+                Some(audit.clone()),
+            )
+            .await
+            .unwrap();
+        }
+
+        let entries: Vec<AuditEntry> = std::fs::read_to_string(audit.path())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command.as_deref(), Some("ping"));
+        assert_eq!(entries[0].source, "127.0.0.1:50000");
+        assert_eq!(entries[0].outcome, crate::daemon::audit::AuditOutcome::Ok);
+        assert_eq!(entries[1].command.as_deref(), Some("list"));
+        assert_eq!(entries[1].outcome, crate::daemon::audit::AuditOutcome::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn test_attached_spawn_streams_until_session_ends() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Arc::new(
+            SessionRegistry::new()
+                .with_sessions_dir(dir.path().to_path_buf())
+                .with_wrapper(vec!["sh".to_string(), "-c".to_string(), "echo hello".to_string()]),
+        );
+        let shutdown = Arc::new(RwLock::new(false));
+
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let request = "{\"command\":\"spawn\",\"role\":\"DEVELOPER\",\"task\":\"greet\",\"attach\":true}\n";
+        client.write_all(request.as_bytes()).await.unwrap();
+        let handled = DaemonServer::handle_client(Box::new(server), "unix".to_string(), registry, shutdown, None, None);
+        tokio::time::timeout(Duration::from_secs(10), handled).await.unwrap().unwrap();
+
+        let mut lines = String::new();
+        client.read_to_string(&mut lines).await.unwrap();
+        let responses: Vec<DaemonResponse> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+
+        // The spawn response, the start, the output, then the end
+        let DaemonResponse::Ok { session_id: Some(session_id), .. } = &responses[0] else {
+            panic!("unexpected first response: {:?}", responses[0]);
+        };
+        let streamed: Vec<(&str, &str)> = responses[1..]
+            .iter()
+            .filter_map(|response| match response {
+                DaemonResponse::Output { session_id: from, content, event_type } if from == session_id => {
+                    Some((event_type.as_str(), content.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(streamed.first(), Some(&("lifecycle", "Session running")), "{:?}", responses);
+        assert!(streamed.contains(&("output", "hello")), "{:?}", responses);
+        assert!(matches!(
+            responses.last(),
+            Some(DaemonResponse::SessionEnded { session_id: ended, session_status: SessionStatus::Completed }) if ended == session_id
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+//! Shared-secret authentication for daemon clients
+//!
+//! On startup the daemon writes a random token to `.claude-man/daemon.token`,
+//! readable only by its owner. Clients send it with every request, so only
+//! users who can read the file can drive the daemon.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::default_home_dir;
+use crate::types::error::Result;
+
+/// Get the default token file path
+pub fn default_token_path() -> PathBuf {
+    default_home_dir().join("daemon.token")
+}
+
+/// Token file owned by the running daemon, removed when dropped
+#[derive(Debug)]
+pub struct TokenFile {
+    path: PathBuf,
+    token: String,
+}
+
+impl TokenFile {
+    /// Generate a new token and write it to `path`, replacing any old token
+    pub fn create(path: PathBuf) -> Result<Self> {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Recreate rather than truncate, so an old file's permissions don't carry over
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+        writeln!(file, "{}", token)?;
+
+        Ok(Self { path, token })
+    }
+
+    /// The token clients must send
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Drop for TokenFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another daemon has replaced the token
+        if read_token(&self.path).ok().flatten().as_deref() == Some(self.token.as_str()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Check an HTTP or gRPC `Authorization` value against the daemon's token
+///
+/// Always passes when the daemon has no token; otherwise the value must be
+/// `Bearer <token>`.
+pub fn bearer_authorized(token: Option<&str>, authorization: Option<&str>) -> bool {
+    match token {
+        Some(token) => authorization.and_then(|value| value.trim().strip_prefix("Bearer ")) == Some(token),
+        None => true,
+    }
+}
+
+/// Read the token stored in `path` (`None` if there is no token file)
+pub fn read_token(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents.trim().to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::core::SessionRegistry;
+    use crate::daemon::protocol::{DaemonRequest, DaemonResponse};
+    use crate::daemon::transport::{ClientListener, DaemonTransport};
+    use crate::daemon::{DaemonClient, DaemonServer};
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_requests_need_the_daemon_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let token_path = dir.path().join("daemon.token");
+        let token_file = TokenFile::create(token_path.clone()).unwrap();
+        let mode = fs::metadata(&token_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let transport = DaemonTransport::Unix(dir.path().join("claude-man.sock"));
+        let listener = transport.bind().await.unwrap();
+        let token: Arc<str> = token_file.token().into();
+        let server = tokio::spawn(async move {
+            let registry = Arc::new(SessionRegistry::new());
+            let shutdown = Arc::new(RwLock::new(false));
+            loop {
+                let (stream, peer) = listener.accept().await.unwrap();
+                DaemonServer::handle_client(stream, peer, registry.clone(), shutdown.clone(), Some(token.clone()), None)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let wrong_path = dir.path().join("wrong.token");
+        fs::write(&wrong_path, "not-the-token\n").unwrap();
+        for path in [wrong_path, dir.path().join("missing.token")] {
+            let client = DaemonClient::new(transport.clone()).with_token_file(path);
+            match client.send_request(DaemonRequest::Ping).await.unwrap() {
+                DaemonResponse::Error { message } => assert_eq!(message, "unauthorized"),
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+
+        let client = DaemonClient::new(transport).with_token_file(token_path.clone());
+        match client.send_request(DaemonRequest::Ping).await.unwrap() {
+            DaemonResponse::Ok { message, .. } => assert_eq!(message.as_deref(), Some("pong")),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        server.abort();
+        let _ = server.await;
+        drop(token_file);
+        assert!(!token_path.exists());
+    }
+}
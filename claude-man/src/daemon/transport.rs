@@ -0,0 +1,252 @@
+//! How CLI commands reach the daemon
+//!
+//! The daemon listens on TCP 127.0.0.1:47520 by default, or on a Unix domain
+//! socket (`--socket`, by default `$XDG_RUNTIME_DIR/claude-man.sock`). Both
+//! carry the same line-based JSON protocol. Windows has no Unix sockets and
+//! always uses TCP.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::core::config::default_home_dir;
+use crate::daemon::server::DEFAULT_DAEMON_PORT;
+use crate::types::error::{ClaudeManError, Result};
+
+/// File name of the daemon's socket in the runtime directory
+const SOCKET_FILE_NAME: &str = "claude-man.sock";
+
+/// Transport chosen for this process with [`DaemonTransport::init_default`]
+static DEFAULT_TRANSPORT: OnceLock<DaemonTransport> = OnceLock::new();
+
+/// Where the daemon listens and clients connect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DaemonTransport {
+    /// TCP on 127.0.0.1 at this port
+    Tcp(u16),
+
+    /// Unix domain socket at this path
+    Unix(PathBuf),
+}
+
+impl Default for DaemonTransport {
+    /// The transport set with `init_default`, else TCP on the default port
+    fn default() -> Self {
+        DEFAULT_TRANSPORT
+            .get()
+            .cloned()
+            .unwrap_or(DaemonTransport::Tcp(DEFAULT_DAEMON_PORT))
+    }
+}
+
+impl std::fmt::Display for DaemonTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DaemonTransport::Tcp(port) => write!(f, "127.0.0.1:{}", port),
+            DaemonTransport::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl DaemonTransport {
+    /// Make `transport` the default for this process's clients and daemon
+    ///
+    /// Only the first call has an effect.
+    pub fn init_default(transport: DaemonTransport) {
+        let _ = DEFAULT_TRANSPORT.set(transport);
+    }
+
+    /// A Unix socket at `path` (default: [`default_socket_path`])
+    ///
+    /// Falls back to TCP on the default port where Unix sockets aren't available.
+    pub fn unix(path: Option<PathBuf>) -> Self {
+        #[cfg(unix)]
+        {
+            DaemonTransport::Unix(path.unwrap_or_else(default_socket_path))
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            tracing::warn!("Unix sockets are not supported on this platform, using TCP");
+            DaemonTransport::Tcp(DEFAULT_DAEMON_PORT)
+        }
+    }
+
+    /// Start listening, replacing a socket file left behind by a dead daemon
+    pub(crate) async fn bind(&self) -> Result<Listener> {
+        match self {
+            DaemonTransport::Tcp(port) => {
+                let addr = format!("127.0.0.1:{}", port);
+                let listener = TcpListener::bind(&addr)
+                    .await
+                    .map_err(|e| ClaudeManError::Other(format!("Failed to bind to {}: {}", addr, e)))?;
+                Ok(Listener::Tcp(listener))
+            }
+            #[cfg(unix)]
+            DaemonTransport::Unix(path) => {
+                if path.exists() {
+                    if UnixStream::connect(path).await.is_ok() {
+                        return Err(ClaudeManError::Other(format!(
+                            "A daemon is already listening on {}",
+                            self
+                        )));
+                    }
+                    std::fs::remove_file(path)?;
+                }
+                if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let listener = UnixListener::bind(path)
+                    .map_err(|e| ClaudeManError::Other(format!("Failed to bind to {}: {}", self, e)))?;
+                Ok(Listener::Unix {
+                    listener,
+                    path: path.clone(),
+                })
+            }
+            #[cfg(not(unix))]
+            DaemonTransport::Unix(_) => Err(ClaudeManError::InvalidInput(format!(
+                "Cannot listen on {}: Unix sockets are not supported on this platform",
+                self
+            ))),
+        }
+    }
+
+    /// Connect to the daemon
+    pub(crate) async fn connect(&self) -> std::io::Result<Box<dyn Connection>> {
+        match self {
+            DaemonTransport::Tcp(port) => Ok(Box::new(TcpStream::connect(("127.0.0.1", *port)).await?)),
+            #[cfg(unix)]
+            DaemonTransport::Unix(path) => Ok(Box::new(UnixStream::connect(path).await?)),
+            #[cfg(not(unix))]
+            DaemonTransport::Unix(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Unix sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+/// Default path of the daemon's Unix socket
+///
+/// `$XDG_RUNTIME_DIR/claude-man.sock`, or `.claude-man/claude-man.sock` when
+/// there is no runtime directory.
+pub fn default_socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(default_home_dir)
+        .join(SOCKET_FILE_NAME)
+}
+
+/// A connection over either transport
+pub(crate) trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// A bound daemon listener
+///
+/// A Unix socket's file is removed when the listener is dropped.
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix { listener: UnixListener, path: PathBuf },
+}
+
+/// Where the daemon's accept loop gets client connections
+///
+/// Implemented by [`Listener`]; tests substitute listeners that fail on cue.
+pub(crate) trait ClientListener: Sized {
+    /// Accept the next client connection and describe where it came from
+    async fn accept(&self) -> std::io::Result<(Box<dyn Connection>, String)>;
+
+    /// Close this listener and bind a new one at the same address
+    async fn rebind(self) -> Result<Self>;
+}
+
+impl ClientListener for Listener {
+    async fn accept(&self) -> std::io::Result<(Box<dyn Connection>, String)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                Ok((Box::new(stream), addr.to_string()))
+            }
+            // Unix socket peers are unnamed; only local processes can connect
+            #[cfg(unix)]
+            Listener::Unix { listener, .. } => Ok((Box::new(listener.accept().await?.0), "unix".to_string())),
+        }
+    }
+
+    async fn rebind(self) -> Result<Self> {
+        let transport = match &self {
+            Listener::Tcp(listener) => DaemonTransport::Tcp(listener.local_addr()?.port()),
+            #[cfg(unix)]
+            Listener::Unix { path, .. } => DaemonTransport::Unix(path.clone()),
+        };
+        // Release the port or socket file first
+        drop(self);
+        transport.bind().await
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix { path, .. } = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::core::SessionRegistry;
+    use crate::daemon::protocol::{DaemonRequest, DaemonResponse};
+    use crate::daemon::{DaemonClient, DaemonServer};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_ping_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("claude-man.sock");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "").unwrap();
+
+        // A stale socket file is replaced
+        let transport = DaemonTransport::Unix(path.clone());
+        let listener = transport.bind().await.unwrap();
+
+        // Rebinding replaces the listener at the same path
+        let listener = listener.rebind().await.unwrap();
+        assert!(path.exists());
+        let server = tokio::spawn(async move {
+            let registry = Arc::new(SessionRegistry::new());
+            let shutdown = Arc::new(RwLock::new(false));
+            loop {
+                let (stream, peer) = listener.accept().await.unwrap();
+                DaemonServer::handle_client(stream, peer, registry.clone(), shutdown.clone(), None, None)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let client = DaemonClient::new(transport.clone());
+        assert!(client.is_running().await);
+        match client.send_request(DaemonRequest::Ping).await.unwrap() {
+            DaemonResponse::Ok { message, .. } => assert_eq!(message.as_deref(), Some("pong")),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        // A live daemon's socket is left alone
+        assert!(transport.bind().await.is_err());
+
+        // The socket file goes away with the listener
+        server.abort();
+        let _ = server.await;
+        assert!(!path.exists());
+        assert!(!client.is_running().await);
+    }
+}
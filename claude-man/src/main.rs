@@ -2,13 +2,26 @@
 //!
 //! Main entry point for the claude-man command-line interface.
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use claude_man::cli::commands;
+use claude_man::cli::output::{self, ActionResult, ColorChoice, ListFormat, ListPage, ListSort, OutputFormat};
 use claude_man::core::auth;
-use claude_man::core::SessionRegistry;
-use claude_man::daemon::{DaemonClient, DaemonServer};
-use claude_man::types::{ClaudeManError, Result, Role, SessionId};
+use claude_man::core::filter::ReadyMarker;
+use claude_man::core::logger::{self, IoEventType, LogSelection, DEFAULT_CONTEXT_BYTES};
+use claude_man::core::process::{resolve_encoding, split_command, ConsoleEcho, InputNewline};
+use claude_man::core::result::{CaptureResult, ResultExtraction};
+use claude_man::core::{Config, SessionRegistry, SpawnOptions};
+use claude_man::daemon::{
+    lifecycle, token, DaemonClient, DaemonRequest, DaemonResponse, DaemonServer, DaemonTransport, SpawnLaunch,
+};
+use claude_man::types::session::{
+    parse_key_value, validate_project_name, Approval, ApprovalDecision, SessionMetadata,
+    SpawnSettings,
+};
+use claude_man::types::{expand_path, expand_path_str, parse_duration, ClaudeManError, Result, Role, SessionId};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, info};
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -18,59 +31,392 @@ use tracing_subscriber::{fmt, EnvFilter};
 #[command(about = "Manage multiple Claude AI sessions from a single CLI", long_about = None)]
 #[command(version)]
 struct Cli {
+    /// Project prefix for new session IDs (e.g. ACME → ACME-DEV-001)
+    #[arg(long, global = true, env = "CLAUDE_MAN_PROJECT")]
+    project: Option<String>,
+
+    /// Output format for command results
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Command to run claude through, e.g. "docker run --rm img" (overrides config)
+    #[arg(long, global = true, env = "CLAUDE_MAN_WRAPPER", value_name = "COMMAND")]
+    wrapper: Option<String>,
+
+    /// When to color status output (auto honors NO_COLOR)
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Milliseconds between checks when waiting on or tailing a session
+    #[arg(long, global = true, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    poll_interval: Option<u64>,
+
+    /// How long a stopped session gets to exit after SIGTERM before SIGKILL, e.g. 10s (overrides config)
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    termination_timeout: Option<Duration>,
+
+    /// Truncate session output lines longer than this many bytes (overrides config)
+    #[arg(long, global = true, value_name = "BYTES", value_parser = clap::value_parser!(u64).range(1..))]
+    max_line_bytes: Option<u64>,
+
+    /// Write session metadata.json as compact single-line JSON (or set compact_metadata in config)
+    #[arg(long, global = true)]
+    compact_json: bool,
+
+    /// Reject spawns beyond this many sessions in any rolling hour (overrides config)
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_sessions_per_hour: Option<u64>,
+
+    /// Reject spawns while this many sessions are running (overrides config)
+    #[arg(long, global = true, env = "CLAUDE_MAN_MAX_SESSIONS", value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_sessions: Option<u64>,
+
+    /// Skip Claude CLI authentication checks (offline/air-gapped testing)
+    #[arg(long, global = true, env = "CLAUDE_MAN_SKIP_AUTH")]
+    skip_auth: bool,
+
+    /// Only accept full role names (DEVELOPER, not DEV)
+    #[arg(long, global = true, env = "CLAUDE_MAN_STRICT_ROLES")]
+    strict_roles: bool,
+
+    /// Fail instead of skipping sessions whose metadata.json can't be read
+    #[arg(long, global = true, env = "CLAUDE_MAN_STRICT")]
+    strict: bool,
+
+    /// Don't echo session stderr to the console (still logged to io.log)
+    #[arg(long, global = true)]
+    quiet_errors: bool,
+
+    /// Don't echo session stdout to the console (still logged to io.log)
+    #[arg(long, global = true)]
+    quiet_output: bool,
+
+    /// Reach the daemon over a Unix socket instead of TCP (default: $XDG_RUNTIME_DIR/claude-man.sock;
+    /// TCP on Windows)
+    #[arg(long, global = true, env = "CLAUDE_MAN_SOCKET", value_name = "PATH", num_args = 0..=1, require_equals = true, value_parser = expand_path)]
+    socket: Option<Option<PathBuf>>,
+
+    /// Store sessions in this directory instead of .claude-man/sessions (use the same one for
+    /// the daemon and its clients)
+    #[arg(long, global = true, env = "CLAUDE_MAN_DATA_DIR", value_name = "PATH", value_parser = expand_path)]
+    data_dir: Option<PathBuf>,
+
     /// Subcommand to execute
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Parentage, priming and launch settings for `spawn`
+#[derive(Args)]
+struct LaunchArgs {
+    /// Spawn as a child of this session, inheriting its launch settings
+    #[arg(long, value_name = "SESSION_ID", value_parser = SessionId::parse)]
+    parent: Option<SessionId>,
+
+    /// Start the task with a transcript of this session's conversation (a fresh session, not a resume)
+    #[arg(long, value_name = "SESSION_ID", value_parser = SessionId::parse)]
+    context_from: Option<SessionId>,
+
+    /// Cap on the --context-from transcript; older lines are dropped first
+    #[arg(long, value_name = "BYTES", requires = "context_from", default_value_t = DEFAULT_CONTEXT_BYTES)]
+    context_bytes: usize,
+
+    /// While a session spawned with this key is unfinished, print its ID instead of spawning another
+    #[arg(long, value_name = "KEY")]
+    idempotency_key: Option<String>,
+
+    /// Don't inherit the parent's model, env, working directory or claude binary
+    #[arg(long, requires = "parent")]
+    no_inherit: bool,
+
+    /// Model passed to claude as --model
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Set an environment variable for the process (repeatable)
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    env: Vec<(String, String)>,
+
+    /// Working directory for the process (default: the session's log directory)
+    #[arg(long, value_name = "DIR", value_parser = expand_path)]
+    cwd: Option<PathBuf>,
+
+    /// Claude executable to run instead of `claude`
+    #[arg(long, value_name = "PATH", value_parser = expand_path_str)]
+    claude_bin: Option<String>,
+}
+
+impl LaunchArgs {
+    /// Build launch settings
+    ///
+    /// The working directory is made absolute so the daemon uses the one the user meant.
+    fn settings(&self) -> Result<SpawnSettings> {
+        let working_dir = match &self.cwd {
+            Some(dir) if dir.is_relative() => Some(std::env::current_dir()?.join(dir)),
+            dir => dir.clone(),
+        };
+        Ok(SpawnSettings {
+            model: self.model.clone(),
+            env: self.env.iter().cloned().collect(),
+            working_dir,
+            claude_binary: self.claude_bin.clone(),
+        })
+    }
+}
+
+/// How `spawn` waits on a daemon session before returning
+#[derive(Args)]
+struct WaitArgs {
+    /// Wait at most this long for a daemon session to finish, then leave it running
+    /// (exits 124 if it is still running), e.g. 90s or 1h30m
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    wait_timeout: Option<Duration>,
+
+    /// With a daemon, return once an output line matches this regex, leaving the session
+    /// running (exits 3 if the session ends first)
+    #[arg(long, value_name = "REGEX", conflicts_with = "wait_timeout")]
+    wait_for_ready: Option<String>,
+
+    /// With a daemon, follow the session's output like `attach` until it ends (without one,
+    /// spawn always shows the output)
+    #[arg(long, conflicts_with_all = ["wait_timeout", "wait_for_ready"])]
+    attach: bool,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Spawn a new Claude session
     Spawn {
-        /// Role for the session (MANAGER, ARCHITECT, DEVELOPER, STAKEHOLDER)
+        /// Role for the session (MANAGER, ARCHITECT, DEVELOPER, STAKEHOLDER or a custom role from config)
         #[arg(short, long)]
         role: String,
 
         /// Task description for the session
         task: String,
+
+        /// Attach a key=value annotation (repeatable)
+        #[arg(long = "annotate", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        annotations: Vec<(String, String)>,
+
+        /// Log lines to print if the session fails (0 for all)
+        #[arg(long, value_name = "N", default_value = "20")]
+        tail_lines: usize,
+
+        /// Write the session's final output block to this file on success
+        #[arg(long, value_name = "PATH", value_parser = expand_path)]
+        capture_result: Option<PathBuf>,
+
+        /// Capture the lines after this marker line instead of the last block
+        #[arg(long, value_name = "MARKER", requires_all = ["capture_result", "result_end"])]
+        result_start: Option<String>,
+
+        /// Marker line ending the captured result
+        #[arg(long, value_name = "MARKER", requires_all = ["capture_result", "result_start"])]
+        result_end: Option<String>,
+
+        /// Charset of the session's output, e.g. windows-1252 (default: UTF-8)
+        #[arg(long, value_name = "LABEL")]
+        encoding: Option<String>,
+
+        /// On success, store the last JSON object or array in the output as the session's result
+        #[arg(long)]
+        expect_json_result: bool,
+
+        /// Shell command to run when the session finishes; sees CLAUDE_MAN_SESSION_ID,
+        /// CLAUDE_MAN_STATUS and CLAUDE_MAN_EXIT_CODE in its environment
+        #[arg(long, value_name = "COMMAND")]
+        on_complete: Option<String>,
+
+        /// Use this session ID (e.g. DEV-001) instead of the next free one
+        #[arg(long, value_name = "ID", value_parser = SessionId::parse)]
+        id: Option<SessionId>,
+
+        /// If the ID is taken by a finished session, archive its logs and reuse it
+        #[arg(long, requires = "id")]
+        replace: bool,
+
+        /// With --replace, stop the existing session first if it is still running
+        #[arg(long, requires = "replace")]
+        force: bool,
+
+        /// Ask on this terminal when the session shows an approval prompt and send back the answer
+        #[arg(long)]
+        interactive_approval: bool,
+
+        /// Terminate the session's process (SIGTERM, then SIGKILL) and mark it failed
+        /// if it is still running after this long, e.g. 30m
+        #[arg(long, value_name = "DURATION", value_parser = parse_positive_duration)]
+        timeout: Option<Duration>,
+
+        /// Restart the session up to N times if it fails; the last attempt decides the outcome
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        retry_on_failure: u32,
+
+        #[command(flatten)]
+        wait: Box<WaitArgs>,
+
+        #[command(flatten)]
+        launch: Box<LaunchArgs>,
     },
 
     /// Resume an existing Claude session with additional input
     Resume {
-        /// Session ID to resume
-        session_id: String,
+        /// Session ID to resume (omit with --last)
+        session_id: Option<String>,
 
         /// Additional message/input to provide
-        message: String,
+        message: Option<String>,
+
+        /// Resume the most recently created session instead of naming one
+        #[arg(long)]
+        last: bool,
+
+        /// With --last, only consider sessions of this role
+        #[arg(long, requires = "last")]
+        role: Option<String>,
+
+        /// Prepend a recap of the last N input/output events from the log
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        replay_context: usize,
+    },
+
+    /// Re-run a failed or stopped session with its original role and task
+    ///
+    /// The session keeps its ID and log directory, so its history stays in one io.log.
+    Restart {
+        /// Session ID to restart
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
+
+        /// Log lines to print if the new run fails (0 for all; direct mode only)
+        #[arg(long, value_name = "N", default_value = "20")]
+        tail_lines: usize,
     },
 
     /// Initialize claude-man configuration (sets up auto-approval for orchestration)
     Init,
 
+    /// Show version information
+    Version {
+        /// Include build, protocol, Claude CLI, and platform details
+        #[arg(long)]
+        full: bool,
+    },
+
     /// List all active sessions
-    List,
+    List {
+        /// Only show sessions with this key=value annotation (repeatable)
+        #[arg(long = "where", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        filters: Vec<(String, String)>,
+
+        /// Print the status color key after the table
+        #[arg(long)]
+        legend: bool,
+
+        /// Render as a table (fixed-width `table`/`compact` or auto-sized `wide`), JSON or CSV
+        /// (default: table, or json with --output json)
+        #[arg(long, value_enum)]
+        format: Option<ListFormat>,
+
+        /// Add a column with each session's task (cut to fit) to the table
+        #[arg(long)]
+        show_task: bool,
+
+        /// Order sessions by this field (ties go by ID)
+        #[arg(long, value_enum, default_value_t = ListSort::Started)]
+        sort: ListSort,
+
+        /// List sessions in descending order
+        #[arg(long)]
+        reverse: bool,
+
+        /// Show at most N sessions (after sorting)
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Skip the first N sessions (after sorting)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        offset: usize,
+    },
+
+    /// Show sessions as a tree of parents and their children
+    Tree,
 
     /// Stop a session
     Stop {
         /// Session ID to stop, or --all to stop all sessions
-        #[arg(conflicts_with = "all")]
-        session_id: Option<String>,
+        #[arg(conflicts_with = "all", value_parser = SessionId::parse)]
+        session_id: Option<SessionId>,
 
         /// Stop all sessions
         #[arg(short, long)]
         all: bool,
+
+        /// With --all, list the sessions that would be stopped without stopping them
+        #[arg(long, requires = "all")]
+        dry_run: bool,
+
+        /// With --all, skip the confirmation prompt
+        #[arg(short, long, requires = "all")]
+        yes: bool,
+
+        /// Cancel not-yet-started dependents instead of marking them failed
+        #[arg(long, conflicts_with = "all")]
+        cascade: bool,
+    },
+
+    /// Suspend a running session's process (SIGSTOP) without killing it
+    Pause {
+        /// Session ID
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
+    },
+
+    /// Continue a paused session's process (SIGCONT)
+    Continue {
+        /// Session ID
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
+    },
+
+    /// Mark running sessions whose process is gone as failed
+    HealthCheck,
+
+    /// Store a value in the shared key/value store
+    Set {
+        /// Key to set
+        key: String,
+
+        /// Value to store
+        value: String,
+
+        /// Scope the key to this session's tree (shared by its root and all descendants)
+        #[arg(long, value_name = "SESSION_ID", value_parser = SessionId::parse)]
+        tree: Option<SessionId>,
+    },
+
+    /// Print a value from the shared key/value store (exits 1 if unset)
+    Get {
+        /// Key to read
+        key: String,
+
+        /// Read from this session's tree scope instead of the global scope
+        #[arg(long, value_name = "SESSION_ID", value_parser = SessionId::parse)]
+        tree: Option<SessionId>,
     },
 
     /// Get detailed information about a session
     Info {
         /// Session ID
-        session_id: String,
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
     },
 
     /// View session logs
     Logs {
         /// Session ID
-        session_id: String,
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
 
         /// Follow log output (like tail -f)
         #[arg(short, long)]
@@ -79,30 +425,208 @@ enum Commands {
         /// Number of lines to show (default: 50, use 0 for all)
         #[arg(short = 'n', long, default_value = "50")]
         lines: usize,
+
+        /// Report event counts, error rate and output gaps instead of lines
+        #[arg(long, conflicts_with = "follow")]
+        stats: bool,
+
+        /// Only count events from this long ago until now, e.g. 10m (with --stats)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "stats")]
+        since: Option<Duration>,
+
+        /// Print the conversation as a User:/Assistant: transcript, as `spawn --context-from` injects it
+        #[arg(long, conflicts_with_all = ["follow", "stats", "reverse", "page", "download"])]
+        transcript: bool,
+
+        /// Show the selected lines newest first
+        #[arg(long, conflicts_with_all = ["follow", "stats"])]
+        reverse: bool,
+
+        /// Show the selected lines through $PAGER (default: less -R)
+        #[arg(long, conflicts_with_all = ["follow", "stats"])]
+        page: bool,
+
+        /// Fetch the log from the daemon instead of reading it from local disk
+        #[arg(long, conflicts_with_all = ["follow", "stats"])]
+        download: bool,
+
+        /// Only show events of this type (repeatable; default: all)
+        #[arg(long = "type", value_name = "TYPE", conflicts_with = "stats")]
+        event_types: Vec<IoEventType>,
     },
 
+    /// Show live CPU, memory and runtime of running sessions
+    Top {
+        /// Seconds between refreshes (CPU% is averaged over this)
+        #[arg(long, value_name = "SECS", default_value = "2")]
+        interval: f64,
+
+        /// Print one view and exit instead of refreshing
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Show the role context and hook files a spawn would generate, without launching anything
+    Validate {
+        /// Role to generate files for
+        #[arg(short, long)]
+        role: String,
+    },
+
+    /// Check the sessions directory for orphaned, outdated or corrupt files
+    Gc {
+        /// Remove leftovers and rewrite outdated metadata (default: only report)
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Print the JSON Schema of the daemon protocol, for writing clients
+    #[command(hide = true)]
+    ProtocolSchema,
+
     /// Attach to a running session (view live output)
     Attach {
         /// Session ID
-        session_id: String,
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
+
+        /// Keep showing the final output after the session ends until Enter is pressed
+        #[arg(long)]
+        keep_open: bool,
+
+        /// Only show the last N events of history (default 20) instead of reading the whole log
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "20")]
+        from_end: Option<usize>,
+
+        /// Ask on this terminal when the session shows an approval prompt and send back the answer
+        #[arg(long)]
+        interactive_approval: bool,
+    },
+
+    /// Follow the live output of several sessions at once, each line prefixed with its session
+    Tail {
+        /// Session IDs
+        #[arg(required_unless_present = "all", conflicts_with = "all", value_parser = SessionId::parse)]
+        session_ids: Vec<SessionId>,
+
+        /// Follow every session, including ones spawned later
+        #[arg(long)]
+        all: bool,
     },
 
     /// Send input to a running session
     Input {
         /// Session ID
-        session_id: String,
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
 
         /// Input text to send
-        text: String,
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        text: Option<String>,
+
+        /// Send the contents of a file instead (newlines preserved)
+        #[arg(long, value_name = "PATH", value_parser = expand_path)]
+        file: Option<PathBuf>,
+
+        /// How the input is terminated
+        #[arg(long, value_enum, default_value_t = InputNewline::Lf)]
+        input_newline: InputNewline,
+
+        /// Send multi-line input as a bracketed paste (ESC[200~ ... ESC[201~) so an
+        /// interactive prompt doesn't submit it line by line
+        #[arg(long)]
+        paste: bool,
+
+        /// Allow empty input (e.g. to just send a newline to submit)
+        #[arg(long)]
+        allow_empty: bool,
+    },
+
+    /// Approve a session's work (records the decision in its metadata and log)
+    Approve {
+        /// Session ID
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
+
+        /// Explanation recorded with the decision
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Session making the decision (e.g. a STAKEHOLDER session)
+        #[arg(long, value_name = "SESSION_ID")]
+        by: Option<String>,
+    },
+
+    /// Reject a session's work (records the decision in its metadata and log)
+    Reject {
+        /// Session ID
+        #[arg(value_parser = SessionId::parse)]
+        session_id: SessionId,
+
+        /// Explanation recorded with the decision
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Session making the decision (e.g. a STAKEHOLDER session)
+        #[arg(long, value_name = "SESSION_ID")]
+        by: Option<String>,
+    },
+
+    /// Back up all sessions (metadata and logs) and the key/value store to a tarball
+    Backup {
+        /// Archive file to write
+        #[arg(value_parser = expand_path)]
+        file: PathBuf,
+    },
+
+    /// Restore sessions and the key/value store from a tarball created by `backup`
+    Restore {
+        /// Archive file to read
+        #[arg(value_parser = expand_path)]
+        file: PathBuf,
     },
 
     /// Start the daemon server
-    Daemon,
+    Daemon {
+        /// Hot-reload settings when the config file changes (default: .claude-man/config.toml)
+        #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = ".claude-man/config.toml", value_parser = expand_path)]
+        watch_file: Option<PathBuf>,
+
+        /// Also serve a REST API on this port (127.0.0.1 only, disabled by default)
+        #[arg(long, value_name = "PORT")]
+        http_port: Option<u16>,
+
+        /// Also serve the gRPC interface on this port (127.0.0.1 only; needs a build with `--features grpc`)
+        #[arg(long, value_name = "PORT")]
+        grpc_port: Option<u16>,
+
+        /// Run in the foreground (the default)
+        #[arg(long, conflicts_with_all = ["background", "status"])]
+        foreground: bool,
+
+        /// Detach from the terminal and return once the daemon answers (output goes to .claude-man/daemon.log)
+        #[arg(long, conflicts_with = "status")]
+        background: bool,
+
+        /// Report whether the daemon is running (PID file plus a ping) instead of starting it
+        #[arg(long)]
+        status: bool,
+    },
 
     /// Shutdown the daemon server
     Shutdown,
 }
 
+impl Commands {
+    /// Whether the command starts a Claude process (and so needs auth)
+    fn launches_claude(&self) -> bool {
+        matches!(
+            self,
+            Commands::Spawn { .. } | Commands::Resume { .. } | Commands::Restart { .. }
+        )
+    }
+}
+
 #[tokio::main]
 async fn main() {
     // Initialize tracing
@@ -167,30 +691,228 @@ async fn init_claude_man_config() -> Result<()> {
     Ok(())
 }
 
-async fn run(cli: Cli) -> Result<()> {
+/// Parse a duration flag that must be longer than zero
+fn parse_positive_duration(s: &str) -> Result<Duration> {
+    match parse_duration(s)? {
+        duration if duration.is_zero() => Err(ClaudeManError::InvalidInput(format!(
+            "Duration '{}' must be longer than zero",
+            s
+        ))),
+        duration => Ok(duration),
+    }
+}
+
+/// Build an approval decision from `approve`/`reject` arguments
+fn approval(decision: ApprovalDecision, note: Option<String>, by: Option<String>) -> Approval {
+    Approval::new(decision)
+        .with_note(note)
+        .with_decided_by(by.map(SessionId::from_string))
+}
+
+/// Print an event of a `tail` or `spawn --attach` stream, prefixed with its session
+fn print_stream_event(response: DaemonResponse) {
+    match response {
+        DaemonResponse::Output { session_id, content, event_type } if event_type == "lifecycle" => {
+            println!("{}", output::info(&format!("[{}] {}", session_id, content)));
+        }
+        DaemonResponse::Output { session_id, content, .. } => println!("[{}] {}", session_id, content),
+        DaemonResponse::SessionEnded { session_id, session_status } => {
+            let message = format!("Session ended with status: {}", session_status);
+            println!("[{}] {}", session_id, output::paint_status(session_status, &message));
+        }
+        _ => {}
+    }
+}
+
+/// Which session streams to echo, from `--quiet-output` / `--quiet-errors`
+fn console_echo(cli: &Cli) -> ConsoleEcho {
+    ConsoleEcho {
+        stdout: !cli.quiet_output,
+        stderr: !cli.quiet_errors,
+    }
+}
+
+/// Pick the `list` format: explicit `--format`, else follow `--output`
+fn list_format(format: Option<ListFormat>, output: OutputFormat) -> ListFormat {
+    match (format, output) {
+        (Some(format), _) => format,
+        (None, OutputFormat::Json) => ListFormat::Json,
+        (None, OutputFormat::Text) => ListFormat::Table,
+    }
+}
+
+/// Build the result capture settings for `spawn --capture-result`
+///
+/// The path is made absolute so the daemon writes where the user expects.
+fn capture_result_option(
+    path: Option<PathBuf>,
+    start: Option<String>,
+    end: Option<String>,
+) -> Result<Option<CaptureResult>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let path = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+
+    let mut capture = CaptureResult::new(path);
+    if let (Some(start), Some(end)) = (start, end) {
+        capture = capture.with_rule(ResultExtraction::Between { start, end });
+    }
+
+    Ok(Some(capture))
+}
+
+/// Sort out `resume`'s positional arguments
+///
+/// With `--last` the only positional is the message; otherwise both the
+/// session ID and the message are required.
+fn resume_args(
+    session_id: Option<String>,
+    message: Option<String>,
+    last: bool,
+) -> Result<(Option<SessionId>, String)> {
+    match (last, session_id, message) {
+        (true, Some(message), None) => Ok((None, message)),
+        (true, _, _) => Err(ClaudeManError::InvalidInput(
+            "With --last, give only the message to send".to_string(),
+        )),
+        (false, Some(session_id), Some(message)) => Ok((Some(SessionId::parse(&session_id)?), message)),
+        (false, _, _) => Err(ClaudeManError::InvalidInput(
+            "Must specify a session ID and message, or --last and a message".to_string(),
+        )),
+    }
+}
+
+/// Get the ID of the session picked by `resume --last`, or explain why there is none
+fn last_session(session: Option<SessionMetadata>, role: Option<Role>) -> Result<SessionId> {
+    match (session, role) {
+        (Some(session), _) => Ok(session.id),
+        (None, Some(role)) => Err(ClaudeManError::Session(format!(
+            "No {} sessions to resume",
+            role
+        ))),
+        (None, None) => Err(ClaudeManError::Session("No sessions to resume".to_string())),
+    }
+}
+
+/// Resolve the project prefix from the CLI flag, falling back to config.toml
+fn resolve_project(cli: &Cli, config: &Config) -> Result<Option<String>> {
+    let project = match &cli.project {
+        Some(project) => Some(project.clone()),
+        None => config.project.clone(),
+    };
+
+    if let Some(project) = &project {
+        validate_project_name(project)?;
+    }
+
+    Ok(project)
+}
+
+async fn run(mut cli: Cli) -> Result<()> {
+    output::init_color(cli.color);
+    if let Some(path) = &cli.socket {
+        DaemonTransport::init_default(DaemonTransport::unix(path.clone()));
+    }
+    if let Some(dir) = &cli.data_dir {
+        // Absolute, so a daemon and clients started from other directories agree
+        logger::init_data_dir(std::path::absolute(dir)?);
+    }
+
     // Handle init command first (doesn't need auth or daemon)
     if let Some(Commands::Init) = &cli.command {
         return init_claude_man_config().await;
     }
 
+    // Version is purely local information
+    if let Some(Commands::Version { full }) = &cli.command {
+        commands::print_version(*full);
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+    let project = resolve_project(&cli, &config)?;
+    config.register_roles()?;
+    cli.strict_roles |= config.strict_roles;
+
+    // Handle backup/restore (operate on disk only, no auth required)
+    match &cli.command {
+        Some(Commands::Backup { file }) => {
+            let registry = Arc::new(SessionRegistry::new().with_strict_load(cli.strict));
+            registry.load_from_disk().await?;
+            return commands::backup_sessions(registry, file).await;
+        }
+        Some(Commands::Restore { file }) => {
+            let registry = Arc::new(SessionRegistry::new().with_strict_load(cli.strict));
+            return commands::restore_sessions(registry, file).await;
+        }
+        _ => {}
+    }
+
     // Handle daemon commands separately (don't require auth validation)
     match &cli.command {
-        Some(Commands::Daemon) => {
+        Some(Commands::Daemon { status: true, .. }) => {
+            let status = lifecycle::status(&DaemonClient::default(), &lifecycle::default_pid_path()).await?;
+            match cli.output {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&status)?),
+                OutputFormat::Text => println!("{}", status),
+            }
+            if !status.is_running() {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Commands::Daemon { background: true, .. }) => {
+            let args = std::env::args_os().skip(1).filter(|arg| arg != "--background").collect();
+            let log_path = lifecycle::default_daemon_log_path();
+            let pid = lifecycle::spawn_background(args, &DaemonClient::default(), &log_path).await?;
+            println!("Daemon started in background (PID {}), logging to {}", pid, log_path.display());
+            return Ok(());
+        }
+        Some(Commands::Daemon { watch_file, http_port, grpc_port, .. }) => {
             // Start daemon in foreground
-            let daemon = DaemonServer::default();
+            let mut daemon = DaemonServer::default()
+                .with_pid_file(lifecycle::default_pid_path())
+                .with_token_file(token::default_token_path())
+                .with_config(config)
+                .with_project(cli.project.clone())
+                .with_wrapper(cli.wrapper.as_deref().map(split_command).transpose()?)
+                .with_termination_timeout(cli.termination_timeout)
+                .with_max_line_bytes(cli.max_line_bytes.map(|limit| limit as usize))
+                .with_max_sessions_per_hour(cli.max_sessions_per_hour.map(|limit| limit as usize))
+                .with_max_concurrent(cli.max_sessions.map(|limit| limit as usize))
+                .with_compact_metadata(cli.compact_json)
+                .with_strict_load(cli.strict)
+                .with_console_echo(console_echo(&cli));
+            if let Some(path) = watch_file {
+                daemon = daemon.with_config_watch(path.clone());
+            }
+            if let Some(port) = http_port {
+                daemon = daemon.with_http_port(*port);
+                println!("Serving HTTP API on 127.0.0.1:{}", port);
+            }
+            if let Some(port) = grpc_port {
+                daemon = daemon.with_grpc_port(*port);
+                println!("Serving gRPC on 127.0.0.1:{}", port);
+            }
             println!("Starting daemon on {}", daemon.address());
             return daemon.start().await;
         }
         Some(Commands::Shutdown) => {
-            // Shutdown daemon
+            // Shutdown daemon, terminating it via the PID file if it doesn't answer
             let client = DaemonClient::default();
-            match client.shutdown().await {
-                Ok(_) => {
-                    println!("Daemon shut down successfully");
+            match lifecycle::shutdown(&client, &lifecycle::default_pid_path()).await {
+                Ok(message) => {
+                    println!("{}", message);
                     return Ok(());
                 }
                 Err(e) => {
-                    eprintln!("Error shutting down daemon: {}", e);
+                    eprintln!("{}", e);
                     std::process::exit(1);
                 }
             }
@@ -198,8 +920,10 @@ async fn run(cli: Cli) -> Result<()> {
         _ => {}
     }
 
-    // Validate authentication for all other commands
-    auth::validate_auth()?;
+    // Validate authentication only for commands that launch Claude
+    if cli.command.as_ref().is_some_and(Commands::launches_claude) && !cli.skip_auth {
+        auth::validate_auth()?;
+    }
 
     // Check if daemon is running
     let client = DaemonClient::default();
@@ -207,27 +931,111 @@ async fn run(cli: Cli) -> Result<()> {
 
     if use_daemon {
         info!("Using daemon mode");
-        return run_with_daemon(cli, client).await;
+        run_with_daemon(cli, client, project).await
     } else {
         info!("Running in direct mode (no daemon)");
-        return run_without_daemon(cli).await;
+        run_without_daemon(cli, project).await
     }
 }
 
 /// Run command using daemon
-async fn run_with_daemon(cli: Cli, client: DaemonClient) -> Result<()> {
+async fn run_with_daemon(cli: Cli, client: DaemonClient, project: Option<String>) -> Result<()> {
     match cli.command {
-        Some(Commands::Spawn { role, task }) => {
-            match client.spawn(role, task).await {
+        Some(Commands::Spawn {
+            role, task, annotations, tail_lines, capture_result, result_start, result_end, encoding,
+            expect_json_result, on_complete, id, replace, force, interactive_approval, timeout,
+            retry_on_failure, wait, launch,
+        }) => {
+            let WaitArgs { wait_timeout, wait_for_ready, attach } = *wait;
+            if interactive_approval {
+                return Err(ClaudeManError::InvalidInput(
+                    "--interactive-approval needs a foreground spawn; with a daemon, use attach --interactive-approval"
+                        .to_string(),
+                ));
+            }
+            let role = Role::parse(&role, cli.strict_roles)?.to_string();
+            let capture = capture_result_option(capture_result, result_start, result_end)?;
+            if let Some(label) = &encoding {
+                resolve_encoding(label)?;
+            }
+            if let Some(pattern) = &wait_for_ready {
+                ReadyMarker::new(pattern)?;
+            }
+            let annotations = annotations.into_iter().collect();
+            let launch = SpawnLaunch {
+                settings: launch.settings()?,
+                parent_id: launch.parent.map(|id| id.to_string()),
+                skip_parent_settings: launch.no_inherit,
+                expect_json_result,
+                on_complete,
+                timeout_secs: timeout.map(|timeout| timeout.as_secs()),
+                retry_on_failure,
+                session_id: id.map(|id| id.to_string()),
+                replace,
+                force_replace: force,
+                wait_for_ready: wait_for_ready.clone(),
+                context_from: launch.context_from.as_ref().map(SessionId::to_string),
+                context_bytes: launch.context_from.is_some().then_some(launch.context_bytes),
+                idempotency_key: launch.idempotency_key.clone(),
+                attach,
+            };
+            if attach {
+                let request = DaemonRequest::Spawn {
+                    role, task, annotations, capture_result: capture, encoding, launch: Box::new(launch),
+                };
+                let mut failed = false;
+                client
+                    .stream(request, |response| match response {
+                        DaemonResponse::Ok { session_id: Some(sid), pid, .. } => {
+                            println!("✓ Session {} started{}", sid,
+                                pid.map(|p| format!(" (PID: {})", p)).unwrap_or_default());
+                            println!();
+                        }
+                        DaemonResponse::Error { message } => {
+                            eprintln!("Error: {}", message);
+                            failed = true;
+                        }
+                        event => print_stream_event(event),
+                    })
+                    .await?;
+                if failed {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            match client.spawn(role, task, annotations, capture, encoding, launch).await {
                 Ok(response) => {
-                    use claude_man::daemon::DaemonResponse;
                     match response {
-                        DaemonResponse::Ok { session_id, pid, .. } => {
-                            if let Some(sid) = session_id {
-                                println!("✓ Session {} started{}", sid,
-                                    pid.map(|p| format!(" (PID: {})", p)).unwrap_or_default());
-                                println!();
-                                println!("View output: claude-man logs {}", sid);
+                        DaemonResponse::Ok { session_id: Some(sid), session: Some(ended), .. }
+                            if wait_for_ready.is_some() =>
+                        {
+                            eprintln!("Session {} ended ({}) before it was ready", sid, ended.status);
+                            println!("View output: claude-man logs {}", sid);
+                            std::process::exit(commands::EXIT_NOT_READY);
+                        }
+                        DaemonResponse::Ok { session_id: Some(sid), pid, message, .. } => {
+                            println!("✓ Session {} started{}", sid,
+                                pid.map(|p| format!(" (PID: {})", p)).unwrap_or_default());
+                            if let (Some(_), Some(line)) = (&wait_for_ready, message) {
+                                println!("✓ Session {} is ready: {}", sid, line);
+                            }
+                            let finished = match wait_timeout {
+                                Some(wait_timeout) => {
+                                    commands::wait_for_daemon_session(
+                                        &client,
+                                        &sid,
+                                        wait_timeout,
+                                        tail_lines,
+                                        cli.poll_interval.map(Duration::from_millis),
+                                    )
+                                    .await?
+                                }
+                                None => true,
+                            };
+                            println!();
+                            println!("View output: claude-man logs {}", sid);
+                            if !finished {
+                                std::process::exit(commands::EXIT_STILL_RUNNING);
                             }
                         }
                         DaemonResponse::Error { message } => {
@@ -244,13 +1052,43 @@ async fn run_with_daemon(cli: Cli, client: DaemonClient) -> Result<()> {
             }
         }
 
-        Some(Commands::Resume { session_id, message }) => {
-            match client.resume(session_id.clone(), message).await {
+        Some(Commands::Resume { session_id, message, last, role, replay_context }) => {
+            let (session_id, message) = resume_args(session_id, message, last)?;
+            let session_id = match session_id {
+                Some(session_id) => session_id,
+                None => {
+                    let role = role.map(|r| Role::parse(&r, cli.strict_roles)).transpose()?;
+                    let sessions = SessionRegistry::new().list_from_disk()?;
+                    last_session(SessionRegistry::most_recent(sessions, role), role)?
+                }
+            };
+            let response = client.resume(session_id.to_string(), message, replay_context).await;
+            report_action(
+                cli.output,
+                action_from_response("resumed", session_id, response, "Session resumed".to_string()),
+            );
+        }
+
+        Some(Commands::Restart { session_id, .. }) => {
+            let response = client.restart(session_id.to_string()).await;
+            report_action(
+                cli.output,
+                action_from_response("restarted", session_id, response, "Session restarted".to_string()),
+            );
+        }
+
+        Some(Commands::List { filters, legend, format, show_task, sort, reverse, limit, offset }) => {
+            let format = list_format(format, cli.output);
+            match client.list().await {
                 Ok(response) => {
-                    use claude_man::daemon::DaemonResponse;
                     match response {
-                        DaemonResponse::Ok { message: Some(msg), .. } => {
-                            println!("✓ {}", msg);
+                        DaemonResponse::Ok { sessions: Some(sessions), .. } => {
+                            let mut sessions = commands::filter_by_annotations(sessions, &filters);
+                            output::sort_sessions(&mut sessions, sort, reverse);
+                            commands::print_sessions_list(&sessions, format, show_task, ListPage { offset, limit })?;
+                            if legend && format.is_table() {
+                                output::print_status_legend();
+                            }
                         }
                         DaemonResponse::Error { message } => {
                             eprintln!("Error: {}", message);
@@ -266,21 +1104,16 @@ async fn run_with_daemon(cli: Cli, client: DaemonClient) -> Result<()> {
             }
         }
 
-        Some(Commands::List) => {
+        Some(Commands::Tree) => {
             match client.list().await {
-                Ok(response) => {
-                    use claude_man::daemon::DaemonResponse;
-                    match response {
-                        DaemonResponse::Ok { sessions: Some(sessions), .. } => {
-                            commands::print_sessions_list(&sessions);
-                        }
-                        DaemonResponse::Error { message } => {
-                            eprintln!("Error: {}", message);
-                            std::process::exit(1);
-                        }
-                        _ => {}
-                    }
+                Ok(DaemonResponse::Ok { sessions: Some(sessions), .. }) => {
+                    output::print_session_tree(&sessions);
+                }
+                Ok(DaemonResponse::Error { message }) => {
+                    eprintln!("Error: {}", message);
+                    std::process::exit(1);
                 }
+                Ok(_) => {}
                 Err(e) => {
                     eprintln!("Error: {}", e);
                     std::process::exit(1);
@@ -288,36 +1121,141 @@ async fn run_with_daemon(cli: Cli, client: DaemonClient) -> Result<()> {
             }
         }
 
-        Some(Commands::Stop { session_id, all }) => {
+        Some(Commands::Stop { session_id, all, dry_run, yes, cascade }) => {
             if all {
-                match client.stop_all().await {
-                    Ok(_) => println!("✓ All sessions stopped"),
-                    Err(e) => {
-                        eprintln!("Error: {}", e);
-                        std::process::exit(1);
+                let sessions = match client.list().await {
+                    Ok(DaemonResponse::Ok { sessions: Some(sessions), .. }) => {
+                        commands::active_sessions(sessions)
                     }
-                }
-            } else if let Some(id) = session_id {
-                match client.stop(id.clone()).await {
-                    Ok(_) => println!("✓ Session {} stopped", id),
+                    Ok(DaemonResponse::Error { message }) => {
+                        report_action(cli.output, ActionResult::failure("stopped", None, message));
+                        return Ok(());
+                    }
+                    Ok(_) => Vec::new(),
                     Err(e) => {
-                        eprintln!("Error: {}", e);
-                        std::process::exit(1);
+                        report_action(cli.output, ActionResult::failure("stopped", None, e.to_string()));
+                        return Ok(());
                     }
+                };
+
+                if !commands::confirm_stop_all(&sessions, dry_run, yes, cli.output)? {
+                    return Ok(());
                 }
+
+                let ids: Vec<SessionId> = sessions.into_iter().map(|s| s.id).collect();
+                let message = format!("Stopped {} session(s)", ids.len());
+                let result = match client.stop_all().await {
+                    Ok(DaemonResponse::Error { message }) => {
+                        ActionResult::failure("stopped", None, message)
+                    }
+                    Ok(_) => ActionResult::success("stopped", None, message).with_session_ids(ids),
+                    Err(e) => ActionResult::failure("stopped", None, e.to_string()),
+                };
+                report_action(cli.output, result);
+            } else if let Some(id) = session_id {
+                let response = client.stop(id.to_string(), cascade).await;
+                let default_message = format!("Session {} stopped", id);
+                report_action(
+                    cli.output,
+                    action_from_response("stopped", id, response, default_message),
+                );
             } else {
                 eprintln!("Must specify either session ID or --all");
                 std::process::exit(1);
             }
         }
 
+        Some(Commands::Tail { session_ids, .. }) => {
+            let mut failed = false;
+            client
+                .tail(session_ids.iter().map(SessionId::to_string).collect(), |response| match response {
+                    DaemonResponse::Error { message } => {
+                        eprintln!("Error: {}", message);
+                        failed = true;
+                    }
+                    event => print_stream_event(event),
+                })
+                .await?;
+            if failed {
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Pause { session_id }) => {
+            let response = client.pause(session_id.to_string()).await;
+            let default_message = format!("Session {} paused", session_id);
+            report_action(
+                cli.output,
+                action_from_response("paused", session_id, response, default_message),
+            );
+        }
+
+        Some(Commands::Continue { session_id }) => {
+            let response = client.continue_session(session_id.to_string()).await;
+            let default_message = format!("Session {} continued", session_id);
+            report_action(
+                cli.output,
+                action_from_response("continued", session_id, response, default_message),
+            );
+        }
+
+        Some(Commands::Set { key, value, tree }) => {
+            let response = client.kv_set(key.clone(), value, tree.map(|tree| tree.to_string())).await;
+            let result = match response {
+                Ok(DaemonResponse::Error { message }) => ActionResult::failure("set", None, message),
+                Ok(_) => ActionResult::success("set", None, format!("Set {}", key)),
+                Err(e) => ActionResult::failure("set", None, e.to_string()),
+            };
+            report_action(cli.output, result);
+        }
+
+        Some(Commands::Get { key, tree }) => {
+            match client.kv_get(key.clone(), tree.map(|tree| tree.to_string())).await {
+                Ok(DaemonResponse::Ok { value, .. }) => {
+                    if !commands::print_kv_value(&key, value, cli.output) {
+                        std::process::exit(1);
+                    }
+                }
+                Ok(DaemonResponse::Error { message }) => {
+                    eprintln!("Error: {}", message);
+                    std::process::exit(1);
+                }
+                Ok(other) => {
+                    eprintln!("Error: Unexpected daemon response: {:?}", other);
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(Commands::HealthCheck) => {
+            let result = match client.health_check().await {
+                Ok(DaemonResponse::Ok { sessions, .. }) => {
+                    let ids = sessions.unwrap_or_default().into_iter().map(|s| s.id).collect();
+                    commands::health_check_result(ids)
+                }
+                Ok(DaemonResponse::Error { message }) => {
+                    ActionResult::failure("health_checked", None, message)
+                }
+                Ok(other) => ActionResult::failure(
+                    "health_checked",
+                    None,
+                    format!("Unexpected daemon response: {:?}", other),
+                ),
+                Err(e) => ActionResult::failure("health_checked", None, e.to_string()),
+            };
+            report_action(cli.output, result);
+        }
+
         Some(Commands::Info { session_id }) => {
-            match client.info(session_id).await {
+            match client.info(session_id.to_string()).await {
                 Ok(response) => {
-                    use claude_man::daemon::DaemonResponse;
                     match response {
                         DaemonResponse::Ok { session: Some(metadata), .. } => {
-                            commands::print_session_info(&metadata);
+                            commands::print_session_info(&metadata, cli.output)?;
                         }
                         DaemonResponse::Error { message } => {
                             eprintln!("Error: {}", message);
@@ -333,43 +1271,93 @@ async fn run_with_daemon(cli: Cli, client: DaemonClient) -> Result<()> {
             }
         }
 
+        Some(Commands::Logs { session_id, lines, reverse, page, download: true, event_types, .. }) => {
+            let selection = LogSelection::new(lines).with_event_types(event_types);
+            match client.get_log(session_id.to_string(), selection).await {
+                Ok(DaemonResponse::Ok { events: Some(events), .. }) => {
+                    commands::show_log_events(events, &session_id, reverse, page)?;
+                }
+                Ok(DaemonResponse::Error { message }) => {
+                    eprintln!("Error: {}", message);
+                    std::process::exit(1);
+                }
+                Ok(other) => {
+                    eprintln!("Error: Unexpected daemon response: {:?}", other);
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
         Some(Commands::Logs { .. }) => {
             // Logs command reads from disk, doesn't need daemon
-            return run_without_daemon(cli).await;
+            return run_without_daemon(cli, project).await;
+        }
+
+        Some(Commands::Top { .. }) => {
+            // Top reads sessions from disk, doesn't need daemon
+            return run_without_daemon(cli, project).await;
+        }
+
+        Some(Commands::Validate { .. }) => {
+            // Validate only writes to a temp dir, doesn't need daemon
+            return run_without_daemon(cli, project).await;
+        }
+
+        Some(Commands::Gc { .. }) => {
+            // Gc works on the sessions directory, doesn't need daemon
+            return run_without_daemon(cli, project).await;
+        }
+
+        Some(Commands::ProtocolSchema) => {
+            // The schema is built in, doesn't need daemon
+            return run_without_daemon(cli, project).await;
         }
 
         Some(Commands::Attach { .. }) => {
             // Attach command reads from disk, doesn't need daemon
-            return run_without_daemon(cli).await;
+            return run_without_daemon(cli, project).await;
         }
 
-        Some(Commands::Init) => {
-            unreachable!("Init handled earlier in run()")
+        Some(Commands::Init) | Some(Commands::Version { .. }) => {
+            unreachable!("Init and Version handled earlier in run()")
         }
 
-        Some(Commands::Input { session_id, text }) => {
-            match client.input(session_id.clone(), text).await {
-                Ok(response) => {
-                    use claude_man::daemon::DaemonResponse;
-                    match response {
-                        DaemonResponse::Ok { message: Some(msg), .. } => {
-                            println!("✓ {}", msg);
-                        }
-                        DaemonResponse::Error { message } => {
-                            eprintln!("Error: {}", message);
-                            std::process::exit(1);
-                        }
-                        _ => {}
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+        Some(Commands::Approve { session_id, note, by }) => {
+            let approval = approval(ApprovalDecision::Approved, note, by);
+            let response = client.record_approval(session_id.to_string(), approval).await;
+            let default_message = format!("Session {} approved", session_id);
+            report_action(
+                cli.output,
+                action_from_response("approved", session_id, response, default_message),
+            );
+        }
+
+        Some(Commands::Reject { session_id, note, by }) => {
+            let approval = approval(ApprovalDecision::Rejected, note, by);
+            let response = client.record_approval(session_id.to_string(), approval).await;
+            let default_message = format!("Session {} rejected", session_id);
+            report_action(
+                cli.output,
+                action_from_response("rejected", session_id, response, default_message),
+            );
         }
 
-        Some(Commands::Daemon) | Some(Commands::Shutdown) => {
+        Some(Commands::Input { session_id, text, file, input_newline, paste, allow_empty }) => {
+            let text = commands::resolve_input(text, file.as_deref(), allow_empty)?;
+            let response = client.input(session_id.to_string(), text, input_newline, paste).await;
+            let default_message = format!("Input sent to session {}", session_id);
+            report_action(
+                cli.output,
+                action_from_response("input_sent", session_id, response, default_message),
+            );
+        }
+
+        Some(Commands::Daemon { .. }) | Some(Commands::Shutdown)
+        | Some(Commands::Backup { .. }) | Some(Commands::Restore { .. }) => {
             unreachable!("Handled above")
         }
 
@@ -383,9 +1371,41 @@ async fn run_with_daemon(cli: Cli, client: DaemonClient) -> Result<()> {
 }
 
 /// Run command without daemon (direct mode)
-async fn run_without_daemon(cli: Cli) -> Result<()> {
+async fn run_without_daemon(cli: Cli, project: Option<String>) -> Result<()> {
     // Create session registry and load existing sessions
-    let registry = Arc::new(SessionRegistry::new());
+    let config = Config::load()?;
+    let registry = Arc::new(
+        SessionRegistry::new()
+            .with_project(project)
+            .with_flush_policy(config.flush_policy())
+            .with_compact_metadata(cli.compact_json || config.compact_metadata)
+            .with_strict_load(cli.strict)
+            .with_max_concurrent_per_parent(config.max_concurrent_per_parent)
+            .with_max_sessions_per_hour(
+                cli.max_sessions_per_hour.map(|limit| limit as usize).or(config.max_sessions_per_hour),
+            )
+            .with_max_concurrent(cli.max_sessions.map(|limit| limit as usize).or(config.max_concurrent_sessions))
+            .with_session_dir_template(config.session_dir_template()?)
+            .with_role_working_dirs(config.role_working_dirs()?)
+            .with_max_line_bytes(cli.max_line_bytes.map(|limit| limit as usize).or(config.max_line_bytes))
+            .with_max_log_bytes(config.log_rotation_bytes())
+            .with_wrapper(match &cli.wrapper {
+                Some(wrapper) => split_command(wrapper)?,
+                None => config.wrapper_command()?,
+            })
+            .with_termination_timeout(
+                cli.termination_timeout
+                    .unwrap_or_else(|| config.termination_timeout()),
+            )
+            .with_console_echo(console_echo(&cli)),
+    );
+    let poll_interval = cli
+        .poll_interval
+        .or(config.poll_interval_ms)
+        .map(Duration::from_millis);
+    if let Some(redactor) = config.redactor()? {
+        registry.on_output(move |line| Some(redactor.redact(line))).await;
+    }
     registry.load_from_disk().await?;
 
     // Setup signal handlers for cleanup
@@ -393,27 +1413,110 @@ async fn run_without_daemon(cli: Cli) -> Result<()> {
 
     // Execute command
     match cli.command {
-        Some(Commands::Spawn { role, task }) => {
-            let role = role.parse::<Role>()?;
-            commands::spawn_session(registry.clone(), role, task).await?;
+        Some(Commands::Spawn {
+            role, task, annotations, tail_lines, capture_result, result_start, result_end, encoding,
+            expect_json_result, on_complete, id, replace, force, interactive_approval, timeout,
+            retry_on_failure, wait, launch,
+        }) => {
+            // Without a daemon the spawn runs in the foreground, already attached
+            let WaitArgs { wait_timeout, wait_for_ready, attach: _ } = *wait;
+            if wait_timeout.is_some() {
+                return Err(ClaudeManError::InvalidInput(
+                    "--wait-timeout needs a running daemon; without one the session can't outlive this command"
+                        .to_string(),
+                ));
+            }
+            if wait_for_ready.is_some() {
+                return Err(ClaudeManError::InvalidInput(
+                    "--wait-for-ready needs a running daemon; without one the session can't outlive this command"
+                        .to_string(),
+                ));
+            }
+            if interactive_approval {
+                registry.watch_approval_prompts(config.prompt_detector()?).await;
+            }
+            let role = Role::parse(&role, cli.strict_roles)?;
+            let mut options = SpawnOptions::new()
+                .with_annotations(annotations.into_iter().collect())
+                .with_settings(launch.settings()?);
+            if launch.no_inherit {
+                options = options.without_parent_settings();
+            }
+            if expect_json_result {
+                options = options.with_json_result();
+            }
+            if let Some(command) = on_complete {
+                options = options.with_on_complete(command);
+            }
+            if let Some(timeout) = timeout {
+                options = options.with_timeout(timeout);
+            }
+            if retry_on_failure > 0 {
+                options = options.with_retry_on_failure(retry_on_failure);
+            }
+            if let Some(id) = id {
+                options = options.with_session_id(id);
+            }
+            if replace {
+                options = options.with_replace(force);
+            }
+            if let Some(capture) = capture_result_option(capture_result, result_start, result_end)? {
+                options = options.with_capture_result(capture);
+            }
+            if let Some(label) = &encoding {
+                options = options.with_encoding(resolve_encoding(label)?);
+            }
+            if let Some(source) = launch.context_from {
+                options = options.with_context_from(source, launch.context_bytes);
+            }
+            if let Some(key) = launch.idempotency_key {
+                options = options.with_idempotency_key(key);
+            }
+            let parent = launch.parent;
+            commands::spawn_session(registry.clone(), role, task, parent, options, tail_lines, poll_interval)
+                .await?;
+        }
+
+        Some(Commands::Resume { session_id, message, last, role, replay_context }) => {
+            let (session_id, message) = resume_args(session_id, message, last)?;
+            let session_id = match session_id {
+                Some(session_id) => session_id,
+                None => {
+                    let role = role.map(|r| Role::parse(&r, cli.strict_roles)).transpose()?;
+                    last_session(registry.find_most_recent(role).await?, role)?
+                }
+            };
+            let result = match registry
+                .resume_session_with_recap(session_id.clone(), message, replay_context)
+                .await
+            {
+                Ok(_) => ActionResult::success("resumed", Some(session_id), "Session resumed".to_string()),
+                Err(e) => ActionResult::failure("resumed", Some(session_id), e.to_string()),
+            };
+            report_action(cli.output, result);
+        }
+
+        Some(Commands::Restart { session_id, tail_lines }) => {
+            commands::restart_session(registry.clone(), session_id, tail_lines, poll_interval).await?;
         }
 
-        Some(Commands::Resume { session_id, message }) => {
-            let session_id = SessionId::from_string(session_id);
-            registry.resume_session(session_id, message).await?;
-            println!("✓ Session resumed");
+        Some(Commands::List { filters, legend, format, show_task, sort, reverse, limit, offset }) => {
+            let format = list_format(format, cli.output);
+            commands::list_sessions(registry.clone(), &filters, format, show_task, sort, reverse, ListPage { offset, limit }).await?;
+            if legend && format.is_table() {
+                output::print_status_legend();
+            }
         }
 
-        Some(Commands::List) => {
-            commands::list_sessions(registry.clone()).await?;
+        Some(Commands::Tree) => {
+            commands::show_session_tree(registry.clone()).await?;
         }
 
-        Some(Commands::Stop { session_id, all }) => {
+        Some(Commands::Stop { session_id, all, dry_run, yes, cascade }) => {
             if all {
-                commands::stop_all_sessions(registry.clone()).await?;
-            } else if let Some(id) = session_id {
-                let session_id = SessionId::from_string(id);
-                commands::stop_session(registry.clone(), session_id).await?;
+                commands::stop_all_sessions(registry.clone(), dry_run, yes, cli.output).await?;
+            } else if let Some(session_id) = session_id {
+                commands::stop_session(registry.clone(), session_id, cascade, cli.output).await?;
             } else {
                 return Err(ClaudeManError::InvalidInput(
                     "Must specify either session ID or --all".to_string(),
@@ -421,28 +1524,117 @@ async fn run_without_daemon(cli: Cli) -> Result<()> {
             }
         }
 
+        Some(Commands::Set { key, value, tree }) => {
+            let result = match registry.kv_set(tree.as_ref(), &key, &value).await {
+                Ok(_) => ActionResult::success("set", None, format!("Set {}", key)),
+                Err(e) => ActionResult::failure("set", None, e.to_string()),
+            };
+            report_action(cli.output, result);
+        }
+
+        Some(Commands::Get { key, tree }) => {
+            let value = registry.kv_get(tree.as_ref(), &key).await?;
+            if !commands::print_kv_value(&key, value, cli.output) {
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::HealthCheck) => {
+            let reconciled = registry.health_check().await;
+            report_action(cli.output, commands::health_check_result(reconciled));
+        }
+
         Some(Commands::Info { session_id }) => {
-            let session_id = SessionId::from_string(session_id);
-            commands::get_session_info(registry.clone(), session_id).await?;
+            commands::get_session_info(registry.clone(), session_id, cli.output).await?;
+        }
+
+        Some(Commands::Logs {
+            session_id, follow, lines, stats, since, transcript, reverse, page, download, event_types,
+        }) => {
+            if download {
+                return Err(ClaudeManError::InvalidInput(
+                    "logs --download needs a running daemon".to_string(),
+                ));
+            }
+            if stats {
+                commands::log_stats(registry.clone(), session_id, since, cli.output)?;
+            } else if transcript {
+                commands::print_transcript(registry.clone(), session_id)?;
+            } else {
+                let selection = LogSelection::new(lines).with_event_types(event_types);
+                commands::view_logs(registry.clone(), session_id, follow, selection, reverse, page, poll_interval)
+                    .await?;
+            }
+        }
+
+        Some(Commands::Validate { role }) => {
+            commands::validate_session_files(Role::parse(&role, cli.strict_roles)?)?;
+        }
+
+        Some(Commands::Gc { fix }) => {
+            commands::gc(fix, cli.output)?;
         }
 
-        Some(Commands::Logs { session_id, follow, lines }) => {
-            let session_id = SessionId::from_string(session_id);
-            commands::view_logs(registry.clone(), session_id, follow, lines).await?;
+        Some(Commands::ProtocolSchema) => {
+            commands::print_protocol_schema()?;
         }
 
-        Some(Commands::Attach { session_id }) => {
-            let session_id = SessionId::from_string(session_id);
-            commands::attach_session(registry.clone(), session_id).await?;
+        Some(Commands::Top { interval, once }) => {
+            if !(interval.is_finite() && interval > 0.0) {
+                return Err(ClaudeManError::InvalidInput(
+                    "--interval must be a positive number of seconds".to_string(),
+                ));
+            }
+            commands::top(registry.clone(), Duration::from_secs_f64(interval), once).await?;
         }
 
-        Some(Commands::Input { session_id, text }) => {
-            let session_id = SessionId::from_string(session_id);
-            registry.send_input(&session_id, text).await?;
-            println!("✓ Input sent to session {}", session_id);
+        Some(Commands::Attach { session_id, keep_open, from_end, interactive_approval }) => {
+            let approvals = interactive_approval.then(|| config.prompt_detector()).transpose()?;
+            commands::attach_session(registry.clone(), session_id, poll_interval, keep_open, from_end, approvals)
+                .await?;
         }
 
-        Some(Commands::Init) | Some(Commands::Daemon) | Some(Commands::Shutdown) => {
+        Some(Commands::Input { session_id, text, file, input_newline, paste, allow_empty }) => {
+            let text = commands::resolve_input(text, file.as_deref(), allow_empty)?;
+            let result = match registry.send_input(&session_id, text, input_newline, paste).await {
+                Ok(_) => {
+                    let message = format!("Input sent to session {}", session_id);
+                    ActionResult::success("input_sent", Some(session_id), message)
+                }
+                Err(e) => ActionResult::failure("input_sent", Some(session_id), e.to_string()),
+            };
+            report_action(cli.output, result);
+        }
+
+        Some(Commands::Tail { .. }) => {
+            return Err(ClaudeManError::InvalidInput(
+                "tail follows live output through the daemon; start it with `claude-man daemon --background`".to_string(),
+            ));
+        }
+
+        Some(Commands::Pause { session_id }) => {
+            commands::set_paused(registry.clone(), session_id, true, cli.output).await?;
+        }
+
+        Some(Commands::Continue { session_id }) => {
+            commands::set_paused(registry.clone(), session_id, false, cli.output).await?;
+        }
+
+        Some(Commands::Approve { session_id, note, by }) => {
+            let approval = approval(ApprovalDecision::Approved, note, by);
+            commands::record_approval(registry.clone(), session_id, approval, cli.output)
+                .await?;
+        }
+
+        Some(Commands::Reject { session_id, note, by }) => {
+            let approval = approval(ApprovalDecision::Rejected, note, by);
+            commands::record_approval(registry.clone(), session_id, approval, cli.output)
+                .await?;
+        }
+
+        Some(Commands::Init) | Some(Commands::Version { .. })
+        | Some(Commands::Daemon { .. }) | Some(Commands::Shutdown)
+        | Some(Commands::Backup { .. }) | Some(Commands::Restore { .. }) => {
             unreachable!("Init and Daemon commands handled earlier in run()")
         }
 
@@ -455,6 +1647,37 @@ async fn run_without_daemon(cli: Cli) -> Result<()> {
     Ok(())
 }
 
+/// Print an action result, exiting with a failure status if it didn't succeed
+fn report_action(format: OutputFormat, result: ActionResult) {
+    output::print_action_result(format, &result);
+    if !result.ok {
+        std::process::exit(1);
+    }
+}
+
+/// Convert a daemon response for a mutating command into an action result
+fn action_from_response(
+    action: &str,
+    session_id: SessionId,
+    response: Result<DaemonResponse>,
+    default_message: String,
+) -> ActionResult {
+    match response {
+        Ok(DaemonResponse::Ok { message, .. }) => {
+            ActionResult::success(action, Some(session_id), message.unwrap_or(default_message))
+        }
+        Ok(DaemonResponse::Error { message }) => {
+            ActionResult::failure(action, Some(session_id), message)
+        }
+        Ok(other) => ActionResult::failure(
+            action,
+            Some(session_id),
+            format!("Unexpected daemon response: {:?}", other),
+        ),
+        Err(e) => ActionResult::failure(action, Some(session_id), e.to_string()),
+    }
+}
+
 /// Setup signal handlers for graceful shutdown
 fn setup_signal_handlers(registry: Arc<SessionRegistry>) -> Result<()> {
     // Spawn a task to handle Ctrl+C for cleanup
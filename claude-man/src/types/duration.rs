@@ -0,0 +1,97 @@
+//! Parsing of relative durations given on the command line
+//!
+//! A duration is one or more `<number><unit>` parts, where the unit is `s`,
+//! `m`, `h` or `d`: `30s`, `5m`, `1h30m`, `1d12h`. A bare number counts as
+//! seconds so flags that used to take plain seconds keep working.
+
+use std::time::Duration;
+
+use crate::types::error::{ClaudeManError, Result};
+
+/// Parse a duration such as `30s`, `5m`, `2h`, `1d` or `1h30m`
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let input = s.trim();
+    let invalid = |reason: &str| {
+        ClaudeManError::InvalidInput(format!("Invalid duration '{}': {}", s, reason))
+    };
+
+    if input.is_empty() {
+        return Err(invalid("expected e.g. 30s, 5m, 2h, 1d or 1h30m"));
+    }
+    if input.bytes().all(|b| b.is_ascii_digit()) {
+        let secs = input.parse::<u64>().map_err(|_| invalid("too large"))?;
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total: u64 = 0;
+    let mut rest = input;
+    let mut last_unit: Option<usize> = None;
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(invalid("expected a number before each unit"));
+        }
+        let number = rest[..digits].parse::<u64>().map_err(|_| invalid("too large"))?;
+        rest = &rest[digits..];
+
+        let unit = rest.chars().next().ok_or_else(|| invalid("missing unit after the last number"))?;
+        let (rank, unit_secs) = match unit {
+            'd' => (0, 86_400),
+            'h' => (1, 3_600),
+            'm' => (2, 60),
+            's' => (3, 1),
+            _ => return Err(invalid("units are s, m, h and d")),
+        };
+        // Largest unit first, each at most once (1h30m, not 30m1h or 1h1h)
+        if last_unit.is_some_and(|last| rank <= last) {
+            return Err(invalid("units must go from largest to smallest, each used once"));
+        }
+        last_unit = Some(rank);
+        rest = &rest[unit.len_utf8()..];
+
+        total = number
+            .checked_mul(unit_secs)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| invalid("too large"))?;
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        let secs = |s: &str| parse_duration(s).unwrap().as_secs();
+        assert_eq!(secs("30s"), 30);
+        assert_eq!(secs("5m"), 300);
+        assert_eq!(secs("2h"), 7_200);
+        assert_eq!(secs("1d"), 86_400);
+        assert_eq!(secs("1h30m"), 5_400);
+        assert_eq!(secs("1d2h3m4s"), 93_784);
+        assert_eq!(secs("0s"), 0);
+        assert_eq!(secs(" 90 "), 90);
+        assert_eq!(secs("45"), 45);
+
+        for bad in ["", "   ", "s", "5x", "5 m", "-5m", "1.5h", "m5", "5mm", "30m1h", "1h1h", "1h30", "5µ"] {
+            let err = parse_duration(bad).unwrap_err();
+            assert!(matches!(err, ClaudeManError::InvalidInput(_)), "{:?} gave {:?}", bad, err);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_overflow() {
+        assert_eq!(parse_duration(&u64::MAX.to_string()).unwrap().as_secs(), u64::MAX);
+        for too_large in [
+            "18446744073709551616".to_string(),
+            "99999999999999999999s".to_string(),
+            format!("{}d", u64::MAX / 86_400 + 1),
+            format!("{}m{}s", u64::MAX / 60, 60),
+        ] {
+            let err = parse_duration(&too_large).unwrap_err();
+            assert!(err.to_string().contains("too large"), "{:?} gave {}", too_large, err);
+        }
+    }
+}
@@ -52,6 +52,10 @@ pub enum ClaudeManError {
     #[error("Failed to terminate process: {0}")]
     TerminationFailed(String),
 
+    /// A session ran past its time limit and was terminated
+    #[error("Session timed out after {0}s")]
+    Timeout(u64),
+
     /// Log file errors
     #[error("Log error: {0}")]
     Log(String),
@@ -3,11 +3,15 @@
 //! This module contains all the core types used throughout the application,
 //! including errors, roles, and session types.
 
+pub mod duration;
 pub mod error;
+pub mod path;
 pub mod role;
 pub mod session;
 
 // Re-export commonly used types
+pub use duration::parse_duration;
 pub use error::{ClaudeManError, Result};
+pub use path::{expand_path, expand_path_str};
 pub use role::Role;
 pub use session::{SessionId, SessionMetadata, SessionStatus};
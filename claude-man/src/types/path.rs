@@ -0,0 +1,84 @@
+//! Expansion of `~` and environment variables in path arguments
+//!
+//! Shells only expand `~` at the start of a word, so `--cwd=~/proj`, quoted
+//! paths and paths from environment variables (`CLAUDE_MAN_SOCKET`) would
+//! otherwise reach claude-man with a literal tilde. `~user` is not
+//! supported.
+
+use std::path::PathBuf;
+
+use crate::types::error::{ClaudeManError, Result};
+
+/// Expand a leading `~` and `$VAR` / `${VAR}` references in a path
+pub fn expand_path(s: &str) -> Result<PathBuf> {
+    expand_path_str(s).map(PathBuf::from)
+}
+
+/// [`expand_path`] for arguments kept as strings, such as executable names
+pub fn expand_path_str(s: &str) -> Result<String> {
+    let invalid = |reason: String| ClaudeManError::InvalidInput(format!("Invalid path '{}': {}", s, reason));
+
+    let mut rest = s;
+    let mut expanded = String::with_capacity(s.len());
+    if let Some(after) = s.strip_prefix('~') {
+        if !(after.is_empty() || after.starts_with('/') || after.starts_with(std::path::MAIN_SEPARATOR)) {
+            return Err(invalid("~user paths are not supported; use $HOME or an absolute path".to_string()));
+        }
+        let home = dirs::home_dir().ok_or_else(|| invalid("no home directory to expand ~".to_string()))?;
+        expanded.push_str(&home.to_string_lossy());
+        rest = after;
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            let close = braced.find('}').ok_or_else(|| invalid("unclosed ${".to_string()))?;
+            (&braced[..close], &braced[close + 1..])
+        } else {
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..len], &after[len..])
+        };
+
+        // A `$` not followed by a name is kept as is
+        if name.is_empty() && !after.starts_with('{') {
+            expanded.push('$');
+        } else {
+            let value = std::env::var(name)
+                .map_err(|_| invalid(format!("environment variable {} is not set", name)))?;
+            expanded.push_str(&value);
+        }
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_path() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_path("~").unwrap(), home);
+        assert_eq!(expand_path("~/proj").unwrap(), home.join("proj"));
+        assert_eq!(expand_path("/srv/~app").unwrap(), PathBuf::from("/srv/~app"));
+        assert_eq!(expand_path("relative/dir").unwrap(), PathBuf::from("relative/dir"));
+
+        let home_var = std::env::var("HOME").unwrap();
+        assert_eq!(expand_path_str("$HOME/proj").unwrap(), format!("{}/proj", home_var));
+        assert_eq!(expand_path_str("${HOME}_x/$HOME").unwrap(), format!("{}_x/{}", home_var, home_var));
+        assert_eq!(expand_path_str("a$/b$").unwrap(), "a$/b$");
+
+        for bad in ["~root/proj", "$CLAUDE_MAN_UNSET_FOR_TEST/x", "${HOME", "${}"] {
+            let err = expand_path(bad).unwrap_err();
+            assert!(matches!(err, ClaudeManError::InvalidInput(_)), "{:?} gave {:?}", bad, err);
+        }
+        assert!(expand_path("~root").unwrap_err().to_string().contains("not supported"));
+    }
+}
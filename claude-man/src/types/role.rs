@@ -3,15 +3,21 @@
 //! Defines the different roles that can be assigned to Claude sessions
 //! based on the MANAGER-based orchestration pattern.
 
-use serde::{Deserialize, Serialize};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::str::FromStr;
+use std::sync::RwLock;
 
 use crate::types::error::ClaudeManError;
 
+/// Custom roles registered so far, in registration order
+static CUSTOM_ROLES: RwLock<Vec<CustomRole>> = RwLock::new(Vec::new());
+
 /// Role assigned to a Claude session
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "UPPERCASE")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Role {
     /// MANAGER - Orchestrates and coordinates other sessions
     Manager,
@@ -24,6 +30,31 @@ pub enum Role {
 
     /// STAKEHOLDER - Represents business requirements and validation
     Stakeholder,
+
+    /// A user-defined role such as REVIEWER, registered with [`Role::register`]
+    Custom(CustomRole),
+}
+
+/// Name and session ID prefix of a user-defined role
+///
+/// Registered roles live for the rest of the process, so their strings are
+/// leaked to keep `Role` `Copy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomRole {
+    name: &'static str,
+    prefix: &'static str,
+}
+
+impl CustomRole {
+    /// The role's name, e.g. `REVIEWER`
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The session ID prefix, e.g. `REV`
+    pub fn prefix(&self) -> &'static str {
+        self.prefix
+    }
 }
 
 impl Role {
@@ -34,13 +65,89 @@ impl Role {
             Role::Architect => "ARCH",
             Role::Developer => "DEV",
             Role::Stakeholder => "STAKE",
+            Role::Custom(custom) => custom.prefix,
         }
     }
 
-    /// Returns all available roles
-    pub fn all() -> &'static [Role] {
+    /// Returns the four built-in roles
+    pub fn builtin() -> &'static [Role] {
         &[Role::Manager, Role::Architect, Role::Developer, Role::Stakeholder]
     }
+
+    /// Returns all available roles: the built-ins, then registered custom roles
+    pub fn all() -> Vec<Role> {
+        let custom = CUSTOM_ROLES.read().unwrap_or_else(|e| e.into_inner());
+        Role::builtin()
+            .iter()
+            .copied()
+            .chain(custom.iter().copied().map(Role::Custom))
+            .collect()
+    }
+
+    /// Register a custom role so it can be parsed and spawned
+    ///
+    /// Names and prefixes are uppercased and may contain only ASCII letters,
+    /// digits and `_`. Without a prefix, the first three characters of the
+    /// name are used (REVIEWER → REV). Registering the same role twice is a
+    /// no-op; a name or prefix already taken by another role is an error.
+    pub fn register(name: &str, prefix: Option<&str>) -> Result<Role, ClaudeManError> {
+        let (name, prefix) = custom_role_names(name, prefix)?;
+
+        let mut custom = CUSTOM_ROLES.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = custom.iter().find(|role| role.name == name) {
+            if existing.prefix == prefix {
+                return Ok(Role::Custom(*existing));
+            }
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Role {} is already registered with prefix {}",
+                name, existing.prefix
+            )));
+        }
+
+        let builtin = Role::builtin().iter().copied();
+        let taken = builtin.chain(custom.iter().copied().map(Role::Custom)).find(|role| {
+            let role_name = role.to_string();
+            role_name == name || role_name == prefix || role.prefix() == name || role.prefix() == prefix
+        });
+        if let Some(role) = taken {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Custom role {} ({}) clashes with role {} ({})",
+                name,
+                prefix,
+                role,
+                role.prefix()
+            )));
+        }
+
+        let role = CustomRole {
+            name: Box::leak(name.into_boxed_str()),
+            prefix: Box::leak(prefix.into_boxed_str()),
+        };
+        custom.push(role);
+        Ok(Role::Custom(role))
+    }
+}
+
+/// Check a custom role's name and prefix, deriving the prefix if none is given
+///
+/// Returns the uppercased name and prefix.
+pub fn custom_role_names(name: &str, prefix: Option<&str>) -> Result<(String, String), ClaudeManError> {
+    let name = name.trim().to_uppercase();
+    let prefix = match prefix {
+        Some(prefix) => prefix.trim().to_uppercase(),
+        None => name.chars().take(3).collect(),
+    };
+
+    // A `-` would break splitting session IDs into project, prefix and sequence
+    let valid = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid(&name) || !valid(&prefix) {
+        return Err(ClaudeManError::InvalidInput(format!(
+            "Invalid custom role '{}' (prefix '{}'): use only letters, digits and _",
+            name, prefix
+        )));
+    }
+
+    Ok((name, prefix))
 }
 
 impl fmt::Display for Role {
@@ -50,6 +157,37 @@ impl fmt::Display for Role {
             Role::Architect => write!(f, "ARCHITECT"),
             Role::Developer => write!(f, "DEVELOPER"),
             Role::Stakeholder => write!(f, "STAKEHOLDER"),
+            Role::Custom(custom) => write!(f, "{}", custom.name),
+        }
+    }
+}
+
+impl Role {
+    /// Parse a role name, rejecting the short prefixes (`DEV`, `MGR`, ...) when `strict`
+    ///
+    /// Strict parsing keeps a session ID prefix from being mistaken for a role.
+    /// Custom roles parse only once registered.
+    pub fn parse(s: &str, strict: bool) -> Result<Self, ClaudeManError> {
+        let name = s.to_uppercase();
+        let roles = Role::all();
+        let role = roles
+            .iter()
+            .find(|role| role.to_string() == name || (!strict && role.prefix() == name));
+        let valid_roles = || roles.iter().map(Role::to_string).collect::<Vec<_>>().join(", ");
+        match role {
+            Some(role) => Ok(*role),
+            None if strict && roles.iter().any(|role| role.prefix() == name) => {
+                Err(ClaudeManError::InvalidInput(format!(
+                    "Role alias '{}' is not allowed with strict roles. Valid roles: {}",
+                    s,
+                    valid_roles()
+                )))
+            }
+            None => Err(ClaudeManError::InvalidInput(format!(
+                "Invalid role '{}'. Valid roles: {}",
+                s,
+                valid_roles()
+            ))),
         }
     }
 }
@@ -58,16 +196,37 @@ impl FromStr for Role {
     type Err = ClaudeManError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_uppercase().as_str() {
-            "MANAGER" | "MGR" => Ok(Role::Manager),
-            "ARCHITECT" | "ARCH" => Ok(Role::Architect),
-            "DEVELOPER" | "DEV" => Ok(Role::Developer),
-            "STAKEHOLDER" | "STAKE" => Ok(Role::Stakeholder),
-            _ => Err(ClaudeManError::InvalidInput(format!(
-                "Invalid role '{}'. Valid roles: MANAGER, ARCHITECT, DEVELOPER, STAKEHOLDER",
-                s
-            ))),
+        Role::parse(s, false)
+    }
+}
+
+impl Serialize for Role {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Serialized as the full role name (MANAGER, DEVELOPER, or a custom role's name)
+impl JsonSchema for Role {
+    fn schema_name() -> String {
+        "Role".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+/// Reads the full role name; a custom role that isn't registered (say, it was
+/// removed from config) is registered with a derived prefix so its sessions
+/// still load
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if let Some(role) = Role::all().into_iter().find(|role| role.to_string() == name) {
+            return Ok(role);
         }
+        Role::register(&name, None).map_err(serde::de::Error::custom)
     }
 }
 
@@ -99,6 +258,15 @@ mod tests {
         assert_eq!("dev".parse::<Role>().unwrap(), Role::Developer);
     }
 
+    #[test]
+    fn test_role_parse_strict() {
+        assert_eq!(Role::parse("developer", true).unwrap(), Role::Developer);
+        assert_eq!(Role::parse("STAKEHOLDER", true).unwrap(), Role::Stakeholder);
+        assert!(Role::parse("DEV", true).is_err());
+        assert!(Role::parse("mgr", true).is_err());
+        assert_eq!(Role::parse("ARCH", false).unwrap(), Role::Architect);
+    }
+
     #[test]
     fn test_invalid_role() {
         assert!("INVALID".parse::<Role>().is_err());
@@ -107,11 +275,34 @@ mod tests {
     #[test]
     fn test_role_all() {
         let all_roles = Role::all();
-        assert_eq!(all_roles.len(), 4);
+        // Other tests may register custom roles, which follow the built-ins
+        assert_eq!(&all_roles[..4], Role::builtin());
         assert!(all_roles.contains(&Role::Manager));
         assert!(all_roles.contains(&Role::Developer));
     }
 
+    #[test]
+    fn test_custom_role() {
+        let reviewer = Role::register("Reviewer", None).unwrap();
+        assert_eq!(reviewer.prefix(), "REV");
+        assert_eq!(reviewer.to_string(), "REVIEWER");
+        assert_eq!("reviewer".parse::<Role>().unwrap(), reviewer);
+        assert_eq!("REV".parse::<Role>().unwrap(), reviewer);
+        assert!(Role::parse("REV", true).is_err());
+        assert!(Role::all().contains(&reviewer));
+
+        // Idempotent, but names and prefixes can't be taken twice
+        assert_eq!(Role::register("REVIEWER", Some("rev")).unwrap(), reviewer);
+        assert!(Role::register("REVIEWER", Some("RV")).is_err());
+        assert!(Role::register("REVENUE", None).is_err());
+        assert!(Role::register("DEVOPS", Some("DEV")).is_err());
+        assert!(Role::register("QA-LEAD", None).is_err());
+
+        let json = serde_json::to_string(&reviewer).unwrap();
+        assert_eq!(json, r#""REVIEWER""#);
+        assert_eq!(serde_json::from_str::<Role>(&json).unwrap(), reviewer);
+    }
+
     #[test]
     fn test_role_serialization() {
         let role = Role::Developer;
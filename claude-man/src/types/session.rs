@@ -4,13 +4,16 @@
 //! for tracking Claude session lifecycle.
 
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
+use crate::types::error::{ClaudeManError, Result};
 use crate::types::role::Role;
 
-/// Unique identifier for a session (format: {ROLE}-{sequence})
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Unique identifier for a session (format: [{PROJECT}-]{ROLE}-{sequence})
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct SessionId(String);
 
 impl SessionId {
@@ -19,15 +22,101 @@ impl SessionId {
         SessionId(format!("{}-{:03}", role.prefix(), sequence))
     }
 
-    /// Parse a session ID from a string
+    /// Create a new session ID namespaced under an optional project prefix
+    ///
+    /// With a project of `A` this yields IDs like `A-DEV-001`.
+    pub fn new_in_project(project: Option<&str>, role: Role, sequence: u32) -> Self {
+        match project {
+            Some(project) => SessionId(format!("{}-{}-{:03}", project, role.prefix(), sequence)),
+            None => Self::new(role, sequence),
+        }
+    }
+
+    /// Wrap a string as a session ID without checking it
+    ///
+    /// For IDs that come from the registry or disk; use [`SessionId::parse`]
+    /// for IDs typed by a user.
     pub fn from_string(s: String) -> Self {
         SessionId(s)
     }
 
+    /// Parse a user-supplied session ID of the form `[PROJECT-]PREFIX-NNN`
+    ///
+    /// The role prefix is uppercase letters, digits or `_` and the sequence
+    /// is digits; a project prefix follows [`validate_project_name`].
+    pub fn parse(s: &str) -> Result<Self> {
+        let id = SessionId(s.trim().to_string());
+        let shaped = match id.without_project().split_once('-') {
+            Some((prefix, sequence)) => {
+                !prefix.is_empty()
+                    && prefix.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                    && !sequence.is_empty()
+                    && sequence.chars().all(|c| c.is_ascii_digit())
+            }
+            None => false,
+        };
+
+        if shaped && id.project().is_none_or(|project| validate_project_name(project).is_ok()) {
+            Ok(id)
+        } else {
+            Err(ClaudeManError::InvalidInput(format!(
+                "Invalid session ID '{}'. Expected [PROJECT-]ROLE-NUMBER, e.g. DEV-001",
+                s
+            )))
+        }
+    }
+
     /// Get the string representation of the session ID
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Get the project prefix, if the ID has one
+    pub fn project(&self) -> Option<&str> {
+        let local = self.without_project();
+        let prefix_len = self.0.len() - local.len();
+        (prefix_len > 0).then(|| &self.0[..prefix_len - 1])
+    }
+
+    /// Get the ID with any project prefix removed (e.g. `A-DEV-001` → `DEV-001`)
+    pub fn without_project(&self) -> &str {
+        // The role prefix and sequence are always the last two segments
+        match self.0.rmatch_indices('-').nth(1) {
+            Some((idx, _)) => &self.0[idx + 1..],
+            None => &self.0,
+        }
+    }
+
+    /// Get the role prefix (e.g. `DEV` in `A-DEV-001`)
+    pub fn role_prefix(&self) -> &str {
+        let local = self.without_project();
+        local.split_once('-').map_or(local, |(prefix, _)| prefix)
+    }
+
+    /// Get the sequence number, if the ID ends in one
+    pub fn sequence(&self) -> Option<u32> {
+        self.without_project().split_once('-')?.1.parse().ok()
+    }
+
+    /// Project, role prefix and numeric sequence, falling back to the raw ID
+    /// so that only equal IDs compare equal (e.g. `DEV-1` vs `DEV-001`)
+    fn sort_key(&self) -> (Option<&str>, &str, Option<u32>, &str) {
+        (self.project(), self.role_prefix(), self.sequence(), &self.0)
+    }
+}
+
+/// Orders by project, then role prefix, then sequence number, so `DEV-2`
+/// sorts before `DEV-10`
+impl Ord for SessionId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for SessionId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl std::fmt::Display for SessionId {
@@ -36,16 +125,66 @@ impl std::fmt::Display for SessionId {
     }
 }
 
+/// Parse a `key=value` pair used for session annotations and filters
+pub fn parse_key_value(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(ClaudeManError::InvalidInput(format!(
+            "Expected key=value, got '{}'",
+            s
+        ))),
+    }
+}
+
+/// Validate a project prefix for session IDs
+///
+/// Project names must be non-empty and contain only ASCII letters, digits,
+/// `-` or `_` so that generated IDs remain safe to use as directory names.
+pub fn validate_project_name(project: &str) -> Result<()> {
+    let valid = !project.is_empty()
+        && project
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ClaudeManError::InvalidInput(format!(
+            "Invalid project name '{}'. Use letters, digits, '-' or '_'",
+            project
+        )))
+    }
+}
+
+/// Reject text that is empty or only whitespace
+///
+/// `what` names the text in the error, e.g. `task` → "task must not be empty".
+pub fn validate_non_empty(text: &str, what: &str) -> Result<()> {
+    if text.trim().is_empty() {
+        return Err(ClaudeManError::InvalidInput(format!("{} must not be empty", what)));
+    }
+    Ok(())
+}
+
 /// Session lifecycle states
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionStatus {
     /// Session has been created but not yet started
     Created,
 
+    /// Session is allocated but deliberately waiting to start
+    /// (concurrency limit, unmet dependencies, etc.)
+    Queued,
+
     /// Session is actively running
     Running,
 
+    /// Session's process is suspended (SIGSTOP) until continued
+    Paused,
+
     /// Session completed successfully
     Completed,
 
@@ -56,11 +195,20 @@ pub enum SessionStatus {
     Stopped,
 }
 
+impl SessionStatus {
+    /// Check if this status means the session has ended
+    pub fn is_terminal(self) -> bool {
+        matches!(self, SessionStatus::Completed | SessionStatus::Failed | SessionStatus::Stopped)
+    }
+}
+
 impl std::fmt::Display for SessionStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SessionStatus::Created => write!(f, "created"),
+            SessionStatus::Queued => write!(f, "queued"),
             SessionStatus::Running => write!(f, "running"),
+            SessionStatus::Paused => write!(f, "paused"),
             SessionStatus::Completed => write!(f, "completed"),
             SessionStatus::Failed => write!(f, "failed"),
             SessionStatus::Stopped => write!(f, "stopped"),
@@ -68,8 +216,147 @@ impl std::fmt::Display for SessionStatus {
     }
 }
 
+/// How a session's Claude process is launched
+///
+/// Stored with the session so resumes and child sessions can reuse it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SpawnSettings {
+    /// Model passed to claude as `--model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Extra environment variables for the process
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// Working directory (the session's log directory if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+
+    /// Claude executable to run instead of `claude`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claude_binary: Option<String>,
+}
+
+impl SpawnSettings {
+    /// Check whether every setting is left at its default
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fill unset settings from a parent session's
+    ///
+    /// Environment variables are merged, with this session's values winning.
+    pub fn inherit_from(self, parent: &SpawnSettings) -> Self {
+        let mut env = parent.env.clone();
+        env.extend(self.env);
+
+        Self {
+            model: self.model.or_else(|| parent.model.clone()),
+            env,
+            working_dir: self.working_dir.or_else(|| parent.working_dir.clone()),
+            claude_binary: self.claude_binary.or_else(|| parent.claude_binary.clone()),
+        }
+    }
+
+    /// Check that the working directory, if set, is an existing directory
+    pub fn validate_working_dir(&self) -> Result<()> {
+        let Some(dir) = &self.working_dir else {
+            return Ok(());
+        };
+        if !dir.exists() {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Working directory {} does not exist",
+                dir.display()
+            )));
+        }
+        if !dir.is_dir() {
+            return Err(ClaudeManError::InvalidInput(format!(
+                "Working directory {} is not a directory",
+                dir.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A decision on a session's work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalDecision {
+    /// The work is accepted
+    Approved,
+
+    /// The work is not accepted
+    Rejected,
+}
+
+impl std::fmt::Display for ApprovalDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApprovalDecision::Approved => write!(f, "approved"),
+            ApprovalDecision::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+/// An approval decision recorded on a session (typically by a STAKEHOLDER)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Approval {
+    /// Whether the work was approved or rejected
+    pub decision: ApprovalDecision,
+
+    /// Optional explanation of the decision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Session that made the decision, if recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<SessionId>,
+
+    /// When the decision was made
+    pub decided_at: DateTime<Utc>,
+}
+
+impl Approval {
+    /// Create a decision made now
+    pub fn new(decision: ApprovalDecision) -> Self {
+        Self {
+            decision,
+            note: None,
+            decided_by: None,
+            decided_at: Utc::now(),
+        }
+    }
+
+    /// Attach an explanation
+    pub fn with_note(mut self, note: Option<String>) -> Self {
+        self.note = note;
+        self
+    }
+
+    /// Record which session made the decision
+    pub fn with_decided_by(mut self, decided_by: Option<SessionId>) -> Self {
+        self.decided_by = decided_by;
+        self
+    }
+}
+
+impl std::fmt::Display for Approval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.decision)?;
+        if let Some(decided_by) = &self.decided_by {
+            write!(f, " by {}", decided_by)?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, ": {}", note)?;
+        }
+        Ok(())
+    }
+}
+
 /// Session metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SessionMetadata {
     /// Unique session identifier
     pub id: SessionId,
@@ -101,6 +388,54 @@ pub struct SessionMetadata {
 
     /// Directory where session logs are stored
     pub log_dir: PathBuf,
+
+    /// User-supplied key/value annotations (e.g. `pr=1234`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+
+    /// Sessions that must finish before this one starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<SessionId>,
+
+    /// Why the session ended up in its current status (if not self-explanatory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_reason: Option<String>,
+
+    /// File the session's result is written to when it completes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_path: Option<PathBuf>,
+
+    /// Charset the session's output is decoded from (UTF-8 if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    /// Approval decision on the session's work, if one was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<Approval>,
+
+    /// How the Claude process is launched (model, env, working dir, binary)
+    #[serde(default, skip_serializing_if = "SpawnSettings::is_empty")]
+    pub spawn_settings: SpawnSettings,
+
+    /// Structured result parsed from the output (with `--expect-json-result`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+
+    /// Shell command run when the session's process exits (`--on-complete`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<String>,
+
+    /// Seconds the process may run before it is terminated and the session failed (`--timeout`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    /// How many times the session is restarted automatically if it fails (`--retry-on-failure`)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retry_on_failure: u32,
+
+    /// Automatic restarts after failure so far
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
 }
 
 impl SessionMetadata {
@@ -117,6 +452,18 @@ impl SessionMetadata {
             ended_at: None,
             pid: None,
             log_dir,
+            annotations: HashMap::new(),
+            depends_on: Vec::new(),
+            status_reason: None,
+            result_path: None,
+            encoding: None,
+            approval: None,
+            spawn_settings: SpawnSettings::default(),
+            result: None,
+            on_complete: None,
+            timeout_secs: None,
+            retry_on_failure: 0,
+            retries: 0,
         }
     }
 
@@ -139,16 +486,49 @@ impl SessionMetadata {
             ended_at: None,
             pid: None,
             log_dir,
+            annotations: HashMap::new(),
+            depends_on: Vec::new(),
+            status_reason: None,
+            result_path: None,
+            encoding: None,
+            approval: None,
+            spawn_settings: SpawnSettings::default(),
+            result: None,
+            on_complete: None,
+            timeout_secs: None,
+            retry_on_failure: 0,
+            retries: 0,
         }
     }
 
+    /// Mark session as deliberately waiting to start
+    pub fn mark_queued(&mut self, reason: String) {
+        self.status = SessionStatus::Queued;
+        self.status_reason = Some(reason);
+    }
+
     /// Mark session as started with the given PID
+    ///
+    /// A queued session's waiting reason no longer applies and is cleared.
     pub fn mark_started(&mut self, pid: u32) {
+        if self.status == SessionStatus::Queued {
+            self.status_reason = None;
+        }
         self.status = SessionStatus::Running;
         self.started_at = Some(Utc::now());
         self.pid = Some(pid);
     }
 
+    /// Mark a running session's process as suspended
+    pub fn mark_paused(&mut self) {
+        self.status = SessionStatus::Paused;
+    }
+
+    /// Mark a paused session as running again
+    pub fn mark_continued(&mut self) {
+        self.status = SessionStatus::Running;
+    }
+
     /// Mark session as completed
     pub fn mark_completed(&mut self) {
         self.status = SessionStatus::Completed;
@@ -170,9 +550,40 @@ impl SessionMetadata {
         self.pid = None;
     }
 
+    /// Mark session as failed with a reason
+    pub fn mark_failed_with_reason(&mut self, reason: String) {
+        self.mark_failed();
+        self.status_reason = Some(reason);
+    }
+
+    /// Mark session as stopped with a reason
+    pub fn mark_stopped_with_reason(&mut self, reason: String) {
+        self.mark_stopped();
+        self.status_reason = Some(reason);
+    }
+
+    /// Check if the session has not started yet (created or queued)
+    pub fn is_pending(&self) -> bool {
+        matches!(self.status, SessionStatus::Created | SessionStatus::Queued)
+    }
+
     /// Check if session is currently active
+    ///
+    /// A paused session still has a live process, so it counts as active.
     pub fn is_active(&self) -> bool {
-        matches!(self.status, SessionStatus::Running)
+        matches!(self.status, SessionStatus::Running | SessionStatus::Paused)
+    }
+
+    /// Check if the session has ended and will not run again on its own
+    pub fn is_terminal(&self) -> bool {
+        self.status.is_terminal()
+    }
+
+    /// Check whether every `key=value` filter matches this session's annotations
+    pub fn matches_annotations(&self, filters: &[(String, String)]) -> bool {
+        filters
+            .iter()
+            .all(|(key, value)| self.annotations.get(key) == Some(value))
     }
 
     /// Get the duration of the session (if ended)
@@ -184,6 +595,11 @@ impl SessionMetadata {
     }
 }
 
+/// For skipping zero counters when serializing metadata
+pub(crate) fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,10 +619,135 @@ mod tests {
         assert_eq!(id.to_string(), "ARCH-005");
     }
 
+    #[test]
+    fn test_session_id_with_project() {
+        let id = SessionId::new_in_project(Some("A"), Role::Developer, 1);
+        assert_eq!(id.as_str(), "A-DEV-001");
+        assert_eq!(id.project(), Some("A"));
+        assert_eq!(id.without_project(), "DEV-001");
+
+        let id = SessionId::from_string("my-proj-MGR-002".to_string());
+        assert_eq!(id.project(), Some("my-proj"));
+        assert_eq!(id.without_project(), "MGR-002");
+        assert_eq!(id.role_prefix(), "MGR");
+        assert_eq!(id.sequence(), Some(2));
+
+        let id = SessionId::new_in_project(None, Role::Architect, 3);
+        assert_eq!(id.as_str(), "ARCH-003");
+        assert_eq!(id.project(), None);
+        assert_eq!(id.without_project(), "ARCH-003");
+    }
+
+    #[test]
+    fn test_session_id_parse() {
+        for valid in ["DEV-001", "MGR-42", "REV_2-1000", "A-DEV-001", "my-proj-ARCH-003", " DEV-001\n"] {
+            let id = SessionId::parse(valid).unwrap_or_else(|e| panic!("{:?}: {}", valid, e));
+            assert_eq!(id.as_str(), valid.trim());
+        }
+        assert_eq!(SessionId::parse("A-DEV-001").unwrap().project(), Some("A"));
+
+        for invalid in ["", "   ", "DEV", "DEV-", "-001", "DEV-01a", "dev-001", "DEV001", "-DEV-001", "a b-DEV-001", "DEV-001/.."] {
+            assert!(
+                matches!(SessionId::parse(invalid), Err(ClaudeManError::InvalidInput(_))),
+                "{:?} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_session_id_ordering() {
+        let id = |s: &str| SessionId::from_string(s.to_string());
+
+        // Numeric within a role, not lexicographic
+        assert!(id("DEV-2") < id("DEV-10"));
+        assert!(id("DEV-009") < id("DEV-010"));
+        assert!(id("DEV-99") < id("DEV-100"));
+
+        // Role prefix first, unprefixed IDs before project IDs
+        assert!(id("ARCH-900") < id("DEV-001"));
+        assert!(id("MGR-005") < id("A-DEV-001"));
+
+        // Only equal IDs compare equal
+        assert_ne!(id("DEV-1").cmp(&id("DEV-001")), std::cmp::Ordering::Equal);
+        assert_eq!(id("DEV-1").cmp(&id("DEV-1")), std::cmp::Ordering::Equal);
+
+        let mut ids = [id("DEV-10"), id("MGR-1"), id("DEV-2"), id("DEV-1")];
+        ids.sort();
+        let sorted: Vec<_> = ids.iter().map(SessionId::as_str).collect();
+        assert_eq!(sorted, ["DEV-1", "DEV-2", "DEV-10", "MGR-1"]);
+    }
+
+    #[test]
+    fn test_parse_key_value() {
+        assert_eq!(
+            parse_key_value("pr=1234").unwrap(),
+            ("pr".to_string(), "1234".to_string())
+        );
+        assert_eq!(
+            parse_key_value("url=a=b").unwrap(),
+            ("url".to_string(), "a=b".to_string())
+        );
+        assert!(parse_key_value("novalue").is_err());
+        assert!(parse_key_value("=x").is_err());
+    }
+
+    #[test]
+    fn test_matches_annotations() {
+        let mut metadata = SessionMetadata::new(
+            SessionId::new(Role::Developer, 1),
+            Role::Developer,
+            "test".to_string(),
+            PathBuf::from("/tmp"),
+        );
+        metadata.annotations.insert("pr".to_string(), "1234".to_string());
+
+        assert!(metadata.matches_annotations(&[]));
+        assert!(metadata.matches_annotations(&[("pr".to_string(), "1234".to_string())]));
+        assert!(!metadata.matches_annotations(&[("pr".to_string(), "99".to_string())]));
+        assert!(!metadata.matches_annotations(&[("ticket".to_string(), "1234".to_string())]));
+    }
+
+    #[test]
+    fn test_spawn_settings_inherit_from() {
+        let parent = SpawnSettings {
+            model: Some("opus".to_string()),
+            env: BTreeMap::from([
+                ("A".to_string(), "parent".to_string()),
+                ("B".to_string(), "parent".to_string()),
+            ]),
+            working_dir: Some(PathBuf::from("/srv/app")),
+            claude_binary: None,
+        };
+        let child = SpawnSettings {
+            model: Some("haiku".to_string()),
+            env: BTreeMap::from([("B".to_string(), "child".to_string())]),
+            ..SpawnSettings::default()
+        };
+
+        let merged = child.inherit_from(&parent);
+        assert_eq!(merged.model.as_deref(), Some("haiku"));
+        assert_eq!(merged.env["A"], "parent");
+        assert_eq!(merged.env["B"], "child");
+        assert_eq!(merged.working_dir, Some(PathBuf::from("/srv/app")));
+        assert!(SpawnSettings::default().is_empty());
+    }
+
+    #[test]
+    fn test_validate_project_name() {
+        assert!(validate_project_name("ACME").is_ok());
+        assert!(validate_project_name("team_b-2").is_ok());
+        assert!(validate_project_name("").is_err());
+        assert!(validate_project_name("../etc").is_err());
+        assert!(validate_project_name("has space").is_err());
+    }
+
     #[test]
     fn test_session_status_display() {
         assert_eq!(SessionStatus::Created.to_string(), "created");
+        assert_eq!(SessionStatus::Queued.to_string(), "queued");
         assert_eq!(SessionStatus::Running.to_string(), "running");
+        assert_eq!(SessionStatus::Paused.to_string(), "paused");
         assert_eq!(SessionStatus::Completed.to_string(), "completed");
     }
 
@@ -236,6 +777,61 @@ mod tests {
         assert!(metadata.duration().is_some());
     }
 
+    #[test]
+    fn test_queued_session_transitions() {
+        let id = SessionId::new(Role::Developer, 1);
+        let mut metadata = SessionMetadata::new(
+            id,
+            Role::Developer,
+            "test task".to_string(),
+            PathBuf::from("/tmp/test"),
+        );
+
+        metadata.mark_queued("waiting for a free slot".to_string());
+        assert_eq!(metadata.status, SessionStatus::Queued);
+        assert!(metadata.is_pending());
+        assert!(!metadata.is_active());
+        assert!(!metadata.is_terminal());
+        assert!(metadata.status_reason.is_some());
+
+        metadata.mark_started(1234);
+        assert_eq!(metadata.status, SessionStatus::Running);
+        assert!(!metadata.is_pending());
+        assert!(metadata.status_reason.is_none());
+
+        metadata.mark_stopped();
+        assert!(metadata.is_terminal());
+    }
+
+    #[test]
+    fn test_paused_session_transitions() {
+        let id = SessionId::new(Role::Developer, 1);
+        let mut metadata = SessionMetadata::new(
+            id,
+            Role::Developer,
+            "test task".to_string(),
+            PathBuf::from("/tmp/test"),
+        );
+        metadata.mark_started(1234);
+
+        metadata.mark_paused();
+        assert_eq!(metadata.status, SessionStatus::Paused);
+        assert!(metadata.is_active());
+        assert!(!metadata.is_pending());
+        assert!(!metadata.is_terminal());
+        assert_eq!(metadata.pid, Some(1234));
+        assert!(metadata.ended_at.is_none());
+
+        metadata.mark_continued();
+        assert_eq!(metadata.status, SessionStatus::Running);
+        assert!(metadata.is_active());
+
+        metadata.mark_paused();
+        metadata.mark_stopped();
+        assert!(metadata.is_terminal());
+        assert!(!metadata.is_active());
+    }
+
     #[test]
     fn test_session_metadata_serialization() {
         let id = SessionId::new(Role::Developer, 1);